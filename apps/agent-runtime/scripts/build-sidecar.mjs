@@ -0,0 +1,36 @@
+// Compiles the agent into a standalone executable for the Tauri bundle with Bun, a
+// devDependency of this package. Tauri expects external binaries to carry the target
+// triple, e.g. agent-runtime-x86_64-apple-darwin.
+import { execFileSync } from 'node:child_process';
+import { mkdirSync } from 'node:fs';
+import { dirname, join } from 'node:path';
+import { fileURLToPath } from 'node:url';
+
+const root = join(dirname(fileURLToPath(import.meta.url)), '..');
+const binaries = join(root, '../tauri-shell/src-tauri/binaries');
+
+const rustInfo = execFileSync('rustc', ['-vV'], { encoding: 'utf8' });
+const triple = process.env.TAURI_TARGET_TRIPLE ?? /host: (\S+)/.exec(rustInfo)?.[1];
+if (!triple) {
+  throw new Error('Could not determine the target triple from rustc -vV');
+}
+const extension = triple.includes('windows') ? '.exe' : '';
+
+mkdirSync(binaries, { recursive: true });
+execFileSync(
+  'bun',
+  [
+    'build',
+    'src/index.ts',
+    '--compile',
+    // Native addons can't be loaded from the executable; database.ts uses bun:sqlite
+    '--external',
+    'better-sqlite3',
+    // Tells config.ts there's no checkout to read .env from
+    '--define',
+    'process.env.ASST_SIDECAR="1"',
+    '--outfile',
+    join(binaries, `agent-runtime-${triple}${extension}`),
+  ],
+  { cwd: root, stdio: 'inherit' }
+);
@@ -2,15 +2,27 @@ import Anthropic from '@anthropic-ai/sdk';
 import type { AppConfig } from './config.js';
 import type { Tool } from './tools/index.js';
 import { ConversationDatabase } from './persistence/database.js';
+import { negotiate, useCapabilities, writeMessage, type Capabilities } from './framing.js';
 
 export interface AgentRequest {
   id: string;
-  kind: 'user_message' | 'clear_history' | 'load_conversation' | 'new_conversation';
+  // For 'interrupt', id is the id of the user_message to cancel.
+  // For 'merge_conversations', message is the source and conversation_id the target.
+  // For 'load_conversation_compressed', message is a JSON CompressedHistory.
+  kind: 'user_message' | 'clear_history' | 'load_conversation' | 'new_conversation' | 'interrupt' | 'shutdown' | 'ping' | 'transform' | 'merge_conversations' | 'summarize' | 'load_conversation_compressed' | 'hello';
   message?: string;
   conversation_id?: string;
   images?: string; // JSON string of image attachments
   attachments?: Array<{ path: string; mime: string }>;
   metadata?: Record<string, unknown>;
+  // On 'hello': the shell's protocol version and what it offers
+  protocol_version?: number;
+  capabilities?: Partial<Capabilities>;
+}
+
+export interface CompressedHistory {
+  summary: string;
+  messages: Array<{ role: 'user' | 'assistant'; content: string }>;
 }
 
 export interface ImageAttachment {
@@ -20,14 +32,36 @@ export interface ImageAttachment {
 }
 
 export interface AgentResponse {
-  type: 'token' | 'tool_use' | 'tool_result' | 'done' | 'error';
+  // 'ack' confirms receipt of a user message before any work on it
+  type: 'ack' | 'token' | 'tool_use' | 'tool_result' | 'image_output' | 'done' | 'error' | 'pong' | 'hello';
   id: string;
   data?: unknown;
+  // On 'image_output': media type of the base64 image in data
+  mime?: string;
   token?: string;
   error?: string;
+  // Machine-readable failure class: network_error, provider_unavailable, rate_limited, auth_error, interrupted
+  code?: string;
+  retry_after_ms?: number;
+  // On 'hello': what this agent agreed to
+  capabilities?: Capabilities;
+  // On 'done' for user messages: tokens billed across every API call of the turn
+  usage?: Usage;
   timestamp: number;
 }
 
+export interface Usage {
+  model: string;
+  input_tokens: number;
+  output_tokens: number;
+}
+
+function throwIfInterrupted(signal: AbortSignal): void {
+  if (signal.aborted) {
+    throw new Error('Interrupted');
+  }
+}
+
 export class AgentOrchestrator {
   private client: Anthropic;
   private config: AppConfig;
@@ -35,6 +69,8 @@ export class AgentOrchestrator {
   private conversationHistory: Anthropic.MessageParam[] = [];
   private db: ConversationDatabase;
   private currentConversationId: string;
+  // Abort handles of user_message requests still being processed, keyed by request id
+  private inFlight = new Map<string, AbortController>();
 
   constructor(config: AppConfig, tools: Tool[]) {
     this.config = config;
@@ -73,6 +109,45 @@ export class AgentOrchestrator {
   }
 
   async handleRequest(request: AgentRequest): Promise<void> {
+    if (request.kind === 'interrupt') {
+      // Only the targeted generation stops; it ends with an 'interrupted' error
+      const controller = this.inFlight.get(request.id);
+      if (controller) {
+        controller.abort();
+      } else {
+        this.log('debug', `Interrupt for unknown request ${request.id}`);
+      }
+      return;
+    }
+
+    if (request.kind === 'hello') {
+      // Answered in the old format; everything after it uses the agreed one
+      const capabilities = negotiate(request.capabilities ?? {});
+      this.sendResponse({ type: 'hello', id: request.id, capabilities, timestamp: Date.now() });
+      useCapabilities(capabilities);
+      return;
+    }
+
+    if (request.kind === 'ping') {
+      // Heartbeat from the shell; answered even while generations are running
+      this.sendResponse({ type: 'pong', id: request.id, timestamp: Date.now() });
+      return;
+    }
+
+    if (request.kind === 'shutdown') {
+      // Exiting is the real acknowledgement: the shell waits for it before killing
+      for (const controller of this.inFlight.values()) {
+        controller.abort();
+      }
+      this.db.close();
+      this.sendResponse({
+        type: 'done',
+        id: request.id,
+        timestamp: Date.now(),
+      });
+      process.exit(0);
+    }
+
     if (request.kind === 'clear_history') {
       this.conversationHistory = [];
       this.db.clearMessages(this.currentConversationId);
@@ -111,20 +186,125 @@ export class AgentOrchestrator {
       return;
     }
 
+    if (request.kind === 'load_conversation_compressed' && request.message && request.conversation_id) {
+      const history = JSON.parse(request.message) as CompressedHistory;
+      this.currentConversationId = request.conversation_id;
+      this.conversationHistory = this.compressedHistory(history);
+      this.log('info', `Loaded conversation ${request.conversation_id} from its summary and ${history.messages.length} recent messages`);
+      this.sendResponse({
+        type: 'done',
+        id: request.id,
+        data: { conversation_id: request.conversation_id, message_count: this.conversationHistory.length },
+        timestamp: Date.now(),
+      });
+      return;
+    }
+
+    if (request.kind === 'merge_conversations' && request.message && request.conversation_id) {
+      const moved = this.db.mergeConversations(request.message, request.conversation_id);
+      if (this.currentConversationId === request.message || this.currentConversationId === request.conversation_id) {
+        this.currentConversationId = request.conversation_id;
+        this.loadConversationHistory(request.conversation_id);
+      }
+      this.log('info', `Merged ${moved} messages from ${request.message} into ${request.conversation_id}`);
+      this.sendResponse({
+        type: 'done',
+        id: request.id,
+        data: { conversation_id: request.conversation_id, merged_messages: moved },
+        timestamp: Date.now(),
+      });
+      return;
+    }
+
     if (request.kind === 'user_message' && request.message) {
+      this.sendResponse({ type: 'ack', id: request.id, timestamp: Date.now() });
       await this.processUserMessage(request);
     }
+
+    if (request.kind === 'transform' && request.message) {
+      await this.processTransform(request, 'You transform text as instructed. Reply with only the transformed text, without any preamble, quotes or commentary.');
+    }
+
+    if (request.kind === 'summarize' && request.message) {
+      await this.processTransform(request, 'You summarize conversations between a user and an assistant so they can be continued without the full transcript. Fold in the earlier summary if one is given. Keep decisions, facts, open questions, names, file paths and code identifiers; drop pleasantries. Reply with only the summary.');
+    }
+  }
+
+  /**
+   * History seeded from a checkpoint: the summary as the opening exchange, then the
+   * recent messages, merged where needed so roles keep alternating.
+   */
+  private compressedHistory(history: CompressedHistory): Anthropic.MessageParam[] {
+    const messages: Anthropic.MessageParam[] = [
+      { role: 'user', content: `Summary of our conversation so far:\n\n${history.summary}` },
+      { role: 'assistant', content: 'Understood, I will continue from there.' },
+    ];
+    for (const message of history.messages) {
+      const last = messages[messages.length - 1];
+      if (last.role === message.role) {
+        last.content = `${last.content as string}\n\n${message.content}`;
+      } else {
+        messages.push({ role: message.role, content: message.content });
+      }
+    }
+    return messages;
+  }
+
+  /**
+   * One-shot rewrite of text from another app (fix grammar, translate, ...) or of a
+   * transcript into a summary. Runs outside the conversation: no history, no tools,
+   * nothing saved.
+   */
+  private async processTransform(request: AgentRequest, system: string): Promise<void> {
+    const controller = new AbortController();
+    this.inFlight.set(request.id, controller);
+
+    try {
+      const message = await this.client.messages.create({
+        model: this.config.modelId,
+        max_tokens: this.config.maxTokens,
+        system,
+        messages: [{ role: 'user', content: request.message! }],
+      }, { signal: controller.signal });
+
+      const text = message.content
+        .filter((block): block is Anthropic.TextBlock => block.type === 'text')
+        .map(block => block.text)
+        .join('');
+      this.sendResponse({ type: 'token', id: request.id, token: text, timestamp: Date.now() });
+      this.sendResponse({
+        type: 'done',
+        id: request.id,
+        usage: {
+          model: this.config.modelId,
+          input_tokens: message.usage.input_tokens,
+          output_tokens: message.usage.output_tokens,
+        },
+        timestamp: Date.now(),
+      });
+    } catch (error) {
+      this.sendResponse({
+        type: 'error',
+        id: request.id,
+        error: error instanceof Error ? error.message : String(error),
+        timestamp: Date.now(),
+      });
+    } finally {
+      // A retry under the same id may already have replaced this controller
+      if (this.inFlight.get(request.id) === controller) this.inFlight.delete(request.id);
+    }
   }
 
   /**
    * Simulate streaming by emitting text in chunks with small delays
    * This provides a better UX while using the non-streaming API
    */
-  private async emitTextChunked(text: string, requestId: string): Promise<void> {
+  private async emitTextChunked(text: string, requestId: string, signal: AbortSignal): Promise<void> {
     // Split text into words while preserving whitespace
     const words = text.split(/(\s+)/);
 
     for (const word of words) {
+      throwIfInterrupted(signal);
       if (word.length > 0) {
         this.sendResponse({
           type: 'token',
@@ -143,6 +323,13 @@ export class AgentOrchestrator {
   }
 
   private async processUserMessage(request: AgentRequest): Promise<void> {
+    const controller = new AbortController();
+    const signal = controller.signal;
+    this.inFlight.set(request.id, controller);
+    // History entries and database rows this turn added, dropped again if it is interrupted
+    const turnMessages: Anthropic.MessageParam[] = [];
+    const savedIds: string[] = [];
+
     try {
       // Parse images if provided
       let imageAttachments: ImageAttachment[] = [];
@@ -203,15 +390,18 @@ export class AgentOrchestrator {
           : contentBlocks, // Mixed content - use array format
       };
       this.conversationHistory.push(userMessage);
+      turnMessages.push(userMessage);
 
       // Save to database (serialize content for storage)
-      this.db.addMessage(
+      savedIds.push(this.db.addMessage(
         this.currentConversationId,
         'user',
         typeof userMessage.content === 'string'
           ? userMessage.content
           : JSON.stringify(userMessage.content)
-      );
+      ).id);
+
+      const usage: Usage = { model: this.config.modelId, input_tokens: 0, output_tokens: 0 };
 
       // Agentic loop - continue until Claude doesn't request more tools
       let continueLoop = true;
@@ -220,6 +410,7 @@ export class AgentOrchestrator {
 
       while (continueLoop && iteration < maxIterations) {
         iteration++;
+        throwIfInterrupted(signal);
 
         // Create message with streaming
         const toolSchemas = this.tools.length > 0 ? this.tools.map(t => ({
@@ -241,13 +432,16 @@ export class AgentOrchestrator {
             tools: toolSchemas,
           }),
           timeout: 120000, // 2 minute timeout
-        });
+        }, { signal });
 
         const timeoutPromise = new Promise<never>((_, reject) => {
           setTimeout(() => reject(new Error('API request timed out after 2 minutes')), 120000);
         });
 
         const finalMessage = await Promise.race([apiCallPromise, timeoutPromise]);
+        usage.input_tokens += finalMessage.usage.input_tokens;
+        usage.output_tokens += finalMessage.usage.output_tokens;
+        throwIfInterrupted(signal);
 
         this.log('debug', 'Received response from API');
 
@@ -257,7 +451,7 @@ export class AgentOrchestrator {
         for (const content of finalMessage.content) {
           if (content.type === 'text') {
             // Emit text in chunks to simulate streaming for better UX
-            await this.emitTextChunked(content.text, request.id);
+            await this.emitTextChunked(content.text, request.id, signal);
           }
         }
 
@@ -279,9 +473,10 @@ export class AgentOrchestrator {
           content: finalMessage.content,
         };
         this.conversationHistory.push(assistantMessage);
+        turnMessages.push(assistantMessage);
 
         // Save to database
-        this.db.addMessage(this.currentConversationId, 'assistant', assistantMessage.content);
+        savedIds.push(this.db.addMessage(this.currentConversationId, 'assistant', assistantMessage.content).id);
 
         // Check if we need to execute tools
         if (toolUses.length > 0) {
@@ -291,6 +486,7 @@ export class AgentOrchestrator {
           const toolResults: Array<{ type: 'tool_result'; tool_use_id: string; content: string }> = [];
 
           for (const toolUse of toolUses) {
+            throwIfInterrupted(signal);
             try {
               // Send tool_use event to frontend
               this.sendResponse({
@@ -343,6 +539,21 @@ export class AgentOrchestrator {
                 content: toolResultContent,
               });
 
+              // Images a tool made for the user (output: true) go to the shell, which
+              // stores them; the tool_result then carries the rest of the result only
+              let reported = result;
+              if (result && typeof result === 'object' && 'image' in result && result.output === true) {
+                const { image, ...rest } = result as { image: string; format: string; [key: string]: any };
+                this.sendResponse({
+                  type: 'image_output',
+                  id: request.id,
+                  data: image,
+                  mime: `image/${rest.format}`,
+                  timestamp: Date.now(),
+                });
+                reported = rest;
+              }
+
               // Send tool_result event to frontend
               this.sendResponse({
                 type: 'tool_result',
@@ -350,7 +561,7 @@ export class AgentOrchestrator {
                 data: {
                   tool_use_id: toolUse.id,
                   tool_name: toolUse.name,
-                  result,
+                  result: reported,
                 },
                 timestamp: Date.now(),
               });
@@ -387,9 +598,10 @@ export class AgentOrchestrator {
             content: toolResults,
           };
           this.conversationHistory.push(toolResultMessage);
+          turnMessages.push(toolResultMessage);
 
           // Save to database
-          this.db.addMessage(this.currentConversationId, 'user', toolResultMessage.content);
+          savedIds.push(this.db.addMessage(this.currentConversationId, 'user', toolResultMessage.content).id);
 
           // Continue the loop to let Claude process tool results
           continueLoop = true;
@@ -408,21 +620,66 @@ export class AgentOrchestrator {
       this.sendResponse({
         type: 'done',
         id: request.id,
+        usage,
         timestamp: Date.now(),
       });
 
     } catch (error) {
-      this.sendResponse({
-        type: 'error',
-        id: request.id,
-        error: error instanceof Error ? error.message : 'Unknown error',
-        timestamp: Date.now(),
-      });
+      if (signal.aborted) {
+        // Interrupted turns leave no trace, so a retry under the same id starts clean
+        // instead of sending the user message a second time
+        this.conversationHistory = this.conversationHistory.filter(
+          (message) => !turnMessages.includes(message)
+        );
+        this.db.deleteMessages(savedIds);
+        this.sendResponse({
+          type: 'error',
+          id: request.id,
+          error: 'Interrupted',
+          code: 'interrupted',
+          timestamp: Date.now(),
+        });
+      } else {
+        this.sendResponse(this.errorResponse(request.id, error));
+      }
+    } finally {
+      // A retry under the same id may already have replaced this controller
+      if (this.inFlight.get(request.id) === controller) this.inFlight.delete(request.id);
     }
   }
 
+  /**
+   * Classify API failures so the shell can tell "you're offline" from "the provider is down"
+   */
+  private errorResponse(id: string, error: unknown): AgentResponse {
+    const response: AgentResponse = {
+      type: 'error',
+      id,
+      error: error instanceof Error ? error.message : 'Unknown error',
+      timestamp: Date.now(),
+    };
+
+    if (error instanceof Anthropic.APIConnectionError) {
+      response.code = 'network_error';
+    } else if (error instanceof Anthropic.APIError && error.status !== undefined) {
+      if (error.status === 429) {
+        response.code = 'rate_limited';
+        const retryAfter = Number(error.headers?.['retry-after'] ?? NaN);
+        if (Number.isFinite(retryAfter)) {
+          response.retry_after_ms = retryAfter * 1000;
+        }
+      } else if (error.status === 401 || error.status === 403) {
+        response.code = 'auth_error';
+      } else if (error.status >= 500) {
+        response.code = 'provider_unavailable';
+      }
+    }
+
+    return response;
+  }
+
   private sendResponse(response: AgentResponse): void {
-    console.log(JSON.stringify(response));
+    writeMessage(response);
   }
 
   private log(level: string, message: string, data?: unknown): void {
@@ -4,9 +4,12 @@ import { fileURLToPath } from 'url';
 
 const __dirname = path.dirname(fileURLToPath(import.meta.url));
 
-// Load .env from repository root (../../.env from src/)
-const envPath = path.resolve(__dirname, '../../../.env');
-dotenv.config({ path: envPath });
+// Load .env from repository root (../../.env from src/). The compiled sidecar has no
+// checkout around it and gets its settings from the shell's environment instead.
+if (!process.env.ASST_SIDECAR) {
+  const envPath = path.resolve(__dirname, '../../../.env');
+  dotenv.config({ path: envPath });
+}
 
 export interface AppConfig {
   // Claude API
@@ -0,0 +1,94 @@
+import { encode } from '@msgpack/msgpack';
+import { gzipSync } from 'zlib';
+
+// Optional protocol features, agreed with the shell through 'hello'. 'ready' and the
+// answer to 'hello' are always JSON lines; what follows is written as agreed.
+export interface Capabilities {
+  framing: 'lines' | 'length_prefixed';
+  encoding: 'json' | 'msgpack';
+  // Frames may be gzipped, flagged by the top bit of their length
+  compression: boolean;
+  attachments: boolean;
+  tool_approval: boolean;
+}
+
+// Smaller frames aren't worth gzipping
+const COMPRESS_MIN_BYTES = 64 * 1024;
+const COMPRESSED_FRAME = 0x80000000;
+
+// Where messages go: stdout, or the shell's socket when it gave us one
+let output: NodeJS.WritableStream = process.stdout;
+// Writes a message to output, whatever other stdout writes are redirected to
+let write = (chunk: string | Buffer): void => {
+  output.write(chunk);
+};
+
+let agreed: Capabilities = {
+  framing: 'lines',
+  encoding: 'json',
+  compression: false,
+  attachments: false,
+  tool_approval: false,
+};
+
+// What this agent takes up of `offer`. File attachments and tool approval aren't
+// implemented yet.
+export function negotiate(offer: Partial<Capabilities>): Capabilities {
+  const framed = offer.framing === 'length_prefixed';
+  return {
+    framing: framed ? 'length_prefixed' : 'lines',
+    // Binary payloads can contain newlines, so MessagePack needs frames
+    encoding: framed && offer.encoding === 'msgpack' ? 'msgpack' : 'json',
+    compression: framed && offer.compression === true,
+    attachments: false,
+    tool_approval: false,
+  };
+}
+
+// Called once the answer to 'hello' has been written
+export function useCapabilities(capabilities: Capabilities): void {
+  agreed = capabilities;
+  if (capabilities.framing === 'length_prefixed' && output === process.stdout) {
+    // A stray stdout write, e.g. a dependency's console.log, would be read as a frame
+    // length and desync the stream, so from now on those go to stderr
+    const frames = process.stdout.write.bind(process.stdout);
+    write = (chunk) => {
+      frames(chunk);
+    };
+    process.stdout.write = process.stderr.write.bind(process.stderr) as typeof process.stdout.write;
+  }
+}
+
+export function useOutput(stream: NodeJS.WritableStream): void {
+  output = stream;
+}
+
+// MessagePack carries image_output data as binary rather than base64, a third smaller
+// and with nothing to decode on the shell's side
+function binaryImage(message: unknown): unknown {
+  const image = message as { type?: string; data?: unknown };
+  if (image?.type !== 'image_output' || typeof image.data !== 'string') {
+    return message;
+  }
+  return { ...image, data: Buffer.from(image.data, 'base64') };
+}
+
+export function writeMessage(message: unknown): void {
+  if (agreed.framing === 'lines') {
+    write(JSON.stringify(message) + '\n');
+    return;
+  }
+  // 4-byte big-endian length, then the payload. Undefined fields are left out, as
+  // JSON.stringify does.
+  let payload = agreed.encoding === 'msgpack'
+    ? Buffer.from(encode(binaryImage(message), { ignoreUndefined: true }))
+    : Buffer.from(JSON.stringify(message), 'utf8');
+  let length = payload.length;
+  if (agreed.compression && payload.length >= COMPRESS_MIN_BYTES) {
+    payload = gzipSync(payload);
+    length = (payload.length | COMPRESSED_FRAME) >>> 0;
+  }
+  const header = Buffer.alloc(4);
+  header.writeUInt32BE(length, 0);
+  write(Buffer.concat([header, payload]));
+}
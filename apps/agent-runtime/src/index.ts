@@ -1,7 +1,18 @@
+import { createConnection, Socket } from 'net';
 import { createInterface } from 'readline';
 import { AgentOrchestrator } from './agent.js';
 import { loadConfig } from './config.js';
+import { useOutput, writeMessage } from './framing.js';
 import { setupTools } from './tools/index.js';
+import { AGENT_VERSION, PROTOCOL_VERSION } from './version.js';
+
+// The shell's socket or named pipe, when it spawned us with one instead of stdio
+function connectToShell(path: string): Promise<Socket> {
+  return new Promise((resolve, reject) => {
+    const socket = createConnection(path, () => resolve(socket));
+    socket.once('error', reject);
+  });
+}
 
 async function main() {
   try {
@@ -15,10 +26,14 @@ async function main() {
     const orchestrator = new AgentOrchestrator(config, tools);
     await orchestrator.initialize();
 
-    // Setup stdio IPC
+    // Setup IPC over the shell's socket or pipe, or stdio
+    const socketPath = process.env.ASST_SOCKET;
+    const channel = socketPath ? await connectToShell(socketPath) : undefined;
+    if (channel) {
+      useOutput(channel);
+    }
     const rl = createInterface({
-      input: process.stdin,
-      output: process.stdout,
+      input: channel ?? process.stdin,
       terminal: false,
     });
 
@@ -33,7 +48,7 @@ async function main() {
           error: error instanceof Error ? error.message : 'Unknown error',
           timestamp: Date.now(),
         };
-        console.log(JSON.stringify(errorResponse));
+        writeMessage(errorResponse);
       }
     });
 
@@ -41,8 +56,13 @@ async function main() {
       process.exit(0);
     });
 
-    // Send ready signal
-    console.log(JSON.stringify({ type: 'ready', timestamp: Date.now() }));
+    // Send ready signal; the shell checks the protocol version before using us
+    writeMessage({
+      type: 'ready',
+      protocol_version: PROTOCOL_VERSION,
+      agent_version: AGENT_VERSION,
+      timestamp: Date.now(),
+    });
 
   } catch (error) {
     console.error('Fatal error:', error);
@@ -1,4 +1,3 @@
-import Database from 'better-sqlite3';
 import { join } from 'path';
 import { homedir } from 'os';
 import { mkdirSync, existsSync } from 'fs';
@@ -7,6 +6,24 @@ import type Anthropic from '@anthropic-ai/sdk';
 const CLAUDE_DIR = join(homedir(), '.claude');
 const DB_PATH = join(CLAUDE_DIR, 'history.db');
 
+// The parts of better-sqlite3's API used here, which bun:sqlite shares
+interface SqliteDatabase {
+  exec(sql: string): void;
+  prepare(sql: string): {
+    run(...params: unknown[]): { changes: number | bigint };
+    get(...params: unknown[]): unknown;
+    all(...params: unknown[]): unknown[];
+  };
+  transaction<T>(fn: () => T): () => T;
+  close(): void;
+}
+
+// The sidecar is compiled by Bun, which can't load better-sqlite3's native addon from
+// the executable but has SQLite built in. Node (dev and managed builds) uses the addon.
+const Database: new (path: string) => SqliteDatabase = process.versions.bun
+  ? (await import('bun:sqlite' as string)).Database
+  : ((await import('better-sqlite3')).default as unknown as new (path: string) => SqliteDatabase);
+
 // Ensure .claude directory exists
 if (!existsSync(CLAUDE_DIR)) {
   mkdirSync(CLAUDE_DIR, { recursive: true });
@@ -28,7 +45,7 @@ export interface Message {
 }
 
 export class ConversationDatabase {
-  private db: Database.Database;
+  private db: SqliteDatabase;
 
   constructor(dbPath: string = DB_PATH) {
     this.db = new Database(dbPath);
@@ -132,6 +149,24 @@ export class ConversationDatabase {
     deleteConv.run(id);
   }
 
+  // Moves every message of `sourceId` into `targetId` and deletes the source.
+  // Messages are read back by timestamp, so the threads interleave chronologically.
+  mergeConversations(sourceId: string, targetId: string): number {
+    const merge = this.db.transaction(() => {
+      if (!this.getConversation(targetId)) {
+        const title = this.getConversation(sourceId)?.title || 'New Conversation';
+        this.createConversation(targetId, title);
+      }
+      const moved = Number(this.db.prepare(`
+        UPDATE messages SET conversation_id = ? WHERE conversation_id = ?
+      `).run(targetId, sourceId).changes);
+      this.deleteConversation(sourceId);
+      this.touchConversation(targetId);
+      return moved;
+    });
+    return merge();
+  }
+
   // Message methods
   addMessage(conversationId: string, role: 'user' | 'assistant', content: Anthropic.MessageParam['content']): Message {
     const id = `msg_${Date.now()}_${Math.random().toString(36).substr(2, 9)}`;
@@ -174,6 +209,15 @@ export class ConversationDatabase {
     }));
   }
 
+  deleteMessages(ids: string[]): void {
+    const stmt = this.db.prepare(`
+      DELETE FROM messages WHERE id = ?
+    `);
+    for (const id of ids) {
+      stmt.run(id);
+    }
+  }
+
   clearMessages(conversationId: string): void {
     const stmt = this.db.prepare(`
       DELETE FROM messages WHERE conversation_id = ?
@@ -4,6 +4,7 @@ import { createFilesystemTools } from './filesystem.js';
 import { createSystemTools } from './system.js';
 import { createClipboardTools } from './clipboard.js';
 import { createVisionTools } from './vision.js';
+import { createProjectTools } from './project.js';
 import { loadCustomTools } from './custom.js';
 
 export async function setupTools(config: AppConfig): Promise<Tool[]> {
@@ -12,6 +13,7 @@ export async function setupTools(config: AppConfig): Promise<Tool[]> {
     ...createSystemTools(config),
     ...createClipboardTools(),
     ...createVisionTools(),
+    ...createProjectTools(),
   ];
 
   // Load custom user-defined tools
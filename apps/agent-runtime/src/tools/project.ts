@@ -0,0 +1,102 @@
+import fs from 'fs/promises';
+import type { Tool } from './types.js';
+
+// Written by the desktop shell for the folders the user chose to watch
+interface FolderIndex {
+  updated_at: number;
+  folders: Array<{
+    path: string;
+    files: Record<string, { size: number; modified: number }>;
+  }>;
+  changes: Array<{
+    folder: string;
+    path: string;
+    kind: 'created' | 'modified' | 'removed';
+    at: number;
+  }>;
+}
+
+async function readIndex(): Promise<FolderIndex> {
+  const indexPath = process.env.ASST_FOLDER_INDEX;
+  if (!indexPath) {
+    throw new Error('Folder watching is not available');
+  }
+
+  try {
+    return JSON.parse(await fs.readFile(indexPath, 'utf-8'));
+  } catch {
+    throw new Error('No folders are being watched. Ask the user to add a project folder.');
+  }
+}
+
+export function createProjectTools(): Tool[] {
+  return [
+    {
+      name: 'get_recent_file_changes',
+      description: 'List files recently created, modified or removed in the project folders the user is watching. Use this to see what the user has been working on.',
+      input_schema: {
+        type: 'object',
+        properties: {
+          sinceMinutes: {
+            type: 'number',
+            description: 'Only include changes from the last N minutes (default: 60)',
+          },
+          maxResults: {
+            type: 'number',
+            description: 'Maximum number of changes to return, newest first (default: 50)',
+          },
+        },
+      },
+      execute: async (input: { sinceMinutes?: number; maxResults?: number }) => {
+        const index = await readIndex();
+        const since = Date.now() - (input.sinceMinutes ?? 60) * 60 * 1000;
+        const maxResults = input.maxResults || 50;
+
+        return index.changes
+          .filter(change => change.at >= since)
+          .reverse()
+          .slice(0, maxResults)
+          .map(change => ({
+            ...change,
+            at: new Date(change.at).toISOString(),
+          }));
+      },
+    },
+
+    {
+      name: 'find_project_files',
+      description: 'Find files by name in the watched project folders, without touching the disk. Returns the newest matches first.',
+      input_schema: {
+        type: 'object',
+        properties: {
+          query: {
+            type: 'string',
+            description: 'Case-insensitive substring of the file path (e.g., "config" or "src/app")',
+          },
+          maxResults: {
+            type: 'number',
+            description: 'Maximum number of results to return (default: 100)',
+          },
+        },
+        required: ['query'],
+      },
+      execute: async (input: { query: string; maxResults?: number }) => {
+        const index = await readIndex();
+        const query = input.query.toLowerCase();
+        const maxResults = input.maxResults || 100;
+
+        const matches = index.folders.flatMap(folder =>
+          Object.entries(folder.files)
+            .filter(([path]) => path.toLowerCase().includes(query))
+            .map(([path, file]) => ({ folder: folder.path, path, ...file }))
+        );
+        matches.sort((a, b) => b.modified - a.modified);
+
+        return matches.slice(0, maxResults).map(match => ({
+          ...match,
+          modified: new Date(match.modified).toISOString(),
+        }));
+      },
+    },
+  ];
+}
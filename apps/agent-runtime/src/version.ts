@@ -0,0 +1,7 @@
+// Version of the stdio protocol spoken with the shell; bump on incompatible changes.
+// 2: 'ready' carries versions, user messages are acked
+// 3: capabilities are agreed through 'hello'
+export const PROTOCOL_VERSION = 3;
+
+// Keep in step with package.json
+export const AGENT_VERSION = '0.1.0';
@@ -0,0 +1,75 @@
+//! Decoding cost of agent messages as JSON and as MessagePack.
+//!
+//! Run with `cargo bench --bench agent_encoding`.
+
+#[path = "../src/protocol.rs"]
+mod protocol;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use protocol::{AgentResponse, Encoding};
+use std::hint::black_box;
+
+// Images of the sizes agents produce, in bytes. JSON carries them as base64,
+// MessagePack as binary.
+const IMAGE_SIZES: &[usize] = &[64 * 1024, 1024 * 1024, 8 * 1024 * 1024];
+
+fn image_output(size: usize) -> AgentResponse {
+    let data = (0..size).map(|i| (i * 7) as u8).collect();
+    AgentResponse::ImageOutput {
+        id: "bench".to_string(),
+        data,
+        mime: "image/png".to_string(),
+        timestamp: 1_700_000_000_000,
+    }
+}
+
+fn token() -> AgentResponse {
+    AgentResponse::Token {
+        id: "bench".to_string(),
+        token: "Hello, \"world\"\n".to_string(),
+        timestamp: 1_700_000_000_000,
+    }
+}
+
+fn encoded(response: &AgentResponse) -> [(Encoding, Vec<u8>); 2] {
+    [
+        (Encoding::Json, serde_json::to_vec(response).unwrap()),
+        (
+            Encoding::MessagePack,
+            rmp_serde::to_vec_named(response).unwrap(),
+        ),
+    ]
+}
+
+fn decode_images(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_image_output");
+    for &size in IMAGE_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        for (encoding, message) in encoded(&image_output(size)) {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", encoding), size),
+                &message,
+                |b, message| {
+                    b.iter(|| {
+                        protocol::decode::<AgentResponse>(black_box(message), encoding).unwrap()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// Small messages dominate by count, so MessagePack mustn't make them slower
+fn decode_tokens(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_token");
+    for (encoding, message) in encoded(&token()) {
+        group.bench_function(format!("{:?}", encoding), |b| {
+            b.iter(|| protocol::decode::<AgentResponse>(black_box(&message), encoding).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode_images, decode_tokens);
+criterion_main!(benches);
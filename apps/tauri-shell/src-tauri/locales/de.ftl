@@ -0,0 +1,52 @@
+## Tray
+tray-show = Assistent anzeigen
+tray-pause-agent = Agent anhalten
+tray-resume-agent = Agent fortsetzen
+tray-quit = Beenden
+tray-snippets = Textbausteine
+tray-profiles = Profile
+tray-profile-default = Standard
+tray-agent-profiles = Agent
+tray-agent-profile-builtin = Integriert
+tray-tooltip = Desktop-Assistent
+tray-tooltip-unread = { $count ->
+    [one] Desktop-Assistent - 1 ungelesene Antwort
+   *[other] Desktop-Assistent - { $count } ungelesene Antworten
+}
+
+## App menu
+menu-zoom = Zoom
+menu-zoom-in = Vergrößern
+menu-zoom-out = Verkleinern
+menu-zoom-reset = Originalgröße
+
+## Dialogs
+dialog-open-executable-title = Programm öffnen?
+dialog-open-executable-body = „{ $path }“ ist ein Programm und wird mit Ihren Berechtigungen ausgeführt. Trotzdem öffnen?
+
+## Windows
+window-print-title = Drucken - { $title }
+
+## Screen reader announcements
+a11y-generation-started = Antwort wird erstellt
+a11y-generation-completed = Antwort fertig
+a11y-generation-failed = Antwort fehlgeschlagen: { $error }
+
+## Quick switcher actions
+action-new-conversation = Neue Unterhaltung
+action-clear-history = Verlauf löschen
+action-open-settings = Einstellungen
+action-check-updates = Nach Updates suchen
+action-print-conversation = Unterhaltung drucken
+
+## Relative times
+time-just-now = gerade eben
+time-minutes-ago = vor { $count } Min.
+time-hours-ago = { $count ->
+    [one] vor 1 Stunde
+   *[other] vor { $count } Stunden
+}
+time-days-ago = { $count ->
+    [one] gestern
+   *[other] vor { $count } Tagen
+}
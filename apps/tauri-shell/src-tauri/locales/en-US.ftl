@@ -0,0 +1,55 @@
+## Tray
+tray-show = Show Assistant
+tray-pause-agent = Pause Agent
+tray-resume-agent = Resume Agent
+tray-quit = Quit
+tray-snippets = Snippets
+tray-profiles = Profiles
+tray-profile-default = Default
+tray-agent-profiles = Agent
+tray-agent-profile-builtin = Built-in
+tray-tooltip = Desktop Assistant
+tray-tooltip-unread = { $count ->
+    [one] Desktop Assistant - 1 unread reply
+   *[other] Desktop Assistant - { $count } unread replies
+}
+
+## App menu
+menu-zoom = Zoom
+menu-zoom-in = Zoom In
+menu-zoom-out = Zoom Out
+menu-zoom-reset = Actual Size
+
+## Dialogs
+dialog-open-executable-title = Open executable?
+dialog-open-executable-body = "{ $path }" is a program and will run with your permissions. Open it anyway?
+
+## Windows
+window-print-title = Print - { $title }
+
+## Screen reader announcements
+a11y-generation-started = Generating response
+a11y-generation-completed = Response complete
+a11y-generation-failed = Response failed: { $error }
+
+## Quick switcher actions
+action-new-conversation = New Conversation
+action-clear-history = Clear History
+action-open-settings = Settings
+action-check-updates = Check for Updates
+action-print-conversation = Print Conversation
+
+## Relative times
+time-just-now = just now
+time-minutes-ago = { $count ->
+    [one] 1 min ago
+   *[other] { $count } min ago
+}
+time-hours-ago = { $count ->
+    [one] 1 hour ago
+   *[other] { $count } hours ago
+}
+time-days-ago = { $count ->
+    [one] yesterday
+   *[other] { $count } days ago
+}
@@ -0,0 +1,52 @@
+## Tray
+tray-show = Mostrar asistente
+tray-pause-agent = Pausar agente
+tray-resume-agent = Reanudar agente
+tray-quit = Salir
+tray-snippets = Plantillas
+tray-profiles = Perfiles
+tray-profile-default = Predeterminado
+tray-agent-profiles = Agente
+tray-agent-profile-builtin = Integrado
+tray-tooltip = Asistente de escritorio
+tray-tooltip-unread = { $count ->
+    [one] Asistente de escritorio - 1 respuesta sin leer
+   *[other] Asistente de escritorio - { $count } respuestas sin leer
+}
+
+## App menu
+menu-zoom = Zoom
+menu-zoom-in = Acercar
+menu-zoom-out = Alejar
+menu-zoom-reset = Tamaño real
+
+## Dialogs
+dialog-open-executable-title = ¿Abrir ejecutable?
+dialog-open-executable-body = «{ $path }» es un programa y se ejecutará con tus permisos. ¿Abrirlo de todos modos?
+
+## Windows
+window-print-title = Imprimir - { $title }
+
+## Screen reader announcements
+a11y-generation-started = Generando respuesta
+a11y-generation-completed = Respuesta completa
+a11y-generation-failed = La respuesta falló: { $error }
+
+## Quick switcher actions
+action-new-conversation = Nueva conversación
+action-clear-history = Borrar historial
+action-open-settings = Ajustes
+action-check-updates = Buscar actualizaciones
+action-print-conversation = Imprimir conversación
+
+## Relative times
+time-just-now = ahora mismo
+time-minutes-ago = hace { $count } min
+time-hours-ago = { $count ->
+    [one] hace 1 hora
+   *[other] hace { $count } horas
+}
+time-days-ago = { $count ->
+    [one] ayer
+   *[other] hace { $count } días
+}
@@ -0,0 +1,52 @@
+## Tray
+tray-show = Afficher l’assistant
+tray-pause-agent = Suspendre l’agent
+tray-resume-agent = Reprendre l’agent
+tray-quit = Quitter
+tray-snippets = Modèles
+tray-profiles = Profils
+tray-profile-default = Par défaut
+tray-agent-profiles = Agent
+tray-agent-profile-builtin = Intégré
+tray-tooltip = Assistant de bureau
+tray-tooltip-unread = { $count ->
+    [one] Assistant de bureau - 1 réponse non lue
+   *[other] Assistant de bureau - { $count } réponses non lues
+}
+
+## App menu
+menu-zoom = Zoom
+menu-zoom-in = Agrandir
+menu-zoom-out = Réduire
+menu-zoom-reset = Taille réelle
+
+## Dialogs
+dialog-open-executable-title = Ouvrir l’exécutable ?
+dialog-open-executable-body = « { $path } » est un programme et s’exécutera avec vos autorisations. L’ouvrir quand même ?
+
+## Windows
+window-print-title = Imprimer - { $title }
+
+## Screen reader announcements
+a11y-generation-started = Génération de la réponse
+a11y-generation-completed = Réponse terminée
+a11y-generation-failed = Échec de la réponse : { $error }
+
+## Quick switcher actions
+action-new-conversation = Nouvelle conversation
+action-clear-history = Effacer l’historique
+action-open-settings = Réglages
+action-check-updates = Rechercher des mises à jour
+action-print-conversation = Imprimer la conversation
+
+## Relative times
+time-just-now = à l’instant
+time-minutes-ago = il y a { $count } min
+time-hours-ago = { $count ->
+    [one] il y a 1 heure
+   *[other] il y a { $count } heures
+}
+time-days-ago = { $count ->
+    [one] hier
+   *[other] il y a { $count } jours
+}
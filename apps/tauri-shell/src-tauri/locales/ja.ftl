@@ -0,0 +1,46 @@
+## Tray
+tray-show = アシスタントを表示
+tray-pause-agent = エージェントを一時停止
+tray-resume-agent = エージェントを再開
+tray-quit = 終了
+tray-snippets = スニペット
+tray-profiles = プロファイル
+tray-profile-default = デフォルト
+tray-agent-profiles = エージェント
+tray-agent-profile-builtin = 内蔵
+tray-tooltip = デスクトップアシスタント
+tray-tooltip-unread = デスクトップアシスタント - 未読の返信 { $count } 件
+
+## App menu
+menu-zoom = ズーム
+menu-zoom-in = 拡大
+menu-zoom-out = 縮小
+menu-zoom-reset = 実際のサイズ
+
+## Dialogs
+dialog-open-executable-title = 実行ファイルを開きますか？
+dialog-open-executable-body = 「{ $path }」はプログラムで、あなたの権限で実行されます。開きますか？
+
+## Windows
+window-print-title = 印刷 - { $title }
+
+## Screen reader announcements
+a11y-generation-started = 応答を生成しています
+a11y-generation-completed = 応答が完了しました
+a11y-generation-failed = 応答に失敗しました: { $error }
+
+## Quick switcher actions
+action-new-conversation = 新しい会話
+action-clear-history = 履歴を消去
+action-open-settings = 設定
+action-check-updates = アップデートを確認
+action-print-conversation = 会話を印刷
+
+## Relative times
+time-just-now = たった今
+time-minutes-ago = { $count }分前
+time-hours-ago = { $count }時間前
+time-days-ago = { $count ->
+    [1] 昨日
+   *[other] { $count }日前
+}
@@ -0,0 +1,110 @@
+use crate::i18n;
+use tauri::AppHandle;
+
+pub enum Announcement<'a> {
+    Started,
+    Completed,
+    Failed(&'a str),
+}
+
+/// Posts a native screen-reader announcement (VoiceOver / Narrator / NVDA) so
+/// generation state changes are spoken without the user watching the window.
+pub fn announce(app_handle: &AppHandle, announcement: Announcement) {
+    let text = match announcement {
+        Announcement::Started => i18n::t("a11y-generation-started"),
+        Announcement::Completed => i18n::t("a11y-generation-completed"),
+        Announcement::Failed(error) => {
+            i18n::t_args("a11y-generation-failed", &[("error", error.into())])
+        }
+    };
+
+    let app_handle_clone = app_handle.clone();
+    let result = app_handle.run_on_main_thread(move || post(&app_handle_clone, &text));
+    if let Err(e) = result {
+        eprintln!("Failed to post accessibility announcement: {}", e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn post(_app_handle: &AppHandle, text: &str) {
+    use cocoa::appkit::NSApp;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSDictionary, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        static NSAccessibilityAnnouncementRequestedNotification: id;
+        static NSAccessibilityAnnouncementKey: id;
+        static NSAccessibilityPriorityKey: id;
+        fn NSAccessibilityPostNotificationWithUserInfo(
+            element: id,
+            notification: id,
+            user_info: id,
+        );
+    }
+
+    // NSAccessibilityPriorityHigh
+    const PRIORITY_HIGH: i64 = 90;
+
+    unsafe {
+        let message = NSString::alloc(nil).init_str(text);
+        let priority: id = msg_send![class!(NSNumber), numberWithInteger: PRIORITY_HIGH];
+
+        let keys = NSArray::arrayWithObjects(
+            nil,
+            &[NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey],
+        );
+        let values = NSArray::arrayWithObjects(nil, &[message, priority]);
+        let user_info = NSDictionary::dictionaryWithObjects_forKeys_(nil, values, keys);
+
+        NSAccessibilityPostNotificationWithUserInfo(
+            NSApp(),
+            NSAccessibilityAnnouncementRequestedNotification,
+            user_info,
+        );
+        let _: () = msg_send![message, release];
+    }
+}
+
+#[cfg(windows)]
+fn post(app_handle: &AppHandle, text: &str) {
+    use tauri::Manager;
+    use windows::Win32::Foundation::BSTR;
+    use windows::Win32::UI::Accessibility::{
+        NotificationKind_ActionCompleted, NotificationProcessing_ImportantMostRecent,
+        UiaHostProviderFromHwnd, UiaRaiseNotificationEvent,
+    };
+
+    let Some(window) = app_handle.get_window("main") else {
+        return;
+    };
+    let Ok(hwnd) = window.hwnd() else {
+        return;
+    };
+
+    unsafe {
+        let provider = match UiaHostProviderFromHwnd(hwnd) {
+            Ok(provider) => provider,
+            Err(e) => {
+                eprintln!("Failed to get UIA provider: {}", e);
+                return;
+            }
+        };
+
+        // Same activity id so a newer state replaces a queued older one
+        if let Err(e) = UiaRaiseNotificationEvent(
+            &provider,
+            NotificationKind_ActionCompleted,
+            NotificationProcessing_ImportantMostRecent,
+            &BSTR::from(text),
+            &BSTR::from("asst-generation-state"),
+        ) {
+            eprintln!("Failed to raise UIA notification: {}", e);
+        }
+    }
+}
+
+// AT-SPI has no announcement API; the webview's aria-live regions cover Linux
+#[cfg(not(any(target_os = "macos", windows)))]
+fn post(_app_handle: &AppHandle, _text: &str) {}
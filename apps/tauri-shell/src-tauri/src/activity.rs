@@ -0,0 +1,145 @@
+use crate::store::ConversationStore;
+use anyhow::Result;
+use chrono::{Days, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// Days ending today that get_activity covers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityPeriod {
+    Week,
+    Month,
+    Year,
+}
+
+impl ActivityPeriod {
+    fn days(self) -> u64 {
+        match self {
+            ActivityPeriod::Week => 7,
+            ActivityPeriod::Month => 30,
+            ActivityPeriod::Year => 365,
+        }
+    }
+}
+
+/// What happened on one local calendar day.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct DayActivity {
+    // YYYY-MM-DD
+    pub date: String,
+    pub user_messages: usize,
+    pub replies: usize,
+    // Conversations with a message that day
+    pub conversations: usize,
+    // Replies per model, for agents that report usage
+    pub models: BTreeMap<String, usize>,
+    // Calls per tool
+    pub tools: BTreeMap<String, usize>,
+}
+
+/// Every day of the period, oldest first, including days without activity.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Activity {
+    pub period: ActivityPeriod,
+    pub days: Vec<DayActivity>,
+}
+
+/// Per-day activity over `period`, read from the shell's transcripts.
+#[tauri::command]
+pub async fn get_activity(
+    app_handle: AppHandle,
+    period: ActivityPeriod,
+) -> Result<Activity, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        collect(&app_handle.state::<ConversationStore>(), period)
+    })
+    .await
+    .map_err(|e| format!("Activity task failed: {}", e))?
+    .map_err(|e| format!("Failed to read activity: {}", e))
+}
+
+fn collect(store: &ConversationStore, period: ActivityPeriod) -> Result<Activity> {
+    let today = Local::now().date_naive();
+    let first = today - Days::new(period.days() - 1);
+    let start = first
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.timestamp_millis())
+        .unwrap_or_default();
+
+    let mut days: BTreeMap<NaiveDate, (DayActivity, HashSet<String>)> = first
+        .iter_days()
+        .take_while(|date| *date <= today)
+        .map(|date| (date, Default::default()))
+        .collect();
+
+    let since = UNIX_EPOCH + Duration::from_millis(start.max(0) as u64);
+    for (conversation_id, modified) in store.list_modified()? {
+        // Untouched since the period began, so nothing in it is recent
+        if modified < since {
+            continue;
+        }
+        let conversation = match store.load(&conversation_id) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                eprintln!(
+                    "Skipping conversation {} in activity: {}",
+                    conversation_id, e
+                );
+                continue;
+            }
+        };
+
+        for message in conversation
+            .messages
+            .iter()
+            .filter(|m| m.timestamp >= start)
+        {
+            let Some(date) = Local
+                .timestamp_millis_opt(message.timestamp)
+                .single()
+                .map(|time| time.date_naive())
+            else {
+                continue;
+            };
+            let Some((day, conversations)) = days.get_mut(&date) else {
+                continue;
+            };
+
+            match message.role.as_str() {
+                "user" => day.user_messages += 1,
+                "assistant" => {
+                    day.replies += 1;
+                    if let Some(model) = &message.model {
+                        *day.models.entry(model.clone()).or_default() += 1;
+                    }
+                    for tool in &message.tools {
+                        *day.tools.entry(tool.clone()).or_default() += 1;
+                    }
+                }
+                // Dividers mark merges, not activity
+                _ => continue,
+            }
+            conversations.insert(conversation.id.clone());
+        }
+    }
+
+    Ok(Activity {
+        period,
+        days: days
+            .into_iter()
+            .map(|(date, (mut day, conversations))| {
+                day.date = date.format("%Y-%m-%d").to_string();
+                day.conversations = conversations.len();
+                day
+            })
+            .collect(),
+    })
+}
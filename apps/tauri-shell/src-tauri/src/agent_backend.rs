@@ -0,0 +1,288 @@
+use crate::agent_profiles::AgentProfile;
+use crate::agent_runtime::{self, AgentCommand};
+use crate::agent_updates;
+use crate::http_agent::HttpBackend;
+use crate::resource_limits::{self, ResourceLimits};
+use crate::settings::SettingsStore;
+use crate::transport::{self, Transport, TransportKind};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::process::{ExitStatus, Stdio};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Where requests to an agent are written.
+pub type AgentStdin = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Where an agent's responses are read from.
+pub type AgentStdout = Box<dyn AsyncRead + Send + Unpin>;
+
+/// The responses of each new connection of an agent that restarted on its own, for
+/// transports it can reconnect over.
+pub type Reconnects = Option<mpsc::Receiver<AgentStdout>>;
+
+/// How an agent is run and reached. AgentProcess speaks the JSON protocol over
+/// whatever start() returns, so supervision, replay and event routing work the same
+/// for every backend.
+pub trait AgentBackend: Send + Sync {
+    /// Short name for the logs, e.g. "node".
+    fn name(&self) -> &'static str;
+
+    /// Starts the agent. `env` holds the profile's variables and the agent secrets.
+    fn start(
+        &self,
+        app_handle: &AppHandle,
+        env: &HashMap<String, String>,
+    ) -> Result<AgentConnection>;
+}
+
+/// A started agent: its protocol streams and what to stop or wait on.
+pub struct AgentConnection {
+    pub stdin: AgentStdin,
+    pub stdout: AgentStdout,
+    pub reconnects: Reconnects,
+    // Diagnostics only; kept for error reports
+    pub stderr: Box<dyn AsyncRead + Send + Unpin>,
+    pub handle: AgentHandle,
+    // What the process was spawned under, none for in-process agents
+    pub limits: ResourceLimits,
+}
+
+/// The running agent: a child process, or a task for backends that run in the shell.
+pub enum AgentHandle {
+    Process(Child),
+    Task {
+        task: JoinHandle<()>,
+        exit: Option<AgentExit>,
+    },
+}
+
+/// How an agent ended.
+#[derive(Debug, Clone, Copy)]
+pub struct AgentExit {
+    pub code: Option<i32>,
+    // Unix signal that killed the process
+    pub signal: Option<i32>,
+}
+
+impl From<ExitStatus> for AgentExit {
+    fn from(status: ExitStatus) -> Self {
+        AgentExit {
+            code: status.code(),
+            signal: exit_signal(status),
+        }
+    }
+}
+
+impl AgentHandle {
+    pub fn task(task: JoinHandle<()>) -> Self {
+        AgentHandle::Task { task, exit: None }
+    }
+
+    /// The OS process id, None for in-process agents or once the process has exited.
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            AgentHandle::Process(child) => child.id(),
+            AgentHandle::Task { .. } => None,
+        }
+    }
+
+    pub async fn kill(&mut self) -> Result<()> {
+        match self {
+            AgentHandle::Process(child) => {
+                // Takes the tools it started along
+                #[cfg(unix)]
+                if let Some(pid) = child.id() {
+                    unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+                }
+                child.kill().await.context("Failed to kill agent process")
+            }
+            AgentHandle::Task { task, .. } => {
+                task.abort();
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn wait(&mut self) -> Result<AgentExit> {
+        match self {
+            AgentHandle::Process(child) => Ok(child.wait().await?.into()),
+            AgentHandle::Task { task, exit } => {
+                if let Some(exit) = exit {
+                    return Ok(*exit);
+                }
+                let result = task.await;
+                Ok(*exit.insert(task_exit(result.is_ok())))
+            }
+        }
+    }
+
+    pub fn try_wait(&mut self) -> Result<Option<AgentExit>> {
+        match self {
+            AgentHandle::Process(child) => Ok(child.try_wait()?.map(AgentExit::from)),
+            AgentHandle::Task { task, exit } => {
+                if exit.is_none() && task.is_finished() {
+                    // Aborted and panicked tasks are told apart only by wait()
+                    *exit = Some(task_exit(true));
+                }
+                Ok(*exit)
+            }
+        }
+    }
+}
+
+/// The backend `profile` selects: an HTTP endpoint, a custom command, or else the
+/// agent_command setting or the built-in Node agent.
+pub fn for_agent(app_handle: &AppHandle, profile: Option<&AgentProfile>) -> Box<dyn AgentBackend> {
+    if let Some(program) = std::env::var_os("ASST_AGENT_COMMAND") {
+        // Any executable speaking the protocol, e.g. the fake agent used by the tests
+        eprintln!("[DEBUG] Spawning agent override: {:?}", program);
+
+        return Box::new(StdioBackend::new(AgentCommand {
+            program: program.to_string_lossy().into_owned(),
+            args: Vec::new(),
+            cwd: None,
+            env: HashMap::new(),
+            transport: TransportKind::Stdio,
+        }));
+    }
+    if let Some(endpoint) = profile.and_then(|p| p.endpoint.clone()) {
+        return Box::new(HttpBackend::new(endpoint));
+    }
+    let custom = profile
+        .and_then(|p| p.command.clone())
+        .or_else(|| app_handle.state::<SettingsStore>().get().agent_command);
+    match custom {
+        Some(command) => Box::new(StdioBackend::new(command)),
+        None => Box::new(NodeBackend),
+    }
+}
+
+/// The built-in agent-runtime: a managed build if one is installed, otherwise the
+/// sidecar or the dev checkout as the agent_runtime setting says. Only the latter two
+/// are reached over the agent_transport setting; managed builds may predate it.
+pub struct NodeBackend;
+
+impl AgentBackend for NodeBackend {
+    fn name(&self) -> &'static str {
+        "node"
+    }
+
+    fn start(
+        &self,
+        app_handle: &AppHandle,
+        env: &HashMap<String, String>,
+    ) -> Result<AgentConnection> {
+        let (command, transport) = if let Some(bundle) = agent_updates::active_bundle(app_handle) {
+            // A managed agent build downloaded into the app data dir
+            eprintln!(
+                "[DEBUG] Spawning managed agent {} from: {:?}",
+                bundle.version, bundle.path
+            );
+
+            let mut command = Command::new("node");
+            command.arg(&bundle.path);
+            if let Some(dir) = bundle.path.parent() {
+                command.current_dir(dir);
+            }
+            (command, transport::for_kind(TransportKind::Stdio))
+        } else {
+            (
+                agent_runtime::command(app_handle)?,
+                transport::configured(app_handle),
+            )
+        };
+        spawn(app_handle, command, transport, env)
+    }
+}
+
+/// Any executable speaking the protocol, over the transport its command names.
+pub struct StdioBackend {
+    command: AgentCommand,
+}
+
+impl StdioBackend {
+    pub fn new(command: AgentCommand) -> Self {
+        StdioBackend { command }
+    }
+}
+
+impl AgentBackend for StdioBackend {
+    fn name(&self) -> &'static str {
+        "stdio"
+    }
+
+    fn start(
+        &self,
+        app_handle: &AppHandle,
+        env: &HashMap<String, String>,
+    ) -> Result<AgentConnection> {
+        spawn(
+            app_handle,
+            agent_runtime::build_command(&self.command),
+            transport::for_kind(self.command.transport),
+            env,
+        )
+    }
+}
+
+fn spawn(
+    app_handle: &AppHandle,
+    mut command: Command,
+    mut transport: Box<dyn Transport>,
+    env: &HashMap<String, String>,
+) -> Result<AgentConnection> {
+    // Values go only into the child's environment, never into the logs
+    command.envs(env);
+    // A process group of its own, so pausing or killing it reaches its tools too
+    #[cfg(unix)]
+    command.process_group(0);
+    let limits = resource_limits::configured(app_handle);
+    resource_limits::apply(&mut command, &limits);
+
+    transport.prepare(&mut command)?;
+
+    let program = command.as_std().get_program().to_owned();
+    let mut child = command
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to spawn agent process {:?}", program))?;
+    if let Err(e) = resource_limits::attach(&child, &limits) {
+        // Running unlimited beats not running
+        eprintln!("[LIMITS] {}", e);
+    }
+
+    let stderr = child.stderr.take().context("Failed to get stderr")?;
+    let (stdin, stdout, reconnects) = transport.connect(&mut child)?;
+    eprintln!("[DEBUG] Agent reached over {}", transport.name());
+    Ok(AgentConnection {
+        stdin,
+        stdout,
+        reconnects,
+        stderr: Box::new(stderr),
+        handle: AgentHandle::Process(child),
+        limits,
+    })
+}
+
+// An in-process agent that returned on its own shut down cleanly
+fn task_exit(finished: bool) -> AgentExit {
+    AgentExit {
+        code: finished.then_some(0),
+        signal: None,
+    }
+}
+
+#[cfg(unix)]
+fn exit_signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
@@ -1,117 +1,2697 @@
-use anyhow::{Context, Result};
+use crate::accessibility::{self, Announcement};
+use crate::agent_backend::{self, AgentConnection, AgentHandle, AgentStdin};
+use crate::agent_logs::SessionLog;
+use crate::agent_profiles;
+use crate::annotate::ImageAttachment;
+use crate::budget;
+use crate::checkpoints;
+use crate::connectivity;
+use crate::crash_reports::{self, CrashReport, InFlightRequest};
+use crate::event_queue::{EventQueue, EventQueueStats, QueuedEvent};
+use crate::feedback::{self, Cue};
+use crate::folder_watch;
+use crate::generated_images;
+use crate::heartbeat;
+use crate::network_config;
+use crate::outbox;
+use crate::pacing::{self, MAX_RATE_LIMIT_RETRIES};
+use crate::protocol::{
+    self, Capabilities, Encoding, Framing, InvalidResponse, HELLO_PROTOCOL_VERSION,
+    MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+pub use crate::protocol::{AgentRequest, AgentResponse};
+use crate::resource_limits::{self, ResourceLimits};
+use crate::secrets;
+use crate::settings::SettingsStore;
+use crate::spill::{self, Spill};
+use crate::stall;
+use crate::standby;
+use crate::store::{self, ConversationStore, StoredMessage, DEFAULT_CONVERSATION_ID};
+use crate::suspend;
+use crate::taskbar;
+use crate::unread;
+use crate::watchdog;
+use crate::window_title;
+use crate::{AgentSlot, AppState};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Stdio;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, Command};
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, watch, Mutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use ts_rs::TS;
 
+// How long a spawned agent gets to print Ready before it counts as failed to start
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+// Everything the shell can read, offered in hello. File attachments and tool approval
+// stay off until the shell handles them.
+const OFFERED_CAPABILITIES: Capabilities = Capabilities {
+    framing: Framing::LengthPrefixed,
+    encoding: Encoding::MessagePack,
+    compression: true,
+    attachments: false,
+    tool_approval: false,
+};
+// Restart backoff after a crash: 1s, 2s, 4s, ... capped, giving up after MAX_RESTARTS
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_RESTARTS: u32 = 5;
+// How long a shutdown request gets before the process is killed
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+// A process that stayed up this long resets the restart count
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+// Lines of agent stderr kept to explain crashes and failed spawns
+const STDERR_TAIL_LINES: usize = 50;
+// Lines of agent stdout kept for crash reports
+const STDOUT_TAIL_LINES: usize = 50;
+// Characters of an invalid message quoted in agent_protocol_error
+const PROTOCOL_ERROR_EXCERPT_CHARS: usize = 500;
+// How long send_and_wait waits for the Done/Error of its request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+// How often request deadlines are checked
+const TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+static NEXT_SERIAL: AtomicU64 = AtomicU64::new(1);
+
+/// The agent the shell starts on its own; the outbox, warm standby and one-shot
+/// requests like text transforms all go to it.
+pub const DEFAULT_AGENT_ID: &str = "default";
+
+// A request that has been written to the agent but not yet answered with Done/Error
+struct PendingRequest {
+    request: AgentRequest,
+    // Label of the window that issued the request; its stream is routed only there
+    owner: Option<String>,
+    rate_limit_retries: u32,
+    stall_retries: u32,
+    // Last output for this request; the stall watchdog measures from here
+    last_activity: Instant,
+    // Streamed tokens accumulated so the reply can be recorded in the store on Done
+    response: String,
+    // Set once the reply outgrew spill::SPILL_THRESHOLD and is mirrored to disk
+    spill: Option<Spill>,
+    // While paused: length of `response` the webview had received; the rest is held back
+    paused_at: Option<usize>,
+    state: MessageState,
+    // Images from image_output, stored with the reply on Done
+    images: Vec<ImageAttachment>,
+    // How long it may go without output once sent before failing with a timeout error
+    idle_timeout: Option<Duration>,
+    // Names of the tools called for the reply, stored with it on Done
+    tools: Vec<String>,
+    // Set when retried after stalling: output of the interrupted generation, which
+    // shares the id, is dropped up to its Done or error
+    superseded: bool,
+}
+
+// A one-shot request whose reply goes back to the caller instead of a window
+struct Completion {
+    text: String,
+    done: oneshot::Sender<Result<String>>,
+}
+
+type Completions = Arc<Mutex<HashMap<String, Completion>>>;
+
+// Callers of send_and_wait by request id, handed the data of its Done or its error
+type Replies = Arc<Mutex<HashMap<String, ReplySender>>>;
+type ReplySender = oneshot::Sender<Result<Option<serde_json::Value>>>;
+
+// A request other than a user message awaiting its Done/Error
+struct Deadline {
+    timeout: Duration,
+    at: Instant,
+}
+
+type Deadlines = Arc<Mutex<HashMap<String, Deadline>>>;
+
+// What the timeout watchdog needs of a process, held weakly so it ends with it
+struct Tracked {
+    stdin: Weak<Mutex<AgentStdin>>,
+    pending: Weak<Mutex<HashMap<String, PendingRequest>>>,
+    deadlines: Weak<Mutex<HashMap<String, Deadline>>>,
+    replies: Weak<Mutex<HashMap<String, ReplySender>>>,
+    completions: Weak<Mutex<HashMap<String, Completion>>>,
+    events: Weak<EventQueue>,
+}
+
+impl PendingRequest {
+    fn new(request: AgentRequest, owner: Option<String>, idle_timeout: Option<Duration>) -> Self {
+        PendingRequest {
+            request,
+            owner,
+            rate_limit_retries: 0,
+            stall_retries: 0,
+            last_activity: Instant::now(),
+            response: String::new(),
+            spill: None,
+            paused_at: None,
+            state: MessageState::Queued,
+            images: Vec::new(),
+            idle_timeout,
+            tools: Vec::new(),
+            superseded: false,
+        }
+    }
+
+    fn conversation_id(&self) -> &str {
+        match &self.request {
+            AgentRequest::UserMessage {
+                conversation_id: Some(conversation_id),
+                ..
+            } => conversation_id,
+            _ => DEFAULT_CONVERSATION_ID,
+        }
+    }
+
+    // Starts the reply over for a retry under the same id
+    fn restart(&mut self) {
+        self.stall_retries += 1;
+        // The reply keeps the id, so it pairs with the user message in the store
+        self.superseded = true;
+        self.response.clear();
+        self.spill = None;
+        // A paused stream stays paused, holding back the new reply from its start
+        self.paused_at = self.paused_at.map(|_| 0);
+        self.last_activity = Instant::now();
+        self.state = MessageState::Sent;
+    }
+
+    // Whether `response` still comes from the generation a retry replaced; its Done
+    // or error is the last of it
+    fn is_stale(&mut self, response: &AgentResponse) -> bool {
+        if !self.superseded {
+            return false;
+        }
+        self.superseded = !matches!(
+            response,
+            AgentResponse::Done { .. } | AgentResponse::Error { .. }
+        );
+        true
+    }
+
+    // Moves forward to `state` and reports it; later states are never undone
+    fn advance(&mut self, app_handle: &AppHandle, agent_id: &str, state: MessageState) {
+        if state <= self.state {
+            return;
+        }
+        self.state = state;
+        emit_message_state(
+            app_handle,
+            agent_id,
+            self.owner.as_deref(),
+            self.request.id(),
+            state,
+            None,
+        );
+    }
+}
+
+/// Delivery state of a user message, emitted as message_state on each transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageState {
+    // Waiting in the outbox for the agent
+    Queued,
+    // Written to the agent's stdin
+    Sent,
+    // The agent confirmed receipt
+    Acked,
+    // Reply tokens are arriving
+    Streaming,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct MessageStateChange<'a> {
+    agent_id: &'a str,
+    id: &'a str,
+    state: MessageState,
+    // Why it failed, for Failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+    #[ts(as = "f64")]
+    timestamp: i64,
+}
+
+// agent_response payload: the response tagged with the agent that produced it
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct RoutedResponse<'a> {
+    agent_id: &'a str,
+    #[serde(flatten)]
+    response: &'a AgentResponse,
+}
+
+/// What send_request does when it finds the agent's stdin closed. Read on every send.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum AgentResponse {
-    Ready { timestamp: i64 },
-    Token { id: String, token: String, timestamp: i64 },
-    ToolUse { id: String, data: serde_json::Value, timestamp: i64 },
-    ToolResult { id: String, data: serde_json::Value, timestamp: i64 },
-    Done { id: String, timestamp: i64 },
-    Error { id: String, error: String, timestamp: i64 },
+#[serde(default)]
+pub struct RecoverySettings {
+    // Respawn the agent and resend the request; otherwise the send just fails
+    pub enabled: bool,
+    // Respawns tried for one request before giving up
+    pub max_replays: u32,
 }
 
+impl Default for RecoverySettings {
+    fn default() -> Self {
+        RecoverySettings {
+            enabled: true,
+            max_replays: 1,
+        }
+    }
+}
+
+/// How long the agent has to answer a request before it is failed with a "timeout"
+/// error. User messages are timed from their last output instead, as long replies
+/// stream for a while. Read on every send.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AgentRequest {
-    pub id: String,
-    pub kind: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub message: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub images: Option<String>, // JSON string of image attachments
+#[serde(default)]
+pub struct RequestTimeouts {
+    pub enabled: bool,
+    pub default_secs: u64,
+    // Overrides by request kind, e.g. "user_message"; 0 means no limit
+    pub kinds: HashMap<String, u64>,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        RequestTimeouts {
+            enabled: true,
+            default_secs: 60,
+            kinds: HashMap::from([
+                ("user_message".to_string(), 600),
+                ("summarize".to_string(), 300),
+                ("transform".to_string(), 120),
+            ]),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    // How long `request` may take, None if unlimited
+    fn timeout(&self, request: &AgentRequest) -> Option<Duration> {
+        // Interrupts end their target instead of being answered; pings have the heartbeat
+        // and hello the ready timeout
+        if !self.enabled
+            || matches!(
+                request,
+                AgentRequest::Interrupt { .. }
+                    | AgentRequest::Ping { .. }
+                    | AgentRequest::Shutdown { .. }
+                    | AgentRequest::Hello { .. }
+            )
+        {
+            return None;
+        }
+        let secs = self
+            .kinds
+            .get(request.kind())
+            .copied()
+            .unwrap_or(self.default_secs);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentRecovered {
+    request_id: String,
+    conversation_id: Option<String>,
+    // In-flight requests that died with the old process and were failed
+    lost_requests: Vec<String>,
+    // Whether a warm standby took over instead of a freshly spawned process
+    from_standby: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentExited {
+    agent_id: String,
+    // For get_agent_session_logs
+    session_id: String,
+    exit_code: Option<i32>,
+    // Unix signal that terminated the process, e.g. 9 after an OOM kill
+    signal: Option<i32>,
+    stderr_tail: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnStage {
+    // The command couldn't be built or started
+    Launch,
+    // The process exited before printing Ready
+    Exited,
+    // Still running but silent after READY_TIMEOUT
+    Timeout,
+    // Announced a protocol version outside the supported range
+    Incompatible,
+}
+
+/// Emitted as agent_incompatible when an agent's Ready announces a protocol version
+/// this shell can't speak.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AgentIncompatible {
+    pub agent_id: String,
+    pub protocol_version: u32,
+    pub agent_version: Option<String>,
+    pub min_protocol_version: u32,
+    pub max_protocol_version: u32,
+    pub shell_version: String,
+}
+
+/// Emitted as agent_protocol_error for each agent message that isn't a valid response.
+/// The message itself is dropped.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AgentProtocolError {
+    pub agent_id: String,
+    #[serde(flatten)]
+    pub invalid: InvalidResponse,
+    // Start of the message, as JSON
+    pub excerpt: String,
+}
+
+/// Why an agent failed to start, emitted as agent_spawn_failed and returned from
+/// AgentProcess::spawn.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SpawnFailure {
+    pub agent_id: String,
+    pub stage: SpawnStage,
+    pub message: String,
+    // Best guesses at what to fix, most specific first
+    pub probable_causes: Vec<String>,
+    pub stderr_tail: Vec<String>,
+}
+
+impl std::fmt::Display for SpawnFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        for cause in &self.probable_causes {
+            write!(f, "\n- {}", cause)?;
+        }
+        if !self.stderr_tail.is_empty() {
+            write!(f, "\n\nLast agent output:\n{}", self.stderr_tail.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SpawnFailure {}
+
+impl SpawnFailure {
+    fn new(agent_id: &str, stage: SpawnStage, message: String, stderr_tail: Vec<String>) -> Self {
+        SpawnFailure {
+            agent_id: agent_id.to_string(),
+            stage,
+            message,
+            probable_causes: probable_causes(stage, &stderr_tail),
+            stderr_tail,
+        }
+    }
+
+    fn launch(agent_id: &str, error: &anyhow::Error) -> Self {
+        let mut failure = Self::new(
+            agent_id,
+            SpawnStage::Launch,
+            format!("{:#}", error),
+            Vec::new(),
+        );
+        let not_found = error.chain().any(|cause| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+        });
+        if not_found {
+            failure.probable_causes.insert(
+                0,
+                "Node.js is not installed, or not on the PATH the app was started with".to_string(),
+            );
+        }
+        failure
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentCrashed {
+    agent_id: String,
+    session_id: String,
+    exit_code: Option<i32>,
+    lost_requests: Vec<String>,
+    // Last lines the agent wrote to stderr, usually the reason it died
+    stderr_tail: Vec<String>,
+    // Restarts since the agent last stayed up for STABLE_UPTIME
+    restarts: u32,
+    will_restart: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentLifecycle<'a> {
+    agent_id: &'a str,
+    // In-flight requests failed because their process went away
+    lost_requests: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentRestarted {
+    agent_id: String,
+    attempt: u32,
+    from_standby: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentState {
+    Stopped,
+    // Spawned but hasn't printed Ready yet
+    Starting,
+    Ready,
+    // At least one request in flight
+    Busy,
+    // Exited on its own; the supervisor may be restarting it
+    Crashed,
+    // Frozen by pause_agent until resume_agent
+    Suspended,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AgentStatus {
+    pub state: AgentState,
+    pub pid: Option<u32>,
+    #[ts(as = "Option<f64>")]
+    pub spawned_at: Option<i64>,
+    #[ts(as = "Option<f64>")]
+    pub uptime_ms: Option<u64>,
+    // Last line the agent wrote to stdout
+    #[ts(as = "Option<f64>")]
+    pub last_message_at: Option<i64>,
+    pub in_flight: usize,
+    // Session whose output is being logged, see agent_logs
+    pub session_id: Option<String>,
+}
+
+impl AgentStatus {
+    pub fn stopped() -> Self {
+        AgentStatus {
+            state: AgentState::Stopped,
+            pid: None,
+            spawned_at: None,
+            uptime_ms: None,
+            last_message_at: None,
+            in_flight: 0,
+            session_id: None,
+        }
+    }
 }
 
 pub struct AgentProcess {
-    #[allow(dead_code)]
-    child: Child,
-    stdin: Arc<Mutex<ChildStdin>>,
+    app_handle: AppHandle,
+    // Key of the AppState slot this process runs in
+    agent_id: String,
+    // Tells the supervisor whether the process that exited is still the active one
+    serial: u64,
+    started_at: Instant,
+    spawned_at: i64,
+    // Epoch millis, 0 until the first line arrives
+    last_message_at: Arc<AtomicI64>,
+    restarts: u32,
+    handle: AgentHandle,
+    stdin: Arc<Mutex<AgentStdin>>,
+    pending: Arc<Mutex<HashMap<String, PendingRequest>>>,
+    completions: Completions,
+    replies: Replies,
+    deadlines: Deadlines,
+    stderr_tail: Arc<std::sync::Mutex<VecDeque<String>>>,
+    stdout_tail: Arc<std::sync::Mutex<VecDeque<String>>>,
+    // Finishes once stderr is closed, i.e. the tail is complete
+    stderr_reader: Option<JoinHandle<()>>,
+    // Limits the process was spawned under, for explaining its exit
+    limits: ResourceLimits,
+    log: SessionLog,
+    ready: watch::Receiver<bool>,
+    // Set instead of ready when the handshake fails
+    incompatible: Arc<std::sync::Mutex<Option<AgentIncompatible>>>,
+    // Agreed in hello; none for agents older than protocol 3
+    capabilities: Arc<std::sync::Mutex<Capabilities>>,
+    // What the stdout reader emits goes through here
+    events: Arc<EventQueue>,
+    // Last conversation loaded into the agent, restored after a respawn
+    active_conversation: Option<String>,
+    // Stopped by pause_agent; heartbeat, watchdog and stall checks hold off meanwhile
+    suspended: Arc<AtomicBool>,
 }
 
 impl AgentProcess {
-    pub async fn spawn(app_handle: AppHandle) -> Result<Self> {
-        // Get the path to the agent runtime
-        let agent_path = std::env::current_dir()
-            .context("Failed to get current directory")?
-            .join("../../agent-runtime");
-
-        eprintln!("[DEBUG] Spawning agent process from: {:?}", agent_path);
-
-        // Spawn the agent runtime process
-        let mut child = Command::new("npx")
-            .arg("tsx")
-            .arg("src/index.ts")
-            .current_dir(&agent_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to spawn agent process")?;
-
-        let stdout = child.stdout.take().context("Failed to get stdout")?;
-        let stderr = child.stderr.take().context("Failed to get stderr")?;
-        let stdin = child.stdin.take().context("Failed to get stdin")?;
+    /// Starts the agent and waits for its Ready message. If it doesn't arrive, the
+    /// process is killed, agent_spawn_failed is emitted and the error is a SpawnFailure.
+    pub async fn spawn(app_handle: AppHandle, agent_id: &str) -> Result<Self> {
+        let failure = match Self::launch(app_handle.clone(), agent_id).await {
+            Ok(process) => {
+                if process.wait_ready(READY_TIMEOUT).await {
+                    return Ok(process);
+                }
+                process.startup_failure().await
+            }
+            Err(e) => SpawnFailure::launch(agent_id, &e),
+        };
+
+        eprintln!("[AGENT] Failed to start {}: {}", agent_id, failure.message);
+        if let Err(e) = app_handle.emit_all("agent_spawn_failed", &failure) {
+            eprintln!("Failed to emit agent_spawn_failed: {}", e);
+        }
+        Err(failure.into())
+    }
+
+    async fn launch(app_handle: AppHandle, agent_id: &str) -> Result<Self> {
+        let profile = agent_profiles::profile_for(&app_handle, agent_id);
+        let backend = agent_backend::for_agent(&app_handle, profile.as_ref());
+        eprintln!(
+            "[DEBUG] Agent {} uses the {} backend",
+            agent_id,
+            backend.name()
+        );
+
+        let mut env = HashMap::new();
+        if let Some(profile) = &profile {
+            eprintln!("[DEBUG] Agent {} runs profile {}", agent_id, profile.id);
+            env.extend(secrets::profile_env(profile));
+            if let Some(model) = &profile.default_model {
+                env.insert("ANTHROPIC_MODEL".to_string(), model.clone());
+            }
+        }
+        env.extend(secrets::agent_env(&app_handle));
+        env.extend(network_config::agent_env());
+        // Read by the project tools; the file appears once a folder is watched
+        if let Some(index) = folder_watch::index_path(&app_handle) {
+            env.insert(
+                "ASST_FOLDER_INDEX".to_string(),
+                index.to_string_lossy().into_owned(),
+            );
+        }
+
+        let AgentConnection {
+            stdin,
+            stdout,
+            mut reconnects,
+            stderr,
+            handle,
+            limits,
+        } = backend.start(&app_handle, &env)?;
+
+        let stdin = Arc::new(Mutex::new(stdin));
+        let pending: Arc<Mutex<HashMap<String, PendingRequest>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+        let (ready_tx, ready_rx) = watch::channel(false);
+        let serial = NEXT_SERIAL.fetch_add(1, Ordering::SeqCst);
+        let last_message_at = Arc::new(AtomicI64::new(0));
+        let suspended = Arc::new(AtomicBool::new(false));
+        let (pong_tx, pong_rx) = watch::channel(String::new());
+        let (missed_tx, missed_rx) = watch::channel(false);
+        let completions: Completions = Arc::new(Mutex::new(HashMap::new()));
+        let replies: Replies = Arc::new(Mutex::new(HashMap::new()));
+        let deadlines: Deadlines = Arc::new(Mutex::new(HashMap::new()));
+        let incompatible = Arc::new(std::sync::Mutex::new(None));
+        let capabilities = Arc::new(std::sync::Mutex::new(Capabilities::default()));
+        let events = EventQueue::start(app_handle.clone(), agent_id.to_string());
+        let log = SessionLog::start(&app_handle, agent_id, serial);
+        let stdout_tail = Arc::new(std::sync::Mutex::new(VecDeque::new()));
 
         // Spawn task to read stdout and emit events
         let app_handle_clone = app_handle.clone();
+        let agent_id_clone = agent_id.to_string();
+        let stdin_clone = stdin.clone();
+        let pending_clone = pending.clone();
+        let last_message_at_clone = last_message_at.clone();
+        let completions_clone = completions.clone();
+        let replies_clone = replies.clone();
+        let deadlines_clone = deadlines.clone();
+        let incompatible_clone = incompatible.clone();
+        let capabilities_clone = capabilities.clone();
+        let events_clone = events.clone();
+        let log_clone = log.clone();
+        let stdout_tail_clone = stdout_tail.clone();
         tokio::spawn(async move {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
+            let mut reader = BufReader::new(stdout);
+            let mut agreed = Capabilities::default();
 
-            while let Ok(Some(line)) = lines.next_line().await {
+            loop {
+                let message = match protocol::read_message(&mut reader, &agreed).await {
+                    Ok(Some(message)) => message,
+                    read => {
+                        // A frame that can't be read leaves no way to find the next one;
+                        // the webview hears why the stream ended
+                        if let Err(e) = read {
+                            eprintln!("Failed to read agent output: {}", e);
+                            let error = AgentProtocolError {
+                                agent_id: agent_id_clone.clone(),
+                                invalid: InvalidResponse {
+                                    error: format!("Unreadable {:?} frame: {}", agreed.framing, e),
+                                    ..Default::default()
+                                },
+                                excerpt: String::new(),
+                            };
+                            events_clone.push(QueuedEvent::ProtocolError(error)).await;
+                        }
+                        let next = match reconnects.as_mut() {
+                            Some(reconnects) => reconnects.recv().await,
+                            None => None,
+                        };
+                        let Some(next) = next else { break };
+                        // An agent that restarted on its own starts over from Ready,
+                        // and knows nothing of what its predecessor was working on
+                        reader = BufReader::new(next);
+                        agreed = Capabilities::default();
+                        *capabilities_clone.lock().unwrap() = Capabilities::default();
+                        let lost: HashMap<_, _> = pending_clone.lock().await.drain().collect();
+                        fail_pending(
+                            &app_handle_clone,
+                            &agent_id_clone,
+                            &events_clone,
+                            &lost,
+                            "Agent restarted",
+                            "agent_restarted",
+                        );
+                        continue;
+                    }
+                };
+                let decoded = protocol::decode::<AgentResponse>(&message, agreed.encoding);
+                // Logs and crash reports show JSON whatever the agent sent
+                let line = match (agreed.encoding, &decoded) {
+                    (Encoding::Json, _) => String::from_utf8_lossy(&message).into_owned(),
+                    (Encoding::MessagePack, Ok(response)) => {
+                        serde_json::to_string(response).unwrap_or_default()
+                    }
+                    (Encoding::MessagePack, Err(_)) => {
+                        format!("<{} bytes of MessagePack>", message.len())
+                    }
+                };
                 eprintln!("[AGENT STDOUT] {}", line);
+                log_clone.line("stdout", &line);
+                push_tail(&stdout_tail_clone, &line, STDOUT_TAIL_LINES);
+                last_message_at_clone.store(store::now_millis(), Ordering::Relaxed);
 
-                match serde_json::from_str::<AgentResponse>(&line) {
+                match decoded {
                     Ok(response) => {
-                        if let Err(e) = app_handle_clone.emit_all("agent_response", &response) {
-                            eprintln!("Failed to emit agent response: {}", e);
+                        if let AgentResponse::Ready {
+                            protocol_version,
+                            agent_version,
+                            ..
+                        } = &response
+                        {
+                            // Stop reading rather than misparse what a mismatched agent says
+                            if let Err(version) = protocol::supported_protocol(*protocol_version) {
+                                let incompatible = AgentIncompatible {
+                                    agent_id: agent_id_clone.clone(),
+                                    protocol_version: version,
+                                    agent_version: agent_version.clone(),
+                                    min_protocol_version: MIN_PROTOCOL_VERSION,
+                                    max_protocol_version: PROTOCOL_VERSION,
+                                    shell_version: env!("CARGO_PKG_VERSION").to_string(),
+                                };
+                                if let Err(e) =
+                                    app_handle_clone.emit_all("agent_incompatible", &incompatible)
+                                {
+                                    eprintln!("Failed to emit agent_incompatible: {}", e);
+                                }
+                                *incompatible_clone.lock().unwrap() = Some(incompatible);
+                                break;
+                            }
+                            if protocol_version.is_some_and(|v| v >= HELLO_PROTOCOL_VERSION) {
+                                // Ready once the agent has answered
+                                let hello = AgentRequest::Hello {
+                                    id: uuid::Uuid::new_v4().to_string(),
+                                    protocol_version: PROTOCOL_VERSION,
+                                    capabilities: OFFERED_CAPABILITIES,
+                                };
+                                if let Err(e) = write_request(&stdin_clone, &hello).await {
+                                    eprintln!("Failed to send hello: {}", e);
+                                }
+                            } else {
+                                let _ = ready_tx.send(true);
+                            }
+                        }
+                        // Everything after the answer is written as it agreed
+                        if let AgentResponse::Hello { capabilities, .. } = response {
+                            // Stop reading rather than misparse what comes in another format
+                            if !capabilities.within(&OFFERED_CAPABILITIES) {
+                                eprintln!(
+                                    "Agent agreed to capabilities that weren't offered: {:?}",
+                                    capabilities
+                                );
+                                break;
+                            }
+                            eprintln!("[AGENT] Agreed capabilities: {:?}", capabilities);
+                            agreed = capabilities.clone();
+                            *capabilities_clone.lock().unwrap() = capabilities;
+                            let _ = ready_tx.send(true);
+                            continue;
+                        }
+                        // Heartbeats concern only the shell
+                        if let AgentResponse::Pong { id, .. } = response {
+                            let _ = pong_tx.send(id);
+                            continue;
+                        }
+                        // Receipts reach the webview as message_state only
+                        if let AgentResponse::Ack { id, .. } = &response {
+                            if let Some(entry) = pending_clone.lock().await.get_mut(id) {
+                                entry.last_activity = Instant::now();
+                                entry.advance(
+                                    &app_handle_clone,
+                                    &agent_id_clone,
+                                    MessageState::Acked,
+                                );
+                            }
+                            continue;
+                        }
+                        if let Some(id) = response.id() {
+                            let mut pending = pending_clone.lock().await;
+                            if let Some(entry) = pending.get_mut(id) {
+                                if entry.is_stale(&response) {
+                                    continue;
+                                }
+                            }
+                        }
+                        // Images are saved here and reach the webview as image_generated
+                        if let AgentResponse::ImageOutput { id, data, mime, .. } = &response {
+                            on_image_output(
+                                &app_handle_clone,
+                                &agent_id_clone,
+                                &pending_clone,
+                                id,
+                                data,
+                                mime,
+                            )
+                            .await;
+                            continue;
+                        }
+                        if let AgentResponse::Done { id, .. } | AgentResponse::Error { id, .. } =
+                            &response
+                        {
+                            deadlines_clone.lock().await.remove(id);
+                        }
+                        if collect_completion(&app_handle_clone, &completions_clone, &response)
+                            .await
+                        {
+                            continue;
                         }
+                        // Still broadcast below, for windows that listen for it
+                        resolve_reply(&replies_clone, &response).await;
+
+                        let mut pending = pending_clone.lock().await;
+
+                        if let AgentResponse::Error {
+                            id,
+                            code: Some(code),
+                            retry_after_ms,
+                            ..
+                        } = &response
+                        {
+                            if code == "rate_limited" {
+                                if let Some(entry) = pending.get_mut(id) {
+                                    if entry.rate_limit_retries < MAX_RATE_LIMIT_RETRIES {
+                                        entry.rate_limit_retries += 1;
+                                        // Quiet until resent, which isn't the agent's doing
+                                        entry.last_activity =
+                                            Instant::now() + pacing::retry_delay(*retry_after_ms);
+                                        pacing::schedule_retry(
+                                            app_handle_clone.clone(),
+                                            stdin_clone.clone(),
+                                            entry.request.clone(),
+                                            entry.owner.clone(),
+                                            entry.rate_limit_retries,
+                                            *retry_after_ms,
+                                        );
+                                        continue;
+                                    }
+                                }
+                            }
+                        }
+
+                        // Route request-scoped events to the window that issued the request
+                        let owner = response
+                            .id()
+                            .and_then(|id| pending.get(id))
+                            .and_then(|entry| entry.owner.clone());
+
+                        // Tokens of spilled replies reach the webview as response_window instead
+                        let mut forward = true;
+                        match &response {
+                            AgentResponse::Token { id, token, .. } => {
+                                if let Some(entry) = pending.get_mut(id) {
+                                    entry.response.push_str(token);
+                                    entry.last_activity = Instant::now();
+                                    entry.advance(
+                                        &app_handle_clone,
+                                        &agent_id_clone,
+                                        MessageState::Streaming,
+                                    );
+                                    forward = entry.paused_at.is_none()
+                                        && !spill::on_token(
+                                            &app_handle_clone,
+                                            owner.as_deref(),
+                                            id,
+                                            &entry.response,
+                                            &mut entry.spill,
+                                        );
+                                }
+                            }
+                            AgentResponse::ToolUse { id, data, .. } => {
+                                if let Some(entry) = pending.get_mut(id) {
+                                    entry.last_activity = Instant::now();
+                                    if let Some(tool) = data["tool_name"].as_str() {
+                                        entry.tools.push(tool.to_string());
+                                    }
+                                }
+                            }
+                            AgentResponse::ToolResult { id, .. } => {
+                                if let Some(entry) = pending.get_mut(id) {
+                                    entry.last_activity = Instant::now();
+                                }
+                            }
+                            AgentResponse::Done {
+                                id,
+                                usage,
+                                timestamp,
+                                ..
+                            } => {
+                                if let Some(usage) = usage {
+                                    budget::record(&app_handle_clone, usage);
+                                }
+                                if let Some(mut entry) = pending.remove(id) {
+                                    catch_up(&app_handle_clone, &events_clone, id, &mut entry);
+                                    if let Some(spill) = entry.spill.as_mut() {
+                                        spill::finish(
+                                            &app_handle_clone,
+                                            owner.as_deref(),
+                                            id,
+                                            &entry.response,
+                                            spill,
+                                        );
+                                    }
+                                    let store = app_handle_clone.state::<ConversationStore>();
+                                    let message = StoredMessage {
+                                        id: id.clone(),
+                                        role: "assistant".to_string(),
+                                        content: entry.response.clone(),
+                                        timestamp: *timestamp,
+                                        images: stored_images(&entry.images),
+                                        model: usage.as_ref().map(|usage| usage.model.clone()),
+                                        tools: std::mem::take(&mut entry.tools),
+                                    };
+                                    if let Err(e) =
+                                        store.append_message(entry.conversation_id(), message)
+                                    {
+                                        eprintln!("Failed to record reply {}: {}", id, e);
+                                    }
+
+                                    unread::record_reply(
+                                        &app_handle_clone,
+                                        entry.conversation_id(),
+                                        entry.owner.as_deref(),
+                                    );
+                                    checkpoints::on_reply(
+                                        &app_handle_clone,
+                                        entry.conversation_id(),
+                                    );
+                                    emit_message_state(
+                                        &app_handle_clone,
+                                        &agent_id_clone,
+                                        owner.as_deref(),
+                                        id,
+                                        MessageState::Done,
+                                        None,
+                                    );
+                                    feedback::play(&app_handle_clone, Cue::Completed);
+                                    accessibility::announce(
+                                        &app_handle_clone,
+                                        Announcement::Completed,
+                                    );
+                                }
+                            }
+                            AgentResponse::Error { id, error, code, .. } => {
+                                if matches!(
+                                    code.as_deref(),
+                                    Some("network_error" | "provider_unavailable")
+                                ) {
+                                    connectivity::probe_now(&app_handle_clone);
+                                }
+
+                                let was_pending = pending.remove(id).is_some();
+                                if was_pending {
+                                    emit_message_state(
+                                        &app_handle_clone,
+                                        &agent_id_clone,
+                                        owner.as_deref(),
+                                        id,
+                                        MessageState::Failed,
+                                        Some(error.as_str()),
+                                    );
+                                    feedback::play(&app_handle_clone, Cue::Error);
+                                    accessibility::announce(
+                                        &app_handle_clone,
+                                        Announcement::Failed(error),
+                                    );
+                                }
+                            }
+                            _ => {}
+                        }
+
+                        let streamed_chars = pending.values().map(|entry| entry.response.len()).sum();
+                        taskbar::update(&app_handle_clone, pending.len(), streamed_chars);
+                        window_title::sync_streaming(&app_handle_clone, streaming_owners(&pending));
+                        drop(pending);
+                        if !forward {
+                            continue;
+                        }
+
+                        events_clone
+                            .push(QueuedEvent::Response { owner, response })
+                            .await;
                     }
                     Err(e) => {
                         eprintln!("Failed to parse agent response: {} | Line: {}", e, line);
+                        let error = AgentProtocolError {
+                            agent_id: agent_id_clone.clone(),
+                            invalid: protocol::diagnose(&message, agreed.encoding),
+                            excerpt: line.chars().take(PROTOCOL_ERROR_EXCERPT_CHARS).collect(),
+                        };
+                        events_clone.push(QueuedEvent::ProtocolError(error)).await;
                     }
                 }
             }
+
+            eprintln!("[AGENT] Stream ended");
+            // What was read reaches the webview before the exit is handled
+            events_clone.finish().await;
+            // Lets a spawn that is still waiting for Ready see the exit right away
+            drop(ready_tx);
+            supervise(app_handle_clone, agent_id_clone, serial).await;
         });
 
-        // Spawn task to read stderr for debugging
-        tokio::spawn(async move {
+        spawn_stall_watchdog(
+            app_handle.clone(),
+            Arc::downgrade(&stdin),
+            Arc::downgrade(&pending),
+            suspended.clone(),
+        );
+        spawn_timeout_watchdog(
+            app_handle.clone(),
+            agent_id.to_string(),
+            Tracked {
+                stdin: Arc::downgrade(&stdin),
+                pending: Arc::downgrade(&pending),
+                deadlines: Arc::downgrade(&deadlines),
+                replies: Arc::downgrade(&replies),
+                completions: Arc::downgrade(&completions),
+                events: Arc::downgrade(&events),
+            },
+            suspended.clone(),
+        );
+        watchdog::spawn(app_handle.clone(), agent_id.to_string(), serial, missed_rx);
+        heartbeat::spawn(
+            app_handle.clone(),
+            agent_id.to_string(),
+            serial,
+            Arc::downgrade(&stdin),
+            pong_rx,
+            missed_tx,
+            suspended.clone(),
+        );
+
+        // Spawn task to read stderr for debugging, keeping the tail for error reports
+        let stderr_tail = Arc::new(std::sync::Mutex::new(VecDeque::new()));
+        let stderr_tail_clone = stderr_tail.clone();
+        let log_clone = log.clone();
+        let stderr_reader = tokio::spawn(async move {
             let reader = BufReader::new(stderr);
             let mut lines = reader.lines();
 
             while let Ok(Some(line)) = lines.next_line().await {
                 eprintln!("[AGENT STDERR] {}", line);
+                log_clone.line("stderr", &line);
+                push_tail(&stderr_tail_clone, &line, STDERR_TAIL_LINES);
             }
         });
 
         Ok(AgentProcess {
-            child,
-            stdin: Arc::new(Mutex::new(stdin)),
+            app_handle,
+            agent_id: agent_id.to_string(),
+            serial,
+            started_at: Instant::now(),
+            spawned_at: store::now_millis(),
+            last_message_at,
+            restarts: 0,
+            handle,
+            stdin,
+            pending,
+            completions,
+            replies,
+            deadlines,
+            stderr_tail,
+            stdout_tail,
+            stderr_reader: Some(stderr_reader),
+            limits,
+            log,
+            ready: ready_rx,
+            incompatible,
+            capabilities,
+            events,
+            active_conversation: None,
+            suspended,
         })
     }
 
-    pub async fn send_request(&self, request: &AgentRequest) -> Result<()> {
-        let json = serde_json::to_string(request).context("Failed to serialize request")?;
-        let mut stdin = self.stdin.lock().await;
-
-        stdin
-            .write_all(json.as_bytes())
+    /// Waits until the agent has printed its Ready message, up to `timeout`.
+    pub async fn wait_ready(&self, timeout: Duration) -> bool {
+        let mut ready = self.ready.clone();
+        tokio::time::timeout(timeout, ready.wait_for(|ready| *ready))
             .await
-            .context("Failed to write to stdin")?;
-        stdin
-            .write_all(b"\n")
+            .map(|result| result.is_ok())
+            .unwrap_or(false)
+    }
+
+    // Kills a process that never printed Ready and works out why
+    async fn startup_failure(mut self) -> SpawnFailure {
+        // The stdout reader drops its end of the channel once the process is gone
+        let exited = self.ready.has_changed().is_err();
+        let _ = self.kill().await;
+        self.log.finish(None, true);
+        if let Some(reader) = self.stderr_reader.take() {
+            let _ = tokio::time::timeout(Duration::from_secs(1), reader).await;
+        }
+
+        let incompatible = self.incompatible.lock().unwrap().clone();
+        if let Some(incompatible) = incompatible {
+            let message = format!(
+                "The agent speaks protocol version {}, this app supports {} to {}",
+                incompatible.protocol_version,
+                incompatible.min_protocol_version,
+                incompatible.max_protocol_version
+            );
+            let mut failure = SpawnFailure::new(
+                &self.agent_id,
+                SpawnStage::Incompatible,
+                message,
+                self.stderr_tail(),
+            );
+            failure.probable_causes = vec![if incompatible.protocol_version > PROTOCOL_VERSION {
+                "The agent runtime is newer than this app; update the app".to_string()
+            } else {
+                "The agent runtime is older than this app; update the agent".to_string()
+            }];
+            return failure;
+        }
+
+        let (stage, message) = if exited {
+            (
+                SpawnStage::Exited,
+                "The agent exited before it was ready".to_string(),
+            )
+        } else {
+            (
+                SpawnStage::Timeout,
+                format!(
+                    "The agent did not report ready within {}s",
+                    READY_TIMEOUT.as_secs()
+                ),
+            )
+        };
+        SpawnFailure::new(&self.agent_id, stage, message, self.stderr_tail())
+    }
+
+    pub async fn status(&mut self) -> AgentStatus {
+        let in_flight = self.pending.lock().await.len();
+        let state = if self.has_exited() {
+            AgentState::Crashed
+        } else if self.is_suspended() {
+            AgentState::Suspended
+        } else if !*self.ready.borrow() {
+            AgentState::Starting
+        } else if in_flight > 0 {
+            AgentState::Busy
+        } else {
+            AgentState::Ready
+        };
+        let last_message_at = self.last_message_at.load(Ordering::Relaxed);
+
+        AgentStatus {
+            state,
+            pid: self.handle.id(),
+            spawned_at: Some(self.spawned_at),
+            uptime_ms: Some(self.started_at.elapsed().as_millis() as u64),
+            last_message_at: (last_message_at > 0).then_some(last_message_at),
+            in_flight,
+            session_id: Some(self.log.session_id().to_string()),
+        }
+    }
+
+    pub fn serial(&self) -> u64 {
+        self.serial
+    }
+
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    pub fn ready_signal(&self) -> watch::Receiver<bool> {
+        self.ready.clone()
+    }
+
+    /// What the agent agreed to in hello.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities.lock().unwrap().clone()
+    }
+
+    pub fn event_queue_stats(&self) -> EventQueueStats {
+        self.events.stats()
+    }
+
+    /// Freezes the process and the tools it started (SIGSTOP to its process group, or
+    /// suspending the threads of its job on Windows). Its requests stay pending.
+    pub fn suspend(&self) -> Result<()> {
+        let pid = self
+            .handle
+            .id()
+            .context("The agent has no process to pause")?;
+        suspend::stop(pid)?;
+        self.suspended.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Continues a suspended process. Its requests get a fresh stall deadline, as the
+    /// time spent paused isn't the agent's.
+    pub async fn resume(&self) -> Result<()> {
+        let pid = self
+            .handle
+            .id()
+            .context("The agent has no process to resume")?;
+        suspend::cont(pid)?;
+        self.suspended.store(false, Ordering::Relaxed);
+        // Time spent paused doesn't count against the requests
+        for entry in self.pending.lock().await.values_mut() {
+            entry.last_activity = Instant::now();
+        }
+        for deadline in self.deadlines.lock().await.values_mut() {
+            deadline.at = Instant::now() + deadline.timeout;
+        }
+        Ok(())
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Relaxed)
+    }
+
+    pub async fn kill(&mut self) -> Result<()> {
+        self.handle.kill().await
+    }
+
+    /// Asks the agent to exit, killing it after SHUTDOWN_GRACE. Requests still in
+    /// flight are failed and their ids returned.
+    pub async fn shutdown(mut self) -> Vec<String> {
+        // A stopped process can't read the request
+        let was_suspended = self.is_suspended();
+        if was_suspended {
+            if let Err(e) = self.resume().await {
+                eprintln!("Failed to resume agent for shutdown: {}", e);
+            }
+        }
+        let request = AgentRequest::Shutdown {
+            id: uuid::Uuid::new_v4().to_string(),
+        };
+        let exited = match write_request(&self.stdin, &request).await {
+            Ok(()) => tokio::time::timeout(SHUTDOWN_GRACE, self.handle.wait())
+                .await
+                .is_ok(),
+            Err(e) => {
+                eprintln!("Failed to request agent shutdown: {}", e);
+                false
+            }
+        };
+        if !exited {
+            eprintln!("[AGENT] No exit after shutdown request, killing");
+            if let Err(e) = self.kill().await {
+                eprintln!("{}", e);
+            }
+        }
+        let exit_code = self
+            .handle
+            .try_wait()
+            .ok()
+            .flatten()
+            .and_then(|exit| exit.code);
+        self.log.finish(exit_code, false);
+
+        let lost = std::mem::take(&mut *self.pending.lock().await);
+        fail_lost(&self.app_handle, &self.agent_id, &self.events, &lost);
+        taskbar::update(&self.app_handle, 0, 0);
+        window_title::sync_streaming(&self.app_handle, HashSet::new());
+        if was_suspended {
+            suspend::released(&self.app_handle, &self.agent_id);
+        }
+        lost.into_keys().collect()
+    }
+
+    /// Kills a process that was replaced for hanging, since it won't honor a shutdown
+    /// request. Its requests fail with `agent_unresponsive`; their ids are returned.
+    pub async fn abandon(mut self) -> Vec<String> {
+        if let Err(e) = self.kill().await {
+            eprintln!("{}", e);
+        }
+        self.log.finish(None, true);
+
+        let lost = std::mem::take(&mut *self.pending.lock().await);
+        fail_pending(
+            &self.app_handle,
+            &self.agent_id,
+            &self.events,
+            &lost,
+            "Agent stopped responding and was restarted",
+            "agent_unresponsive",
+        );
+        taskbar::update(&self.app_handle, 0, 0);
+        window_title::sync_streaming(&self.app_handle, HashSet::new());
+        lost.into_keys().collect()
+    }
+
+    pub async fn send_request(
+        &mut self,
+        request: &AgentRequest,
+        owner: Option<String>,
+    ) -> Result<()> {
+        if let AgentRequest::UserMessage {
+            id,
+            message,
+            images,
+            ..
+        } = request
+        {
+            let entry = PendingRequest::new(request.clone(), owner, self.timeout(request));
+
+            let store = self.app_handle.state::<ConversationStore>();
+            let message = StoredMessage {
+                id: id.clone(),
+                role: "user".to_string(),
+                content: message.clone(),
+                timestamp: store::now_millis(),
+                images: images.clone(),
+                model: None,
+                tools: Vec::new(),
+            };
+            if let Err(e) = store.append_message(entry.conversation_id(), message) {
+                eprintln!("Failed to record message {}: {}", id, e);
+            }
+            // The draft became this message
+            if let Err(e) = store.clear_draft(entry.conversation_id()) {
+                eprintln!("Failed to clear draft: {}", e);
+            }
+
+            let mut pending = self.pending.lock().await;
+            pending.insert(id.clone(), entry);
+            accessibility::announce(&self.app_handle, Announcement::Started);
+
+            let streamed_chars = pending.values().map(|entry| entry.response.len()).sum();
+            taskbar::update(&self.app_handle, pending.len(), streamed_chars);
+            window_title::sync_streaming(&self.app_handle, streaming_owners(&pending));
+        } else {
+            self.start_deadline(request).await;
+        }
+
+        match request {
+            AgentRequest::LoadConversation {
+                conversation_id, ..
+            }
+            | AgentRequest::LoadConversationCompressed {
+                conversation_id, ..
+            } => self.active_conversation = Some(conversation_id.clone()),
+            // The source no longer exists; the agent continues in the target
+            AgentRequest::MergeConversations {
+                conversation_id,
+                source_id,
+                ..
+            } if self.active_conversation.as_ref() == Some(source_id) => {
+                self.active_conversation = Some(conversation_id.clone())
+            }
+            _ => {}
+        }
+
+        let recovery = self.app_handle.state::<SettingsStore>().get().recovery;
+        let mut result = write_request(&self.stdin, request).await;
+        let mut replays = 0;
+        while let Err(e) = &result {
+            if !(is_broken_pipe(e) || self.has_exited()) {
+                break;
+            }
+            if !recovery.enabled || replays >= recovery.max_replays {
+                eprintln!("Agent stdin closed, not replaying {}", request.id());
+                break;
+            }
+            replays += 1;
+            eprintln!(
+                "Agent stdin closed ({}), respawning to replay {} (attempt {})",
+                e,
+                request.id(),
+                replays
+            );
+            result = self.recover(request).await;
+        }
+        let result = result.map_err(|e| self.explain(e));
+        if result.is_ok() {
+            if let Some(entry) = self.pending.lock().await.get_mut(request.id()) {
+                // Time spent queued doesn't count against it
+                entry.last_activity = Instant::now();
+                entry.advance(&self.app_handle, &self.agent_id, MessageState::Sent);
+            }
+        } else {
+            self.deadlines.lock().await.remove(request.id());
+        }
+        result
+    }
+
+    // How long `request` may take under the current settings
+    fn timeout(&self, request: &AgentRequest) -> Option<Duration> {
+        let settings = self.app_handle.state::<SettingsStore>().get();
+        settings.request_timeouts.timeout(request)
+    }
+
+    // Starts the timeout of a request other than a user message
+    async fn start_deadline(&self, request: &AgentRequest) {
+        if let Some(timeout) = self.timeout(request) {
+            let deadline = Deadline {
+                timeout,
+                at: Instant::now() + timeout,
+            };
+            self.deadlines
+                .lock()
+                .await
+                .insert(request.id().to_string(), deadline);
+        }
+    }
+
+    /// Sends `request` and waits for its Done, returning the data it carries, or for its
+    /// Error. The agent is held for the round trip, so this is for requests answered
+    /// right away, such as loading a conversation.
+    pub async fn send_and_wait(
+        &mut self,
+        request: &AgentRequest,
+    ) -> Result<Option<serde_json::Value>> {
+        let id = request.id().to_string();
+        let (reply, receiver) = oneshot::channel();
+        self.replies.lock().await.insert(id.clone(), reply);
+
+        if let Err(e) = self.send_request(request, None).await {
+            self.replies.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        // After a respawn this is the replacement, which the reply moved to
+        let mut ready = self.ready.clone();
+        let result = tokio::select! {
+            biased;
+            reply = receiver => {
+                reply.unwrap_or_else(|_| Err(anyhow!("The agent exited before answering")))
+            }
+            // The reader drops the ready sender when the stream ends
+            _ = async { while ready.changed().await.is_ok() {} } => {
+                Err(anyhow!("The agent exited before answering"))
+            }
+            _ = tokio::time::sleep(REPLY_TIMEOUT) => {
+                Err(anyhow!("The agent didn't answer in time"))
+            }
+        };
+        self.replies.lock().await.remove(&id);
+        result
+    }
+
+    /// Sends a request answered outside any conversation, such as a text transform.
+    /// Its reply is collected instead of streamed; await the receiver after releasing
+    /// the agent lock. It resolves to an error if the agent fails or exits first.
+    pub async fn complete(
+        &mut self,
+        request: &AgentRequest,
+    ) -> Result<oneshot::Receiver<Result<String>>> {
+        let (done, receiver) = oneshot::channel();
+        self.completions.lock().await.insert(
+            request.id().to_string(),
+            Completion {
+                text: String::new(),
+                done,
+            },
+        );
+
+        self.start_deadline(request).await;
+
+        if let Err(e) = write_request(&self.stdin, request).await {
+            self.completions.lock().await.remove(request.id());
+            self.deadlines.lock().await.remove(request.id());
+            return Err(e);
+        }
+        Ok(receiver)
+    }
+
+    /// The last STDERR_TAIL_LINES lines the agent wrote to stderr.
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn crash_report(
+        &self,
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+        lost: &HashMap<String, PendingRequest>,
+    ) -> CrashReport {
+        let crashed_at = store::now_millis();
+        let in_flight = lost
+            .values()
+            .map(|entry| {
+                let (conversation_id, message) = match &entry.request {
+                    AgentRequest::UserMessage {
+                        conversation_id,
+                        message,
+                        ..
+                    } => (conversation_id.clone(), Some(message.clone())),
+                    _ => (None, None),
+                };
+                InFlightRequest {
+                    id: entry.request.id().to_string(),
+                    kind: entry.request.kind().to_string(),
+                    conversation_id,
+                    message,
+                    state: entry.state,
+                    response_chars: entry.response.len(),
+                }
+            })
+            .collect();
+
+        CrashReport {
+            report_id: format!("{}-{}", crashed_at, self.serial),
+            agent_id: self.agent_id.clone(),
+            session_id: self.log.session_id().to_string(),
+            shell_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            started_at: self.spawned_at,
+            crashed_at,
+            exit_code,
+            signal,
+            stderr_tail: self.stderr_tail(),
+            stdout_tail: self.stdout_tail.lock().unwrap().iter().cloned().collect(),
+            in_flight,
+        }
+    }
+
+    /// `error` with the stderr tail appended, so the user sees why the agent failed.
+    pub fn explain(&self, error: anyhow::Error) -> anyhow::Error {
+        with_stderr_tail(error, &self.stderr_tail())
+    }
+
+    pub fn has_exited(&mut self) -> bool {
+        matches!(self.handle.try_wait(), Ok(Some(_)))
+    }
+
+    /// Replaces a dead agent with the warm standby or a fresh process, restores the
+    /// active conversation and replays `request` once. Other requests that were in
+    /// flight are failed.
+    async fn recover(&mut self, request: &AgentRequest) -> Result<()> {
+        let (process, from_standby) = self.successor().await?;
+        let conversation_id = process.active_conversation.clone();
+
+        let (replayed, lost) = split_replay(
+            std::mem::take(&mut *self.pending.lock().await),
+            request.id(),
+        );
+        if let Some(entry) = replayed {
+            process
+                .pending
+                .lock()
+                .await
+                .insert(request.id().to_string(), entry);
+        }
+        fail_lost(&self.app_handle, &self.agent_id, &self.events, &lost);
+        if let Some(reply) = self.replies.lock().await.remove(request.id()) {
+            process
+                .replies
+                .lock()
+                .await
+                .insert(request.id().to_string(), reply);
+        }
+        if let Some(deadline) = self.deadlines.lock().await.remove(request.id()) {
+            process
+                .deadlines
+                .lock()
+                .await
+                .insert(request.id().to_string(), deadline);
+        }
+
+        self.log.finish(None, true);
+        *self = process;
+        write_request(&self.stdin, request)
             .await
-            .context("Failed to write newline")?;
-        stdin.flush().await.context("Failed to flush stdin")?;
+            .context("Failed to replay request after respawn")?;
+
+        let recovered = AgentRecovered {
+            request_id: request.id().to_string(),
+            conversation_id,
+            lost_requests: lost.into_keys().collect(),
+            from_standby,
+        };
+        if let Err(e) = self.app_handle.emit_all("agent_recovered", recovered) {
+            eprintln!("Failed to emit agent_recovered: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// A ready replacement for this process, the warm standby if there is one, with the
+    /// active conversation loaded. Returns whether the standby was used.
+    async fn successor(&self) -> Result<(AgentProcess, bool)> {
+        let (mut process, from_standby) =
+            replacement(self.app_handle.clone(), &self.agent_id).await?;
+        self.hand_over(&mut process).await?;
+        Ok((process, from_standby))
+    }
 
-        eprintln!("[SENT TO AGENT] {}", json);
+    /// Loads the active conversation into `process`, which is about to take over.
+    pub async fn hand_over(&self, process: &mut AgentProcess) -> Result<()> {
+        if let Some(conversation_id) = &self.active_conversation {
+            let restore = AgentRequest::LoadConversation {
+                id: uuid::Uuid::new_v4().to_string(),
+                conversation_id: conversation_id.clone(),
+            };
+            write_request(&process.stdin, &restore)
+                .await
+                .context("Failed to restore conversation")?;
+        }
+        process.active_conversation = self.active_conversation.clone();
+        Ok(())
+    }
+
+    /// Drops a request that never reached the agent so it isn't reported as in flight.
+    pub async fn forget(&self, id: &str) {
+        let mut pending = self.pending.lock().await;
+        if pending.remove(id).is_some() {
+            let streamed_chars = pending.values().map(|entry| entry.response.len()).sum();
+            taskbar::update(&self.app_handle, pending.len(), streamed_chars);
+            window_title::sync_streaming(&self.app_handle, streaming_owners(&pending));
+        }
+    }
+
+    /// The conversation last loaded into the agent; none until one is loaded.
+    pub fn active_conversation(&self) -> Option<&str> {
+        self.active_conversation.as_deref()
+    }
+
+    /// Interrupts a stalled request and resends it under the same id.
+    pub async fn retry_stalled(&self, id: &str) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        retry_stalled(&self.app_handle, &self.stdin, &mut pending, id).await
+    }
+
+    pub async fn in_flight_ids(&self) -> Vec<String> {
+        self.pending.lock().await.keys().cloned().collect()
+    }
+
+    /// In-flight requests that have had no output for at least `deadline`.
+    pub async fn overdue_requests(&self, deadline: Duration) -> Vec<String> {
+        overdue(&self.pending.lock().await, deadline)
+    }
+
+    /// Hands the requests of a closed window to `successor(conversation_id)`, or
+    /// interrupts them when it returns None. Returns the moved ids per new owner.
+    pub async fn release_window(
+        &self,
+        label: &str,
+        successor: impl Fn(&str) -> Option<String>,
+    ) -> HashMap<String, Vec<String>> {
+        let mut reassigned: HashMap<String, Vec<String>> = HashMap::new();
+        let mut orphaned = Vec::new();
+        {
+            let mut pending = self.pending.lock().await;
+            for (id, entry) in pending.iter_mut() {
+                if entry.owner.as_deref() != Some(label) {
+                    continue;
+                }
+                match successor(entry.conversation_id()) {
+                    Some(owner) => {
+                        entry.owner = Some(owner.clone());
+                        reassigned.entry(owner).or_default().push(id.clone());
+                    }
+                    None => orphaned.push(id.clone()),
+                }
+            }
+            window_title::sync_streaming(&self.app_handle, streaming_owners(&pending));
+        }
+
+        // The agent answers with an 'interrupted' error, routed to the closed window
+        for id in orphaned {
+            let interrupt = AgentRequest::Interrupt { id: id.clone() };
+            if let Err(e) = write_request(&self.stdin, &interrupt).await {
+                eprintln!("Failed to interrupt {} after its window closed: {}", id, e);
+            }
+        }
+        reassigned
+    }
 
+    /// Holds back the tokens of `id` without interrupting the generation.
+    pub async fn pause_stream(&self, id: &str) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        let entry = pending
+            .get_mut(id)
+            .with_context(|| format!("No in-flight request with id {}", id))?;
+        entry.paused_at.get_or_insert(entry.response.len());
         Ok(())
     }
+
+    /// Releases everything held back for `id` as one token, then streams normally.
+    pub async fn resume_stream(&self, id: &str) -> Result<()> {
+        let mut pending = self.pending.lock().await;
+        let entry = pending
+            .get_mut(id)
+            .with_context(|| format!("No in-flight request with id {}", id))?;
+        catch_up(&self.app_handle, &self.events, id, entry);
+        Ok(())
+    }
+
+    /// Requests still being answered, with the window that sent each.
+    pub async fn in_flight_requests(&self) -> Vec<(AgentRequest, Option<String>)> {
+        self.pending
+            .lock()
+            .await
+            .values()
+            .map(|entry| (entry.request.clone(), entry.owner.clone()))
+            .collect()
+    }
+
+    /// In-flight request ids, narrowed to one request and/or one conversation.
+    pub async fn in_flight_matching(
+        &self,
+        id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Vec<String> {
+        matching(&self.pending.lock().await, id, conversation_id)
+    }
+}
+
+/// Emits agent_started / agent_stopped for spawns and stops the UI asked for.
+pub fn emit_lifecycle(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    event: &str,
+    lost_requests: Vec<String>,
+) {
+    let lifecycle = AgentLifecycle {
+        agent_id,
+        lost_requests,
+    };
+    if let Err(e) = app_handle.emit_all(event, lifecycle) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+// Unpauses a request, sending the held-back text in one batch (or on to the spill file
+// if it grew large enough meanwhile)
+fn catch_up(app_handle: &AppHandle, events: &EventQueue, id: &str, entry: &mut PendingRequest) {
+    let Some(paused_at) = entry.paused_at.take() else {
+        return;
+    };
+    if entry.response.len() == paused_at {
+        return;
+    }
+    let owner = entry.owner.as_deref();
+    if spill::on_token(app_handle, owner, id, &entry.response, &mut entry.spill) {
+        return;
+    }
+
+    let response = AgentResponse::Token {
+        id: id.to_string(),
+        token: entry.response[paused_at..].to_string(),
+        timestamp: store::now_millis(),
+    };
+    events.push_now(entry.owner.clone(), response);
+}
+
+/// Sends a response to the window that issued the request, or everywhere if unknown.
+/// Called by the EventQueue, which keeps a reply's responses in order.
+pub fn emit_response(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    owner: Option<&str>,
+    response: &AgentResponse,
+) {
+    #[cfg(test)]
+    tests::record_emitted(agent_id, owner, response);
+    let routed = RoutedResponse { agent_id, response };
+    let result = match owner {
+        Some(label) => app_handle.emit_to(label, "agent_response", routed),
+        None => app_handle.emit_all("agent_response", routed),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit agent response: {}", e);
+    }
+}
+
+/// Reports a delivery state change of message `id` to the window that sent it.
+pub fn emit_message_state(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    owner: Option<&str>,
+    id: &str,
+    state: MessageState,
+    error: Option<&str>,
+) {
+    let change = MessageStateChange {
+        agent_id,
+        id,
+        state,
+        error,
+        timestamp: store::now_millis(),
+    };
+    let result = match owner {
+        Some(label) => app_handle.emit_to(label, "message_state", change),
+        None => app_handle.emit_all("message_state", change),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit message_state: {}", e);
+    }
+}
+
+// Fails requests that died with an agent process
+fn fail_lost(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    events: &EventQueue,
+    lost: &HashMap<String, PendingRequest>,
+) {
+    fail_pending(
+        app_handle,
+        agent_id,
+        events,
+        lost,
+        "Agent process exited",
+        "agent_exited",
+    );
+}
+
+// The errors go out behind whatever the requests already streamed
+fn fail_pending(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    events: &EventQueue,
+    lost: &HashMap<String, PendingRequest>,
+    error: &str,
+    code: &str,
+) {
+    for (id, entry) in lost {
+        emit_message_state(
+            app_handle,
+            agent_id,
+            entry.owner.as_deref(),
+            id,
+            MessageState::Failed,
+            Some(error),
+        );
+        let response = AgentResponse::Error {
+            id: id.clone(),
+            error: error.to_string(),
+            code: Some(code.to_string()),
+            retry_after_ms: None,
+            timestamp: store::now_millis(),
+        };
+        events.push_now(entry.owner.clone(), response);
+    }
+}
+
+// A ready process for agent `agent_id`, the warm standby if there is one. Returns
+// whether the standby was used.
+async fn replacement(app_handle: AppHandle, agent_id: &str) -> Result<(AgentProcess, bool)> {
+    // The standby is spawned for the default agent only
+    let standby = match agent_id {
+        DEFAULT_AGENT_ID => standby::take(&app_handle).await,
+        _ => None,
+    };
+    match standby {
+        Some(process) => Ok((process, true)),
+        None => Ok((AgentProcess::spawn(app_handle, agent_id).await?, false)),
+    }
+}
+
+/// What replace() leaves: the slot, still locked, with the new process in it.
+pub struct Replaced {
+    pub agent: OwnedMutexGuard<Option<AgentProcess>>,
+    pub old: AgentProcess,
+    pub from_standby: bool,
+}
+
+/// Puts the warm standby or a fresh process into `slot` in place of the one running
+/// there (only if it is `serial`, when given), with its active conversation loaded.
+/// The slot isn't held while the new process starts, so commands for the agent go on
+/// meanwhile. None if there was no such process, or it went away in the meantime.
+pub async fn replace(slot: &AgentSlot, serial: Option<u64>) -> Result<Option<Replaced>> {
+    let (app_handle, agent_id, serial) = {
+        let agent = slot.lock().await;
+        let Some(process) = agent
+            .as_ref()
+            .filter(|process| serial.map_or(true, |serial| process.serial == serial))
+        else {
+            return Ok(None);
+        };
+        (
+            process.app_handle.clone(),
+            process.agent_id.clone(),
+            process.serial,
+        )
+    };
+
+    let (mut process, from_standby) = replacement(app_handle, &agent_id).await?;
+
+    let mut agent = slot.clone().lock_owned().await;
+    let Some(current) = agent.as_mut().filter(|current| current.serial == serial) else {
+        drop(agent);
+        // Stopped or replaced by someone else while this one started
+        process.shutdown().await;
+        return Ok(None);
+    };
+    // The conversation it has by now, which may have changed while waiting
+    current.hand_over(&mut process).await?;
+    let old = std::mem::replace(current, process);
+    Ok(Some(Replaced {
+        agent,
+        old,
+        from_standby,
+    }))
+}
+
+/// Replaces the process in `slot` as replace() does, then shuts the old one down.
+/// Returns the requests that were failed, or None if there was nothing to restart.
+pub async fn restart(slot: &AgentSlot, serial: Option<u64>) -> Result<Option<Vec<String>>> {
+    let Some(Replaced { agent, old, .. }) = replace(slot, serial).await? else {
+        return Ok(None);
+    };
+    drop(agent);
+    Ok(Some(old.shutdown().await))
+}
+
+// Runs once a process's stdout closes. If it was still the active agent it crashed:
+// report it, fail what was in flight and respawn with exponential backoff. Boxed
+// because spawn() starts this, and it spawns again.
+fn supervise(
+    app_handle: AppHandle,
+    agent_id: String,
+    serial: u64,
+) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    Box::pin(async move {
+        let Some(slot) = app_handle.state::<AppState>().find(&agent_id) else {
+            return;
+        };
+        let restarts = {
+            let mut agent = slot.lock().await;
+            // Stopped or replaced on purpose, or a standby that was discarded
+            let Some(process) = agent.as_mut().filter(|process| process.serial == serial) else {
+                return;
+            };
+
+            let exit =
+                match tokio::time::timeout(Duration::from_secs(2), process.handle.wait()).await {
+                    Ok(Ok(exit)) => Some(exit),
+                    _ => None,
+                };
+            let exit_code = exit.and_then(|exit| exit.code);
+            let signal = exit.and_then(|exit| exit.signal);
+            // Let the last stderr lines arrive before reporting them
+            if let Some(reader) = process.stderr_reader.take() {
+                let _ = tokio::time::timeout(Duration::from_secs(1), reader).await;
+            }
+
+            process.log.finish(exit_code, true);
+
+            let exited = AgentExited {
+                agent_id: agent_id.clone(),
+                session_id: process.log.session_id().to_string(),
+                exit_code,
+                signal,
+                stderr_tail: process.stderr_tail(),
+            };
+            if let Err(e) = app_handle.emit_all("agent_exited", exited) {
+                eprintln!("Failed to emit agent_exited: {}", e);
+            }
+            resource_limits::check_exit(
+                &app_handle,
+                &agent_id,
+                &process.limits,
+                &process.stderr_tail(),
+            );
+            let restarts = if process.started_at.elapsed() >= STABLE_UPTIME {
+                0
+            } else {
+                process.restarts
+            };
+
+            let lost = std::mem::take(&mut *process.pending.lock().await);
+            let report = process.crash_report(exit_code, signal, &lost);
+            match crash_reports::write(&app_handle, report) {
+                Ok(path) => eprintln!("[AGENT] Crash report written to {:?}", path),
+                Err(e) => eprintln!("Failed to write crash report: {}", e),
+            }
+            fail_lost(&app_handle, &agent_id, &process.events, &lost);
+            taskbar::update(&app_handle, 0, 0);
+            window_title::sync_streaming(&app_handle, HashSet::new());
+
+            eprintln!("[AGENT] Crashed with exit code {:?}", exit_code);
+            let crashed = AgentCrashed {
+                agent_id: agent_id.clone(),
+                session_id: process.log.session_id().to_string(),
+                exit_code,
+                lost_requests: lost.into_keys().collect(),
+                stderr_tail: process.stderr_tail(),
+                restarts,
+                will_restart: restarts < MAX_RESTARTS,
+            };
+            if let Err(e) = app_handle.emit_all("agent_crashed", crashed) {
+                eprintln!("Failed to emit agent_crashed: {}", e);
+            }
+            restarts
+        };
+
+        for attempt in restarts + 1..=MAX_RESTARTS {
+            let delay = (RESTART_BACKOFF * 2u32.pow(attempt - 1)).min(MAX_RESTART_BACKOFF);
+            tokio::time::sleep(delay).await;
+
+            match replace(&slot, Some(serial)).await {
+                // Recovered by a send or stopped meanwhile
+                Ok(None) => return,
+                Ok(Some(Replaced {
+                    mut agent,
+                    from_standby,
+                    ..
+                })) => {
+                    eprintln!("[AGENT] Restarted after crash (attempt {})", attempt);
+                    if let Some(process) = agent.as_mut() {
+                        process.restarts = attempt;
+                        if agent_id == DEFAULT_AGENT_ID {
+                            outbox::flush(&app_handle, process).await;
+                        }
+                    }
+
+                    let restarted = AgentRestarted {
+                        agent_id: agent_id.clone(),
+                        attempt,
+                        from_standby,
+                    };
+                    if let Err(e) = app_handle.emit_all("agent_restarted", restarted) {
+                        eprintln!("Failed to emit agent_restarted: {}", e);
+                    }
+                    return;
+                }
+                Err(e) => eprintln!("[AGENT] Restart attempt {} failed: {}", attempt, e),
+            }
+        }
+
+        if restarts < MAX_RESTARTS {
+            eprintln!("[AGENT] Giving up after {} restart attempts", MAX_RESTARTS);
+            if let Err(e) = app_handle.emit_all("agent_restart_failed", MAX_RESTARTS) {
+                eprintln!("Failed to emit agent_restart_failed: {}", e);
+            }
+        }
+    })
+}
+
+// Guesses from the agent's last output; a generic hint per stage if nothing matches
+fn probable_causes(stage: SpawnStage, stderr_tail: &[String]) -> Vec<String> {
+    let output = stderr_tail.join("\n");
+    let mut causes = Vec::new();
+    if output.contains("Need to install the following packages") {
+        causes.push("tsx is not installed; run pnpm install in apps/agent-runtime".to_string());
+    } else if output.contains("Cannot find module") || output.contains("ERR_MODULE_NOT_FOUND") {
+        causes.push(
+            "The agent's dependencies are missing; run pnpm install in apps/agent-runtime"
+                .to_string(),
+        );
+    }
+    if output.contains("ANTHROPIC_API_KEY") {
+        causes.push("No Anthropic API key is configured".to_string());
+    }
+    if output.contains("SyntaxError") || output.contains("Unsupported engine") {
+        causes.push("The installed Node.js is too old for the agent".to_string());
+    }
+
+    if causes.is_empty() {
+        causes.push(
+            match stage {
+                SpawnStage::Launch => "The agent command is misconfigured in settings",
+                SpawnStage::Exited => "The agent crashed during startup; see its output",
+                SpawnStage::Incompatible => "The agent and the app are different versions",
+                SpawnStage::Timeout => {
+                    "The agent is stuck on startup, e.g. waiting for input or the network"
+                }
+            }
+            .to_string(),
+        );
+    }
+    causes
+}
+
+fn with_stderr_tail(error: anyhow::Error, tail: &[String]) -> anyhow::Error {
+    if tail.is_empty() {
+        return error;
+    }
+    anyhow!("{}\n\nLast agent output:\n{}", error, tail.join("\n"))
+}
+
+// Feeds a response belonging to a completion into it; false if it belongs elsewhere
+async fn collect_completion(
+    app_handle: &AppHandle,
+    completions: &Completions,
+    response: &AgentResponse,
+) -> bool {
+    let Some(id) = response.id() else {
+        return false;
+    };
+    let mut completions = completions.lock().await;
+    let Some(completion) = completions.get_mut(id) else {
+        return false;
+    };
+
+    match response {
+        AgentResponse::Token { token, .. } => completion.text.push_str(token),
+        AgentResponse::Done { usage, .. } => {
+            if let Some(usage) = usage {
+                budget::record(app_handle, usage);
+            }
+            if let Some(completion) = completions.remove(id) {
+                let _ = completion.done.send(Ok(completion.text));
+            }
+        }
+        AgentResponse::Error { error, .. } => {
+            if let Some(completion) = completions.remove(id) {
+                let _ = completion.done.send(Err(anyhow!("{}", error)));
+            }
+        }
+        _ => {}
+    }
+    true
+}
+
+// Hands the data of a Done, or the error, to the send_and_wait caller of its request
+async fn resolve_reply(replies: &Replies, response: &AgentResponse) {
+    let (id, result) = match response {
+        AgentResponse::Done { id, data, .. } => (id, Ok(data.clone())),
+        AgentResponse::Error { id, error, .. } => (id, Err(anyhow!("{}", error))),
+        _ => return,
+    };
+    if let Some(reply) = replies.lock().await.remove(id) {
+        let _ = reply.send(result);
+    }
+}
+
+// Saves an image the agent produced for request `id` and tells its window about it
+async fn on_image_output(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    pending: &Mutex<HashMap<String, PendingRequest>>,
+    id: &str,
+    data: &[u8],
+    mime: &str,
+) {
+    // Decoding and writing happen outside the lock, which the stream needs
+    let stored = generated_images::store(app_handle, id, data, mime);
+    let mut pending = pending.lock().await;
+    let owner = pending.get(id).and_then(|entry| entry.owner.clone());
+    match stored {
+        Ok((image, attachment)) => {
+            if let Some(entry) = pending.get_mut(id) {
+                entry.last_activity = Instant::now();
+                entry.images.push(attachment);
+            }
+            drop(pending);
+            generated_images::emit(app_handle, agent_id, owner.as_deref(), &image);
+        }
+        Err(e) => eprintln!("Failed to store generated image for {}: {}", id, e),
+    }
+}
+
+// The `images` JSON of a reply, None if it has none
+fn stored_images(images: &[ImageAttachment]) -> Option<String> {
+    if images.is_empty() {
+        return None;
+    }
+    match serde_json::to_string(images) {
+        Ok(json) => Some(json),
+        Err(e) => {
+            eprintln!("Failed to serialize generated images: {}", e);
+            None
+        }
+    }
+}
+
+// Appends `line`, dropping the oldest once `tail` holds `max` lines
+fn push_tail(tail: &std::sync::Mutex<VecDeque<String>>, line: &str, max: usize) {
+    let mut tail = tail.lock().unwrap();
+    if tail.len() == max {
+        tail.pop_front();
+    }
+    tail.push_back(line.to_string());
+}
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .root_cause()
+        .downcast_ref::<std::io::Error>()
+        .map(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+        .unwrap_or(false)
+}
+
+/// Location of the agent-runtime sources, used when no managed build is installed.
+pub fn dev_runtime_dir() -> Result<PathBuf> {
+    Ok(std::env::current_dir()
+        .context("Failed to get current directory")?
+        .join("../../agent-runtime"))
+}
+
+// Reports requests without output for STALL_TIMEOUT, or retries them when enabled.
+// Holds weak references so it stops once the process is dropped.
+fn spawn_stall_watchdog(
+    app_handle: AppHandle,
+    stdin: Weak<Mutex<AgentStdin>>,
+    pending: Weak<Mutex<HashMap<String, PendingRequest>>>,
+    suspended: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(stall::CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            if suspended.load(Ordering::Relaxed) {
+                continue;
+            }
+            let (Some(stdin), Some(pending)) = (stdin.upgrade(), pending.upgrade()) else {
+                break;
+            };
+
+            let auto_retry = app_handle
+                .state::<SettingsStore>()
+                .get()
+                .auto_retry_stalled_streams;
+            let mut pending = pending.lock().await;
+            let stalled: Vec<String> = pending
+                .iter()
+                .filter(|(_, entry)| entry.last_activity.elapsed() >= stall::STALL_TIMEOUT)
+                .map(|(id, _)| id.clone())
+                .collect();
+
+            for id in stalled {
+                let entry = &pending[&id];
+                if auto_retry && entry.stall_retries < stall::MAX_STALL_RETRIES {
+                    if let Err(e) = retry_stalled(&app_handle, &stdin, &mut pending, &id).await {
+                        eprintln!("Failed to retry stalled request {}: {}", id, e);
+                    }
+                } else {
+                    stall::notify(
+                        &app_handle,
+                        entry.owner.as_deref(),
+                        &id,
+                        entry.last_activity.elapsed(),
+                    );
+                }
+            }
+        }
+    });
+}
+
+// Fails requests the agent hasn't answered by their deadline with a "timeout" error.
+// User messages are also interrupted so the agent stops generating them.
+fn spawn_timeout_watchdog(
+    app_handle: AppHandle,
+    agent_id: String,
+    tracked: Tracked,
+    suspended: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TIMEOUT_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            if suspended.load(Ordering::Relaxed) {
+                continue;
+            }
+            let (
+                Some(stdin),
+                Some(pending),
+                Some(deadlines),
+                Some(replies),
+                Some(completions),
+                Some(events),
+            ) = (
+                tracked.stdin.upgrade(),
+                tracked.pending.upgrade(),
+                tracked.deadlines.upgrade(),
+                tracked.replies.upgrade(),
+                tracked.completions.upgrade(),
+                tracked.events.upgrade(),
+            )
+            else {
+                break;
+            };
+            let now = Instant::now();
+
+            let expired: HashMap<String, PendingRequest> = {
+                let mut pending = pending.lock().await;
+                let ids: Vec<String> = pending
+                    .iter()
+                    .filter(|(_, entry)| {
+                        entry.state >= MessageState::Sent
+                            && entry.idle_timeout.is_some_and(|timeout| {
+                                now.saturating_duration_since(entry.last_activity) >= timeout
+                            })
+                    })
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                let expired: HashMap<String, PendingRequest> = ids
+                    .into_iter()
+                    .filter_map(|id| pending.remove(&id).map(|entry| (id, entry)))
+                    .collect();
+                if !expired.is_empty() {
+                    let streamed_chars = pending.values().map(|entry| entry.response.len()).sum();
+                    taskbar::update(&app_handle, pending.len(), streamed_chars);
+                    window_title::sync_streaming(&app_handle, streaming_owners(&pending));
+                }
+                expired
+            };
+            for id in expired.keys() {
+                eprintln!("[AGENT] Request {} timed out", id);
+                let interrupt = AgentRequest::Interrupt { id: id.clone() };
+                if let Err(e) = write_request(&stdin, &interrupt).await {
+                    eprintln!("Failed to interrupt timed out request {}: {}", id, e);
+                }
+            }
+            fail_pending(
+                &app_handle,
+                &agent_id,
+                &events,
+                &expired,
+                "timeout",
+                "timeout",
+            );
+
+            let expired: Vec<String> = {
+                let mut deadlines = deadlines.lock().await;
+                let ids: Vec<String> = deadlines
+                    .iter()
+                    .filter(|(_, deadline)| now >= deadline.at)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in &ids {
+                    deadlines.remove(id);
+                }
+                ids
+            };
+            for id in expired {
+                eprintln!("[AGENT] Request {} timed out", id);
+                let response = AgentResponse::Error {
+                    id,
+                    error: "timeout".to_string(),
+                    code: Some("timeout".to_string()),
+                    retry_after_ms: None,
+                    timestamp: store::now_millis(),
+                };
+                // Whoever awaits it learns the same way as from the agent
+                resolve_reply(&replies, &response).await;
+                collect_completion(&app_handle, &completions, &response).await;
+                events.push_now(None, response);
+            }
+        }
+    });
+}
+
+async fn retry_stalled(
+    app_handle: &AppHandle,
+    stdin: &Mutex<AgentStdin>,
+    pending: &mut HashMap<String, PendingRequest>,
+    id: &str,
+) -> Result<()> {
+    let entry = pending
+        .get_mut(id)
+        .with_context(|| format!("No in-flight request with id {}", id))?;
+    entry.restart();
+
+    stall::interrupt_and_retry(app_handle, stdin, entry.owner.as_deref(), &entry.request).await
+}
+
+// Ids in `pending` that are `id` and/or in `conversation_id`
+fn matching(
+    pending: &HashMap<String, PendingRequest>,
+    id: Option<&str>,
+    conversation_id: Option<&str>,
+) -> Vec<String> {
+    pending
+        .iter()
+        .filter(|(pending_id, entry)| {
+            id.map(|id| id == pending_id.as_str()).unwrap_or(true)
+                && conversation_id
+                    .map(|conversation_id| conversation_id == entry.conversation_id())
+                    .unwrap_or(true)
+        })
+        .map(|(pending_id, _)| pending_id.clone())
+        .collect()
+}
+
+// Ids in `pending` without output for at least `deadline`
+fn overdue(pending: &HashMap<String, PendingRequest>, deadline: Duration) -> Vec<String> {
+    let now = Instant::now();
+    pending
+        .iter()
+        .filter(|(_, entry)| now.saturating_duration_since(entry.last_activity) >= deadline)
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+// Splits the requests of a dead process into the one to replay and the ones lost with it
+fn split_replay(
+    mut pending: HashMap<String, PendingRequest>,
+    id: &str,
+) -> (Option<PendingRequest>, HashMap<String, PendingRequest>) {
+    (pending.remove(id), pending)
+}
+
+// Labels of windows with at least one request still streaming
+fn streaming_owners(pending: &HashMap<String, PendingRequest>) -> HashSet<String> {
+    pending
+        .values()
+        .filter_map(|entry| entry.owner.clone())
+        .collect()
+}
+
+pub async fn write_request(stdin: &Mutex<AgentStdin>, request: &AgentRequest) -> Result<()> {
+    let json = serde_json::to_string(request).context("Failed to serialize request")?;
+    let mut stdin = stdin.lock().await;
+
+    stdin
+        .write_all(json.as_bytes())
+        .await
+        .context("Failed to write to stdin")?;
+    stdin
+        .write_all(b"\n")
+        .await
+        .context("Failed to write newline")?;
+    stdin.flush().await.context("Failed to flush stdin")?;
+
+    eprintln!("[SENT TO AGENT] {}", json);
+
+    Ok(())
+}
+
+// The pending map as AgentProcess keeps it; spawning a process needs a running app
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_message(id: &str, conversation_id: Option<&str>) -> AgentRequest {
+        AgentRequest::UserMessage {
+            id: id.to_string(),
+            message: "hi".to_string(),
+            images: None,
+            conversation_id: conversation_id.map(str::to_string),
+        }
+    }
+
+    fn pending(requests: &[(&str, Option<&str>)]) -> HashMap<String, PendingRequest> {
+        requests
+            .iter()
+            .map(|(id, conversation_id)| {
+                let request = user_message(id, *conversation_id);
+                (id.to_string(), PendingRequest::new(request, None, None))
+            })
+            .collect()
+    }
+
+    fn response(json: serde_json::Value) -> AgentResponse {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn sorted(mut ids: Vec<String>) -> Vec<String> {
+        ids.sort();
+        ids
+    }
+
+    // What emit_response sent, as (agent id, owner, response); an app without windows
+    // has no webview to hear it
+    static EMITTED: std::sync::Mutex<Vec<(String, Option<String>, AgentResponse)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    pub(super) fn record_emitted(agent_id: &str, owner: Option<&str>, response: &AgentResponse) {
+        EMITTED.lock().unwrap().push((
+            agent_id.to_string(),
+            owner.map(str::to_string),
+            response.clone(),
+        ));
+    }
+
+    #[test]
+    fn in_flight_requests_match_by_id_and_conversation() {
+        let pending = pending(&[("a", None), ("b", Some("work")), ("c", Some("work"))]);
+
+        assert_eq!(sorted(matching(&pending, None, None)), ["a", "b", "c"]);
+        assert_eq!(matching(&pending, Some("b"), None), ["b"]);
+        assert_eq!(sorted(matching(&pending, None, Some("work"))), ["b", "c"]);
+        assert_eq!(
+            matching(&pending, None, Some(DEFAULT_CONVERSATION_ID)),
+            ["a"]
+        );
+        assert!(matching(&pending, Some("a"), Some("work")).is_empty());
+    }
+
+    #[test]
+    fn only_silent_requests_are_overdue() {
+        let mut pending = pending(&[("quiet", None), ("busy", None), ("backing_off", None)]);
+        let minute = Duration::from_secs(60);
+        if let Some(earlier) = Instant::now().checked_sub(2 * minute) {
+            pending.get_mut("quiet").unwrap().last_activity = earlier;
+        }
+        // Rate-limited requests are quiet until resent, as set by the reader
+        pending.get_mut("backing_off").unwrap().last_activity = Instant::now() + 2 * minute;
+
+        assert_eq!(overdue(&pending, minute), ["quiet"]);
+    }
+
+    #[test]
+    fn retry_drops_the_interrupted_generation_then_streams() {
+        let mut pending = pending(&[("a", None)]);
+        let entry = pending.get_mut("a").unwrap();
+        entry.response.push_str("half a rep");
+        entry.paused_at = Some(4);
+        entry.state = MessageState::Streaming;
+
+        entry.restart();
+        assert_eq!(entry.request.id(), "a");
+        assert_eq!(entry.stall_retries, 1);
+        assert!(entry.response.is_empty());
+        assert_eq!(entry.paused_at, Some(0));
+        assert_eq!(entry.state, MessageState::Sent);
+
+        let token = response(serde_json::json!({
+            "type": "token",
+            "id": "a",
+            "token": "ly",
+            "timestamp": 1,
+        }));
+        let interrupted = response(serde_json::json!({
+            "type": "error",
+            "id": "a",
+            "error": "Interrupted",
+            "code": "interrupted",
+            "timestamp": 2,
+        }));
+        assert!(entry.is_stale(&token));
+        assert!(entry.is_stale(&interrupted));
+        assert!(!entry.is_stale(&token));
+    }
+
+    #[test]
+    fn replay_keeps_its_request_and_loses_the_rest() {
+        let pending = pending(&[("a", None), ("b", None), ("c", Some("work"))]);
+
+        let (replayed, lost) = split_replay(pending, "b");
+        assert_eq!(
+            replayed.map(|entry| entry.request.id().to_string()),
+            Some("b".to_string())
+        );
+        assert_eq!(sorted(lost.into_keys().collect()), ["a", "c"]);
+
+        let (replayed, lost) = split_replay(HashMap::new(), "b");
+        assert!(replayed.is_none() && lost.is_empty());
+    }
+
+    // AgentProcess against tests/support/fake_agent.rs, built by
+    // `cargo test --features integration-tests`. Needs a display (xvfb-run on CI), and
+    // Linux, the only platform where the event loop can live off the main thread and
+    // settings come from XDG_CONFIG_HOME.
+    #[cfg(all(feature = "integration-tests", target_os = "linux"))]
+    mod process {
+        use super::*;
+        use crate::AppState;
+
+        const AGENT_ID: &str = "fake";
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        // A windowless app with main's state, keeping its files in a scratch dir
+        fn app() -> tauri::App {
+            let scratch =
+                std::env::temp_dir().join(format!("asst-agent-ipc-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&scratch);
+            std::env::set_var("ASST_DATA_DIR", scratch.join("data"));
+            std::env::set_var("XDG_CONFIG_HOME", scratch.join("config"));
+            // target/debug/fake-agent, next to the deps dir this test runs from
+            let exe = std::env::current_exe().unwrap();
+            let fake_agent = exe.parent().unwrap().parent().unwrap().join("fake-agent");
+            std::env::set_var("ASST_AGENT_COMMAND", fake_agent);
+
+            let mut context = tauri::generate_context!();
+            context.config_mut().tauri.windows.clear();
+            let app = crate::manage_state(tauri::Builder::default().any_thread())
+                .build(context)
+                .expect("Failed to build the app");
+            crate::open_stores(&app, None);
+            app
+        }
+
+        fn message(id: &str, text: &str) -> AgentRequest {
+            AgentRequest::UserMessage {
+                id: id.to_string(),
+                message: text.to_string(),
+                images: None,
+                conversation_id: None,
+            }
+        }
+
+        // The fake agent's responses to request `id`, with the window each went to
+        fn emitted(id: &str) -> Vec<(Option<String>, AgentResponse)> {
+            EMITTED
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(agent_id, _, response)| agent_id == AGENT_ID && response.id() == Some(id))
+                .map(|(_, owner, response)| (owner.clone(), response.clone()))
+                .collect()
+        }
+
+        fn finished(id: &str) -> bool {
+            emitted(id).iter().any(|(_, response)| {
+                matches!(
+                    response,
+                    AgentResponse::Done { .. } | AgentResponse::Error { .. }
+                )
+            })
+        }
+
+        async fn wait_until_finished(ids: &[&str]) {
+            let waited = tokio::time::timeout(TIMEOUT, async {
+                while !ids.iter().all(|id| finished(id)) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await;
+            assert!(waited.is_ok(), "Requests {:?} never finished", ids);
+        }
+
+        fn text(id: &str) -> String {
+            emitted(id)
+                .iter()
+                .filter_map(|(_, response)| match response {
+                    AgentResponse::Token { token, .. } => Some(token.as_str()),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn owners(id: &str) -> Vec<Option<String>> {
+            let mut owners: Vec<_> = emitted(id).into_iter().map(|(owner, _)| owner).collect();
+            owners.dedup();
+            owners
+        }
+
+        fn error_code(id: &str) -> Option<String> {
+            emitted(id)
+                .into_iter()
+                .find_map(|(_, response)| match response {
+                    AgentResponse::Error { code, .. } => code,
+                    _ => None,
+                })
+        }
+
+        #[test]
+        fn routes_replies_and_fails_the_requests_a_crash_loses() {
+            let app = app();
+            let handle = app.handle();
+
+            tauri::async_runtime::block_on(async move {
+                let slot = handle.state::<AppState>().slot(AGENT_ID);
+                assert!(crate::start_agent(&handle, AGENT_ID, &slot)
+                    .await
+                    .expect("Failed to start the fake agent"));
+
+                // Interleaved replies reach the window that asked, each under its own id
+                {
+                    let mut agent = slot.lock().await;
+                    let process = agent.as_mut().unwrap();
+                    process
+                        .send_request(&message("a", "one two three"), Some("left".to_string()))
+                        .await
+                        .unwrap();
+                    process
+                        .send_request(&message("b", "four five"), Some("right".to_string()))
+                        .await
+                        .unwrap();
+                }
+                wait_until_finished(&["a", "b"]).await;
+                assert_eq!(text("a"), "one two three");
+                assert_eq!(text("b"), "four five");
+                assert_eq!(owners("a"), [Some("left".to_string())]);
+                assert_eq!(owners("b"), [Some("right".to_string())]);
+                let agent = slot.lock().await;
+                assert!(agent.as_ref().unwrap().in_flight_ids().await.is_empty());
+                drop(agent);
+
+                // A crash fails what was in flight, to the windows that sent it
+                let pending = {
+                    let mut agent = slot.lock().await;
+                    let process = agent.as_mut().unwrap();
+                    process
+                        .send_request(&message("c", "!stall"), Some("left".to_string()))
+                        .await
+                        .unwrap();
+                    process
+                        .send_request(&message("d", "!crash"), Some("right".to_string()))
+                        .await
+                        .unwrap();
+                    process.pending.clone()
+                };
+                wait_until_finished(&["c", "d"]).await;
+                assert!(pending.lock().await.is_empty());
+                assert_eq!(error_code("c").as_deref(), Some("agent_exited"));
+                assert_eq!(error_code("d").as_deref(), Some("agent_exited"));
+                assert_eq!(owners("c"), [Some("left".to_string())]);
+                assert_eq!(owners("d"), [Some("right".to_string())]);
+
+                if let Some(process) = slot.lock().await.take() {
+                    process.shutdown().await;
+                }
+            });
+            drop(app);
+        }
+    }
 }
@@ -0,0 +1,245 @@
+use crate::data_dir;
+use crate::store;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+use ts_rs::TS;
+
+// Sessions kept on disk; the oldest are deleted as new ones start
+const MAX_SESSIONS: usize = 30;
+// A session's log moves to <id>.log.1 past this size, so each keeps at most twice that
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// The lifetime of one agent process, saved as <id>.json next to its log.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AgentSession {
+    pub session_id: String,
+    pub agent_id: String,
+    #[ts(as = "f64")]
+    pub started_at: i64,
+    // None while running, or if the app itself went down first
+    #[ts(as = "Option<f64>")]
+    pub ended_at: Option<i64>,
+    pub exit_code: Option<i32>,
+    pub crashed: bool,
+}
+
+/// Appends an agent process's stdout and stderr to its session log. Clones share the
+/// file. Logging is best effort: if the file can't be written, lines are dropped.
+#[derive(Clone)]
+pub struct SessionLog {
+    session_id: String,
+    writer: Option<Arc<Mutex<Writer>>>,
+}
+
+struct Writer {
+    dir: PathBuf,
+    session: AgentSession,
+    file: File,
+    written: u64,
+}
+
+/// Past agent sessions, newest first.
+#[tauri::command]
+pub fn list_agent_sessions(app_handle: AppHandle) -> Result<Vec<AgentSession>, String> {
+    let Some(dir) = logs_dir(&app_handle) else {
+        return Ok(Vec::new());
+    };
+    let mut sessions: Vec<AgentSession> = session_ids(&dir)
+        .iter()
+        .filter_map(|id| read_session(&dir, id).ok())
+        .collect();
+    sessions.reverse();
+    Ok(sessions)
+}
+
+/// Everything the agent wrote during `session_id`, as far as rotation kept it.
+#[tauri::command]
+pub fn get_agent_session_logs(app_handle: AppHandle, session_id: String) -> Result<String, String> {
+    let dir = logs_dir(&app_handle).ok_or_else(|| "No data directory available".to_string())?;
+    read_log(&dir, &session_id).map_err(|e| format!("Failed to read agent logs: {}", e))
+}
+
+impl SessionLog {
+    pub fn start(app_handle: &AppHandle, agent_id: &str, serial: u64) -> Self {
+        let started_at = store::now_millis();
+        // Millisecond timestamps first, so ids sort by age
+        let session_id = format!("{}-{}", started_at, serial);
+        let session = AgentSession {
+            session_id: session_id.clone(),
+            agent_id: agent_id.to_string(),
+            started_at,
+            ended_at: None,
+            exit_code: None,
+            crashed: false,
+        };
+
+        let writer = logs_dir(app_handle).and_then(|dir| match Writer::open(dir, session) {
+            Ok(writer) => Some(Arc::new(Mutex::new(writer))),
+            Err(e) => {
+                eprintln!("Failed to start agent session log: {}", e);
+                None
+            }
+        });
+        SessionLog { session_id, writer }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Appends one line of output from `stream` ("stdout" or "stderr").
+    pub fn line(&self, stream: &str, line: &str) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let mut writer = writer.lock().unwrap();
+        if let Err(e) = writer.append(stream, line) {
+            eprintln!("Failed to write agent session log: {}", e);
+        }
+    }
+
+    /// Records how the process ended.
+    pub fn finish(&self, exit_code: Option<i32>, crashed: bool) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let mut writer = writer.lock().unwrap();
+        writer.session.ended_at = Some(store::now_millis());
+        writer.session.exit_code = exit_code;
+        writer.session.crashed = crashed;
+        if let Err(e) = write_session(&writer.dir, &writer.session) {
+            eprintln!("Failed to finish agent session log: {}", e);
+        }
+    }
+}
+
+impl Writer {
+    fn open(dir: PathBuf, session: AgentSession) -> Result<Self> {
+        std::fs::create_dir_all(&dir).context("Failed to create agent log directory")?;
+        prune(&dir);
+        write_session(&dir, &session)?;
+        let file = File::create(log_path(&dir, &session.session_id, false))
+            .context("Failed to create agent log")?;
+
+        Ok(Writer {
+            dir,
+            session,
+            file,
+            written: 0,
+        })
+    }
+
+    fn append(&mut self, stream: &str, line: &str) -> Result<()> {
+        if self.written >= MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        let entry = format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            stream,
+            line
+        );
+        self.file.write_all(entry.as_bytes())?;
+        self.written += entry.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let id = &self.session.session_id;
+        std::fs::rename(
+            log_path(&self.dir, id, false),
+            log_path(&self.dir, id, true),
+        )
+        .context("Failed to rotate agent log")?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(log_path(&self.dir, id, false))
+            .context("Failed to reopen agent log")?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn logs_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("agent-logs"))
+}
+
+fn log_path(dir: &Path, session_id: &str, rotated: bool) -> PathBuf {
+    if rotated {
+        dir.join(format!("{}.log.1", session_id))
+    } else {
+        dir.join(format!("{}.log", session_id))
+    }
+}
+
+fn write_session(dir: &Path, session: &AgentSession) -> Result<()> {
+    let json = serde_json::to_string_pretty(session)?;
+    std::fs::write(dir.join(format!("{}.json", session.session_id)), json)
+        .context("Failed to write agent session")
+}
+
+fn read_session(dir: &Path, session_id: &str) -> Result<AgentSession> {
+    let json = std::fs::read_to_string(dir.join(format!("{}.json", session_id)))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn read_log(dir: &Path, session_id: &str) -> Result<String> {
+    // Ids come from the frontend; only ones we generated may name a file
+    if !session_id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return Err(anyhow!("Invalid session id {}", session_id));
+    }
+    if !dir.join(format!("{}.json", session_id)).exists() {
+        return Err(anyhow!("No agent session {}", session_id));
+    }
+
+    let mut log = std::fs::read_to_string(log_path(dir, session_id, true)).unwrap_or_default();
+    match std::fs::read_to_string(log_path(dir, session_id, false)) {
+        Ok(current) => log.push_str(&current),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(log)
+}
+
+// Ids of the sessions on disk, oldest first
+fn session_ids(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.strip_suffix(".json").map(str::to_string)
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+// Makes room for one more session
+fn prune(dir: &Path) {
+    let ids = session_ids(dir);
+    let excess = (ids.len() + 1).saturating_sub(MAX_SESSIONS);
+    for id in &ids[..excess] {
+        for path in [
+            dir.join(format!("{}.json", id)),
+            log_path(dir, id, false),
+            log_path(dir, id, true),
+        ] {
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => eprintln!("Failed to remove old agent log {:?}: {}", path, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::agent_ipc::{self, DEFAULT_AGENT_ID};
+use crate::agent_runtime::AgentCommand;
+use crate::http_agent::HttpEndpoint;
+use crate::i18n;
+use crate::secrets;
+use crate::settings::SettingsStore;
+use crate::standby;
+use crate::AppState;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, CustomMenuItem, Manager, State, SystemTrayMenu, SystemTraySubmenu};
+
+/// Tray menu ids for agent profiles; the built-in agent's id has an empty profile id.
+pub const TRAY_PREFIX: &str = "agent-profile:";
+
+/// A named way to run the agent: its command or endpoint, extra environment and model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentProfile {
+    pub id: String,
+    pub name: String,
+    // Replaces the built-in agent (and the agent_command setting) when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<AgentCommand>,
+    // Runs the agent against an OpenAI-compatible endpoint instead of a process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<HttpEndpoint>,
+    // Values to store; saving moves them to the keychain and their names to secret_env
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    // Variables passed to the agent whose values live in the OS keychain
+    #[serde(default)]
+    pub secret_env: Vec<String>,
+    // Passed to the agent as ANTHROPIC_MODEL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+}
+
+/// Profiles of agents spawned with one, by agent id. The default agent's is the
+/// agent_profile setting instead, so it survives restarts.
+#[derive(Default)]
+pub struct AgentProfiles {
+    assigned: Mutex<HashMap<String, String>>,
+}
+
+#[tauri::command]
+pub fn list_agent_profiles(settings: State<'_, SettingsStore>) -> Vec<AgentProfile> {
+    settings.get().agent_profiles
+}
+
+/// Adds `profile`, or replaces the one with the same id. Its environment goes to the
+/// keychain. Agents running under it pick up the change when they next restart.
+#[tauri::command]
+pub fn save_agent_profile(app_handle: AppHandle, mut profile: AgentProfile) -> Result<(), String> {
+    if !valid_id(&profile.id) {
+        return Err(format!("Invalid profile id: {}", profile.id));
+    }
+    if profile.name.trim().is_empty() {
+        return Err("The profile needs a name".to_string());
+    }
+    if let Some(command) = &profile.command {
+        if command.program.trim().is_empty() {
+            return Err("The profile's command needs a program".to_string());
+        }
+    }
+    if let Some(endpoint) = &profile.endpoint {
+        if profile.command.is_some() {
+            return Err("The profile can have a command or an endpoint, not both".to_string());
+        }
+        if endpoint.base_url.trim().is_empty() || endpoint.model.trim().is_empty() {
+            return Err("The profile's endpoint needs a URL and a model".to_string());
+        }
+    }
+
+    let settings = app_handle.state::<SettingsStore>().get();
+    let previous = settings.agent_profiles.iter().find(|p| p.id == profile.id);
+    secrets::store_profile_env(&mut profile, previous)
+        .map_err(|e| format!("Failed to save the profile's environment: {}", e))?;
+
+    app_handle
+        .state::<SettingsStore>()
+        .update(
+            |s| match s.agent_profiles.iter_mut().find(|p| p.id == profile.id) {
+                Some(existing) => *existing = profile,
+                None => s.agent_profiles.push(profile),
+            },
+        )
+        .map_err(|e| format!("Failed to save agent profile: {}", e))?;
+    crate::refresh_tray(&app_handle);
+    Ok(())
+}
+
+/// Removes a profile. The default agent falls back to the built-in one if it used it.
+#[tauri::command]
+pub fn delete_agent_profile(app_handle: AppHandle, id: String) -> Result<(), String> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    if let Some(profile) = settings.agent_profiles.iter().find(|p| p.id == id) {
+        secrets::delete_profile_env(profile);
+    }
+    app_handle
+        .state::<SettingsStore>()
+        .update(|s| {
+            s.agent_profiles.retain(|p| p.id != id);
+            if s.agent_profile.as_deref() == Some(id.as_str()) {
+                s.agent_profile = None;
+            }
+        })
+        .map_err(|e| format!("Failed to delete agent profile: {}", e))?;
+    app_handle
+        .state::<AgentProfiles>()
+        .assigned
+        .lock()
+        .unwrap()
+        .retain(|_, profile_id| *profile_id != id);
+    crate::refresh_tray(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_agent_profile(app_handle: AppHandle, agent_id: Option<String>) -> Option<String> {
+    profile_id_for(&app_handle, agent_id.as_deref().unwrap_or(DEFAULT_AGENT_ID))
+}
+
+/// Runs the default agent under `profile_id` (the built-in agent when None) from now
+/// on, restarting it if it is running. The active conversation is reloaded and the
+/// conversation list is untouched, as both live outside the process.
+#[tauri::command]
+pub async fn switch_agent_profile(
+    app_handle: AppHandle,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    if profile_id_for(&app_handle, DEFAULT_AGENT_ID) == profile_id {
+        return Ok(());
+    }
+    assign(&app_handle, DEFAULT_AGENT_ID, profile_id)
+        .map_err(|e| format!("Failed to switch agent profile: {}", e))?;
+    crate::refresh_tray(&app_handle);
+
+    let slot = app_handle.state::<AppState>().agent();
+    // The standby was spawned under the old profile
+    standby::discard(&app_handle).await;
+    let restarted = agent_ipc::restart(&slot, None)
+        .await
+        .map_err(|e| format!("Failed to restart agent: {}", e))?;
+    if let Some(lost_requests) = restarted {
+        agent_ipc::emit_lifecycle(
+            &app_handle,
+            DEFAULT_AGENT_ID,
+            "agent_stopped",
+            lost_requests,
+        );
+        agent_ipc::emit_lifecycle(&app_handle, DEFAULT_AGENT_ID, "agent_started", Vec::new());
+        standby::replenish(&app_handle);
+    }
+    Ok(())
+}
+
+/// Adds the profiles of a settings file that aren't here yet, without their
+/// environment values, which never leave this machine. None is activated.
+pub fn import(app_handle: &AppHandle, imported: Vec<AgentProfile>) -> Result<(usize, Vec<String>)> {
+    let existing = app_handle.state::<SettingsStore>().get().agent_profiles;
+    let mut added = Vec::new();
+    let mut warnings = Vec::new();
+
+    for mut profile in imported {
+        if existing.iter().any(|p| p.id == profile.id) {
+            continue;
+        }
+        if !valid_id(&profile.id) {
+            warnings.push(format!(
+                "Skipped agent profile with invalid id {}",
+                profile.id
+            ));
+            continue;
+        }
+        profile.env.clear();
+        if let Some(command) = profile.command.as_mut() {
+            command.env.clear();
+        }
+        if !profile.secret_env.is_empty() {
+            warnings.push(format!(
+                "Agent profile {} needs values for {}",
+                profile.name,
+                profile.secret_env.join(", ")
+            ));
+        }
+        added.push(profile);
+    }
+
+    let count = added.len();
+    app_handle
+        .state::<SettingsStore>()
+        .update(|s| s.agent_profiles.extend(added))?;
+    crate::refresh_tray(app_handle);
+    Ok((count, warnings))
+}
+
+/// Records the profile `agent_id` runs under from its next spawn on.
+pub fn assign(app_handle: &AppHandle, agent_id: &str, profile_id: Option<String>) -> Result<()> {
+    if let Some(profile_id) = &profile_id {
+        let settings = app_handle.state::<SettingsStore>().get();
+        if !settings.agent_profiles.iter().any(|p| &p.id == profile_id) {
+            return Err(anyhow!("No agent profile {}", profile_id));
+        }
+    }
+
+    if agent_id == DEFAULT_AGENT_ID {
+        return app_handle
+            .state::<SettingsStore>()
+            .update(|s| s.agent_profile = profile_id);
+    }
+    let profiles = app_handle.state::<AgentProfiles>();
+    let mut assigned = profiles.assigned.lock().unwrap();
+    match profile_id {
+        Some(profile_id) => assigned.insert(agent_id.to_string(), profile_id),
+        None => assigned.remove(agent_id),
+    };
+    Ok(())
+}
+
+/// The profile `agent_id` is spawned with, if any.
+pub fn profile_for(app_handle: &AppHandle, agent_id: &str) -> Option<AgentProfile> {
+    let profile_id = profile_id_for(app_handle, agent_id)?;
+    let settings = app_handle.state::<SettingsStore>().get();
+    let profile = settings
+        .agent_profiles
+        .into_iter()
+        .find(|p| p.id == profile_id);
+    if profile.is_none() {
+        eprintln!("[PROFILE] Agent profile {} no longer exists", profile_id);
+    }
+    profile
+}
+
+/// Agent profiles as a tray submenu with the default agent's checked, or None while
+/// there are no profiles.
+pub fn tray_submenu(app_handle: &AppHandle) -> Option<SystemTraySubmenu> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    if settings.agent_profiles.is_empty() {
+        return None;
+    }
+
+    let active = settings.agent_profile.unwrap_or_default();
+    let builtin = (String::new(), i18n::t("tray-agent-profile-builtin"));
+    let profiles = settings
+        .agent_profiles
+        .into_iter()
+        .map(|profile| (profile.id, profile.name));
+    let mut menu = SystemTrayMenu::new();
+    for (id, name) in std::iter::once(builtin).chain(profiles) {
+        let item = CustomMenuItem::new(format!("{}{}", TRAY_PREFIX, id), name);
+        menu = menu.add_item(if id == active { item.selected() } else { item });
+    }
+    Some(SystemTraySubmenu::new(i18n::t("tray-agent-profiles"), menu))
+}
+
+/// Handles a click on an agent profile in the tray menu; `id` follows TRAY_PREFIX.
+pub fn on_tray_click(app_handle: &AppHandle, id: &str) {
+    let profile_id = Some(id).filter(|id| !id.is_empty()).map(str::to_string);
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = switch_agent_profile(app_handle, profile_id).await {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+fn profile_id_for(app_handle: &AppHandle, agent_id: &str) -> Option<String> {
+    if agent_id == DEFAULT_AGENT_ID {
+        return app_handle.state::<SettingsStore>().get().agent_profile;
+    }
+    let profiles = app_handle.state::<AgentProfiles>();
+    let assigned = profiles.assigned.lock().unwrap();
+    assigned.get(agent_id).cloned()
+}
+
+// Ids appear in tray menu ids, so they're kept simple
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
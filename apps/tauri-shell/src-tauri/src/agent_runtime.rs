@@ -0,0 +1,154 @@
+use crate::agent_ipc;
+use crate::settings::SettingsStore;
+use crate::transport::TransportKind;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use tokio::process::Command;
+
+// Declared under bundle.externalBin in tauri.sidecar.conf.json; the bundler installs
+// it next to the app binary without the target triple suffix
+const SIDECAR_NAME: &str = "agent-runtime";
+
+/// How the built-in agent is started when no managed build or override applies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentRuntime {
+    // The sidecar when one is bundled, otherwise the dev checkout in debug builds
+    #[default]
+    Auto,
+    Sidecar,
+    // `npx tsx src/index.ts` in apps/agent-runtime
+    Dev,
+}
+
+/// A user-supplied agent implementation; anything speaking the line-delimited JSON
+/// protocol on stdin/stdout works. Replaces managed builds and the built-in runtime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    // Added to the shell's own environment
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    // Stdio unless the program handles ASST_SOCKET
+    #[serde(default)]
+    pub transport: TransportKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentRuntimeInfo {
+    pub runtime: AgentRuntime,
+    pub sidecar_path: Option<String>,
+    pub sidecar_available: bool,
+}
+
+#[tauri::command]
+pub fn get_agent_runtime(settings: State<'_, SettingsStore>) -> AgentRuntimeInfo {
+    AgentRuntimeInfo {
+        runtime: settings.get().agent_runtime,
+        sidecar_path: sidecar_path().map(|path| path.to_string_lossy().into_owned()),
+        sidecar_available: sidecar_available(),
+    }
+}
+
+/// Takes effect the next time the agent is spawned.
+#[tauri::command]
+pub fn set_agent_runtime(
+    settings: State<'_, SettingsStore>,
+    runtime: AgentRuntime,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.agent_runtime = runtime)
+        .map_err(|e| format!("Failed to save agent runtime: {}", e))
+}
+
+#[tauri::command]
+pub fn get_agent_command(settings: State<'_, SettingsStore>) -> Option<AgentCommand> {
+    settings.get().agent_command
+}
+
+/// Sets or, with None, clears the custom agent command. Takes effect the next time
+/// the agent is spawned.
+#[tauri::command]
+pub fn set_agent_command(
+    settings: State<'_, SettingsStore>,
+    command: Option<AgentCommand>,
+) -> Result<(), String> {
+    if let Some(command) = &command {
+        if command.program.trim().is_empty() {
+            return Err("The agent command needs a program".to_string());
+        }
+        if let Some(cwd) = &command.cwd {
+            if !cwd.is_dir() {
+                return Err(format!("{} is not a directory", cwd.display()));
+            }
+        }
+    }
+
+    settings
+        .update(|s| s.agent_command = command)
+        .map_err(|e| format!("Failed to save agent command: {}", e))
+}
+
+/// The process described by a custom agent command.
+pub fn build_command(custom: &AgentCommand) -> Command {
+    eprintln!(
+        "[DEBUG] Spawning custom agent: {} {:?}",
+        custom.program, custom.args
+    );
+
+    let mut command = Command::new(&custom.program);
+    command.args(&custom.args).envs(&custom.env);
+    if let Some(cwd) = &custom.cwd {
+        command.current_dir(cwd);
+    }
+    command
+}
+
+/// The command starting the built-in agent according to the agent_runtime setting.
+pub fn command(app_handle: &AppHandle) -> Result<Command> {
+    let runtime = app_handle.state::<SettingsStore>().get().agent_runtime;
+    let sidecar = sidecar_path().filter(|path| path.exists());
+
+    match (runtime, sidecar) {
+        (AgentRuntime::Sidecar | AgentRuntime::Auto, Some(path)) => {
+            eprintln!("[DEBUG] Spawning agent sidecar: {:?}", path);
+            Ok(Command::new(path))
+        }
+        (AgentRuntime::Sidecar, None) => Err(anyhow!("No agent sidecar is bundled")),
+        // Release builds have no checkout to fall back to
+        (AgentRuntime::Auto, None) if !cfg!(debug_assertions) => Err(anyhow!(
+            "The agent sidecar is missing from this installation"
+        )),
+        (AgentRuntime::Auto | AgentRuntime::Dev, _) => dev_command(),
+    }
+}
+
+fn dev_command() -> Result<Command> {
+    let agent_path = agent_ipc::dev_runtime_dir()?;
+
+    eprintln!("[DEBUG] Spawning agent process from: {:?}", agent_path);
+
+    let mut command = Command::new("npx");
+    command
+        .arg("tsx")
+        .arg("src/index.ts")
+        .current_dir(&agent_path);
+    Ok(command)
+}
+
+pub fn sidecar_available() -> bool {
+    sidecar_path().map(|path| path.exists()).unwrap_or(false)
+}
+
+fn sidecar_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let file_name = format!("{}{}", SIDECAR_NAME, std::env::consts::EXE_SUFFIX);
+    Some(exe.parent()?.join(file_name))
+}
@@ -0,0 +1,287 @@
+use crate::agent_ipc::{self, AgentProcess, DEFAULT_AGENT_ID};
+use crate::data_dir;
+use crate::network_config;
+use crate::outbox;
+use crate::standby;
+use crate::AppState;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// Agent builds are released independently of the shell
+const AGENT_MANIFEST_URL: &str =
+    "https://raw.githubusercontent.com/ericmday/asst/releases/agent-runtime.json";
+
+// File name of the single-file agent bundle inside each version directory
+const BUNDLE_FILE: &str = "agent.mjs";
+
+// Minisign public key agent builds are signed with, base64 as `tauri signer generate`
+// prints it. Set by release builds; without it agent updates are off, since the
+// manifest alone can't vouch for the code it points to.
+const SIGNING_KEY: Option<&str> = option_env!("ASST_AGENT_SIGNING_KEY");
+
+#[derive(Debug, Clone, Deserialize)]
+struct AgentManifest {
+    version: String,
+    url: String,
+    sha256: String,
+    // Minisign signature of the bundle, base64 like the key
+    signature: String,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+// Which managed versions are installed; persisted as agent/installed.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct InstalledAgents {
+    current: Option<String>,
+    previous: Option<String>,
+}
+
+pub struct AgentBundle {
+    pub version: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AgentUpdateInfo {
+    pub available: bool,
+    pub current_version: Option<String>,
+    pub latest_version: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentUpdateEvent {
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The managed agent build to run, or None to fall back to the bundled/dev agent.
+pub fn active_bundle(app_handle: &AppHandle) -> Option<AgentBundle> {
+    let version = read_installed(app_handle).current?;
+    let path = agents_dir(app_handle)?.join(&version).join(BUNDLE_FILE);
+
+    path.exists().then_some(AgentBundle { version, path })
+}
+
+#[tauri::command]
+pub fn get_agent_version(app_handle: AppHandle) -> Option<String> {
+    active_bundle(&app_handle).map(|bundle| bundle.version)
+}
+
+#[tauri::command]
+pub async fn check_agent_update(app_handle: AppHandle) -> Result<AgentUpdateInfo, String> {
+    signing_key().map_err(|e| e.to_string())?;
+    let manifest = fetch_manifest()
+        .await
+        .map_err(|e| format!("Failed to check for agent updates: {}", e))?;
+    let current_version = active_bundle(&app_handle).map(|bundle| bundle.version);
+
+    Ok(AgentUpdateInfo {
+        available: is_newer(&manifest.version, current_version.as_deref()),
+        current_version,
+        latest_version: manifest.version,
+        notes: manifest.notes,
+    })
+}
+
+/// Downloads the latest agent build, checks its signature and starts it beside the
+/// running agent, which it replaces once ready. Requests the old build still had in
+/// flight are failed. If the new agent doesn't become ready, the old one keeps running.
+#[tauri::command]
+pub async fn install_agent_update(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let key = signing_key().map_err(|e| e.to_string())?;
+    let manifest = fetch_manifest()
+        .await
+        .map_err(|e| format!("Failed to check for agent updates: {}", e))?;
+    let installed = read_installed(&app_handle);
+
+    if !is_newer(&manifest.version, installed.current.as_deref()) {
+        return Err("Agent is already up to date".to_string());
+    }
+
+    download(&app_handle, &manifest, &key)
+        .await
+        .map_err(|e| format!("Failed to download agent update: {}", e))?;
+
+    let updated = InstalledAgents {
+        current: Some(manifest.version.clone()),
+        previous: installed.current.clone(),
+    };
+    write_installed(&app_handle, &updated).map_err(|e| e.to_string())?;
+    // The standby still runs the old build
+    standby::discard(&app_handle).await;
+
+    // Started without the slot lock, so the running agent stays usable meanwhile
+    let mut process = match AgentProcess::spawn(app_handle.clone(), DEFAULT_AGENT_ID).await {
+        Ok(process) => process,
+        Err(e) => {
+            eprintln!(
+                "Agent {} failed readiness, rolling back: {}",
+                manifest.version, e
+            );
+
+            if let Err(e) = write_installed(&app_handle, &installed) {
+                eprintln!("Failed to restore previous agent version: {}", e);
+            }
+            standby::replenish(&app_handle);
+
+            emit(
+                &app_handle,
+                "agent_update_failed",
+                &manifest.version,
+                Some(e.to_string()),
+            );
+            return Err(format!("Agent update failed and was rolled back: {}", e));
+        }
+    };
+
+    let old = {
+        let mut agent = state.agent().lock_owned().await;
+        if let Some(current) = agent.as_ref() {
+            if let Err(e) = current.hand_over(&mut process).await {
+                eprintln!("Failed to hand over to the updated agent: {}", e);
+            }
+        }
+        outbox::flush(&app_handle, &mut process).await;
+        agent.replace(process)
+    };
+    if let Some(old) = old {
+        let lost = old.shutdown().await;
+        agent_ipc::emit_lifecycle(&app_handle, DEFAULT_AGENT_ID, "agent_stopped", lost);
+    }
+
+    standby::replenish(&app_handle);
+    remove_stale_versions(&app_handle, &updated);
+    emit(&app_handle, "agent_updated", &manifest.version, None);
+    Ok(manifest.version)
+}
+
+fn signing_key() -> Result<PublicKey> {
+    let key = SIGNING_KEY.ok_or_else(|| anyhow!("This build can't verify agent updates"))?;
+    let key = decode_base64_text(key).context("Invalid agent signing key")?;
+    PublicKey::decode(&key).map_err(|e| anyhow!("Invalid agent signing key: {}", e))
+}
+
+// Keys and signatures are the base64 of minisign's text files, as in Tauri's updater
+fn decode_base64_text(value: &str) -> Result<String> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(value.trim())?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+async fn fetch_manifest() -> Result<AgentManifest> {
+    network_config::client()
+        .get(AGENT_MANIFEST_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("Invalid agent manifest")
+}
+
+async fn download(app_handle: &AppHandle, manifest: &AgentManifest, key: &PublicKey) -> Result<()> {
+    let bytes = network_config::client()
+        .get(&manifest.url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let digest = format!("{:x}", Sha256::digest(&bytes));
+    if !digest.eq_ignore_ascii_case(&manifest.sha256) {
+        return Err(anyhow!(
+            "checksum mismatch (expected {}, got {})",
+            manifest.sha256,
+            digest
+        ));
+    }
+    let signature = decode_base64_text(&manifest.signature)
+        .ok()
+        .and_then(|signature| Signature::decode(&signature).ok())
+        .context("Invalid agent bundle signature")?;
+    key.verify(&bytes, &signature, false)
+        .map_err(|e| anyhow!("Agent bundle signature doesn't match: {}", e))?;
+
+    let dir = agents_dir(app_handle)
+        .context("No data directory available")?
+        .join(&manifest.version);
+    std::fs::create_dir_all(&dir).context("Failed to create agent directory")?;
+    std::fs::write(dir.join(BUNDLE_FILE), &bytes).context("Failed to write agent bundle")
+}
+
+fn is_newer(latest: &str, current: Option<&str>) -> bool {
+    let Ok(latest) = semver::Version::parse(latest) else {
+        return false;
+    };
+
+    match current.and_then(|current| semver::Version::parse(current).ok()) {
+        Some(current) => latest > current,
+        None => true,
+    }
+}
+
+// Keeps only the current and previous versions on disk
+fn remove_stale_versions(app_handle: &AppHandle, installed: &InstalledAgents) {
+    let Some(dir) = agents_dir(app_handle) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let keep = installed.current.as_deref() == Some(name.as_str())
+            || installed.previous.as_deref() == Some(name.as_str());
+
+        if entry.path().is_dir() && !keep {
+            if let Err(e) = std::fs::remove_dir_all(entry.path()) {
+                eprintln!("Failed to remove old agent {}: {}", name, e);
+            }
+        }
+    }
+}
+
+fn agents_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("agent"))
+}
+
+fn read_installed(app_handle: &AppHandle) -> InstalledAgents {
+    agents_dir(app_handle)
+        .and_then(|dir| std::fs::read_to_string(dir.join("installed.json")).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn write_installed(app_handle: &AppHandle, installed: &InstalledAgents) -> Result<()> {
+    let dir = agents_dir(app_handle).context("No data directory available")?;
+    std::fs::create_dir_all(&dir)?;
+
+    let json = serde_json::to_string_pretty(installed)?;
+    std::fs::write(dir.join("installed.json"), json).context("Failed to record agent version")
+}
+
+fn emit(app_handle: &AppHandle, event: &str, version: &str, error: Option<String>) {
+    let payload = AgentUpdateEvent {
+        version: version.to_string(),
+        error,
+    };
+
+    if let Err(e) = app_handle.emit_all(event, payload) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
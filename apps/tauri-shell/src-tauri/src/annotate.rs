@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use image::{imageops, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const DEFAULT_ARROW_COLOR: Rgba<u8> = Rgba([230, 40, 40, 255]);
+const DEFAULT_ARROW_THICKNESS: f32 = 4.0;
+const REDACTION_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// One editing step. Coordinates are image pixels, relative to the result of the
+/// previous step (so anything after a crop uses the cropped image's origin).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnotationOp {
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Arrow {
+        from: (f32, f32),
+        to: (f32, f32),
+        // "#rrggbb"
+        color: Option<String>,
+        thickness: Option<f32>,
+    },
+    // Opaque box; the covered pixels are overwritten, not blurred
+    Redact {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Same shape as the agent's ImageAttachment, ready to go into `images`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAttachment {
+    pub data: String,
+    pub mime_type: String,
+    pub name: Option<String>,
+}
+
+/// Applies `ops` in order to the image at `path` and returns it as a PNG attachment.
+/// The file on disk is left untouched.
+#[tauri::command]
+pub async fn annotate_image(
+    path: String,
+    ops: Vec<AnnotationOp>,
+) -> Result<ImageAttachment, String> {
+    tauri::async_runtime::spawn_blocking(move || annotate(Path::new(&path), &ops))
+        .await
+        .map_err(|e| format!("Annotation task failed: {}", e))?
+        .map_err(|e| format!("Failed to annotate image: {}", e))
+}
+
+fn annotate(path: &Path, ops: &[AnnotationOp]) -> Result<ImageAttachment> {
+    let mut image = image::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?
+        .to_rgba8();
+
+    for op in ops {
+        image = apply(image, op)?;
+    }
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .context("Failed to encode image")?;
+
+    let name = path
+        .file_stem()
+        .map(|stem| format!("{}-annotated.png", stem.to_string_lossy()));
+    Ok(ImageAttachment {
+        data: base64::engine::general_purpose::STANDARD.encode(png.into_inner()),
+        mime_type: "image/png".to_string(),
+        name,
+    })
+}
+
+fn apply(mut image: RgbaImage, op: &AnnotationOp) -> Result<RgbaImage> {
+    match *op {
+        AnnotationOp::Crop {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let fits = width > 0
+                && height > 0
+                && x.saturating_add(width) <= image.width()
+                && y.saturating_add(height) <= image.height();
+            if !fits {
+                return Err(anyhow!(
+                    "Crop {}x{}+{}+{} is outside the {}x{} image",
+                    width,
+                    height,
+                    x,
+                    y,
+                    image.width(),
+                    image.height()
+                ));
+            }
+            Ok(imageops::crop_imm(&image, x, y, width, height).to_image())
+        }
+        AnnotationOp::Arrow {
+            from,
+            to,
+            ref color,
+            thickness,
+        } => {
+            let color = match color {
+                Some(hex) => parse_color(hex)?,
+                None => DEFAULT_ARROW_COLOR,
+            };
+            draw_arrow(
+                &mut image,
+                from,
+                to,
+                color,
+                thickness.unwrap_or(DEFAULT_ARROW_THICKNESS).max(1.0),
+            );
+            Ok(image)
+        }
+        AnnotationOp::Redact {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            // Boxes hanging off the edge are clipped rather than rejected
+            let right = x.saturating_add(width).min(image.width());
+            let bottom = y.saturating_add(height).min(image.height());
+            for py in y..bottom {
+                for px in x..right {
+                    image.put_pixel(px, py, REDACTION_COLOR);
+                }
+            }
+            Ok(image)
+        }
+    }
+}
+
+fn parse_color(hex: &str) -> Result<Rgba<u8>> {
+    let digits = hex.trim_start_matches('#');
+    let value = u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|_| digits.len() == 6)
+        .ok_or_else(|| anyhow!("Invalid color {}", hex))?;
+
+    Ok(Rgba([
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+        255,
+    ]))
+}
+
+/// A shaft from `from` to `to` with a two-stroke head at `to`.
+fn draw_arrow(
+    image: &mut RgbaImage,
+    from: (f32, f32),
+    to: (f32, f32),
+    color: Rgba<u8>,
+    thickness: f32,
+) {
+    draw_line(image, from, to, color, thickness);
+
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < f32::EPSILON {
+        return;
+    }
+
+    // Head strokes point back along the shaft at +/-30 degrees
+    let head = (thickness * 4.0 + 8.0).min(length);
+    let angle = dy.atan2(dx);
+    for side in [-1.0f32, 1.0] {
+        let theta = angle + std::f32::consts::PI - side * std::f32::consts::FRAC_PI_6;
+        let end = (to.0 + head * theta.cos(), to.1 + head * theta.sin());
+        draw_line(image, to, end, color, thickness);
+    }
+}
+
+fn draw_line(
+    image: &mut RgbaImage,
+    from: (f32, f32),
+    to: (f32, f32),
+    color: Rgba<u8>,
+    thickness: f32,
+) {
+    let radius = thickness / 2.0;
+    let min_x = (from.0.min(to.0) - radius).floor().max(0.0) as u32;
+    let min_y = (from.1.min(to.1) - radius).floor().max(0.0) as u32;
+    let max_x = ((from.0.max(to.0) + radius).ceil().max(0.0) as u32).min(image.width());
+    let max_y = ((from.1.max(to.1) + radius).ceil().max(0.0) as u32).min(image.height());
+
+    // Every pixel whose centre lies within `radius` of the segment
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let point = (x as f32 + 0.5, y as f32 + 0.5);
+            if distance_to_segment(point, from, to) <= radius {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+fn distance_to_segment(point: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((point.0 - from.0) * dx + (point.1 - from.1) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let (nearest_x, nearest_y) = (from.0 + t * dx, from.1 + t * dy);
+    ((point.0 - nearest_x).powi(2) + (point.1 - nearest_y).powi(2)).sqrt()
+}
@@ -0,0 +1,211 @@
+use crate::annotate::ImageAttachment;
+use crate::store::{Conversation, ConversationStore, StoredMessage};
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+// Longest thumbnail edge in pixels
+const THUMBNAIL_EDGE: u32 = 256;
+
+/// An image attached to a message. `id` identifies it for export_conversation_attachments;
+/// a request and its reply share a message id, so the role is part of it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationAttachment {
+    pub id: String,
+    pub message_id: String,
+    // Position among the message's attachments
+    pub index: usize,
+    pub role: String,
+    pub timestamp: i64,
+    pub name: Option<String>,
+    pub mime_type: String,
+    pub size_bytes: usize,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    // PNG data: URL, None if the image couldn't be decoded
+    pub thumbnail: Option<String>,
+}
+
+/// Every attachment in the conversation, oldest first, with thumbnails for a gallery.
+#[tauri::command]
+pub async fn list_conversation_attachments(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<Vec<ConversationAttachment>, String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+
+    tauri::async_runtime::spawn_blocking(move || list(&conversation))
+        .await
+        .map_err(|e| format!("Attachment task failed: {}", e))
+}
+
+/// Writes the attachments with the given ids (all of them when None) into `dir`.
+/// Returns the paths written.
+#[tauri::command]
+pub async fn export_conversation_attachments(
+    app_handle: AppHandle,
+    conversation_id: String,
+    dir: String,
+    ids: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+
+    tauri::async_runtime::spawn_blocking(move || export(&conversation, Path::new(&dir), ids))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+        .map_err(|e| format!("Failed to export attachments: {}", e))
+}
+
+fn list(conversation: &Conversation) -> Vec<ConversationAttachment> {
+    conversation
+        .messages
+        .iter()
+        .flat_map(|message| {
+            images(message)
+                .into_iter()
+                .enumerate()
+                .map(move |(index, (attachment, bytes))| {
+                    describe(message, index, &attachment, &bytes)
+                })
+        })
+        .collect()
+}
+
+fn describe(
+    message: &StoredMessage,
+    index: usize,
+    attachment: &ImageAttachment,
+    bytes: &[u8],
+) -> ConversationAttachment {
+    let decoded = image::load_from_memory(bytes).ok();
+    let thumbnail = decoded.as_ref().and_then(thumbnail);
+
+    ConversationAttachment {
+        id: attachment_id(message, index),
+        message_id: message.id.clone(),
+        index,
+        role: message.role.clone(),
+        timestamp: message.timestamp,
+        name: attachment.name.clone(),
+        mime_type: attachment.mime_type.clone(),
+        size_bytes: bytes.len(),
+        width: decoded.as_ref().map(|image| image.width()),
+        height: decoded.as_ref().map(|image| image.height()),
+        thumbnail,
+    }
+}
+
+/// A PNG data: URL of `image` scaled down to fit THUMBNAIL_EDGE.
+pub fn thumbnail(image: &image::DynamicImage) -> Option<String> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .thumbnail(THUMBNAIL_EDGE, THUMBNAIL_EDGE)
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .ok()?;
+    let data = base64::engine::general_purpose::STANDARD.encode(png.into_inner());
+    Some(format!("data:image/png;base64,{}", data))
+}
+
+fn export(
+    conversation: &Conversation,
+    dir: &Path,
+    ids: Option<Vec<String>>,
+) -> Result<Vec<String>> {
+    std::fs::create_dir_all(dir).context("Failed to create export directory")?;
+
+    let mut written = Vec::new();
+    for message in &conversation.messages {
+        for (index, (attachment, bytes)) in images(message).into_iter().enumerate() {
+            let id = attachment_id(message, index);
+            if ids.as_ref().is_some_and(|ids| !ids.contains(&id)) {
+                continue;
+            }
+
+            let path = unique_path(dir, &file_name(message, index, &attachment));
+            std::fs::write(&path, bytes)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(written)
+}
+
+fn attachment_id(message: &StoredMessage, index: usize) -> String {
+    format!("{}:{}:{}", message.id, message.role, index)
+}
+
+// The message's attachments with their decoded bytes; undecodable ones are skipped
+fn images(message: &StoredMessage) -> Vec<(ImageAttachment, Vec<u8>)> {
+    let Some(images) = message.images.as_deref() else {
+        return Vec::new();
+    };
+    let images: Vec<ImageAttachment> = match serde_json::from_str(images) {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("Skipping attachments of {}: {}", message.id, e);
+            return Vec::new();
+        }
+    };
+
+    images
+        .into_iter()
+        .filter_map(|attachment| {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(&attachment.data)
+                .ok()?;
+            Some((attachment, bytes))
+        })
+        .collect()
+}
+
+fn file_name(message: &StoredMessage, index: usize, attachment: &ImageAttachment) -> String {
+    // Only the final component, so a stored name can't point outside `dir`
+    let name = attachment
+        .name
+        .as_deref()
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| name.to_string_lossy().into_owned());
+    name.unwrap_or_else(|| {
+        let extension = attachment
+            .mime_type
+            .strip_prefix("image/")
+            .map(|subtype| if subtype == "jpeg" { "jpg" } else { subtype })
+            .unwrap_or("bin");
+        format!(
+            "{}-{}-{}.{}",
+            message.id,
+            message.role,
+            index + 1,
+            extension
+        )
+    })
+}
+
+// `dir/name`, or `dir/stem-2.ext`, ... if that already exists
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{}-{}{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(path)
+}
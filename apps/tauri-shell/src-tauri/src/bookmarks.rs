@@ -0,0 +1,229 @@
+use crate::data_dir;
+use crate::store::{self, ConversationStore};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State, Window};
+
+// Enough of the message to recognise it in the library without loading the conversation
+const EXCERPT_CHARS: usize = 280;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub conversation_id: String,
+    pub message_id: String,
+    pub conversation_title: String,
+    pub role: String,
+    pub excerpt: String,
+    // Emoji reaction, shown instead of the default star
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reaction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub created_at: i64,
+}
+
+impl Bookmark {
+    fn is_for(&self, conversation_id: &str, message_id: &str, role: &str) -> bool {
+        self.conversation_id == conversation_id
+            && self.message_id == message_id
+            && self.role == role
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JumpToMessage<'a> {
+    conversation_id: &'a str,
+    message_id: &'a str,
+    role: &'a str,
+}
+
+/// Starred messages across all conversations, persisted as bookmarks.json in the
+/// app data directory. Entries outlive the conversation they point into, and are keyed
+/// by role too, since a user message and its reply share an id.
+pub struct Bookmarks {
+    path: Option<PathBuf>,
+    items: Mutex<Vec<Bookmark>>,
+}
+
+impl Bookmarks {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = data_dir::resolve(app_handle).map(|dir| dir.join("bookmarks.json"));
+
+        let items = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Bookmarks {
+            path,
+            items: Mutex::new(items),
+        }
+    }
+
+    /// Adds the bookmark, replacing an existing one for the same message.
+    pub fn upsert(&self, bookmark: Bookmark) -> Result<()> {
+        let mut items = self.items.lock().unwrap();
+        items.retain(|item| {
+            !item.is_for(
+                &bookmark.conversation_id,
+                &bookmark.message_id,
+                &bookmark.role,
+            )
+        });
+        items.push(bookmark);
+        self.save(&items)
+    }
+
+    pub fn remove(&self, conversation_id: &str, message_id: &str, role: &str) -> Result<bool> {
+        let mut items = self.items.lock().unwrap();
+        let len = items.len();
+        items.retain(|item| !item.is_for(conversation_id, message_id, role));
+
+        if items.len() == len {
+            return Ok(false);
+        }
+        self.save(&items).map(|_| true)
+    }
+
+    pub fn find(&self, conversation_id: &str, message_id: &str, role: &str) -> Option<Bookmark> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|item| item.is_for(conversation_id, message_id, role))
+            .cloned()
+    }
+
+    /// Points the bookmarks of `from` at `to`, e.g. after the two were merged.
+    pub fn move_conversation(&self, from: &str, to: &str, title: &str) -> Result<()> {
+        let mut items = self.items.lock().unwrap();
+        let mut moved = false;
+        for item in items.iter_mut().filter(|item| item.conversation_id == from) {
+            item.conversation_id = to.to_string();
+            item.conversation_title = title.to_string();
+            moved = true;
+        }
+
+        if !moved {
+            return Ok(());
+        }
+        self.save(&items)
+    }
+
+    /// Newest first.
+    pub fn list(&self) -> Vec<Bookmark> {
+        let mut items = self.items.lock().unwrap().clone();
+        items.sort_by_key(|item| std::cmp::Reverse(item.created_at));
+        items
+    }
+
+    fn save(&self, items: &[Bookmark]) -> Result<()> {
+        let path = self.path.as_ref().context("No data directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+
+        let json = serde_json::to_string(items).context("Failed to serialize bookmarks")?;
+        std::fs::write(path, json).context("Failed to write bookmarks")
+    }
+}
+
+/// Bookmarks a stored message. Bookmarking it again updates the reaction and note.
+#[tauri::command]
+pub fn bookmark_message(
+    store: State<'_, ConversationStore>,
+    bookmarks: State<'_, Bookmarks>,
+    conversation_id: String,
+    message_id: String,
+    role: String,
+    reaction: Option<String>,
+    note: Option<String>,
+) -> Result<Bookmark, String> {
+    let conversation = store
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let message = conversation
+        .find_message(&message_id, &role)
+        .ok_or_else(|| format!("No {} message with id {}", role, message_id))?;
+
+    let excerpt = message
+        .content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let bookmark = Bookmark {
+        conversation_id,
+        message_id,
+        conversation_title: conversation.title.clone(),
+        role: message.role.clone(),
+        excerpt: excerpt.chars().take(EXCERPT_CHARS).collect(),
+        reaction: reaction.filter(|reaction| !reaction.trim().is_empty()),
+        note: note.filter(|note| !note.trim().is_empty()),
+        // Re-bookmarking keeps the entry's place in the library
+        created_at: bookmarks
+            .find(&conversation.id, &message.id, &message.role)
+            .map(|existing| existing.created_at)
+            .unwrap_or_else(store::now_millis),
+    };
+
+    bookmarks
+        .upsert(bookmark.clone())
+        .map_err(|e| format!("Failed to save bookmark: {}", e))?;
+    Ok(bookmark)
+}
+
+#[tauri::command]
+pub fn remove_bookmark(
+    bookmarks: State<'_, Bookmarks>,
+    conversation_id: String,
+    message_id: String,
+    role: String,
+) -> Result<(), String> {
+    let removed = bookmarks
+        .remove(&conversation_id, &message_id, &role)
+        .map_err(|e| format!("Failed to remove bookmark: {}", e))?;
+
+    if !removed {
+        return Err(format!("No bookmark for message {}", message_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_bookmarks(bookmarks: State<'_, Bookmarks>) -> Vec<Bookmark> {
+    bookmarks.list()
+}
+
+/// Asks the calling window to open the bookmarked conversation scrolled to the message.
+/// Fails if the conversation has been deleted since; the bookmark itself is kept.
+#[tauri::command]
+pub fn open_bookmark(
+    window: Window,
+    app_handle: AppHandle,
+    conversation_id: String,
+    message_id: String,
+    role: String,
+) -> Result<(), String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to open bookmark: {}", e))?;
+    if conversation.find_message(&message_id, &role).is_none() {
+        return Err(format!(
+            "Message {} is no longer in the conversation",
+            message_id
+        ));
+    }
+
+    let event = JumpToMessage {
+        conversation_id: &conversation_id,
+        message_id: &message_id,
+        role: &role,
+    };
+    window
+        .emit("jump_to_message", event)
+        .map_err(|e| format!("Failed to emit jump_to_message: {}", e))
+}
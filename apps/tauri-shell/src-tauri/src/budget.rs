@@ -0,0 +1,287 @@
+use crate::data_dir;
+use crate::protocol::Usage;
+use crate::settings::SettingsStore;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Days, Local, Months, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// USD per million input / output tokens, matched against the model id. Estimates for
+// budgeting only; the provider's invoice is authoritative.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("opus", 15.0, 75.0),
+    ("sonnet", 3.0, 15.0),
+    ("haiku", 0.8, 4.0),
+];
+// Unknown models are priced like Sonnet
+const DEFAULT_PRICE: (f64, f64) = (3.0, 15.0);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+    #[default]
+    Monthly,
+}
+
+/// Spending limits in USD per period. The soft limit only warns; reaching the hard
+/// limit blocks new messages until the period resets or override_usage_budget is used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    pub soft_limit_usd: Option<f64>,
+    pub hard_limit_usd: Option<f64>,
+    pub period: BudgetPeriod,
+}
+
+// Spending in the current period, persisted as usage.json in the app data directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Ledger {
+    period_start: i64,
+    spent_usd: f64,
+    input_tokens: u64,
+    output_tokens: u64,
+    // budget_warning was already emitted this period
+    warned: bool,
+    // The hard limit was lifted for the rest of this period
+    overridden: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct BudgetStatus {
+    pub period: BudgetPeriod,
+    #[ts(as = "f64")]
+    pub period_start: i64,
+    #[ts(as = "f64")]
+    pub resets_at: i64,
+    pub spent_usd: f64,
+    #[ts(as = "f64")]
+    pub input_tokens: u64,
+    #[ts(as = "f64")]
+    pub output_tokens: u64,
+    pub soft_limit_usd: Option<f64>,
+    pub hard_limit_usd: Option<f64>,
+    pub blocked: bool,
+    pub overridden: bool,
+}
+
+pub struct UsageBudget {
+    path: Option<PathBuf>,
+    ledger: Mutex<Ledger>,
+}
+
+impl UsageBudget {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = data_dir::resolve(app_handle).map(|dir| dir.join("usage.json"));
+
+        let ledger = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        UsageBudget {
+            path,
+            ledger: Mutex::new(ledger),
+        }
+    }
+
+    // The ledger, started over if a new period began since it was last touched
+    fn current(&self, period: BudgetPeriod) -> MutexGuard<'_, Ledger> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let (start, _) = period_bounds(period);
+        if ledger.period_start != start {
+            *ledger = Ledger {
+                period_start: start,
+                ..Ledger::default()
+            };
+        }
+        ledger
+    }
+
+    fn save(&self, ledger: &Ledger) -> Result<()> {
+        let path = self.path.as_ref().context("No data directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+
+        let json = serde_json::to_string(ledger).context("Failed to serialize usage")?;
+        std::fs::write(path, json).context("Failed to write usage")
+    }
+}
+
+#[tauri::command]
+pub fn get_usage_budget(app_handle: AppHandle) -> BudgetStatus {
+    status(&app_handle)
+}
+
+#[tauri::command]
+pub fn set_usage_budget(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    soft_limit_usd: Option<f64>,
+    hard_limit_usd: Option<f64>,
+    period: Option<BudgetPeriod>,
+) -> Result<BudgetStatus, String> {
+    let valid = |limit: Option<f64>| {
+        limit
+            .map(|usd| usd.is_finite() && usd > 0.0)
+            .unwrap_or(true)
+    };
+    if !valid(soft_limit_usd) || !valid(hard_limit_usd) {
+        return Err("Limits must be positive amounts".to_string());
+    }
+
+    settings
+        .update(|s| {
+            s.budget.soft_limit_usd = soft_limit_usd;
+            s.budget.hard_limit_usd = hard_limit_usd;
+            if let Some(period) = period {
+                s.budget.period = period;
+            }
+        })
+        .map_err(|e| format!("Failed to save usage budget: {}", e))?;
+
+    Ok(status(&app_handle))
+}
+
+/// Lets messages through despite the hard limit until the current period ends.
+#[tauri::command]
+pub fn override_usage_budget(app_handle: AppHandle) -> Result<BudgetStatus, String> {
+    let period = app_handle.state::<SettingsStore>().get().budget.period;
+    let budget = app_handle.state::<UsageBudget>();
+    {
+        let mut ledger = budget.current(period);
+        ledger.overridden = true;
+        budget
+            .save(&ledger)
+            .map_err(|e| format!("Failed to save usage budget: {}", e))?;
+    }
+    eprintln!("[BUDGET] Hard limit overridden for this period");
+
+    Ok(status(&app_handle))
+}
+
+/// Fails once the hard limit is reached and hasn't been overridden.
+pub fn check(app_handle: &AppHandle) -> Result<()> {
+    let status = status(app_handle);
+    if status.blocked {
+        return Err(anyhow!(
+            "the usage budget of ${:.2} for this period is used up",
+            status.hard_limit_usd.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Adds a finished turn's usage and warns when it crosses the soft or hard limit.
+pub fn record(app_handle: &AppHandle, usage: &Usage) {
+    let limits = app_handle.state::<SettingsStore>().get().budget;
+    let budget = app_handle.state::<UsageBudget>();
+    let (crossed_soft, crossed_hard) = {
+        let mut ledger = budget.current(limits.period);
+        let before = ledger.spent_usd;
+        ledger.spent_usd += cost(usage);
+        ledger.input_tokens += usage.input_tokens;
+        ledger.output_tokens += usage.output_tokens;
+
+        let crossed = |limit: Option<f64>| {
+            limit
+                .map(|limit| before < limit && ledger.spent_usd >= limit)
+                .unwrap_or(false)
+        };
+        let crossed_soft = crossed(limits.soft_limit_usd) && !ledger.warned;
+        let crossed_hard = crossed(limits.hard_limit_usd);
+        if crossed_soft {
+            ledger.warned = true;
+        }
+
+        if let Err(e) = budget.save(&ledger) {
+            eprintln!("Failed to save usage: {}", e);
+        }
+        (crossed_soft, crossed_hard)
+    };
+
+    if crossed_soft {
+        emit(app_handle, "budget_warning");
+    }
+    if crossed_hard {
+        emit(app_handle, "budget_exceeded");
+    }
+}
+
+fn status(app_handle: &AppHandle) -> BudgetStatus {
+    let limits = app_handle.state::<SettingsStore>().get().budget;
+    let ledger = app_handle
+        .state::<UsageBudget>()
+        .current(limits.period)
+        .clone();
+    let (period_start, resets_at) = period_bounds(limits.period);
+
+    let blocked = !ledger.overridden
+        && limits
+            .hard_limit_usd
+            .map(|limit| ledger.spent_usd >= limit)
+            .unwrap_or(false);
+    BudgetStatus {
+        period: limits.period,
+        period_start,
+        resets_at,
+        spent_usd: ledger.spent_usd,
+        input_tokens: ledger.input_tokens,
+        output_tokens: ledger.output_tokens,
+        soft_limit_usd: limits.soft_limit_usd,
+        hard_limit_usd: limits.hard_limit_usd,
+        blocked,
+        overridden: ledger.overridden,
+    }
+}
+
+fn emit(app_handle: &AppHandle, event: &str) {
+    eprintln!("[BUDGET] {}", event);
+    if let Err(e) = app_handle.emit_all(event, status(app_handle)) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn cost(usage: &Usage) -> f64 {
+    let model = usage.model.to_ascii_lowercase();
+    let (input, output) = PRICES
+        .iter()
+        .find(|(family, _, _)| model.contains(family))
+        .map(|(_, input, output)| (*input, *output))
+        .unwrap_or(DEFAULT_PRICE);
+
+    (usage.input_tokens as f64 * input + usage.output_tokens as f64 * output) / 1_000_000.0
+}
+
+// Start and end of the current period in epoch millis, in local time; weeks start Monday
+fn period_bounds(period: BudgetPeriod) -> (i64, i64) {
+    let today = Local::now().date_naive();
+    let (start, end) = match period {
+        BudgetPeriod::Daily => (today, today + Days::new(1)),
+        BudgetPeriod::Weekly => {
+            let start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+            (start, start + Days::new(7))
+        }
+        BudgetPeriod::Monthly => {
+            let start = today.with_day(1).unwrap_or(today);
+            (start, start + Months::new(1))
+        }
+    };
+    (local_midnight(start), local_midnight(end))
+}
+
+fn local_midnight(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.timestamp_millis())
+        .unwrap_or_default()
+}
@@ -0,0 +1,205 @@
+use crate::diagnostics;
+use crate::print;
+use crate::settings::SettingsStore;
+use crate::spill;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+// Files used this recently may still be open: a reply being spilled, a print window
+// loading its page
+const IN_USE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Size budgets of the files the shell can regenerate or do without. Once an area is
+/// over budget its least recently used files are removed, at startup and then every
+/// sweep_interval_minutes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    // Spilled replies, a reading aid for replies the store keeps in full
+    pub responses_mb: u64,
+    // Window captures for bug reports
+    pub screenshots_mb: u64,
+    // Pages rendered for print_conversation, in the system temp directory
+    pub print_mb: u64,
+    pub sweep_interval_minutes: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            responses_mb: 200,
+            screenshots_mb: 50,
+            print_mb: 20,
+            sweep_interval_minutes: 60,
+        }
+    }
+}
+
+/// Space freed by a sweep or clear_cache.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct Reclaimed {
+    #[ts(as = "f64")]
+    pub bytes: u64,
+    pub files: usize,
+}
+
+// A kind of disposable file, found by name in `dir`
+struct Area {
+    name: &'static str,
+    dir: fn(&AppHandle) -> Option<PathBuf>,
+    matches: fn(&str) -> bool,
+    budget_mb: fn(&CacheSettings) -> u64,
+}
+
+const AREAS: &[Area] = &[
+    Area {
+        name: "responses",
+        dir: spill::responses_dir,
+        matches: |name| name.ends_with(".txt"),
+        budget_mb: |settings| settings.responses_mb,
+    },
+    Area {
+        name: "screenshots",
+        dir: diagnostics::diagnostics_dir,
+        matches: |name| name.starts_with("window-") && name.ends_with(".png"),
+        budget_mb: |settings| settings.screenshots_mb,
+    },
+    Area {
+        name: "print",
+        dir: |_| Some(std::env::temp_dir()),
+        matches: |name| name.starts_with(print::PRINT_FILE_PREFIX) && name.ends_with(".html"),
+        budget_mb: |settings| settings.print_mb,
+    },
+];
+
+struct CachedFile {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// Removes every cached file that isn't in use and reports the space reclaimed.
+#[tauri::command]
+pub async fn clear_cache(app_handle: AppHandle) -> Result<Reclaimed, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut reclaimed = Reclaimed::default();
+        for area in AREAS {
+            evict(&app_handle, area, 0, &mut reclaimed);
+        }
+        eprintln!(
+            "[CACHE] Cleared {} files, {} bytes",
+            reclaimed.files, reclaimed.bytes
+        );
+        reclaimed
+    })
+    .await
+    .map_err(|e| format!("Failed to clear cache: {}", e))
+}
+
+/// Sweeps the cache now and then every sweep_interval_minutes.
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app_handle.clone();
+            if let Err(e) = tauri::async_runtime::spawn_blocking(move || sweep(&handle)).await {
+                eprintln!("Cache sweep failed: {}", e);
+            }
+
+            let minutes = app_handle
+                .state::<SettingsStore>()
+                .get()
+                .cache
+                .sweep_interval_minutes
+                .max(1);
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+        }
+    });
+}
+
+// Brings every area back within its budget
+fn sweep(app_handle: &AppHandle) {
+    // Spilled replies also expire by age
+    spill::prune(app_handle);
+
+    let settings = app_handle.state::<SettingsStore>().get().cache;
+    let mut reclaimed = Reclaimed::default();
+    for area in AREAS {
+        let budget = (area.budget_mb)(&settings).saturating_mul(1024 * 1024);
+        evict(app_handle, area, budget, &mut reclaimed);
+    }
+    if reclaimed.files > 0 {
+        eprintln!(
+            "[CACHE] Reclaimed {} files, {} bytes",
+            reclaimed.files, reclaimed.bytes
+        );
+    }
+}
+
+// Removes the least recently used files of `area` until it fits in `budget` bytes
+fn evict(app_handle: &AppHandle, area: &Area, budget: u64, reclaimed: &mut Reclaimed) {
+    let mut files = files(app_handle, area);
+    files.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+
+    let mut total = 0u64;
+    for file in files {
+        total = total.saturating_add(file.size);
+        let in_use = file
+            .last_used
+            .elapsed()
+            .map(|age| age < IN_USE_WINDOW)
+            .unwrap_or(true);
+        if total <= budget || in_use {
+            continue;
+        }
+
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => {
+                reclaimed.bytes += file.size;
+                reclaimed.files += 1;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!(
+                "Failed to remove cached {} file {:?}: {}",
+                area.name, file.path, e
+            ),
+        }
+    }
+}
+
+fn files(app_handle: &AppHandle, area: &Area) -> Vec<CachedFile> {
+    let Some(entries) = (area.dir)(app_handle).and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .map(|name| (area.matches)(name))
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| {
+            let metadata = entry
+                .metadata()
+                .ok()
+                .filter(|metadata| metadata.is_file())?;
+            let modified = metadata.modified().ok()?;
+            // Access times are often not kept up to date, so a write counts as a use too
+            let last_used = metadata
+                .accessed()
+                .map(|accessed| accessed.max(modified))
+                .unwrap_or(modified);
+            Some(CachedFile {
+                path: entry.path(),
+                size: metadata.len(),
+                last_used,
+            })
+        })
+        .collect()
+}
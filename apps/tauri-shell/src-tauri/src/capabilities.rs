@@ -0,0 +1,143 @@
+use crate::onboarding::{self, PermissionState};
+use crate::secrets;
+use crate::shortcut;
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub available: bool,
+    // Why it's unavailable (or limited), suitable for showing next to the feature
+    pub reason: Option<String>,
+}
+
+impl Capability {
+    fn available() -> Self {
+        Capability {
+            available: true,
+            reason: None,
+        }
+    }
+
+    fn unavailable(reason: &str) -> Self {
+        Capability {
+            available: false,
+            reason: Some(reason.to_string()),
+        }
+    }
+
+    fn from_permission(state: PermissionState, permission: &str) -> Self {
+        match state {
+            PermissionState::Granted | PermissionState::NotRequired => Capability::available(),
+            PermissionState::Denied | PermissionState::NotDetermined => {
+                Capability::unavailable(&format!("{} permission has not been granted", permission))
+            }
+        }
+    }
+}
+
+/// Native features as they actually work right now, taking platform and permissions
+/// into account, so the frontend can hide or explain them instead of erroring.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub transparency: Capability,
+    pub global_shortcuts: Capability,
+    pub screen_capture: Capability,
+    pub accessibility: Capability,
+    pub keychain: Capability,
+    pub notifications: Capability,
+}
+
+#[tauri::command]
+pub fn get_capabilities(app_handle: AppHandle) -> Capabilities {
+    let permissions = onboarding::permissions();
+
+    Capabilities {
+        transparency: transparency(),
+        global_shortcuts: global_shortcuts(&app_handle),
+        screen_capture: screen_capture(permissions.screen_recording),
+        accessibility: accessibility(permissions.accessibility),
+        keychain: keychain(),
+        notifications: Capability::from_permission(permissions.notifications, "Notifications"),
+    }
+}
+
+// Agent secrets are kept there; a locked or missing keychain leaves them unreadable
+fn keychain() -> Capability {
+    match secrets::probe() {
+        Ok(()) => Capability::available(),
+        Err(e) => Capability::unavailable(&format!("{:#}", e)),
+    }
+}
+
+fn global_shortcuts(app_handle: &AppHandle) -> Capability {
+    if is_wayland() {
+        return Capability::unavailable("Wayland doesn't allow apps to register global shortcuts");
+    }
+    if shortcut::is_registered(app_handle) {
+        Capability::available()
+    } else {
+        Capability::unavailable(&format!(
+            "{} is in use by another app",
+            shortcut::current(app_handle)
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn transparency() -> Capability {
+    Capability::unavailable("Requires the macos-private-api build feature")
+}
+
+#[cfg(windows)]
+fn transparency() -> Capability {
+    Capability::available()
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn transparency() -> Capability {
+    Capability {
+        available: true,
+        reason: Some("Needs a compositing window manager".to_string()),
+    }
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn screen_capture(permission: PermissionState) -> Capability {
+    Capability::from_permission(permission, "Screen Recording")
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn screen_capture(_permission: PermissionState) -> Capability {
+    Capability::unavailable("Screen capture is not supported on this platform")
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn accessibility(permission: PermissionState) -> Capability {
+    Capability::from_permission(permission, "Accessibility")
+}
+
+// Window titles and selected text come from command-line helpers on Linux
+#[cfg(not(any(target_os = "macos", windows)))]
+fn accessibility(_permission: PermissionState) -> Capability {
+    let helper = if is_wayland() { "wl-paste" } else { "xdotool" };
+    if on_path(helper) {
+        Capability::available()
+    } else {
+        Capability::unavailable(&format!(
+            "Install {} to read the active window and selection",
+            helper
+        ))
+    }
+}
+
+fn is_wayland() -> bool {
+    cfg!(target_os = "linux") && std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
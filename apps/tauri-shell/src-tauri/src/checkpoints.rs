@@ -0,0 +1,279 @@
+use crate::agent_ipc::{AgentRequest, DEFAULT_AGENT_ID};
+use crate::budget;
+use crate::conversation_agents;
+use crate::settings::SettingsStore;
+use crate::store::{self, Checkpoint, Conversation, ConversationStore, StoredMessage};
+use crate::AppState;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// Summaries are answered by the model alone, so this only guards against a hung agent
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(180);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckpointSettings {
+    pub enabled: bool,
+    // Characters written since the last checkpoint that trigger a new summary
+    pub threshold_chars: usize,
+    // Latest messages left out of the summary and given to the agent verbatim
+    pub recent_messages: usize,
+}
+
+impl Default for CheckpointSettings {
+    fn default() -> Self {
+        CheckpointSettings {
+            enabled: true,
+            threshold_chars: 60_000,
+            recent_messages: 10,
+        }
+    }
+}
+
+/// Conversations being summarized, so a burst of replies starts only one summary.
+#[derive(Default)]
+pub struct Checkpoints {
+    running: Mutex<HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct CheckpointCreated<'a> {
+    conversation_id: &'a str,
+    #[serde(flatten)]
+    checkpoint: &'a Checkpoint,
+}
+
+/// What load_conversation_compressed gave the agent.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct CompressedLoad {
+    pub conversation_id: String,
+    pub agent_id: String,
+    // Messages replaced by the summary
+    pub summarized_messages: usize,
+    pub recent_messages: usize,
+}
+
+// Payload of load_conversation_compressed, carried in the request's message field
+#[derive(Debug, Serialize)]
+struct CompressedHistory<'a> {
+    summary: &'a str,
+    messages: Vec<HistoryMessage<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct HistoryMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[tauri::command]
+pub fn get_conversation_checkpoint(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<Option<Checkpoint>, String> {
+    store
+        .load_checkpoint(&conversation_id)
+        .map_err(|e| format!("Failed to load checkpoint: {}", e))
+}
+
+/// Makes `conversation_id` the agent's current conversation, with its checkpoint
+/// summary in place of the messages it covers and the rest of the thread after it.
+#[tauri::command]
+pub async fn load_conversation_compressed(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<CompressedLoad, String> {
+    let conversation = store
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let checkpoint = store
+        .load_checkpoint(&conversation_id)
+        .map_err(|e| format!("Failed to load checkpoint: {}", e))?
+        .ok_or_else(|| format!("Conversation {} has no checkpoint yet", conversation_id))?;
+
+    let covered = covered(&conversation, &checkpoint);
+    let history = CompressedHistory {
+        summary: &checkpoint.summary,
+        messages: conversation.messages[covered..]
+            .iter()
+            .filter(|m| m.role != "divider")
+            .map(|m| HistoryMessage {
+                role: &m.role,
+                content: &m.content,
+            })
+            .collect(),
+    };
+    let recent_messages = history.messages.len();
+    let request = AgentRequest::LoadConversationCompressed {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.clone(),
+        message: serde_json::to_string(&history).map_err(|e| e.to_string())?,
+    };
+
+    let agent_id = conversation_agents::route(&app_handle, Some(&conversation_id))
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let slot = state.slot(&agent_id);
+    let mut agent = slot.lock().await;
+    let process = agent
+        .as_mut()
+        .ok_or_else(|| format!("Agent {} not running", agent_id))?;
+    process
+        .send_and_wait(&request)
+        .await
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+
+    Ok(CompressedLoad {
+        conversation_id,
+        agent_id,
+        summarized_messages: covered,
+        recent_messages,
+    })
+}
+
+/// Called after a reply is recorded. Once the messages since the last checkpoint pass
+/// the threshold, asks the agent for a summary in the background and stores it.
+pub fn on_reply(app_handle: &AppHandle, conversation_id: &str) {
+    let settings = app_handle.state::<SettingsStore>().get().checkpoints;
+    if !settings.enabled {
+        return;
+    }
+    let checkpoints = app_handle.state::<Checkpoints>();
+    if !checkpoints
+        .running
+        .lock()
+        .unwrap()
+        .insert(conversation_id.to_string())
+    {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    let conversation_id = conversation_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = update(&app_handle, &conversation_id, &settings).await {
+            eprintln!("[CHECKPOINT] {} not summarized: {}", conversation_id, e);
+        }
+        let checkpoints = app_handle.state::<Checkpoints>();
+        checkpoints.running.lock().unwrap().remove(&conversation_id);
+    });
+}
+
+async fn update(
+    app_handle: &AppHandle,
+    conversation_id: &str,
+    settings: &CheckpointSettings,
+) -> Result<()> {
+    let store = app_handle.state::<ConversationStore>();
+    let conversation = store.load(conversation_id)?;
+    let previous = store.load_checkpoint(conversation_id)?;
+    let start = previous
+        .as_ref()
+        .map_or(0, |checkpoint| covered(&conversation, checkpoint));
+    let end = conversation
+        .messages
+        .len()
+        .saturating_sub(settings.recent_messages);
+    if end <= start {
+        return Ok(());
+    }
+    let uncovered: usize = conversation.messages[start..]
+        .iter()
+        .map(|m| m.content.len())
+        .sum();
+    if uncovered < settings.threshold_chars {
+        return Ok(());
+    }
+    budget::check(app_handle)?;
+
+    let summary = summarize(
+        app_handle,
+        previous
+            .as_ref()
+            .map(|checkpoint| checkpoint.summary.as_str()),
+        &conversation.messages[start..end],
+    )
+    .await?;
+    let checkpoint = Checkpoint {
+        summary,
+        covered_messages: end,
+        created_at: store::now_millis(),
+    };
+    store.save_checkpoint(conversation_id, &checkpoint)?;
+    eprintln!(
+        "[CHECKPOINT] {} summarized through message {}",
+        conversation_id, end
+    );
+
+    let event = CheckpointCreated {
+        conversation_id,
+        checkpoint: &checkpoint,
+    };
+    if let Err(e) = app_handle.emit_all("checkpoint_created", event) {
+        eprintln!("Failed to emit checkpoint_created: {}", e);
+    }
+    Ok(())
+}
+
+async fn summarize(
+    app_handle: &AppHandle,
+    previous: Option<&str>,
+    messages: &[StoredMessage],
+) -> Result<String> {
+    let request = AgentRequest::Summarize {
+        id: uuid::Uuid::new_v4().to_string(),
+        message: prompt(previous, messages),
+    };
+    let reply = {
+        let state = app_handle.state::<AppState>();
+        let mut agent = state.agent().lock_owned().await;
+        let process = agent
+            .as_mut()
+            .ok_or_else(|| anyhow!("Agent is not running"))?;
+        process.complete(&request).await?
+    };
+    let summary = tokio::time::timeout(SUMMARY_TIMEOUT, reply)
+        .await
+        .map_err(|_| anyhow!("The agent didn't answer in time"))?
+        .map_err(|_| anyhow!("The agent exited before answering"))??;
+
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err(anyhow!("The agent returned no summary"));
+    }
+    Ok(summary.to_string())
+}
+
+fn prompt(previous: Option<&str>, messages: &[StoredMessage]) -> String {
+    let mut prompt = String::new();
+    if let Some(previous) = previous {
+        prompt.push_str(&format!(
+            "<earlier_summary>\n{}\n</earlier_summary>\n\n",
+            previous
+        ));
+    }
+    prompt.push_str("<transcript>\n");
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" => "User",
+            "assistant" => "Assistant",
+            _ => continue,
+        };
+        prompt.push_str(&format!("{}: {}\n\n", speaker, message.content));
+    }
+    prompt.push_str("</transcript>");
+    prompt
+}
+
+// Messages are only appended (merges drop the checkpoint), so the count stays valid
+fn covered(conversation: &Conversation, checkpoint: &Checkpoint) -> usize {
+    checkpoint.covered_messages.min(conversation.messages.len())
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Context, Result};
+use arboard::{Clipboard, ImageData};
+use base64::Engine;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardFormat {
+    Text,
+    Html,
+    Rtf,
+    Image, // base64 encoded PNG/JPEG, optionally as a data: URL
+}
+
+#[tauri::command]
+pub fn write_clipboard(
+    content: String,
+    format: ClipboardFormat,
+    plain_text: Option<String>,
+) -> Result<(), String> {
+    write(&content, format, plain_text.as_deref())
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+}
+
+pub fn write(content: &str, format: ClipboardFormat, plain_text: Option<&str>) -> Result<()> {
+    match format {
+        ClipboardFormat::Text => {
+            let mut clipboard = Clipboard::new().context("Failed to open clipboard")?;
+            clipboard.set_text(content)?;
+        }
+        ClipboardFormat::Html => {
+            // Plain-text alternative lets apps without HTML support still paste something
+            let mut clipboard = Clipboard::new().context("Failed to open clipboard")?;
+            clipboard.set_html(content, plain_text)?;
+        }
+        ClipboardFormat::Rtf => write_rtf(content)?,
+        ClipboardFormat::Image => write_image(&decode_base64(content)?)?,
+    }
+
+    Ok(())
+}
+
+pub fn read_text() -> Result<String> {
+    let mut clipboard = Clipboard::new().context("Failed to open clipboard")?;
+    clipboard.get_text().context("Clipboard has no text")
+}
+
+/// Places an encoded (PNG/JPEG) image on the clipboard.
+pub fn write_image(bytes: &[u8]) -> Result<()> {
+    let image = image::load_from_memory(bytes)
+        .context("Failed to decode image")?
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let mut clipboard = Clipboard::new().context("Failed to open clipboard")?;
+    clipboard.set_image(ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: Cow::Owned(image.into_raw()),
+    })?;
+
+    Ok(())
+}
+
+fn decode_base64(content: &str) -> Result<Vec<u8>> {
+    let data = match content.split_once(";base64,") {
+        Some((_, data)) => data,
+        None => content,
+    };
+
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .context("Invalid base64 image data")
+}
+
+#[cfg(target_os = "macos")]
+fn write_rtf(content: &str) -> Result<()> {
+    pipe_to_command("pbcopy", &["-Prefer", "rtf"], content)
+}
+
+#[cfg(target_os = "linux")]
+fn write_rtf(content: &str) -> Result<()> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        pipe_to_command("wl-copy", &["--type", "text/rtf"], content)
+    } else {
+        pipe_to_command(
+            "xclip",
+            &["-selection", "clipboard", "-target", "text/rtf"],
+            content,
+        )
+    }
+}
+
+#[cfg(windows)]
+fn write_rtf(content: &str) -> Result<()> {
+    use clipboard_win::{formats, register_format, set_clipboard};
+
+    let format = register_format("Rich Text Format")
+        .ok_or_else(|| anyhow!("Failed to register RTF clipboard format"))?;
+
+    set_clipboard(formats::RawData(format.get()), content.as_bytes())
+        .map_err(|e| anyhow!("Failed to set RTF clipboard data: {}", e))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn write_rtf(_content: &str) -> Result<()> {
+    Err(anyhow!("RTF clipboard data is not supported on this platform"))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn pipe_to_command(program: &str, args: &[&str], input: &str) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open stdin")?
+        .write_all(input.as_bytes())?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+
+    Ok(())
+}
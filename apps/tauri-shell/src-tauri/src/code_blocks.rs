@@ -0,0 +1,426 @@
+use crate::redact;
+use crate::store::ConversationStore;
+use anyhow::{anyhow, Context, Result};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use ts_rs::TS;
+
+// Fence languages and the extension a saved block gets when the path has none
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("bash", "sh"),
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("csharp", "cs"),
+    ("css", "css"),
+    ("go", "go"),
+    ("html", "html"),
+    ("java", "java"),
+    ("javascript", "js"),
+    ("js", "js"),
+    ("json", "json"),
+    ("jsx", "jsx"),
+    ("kotlin", "kt"),
+    ("markdown", "md"),
+    ("php", "php"),
+    ("python", "py"),
+    ("py", "py"),
+    ("ruby", "rb"),
+    ("rust", "rs"),
+    ("sh", "sh"),
+    ("shell", "sh"),
+    ("sql", "sql"),
+    ("swift", "swift"),
+    ("toml", "toml"),
+    ("ts", "ts"),
+    ("tsx", "tsx"),
+    ("typescript", "ts"),
+    ("xml", "xml"),
+    ("yaml", "yaml"),
+    ("yml", "yml"),
+    ("zsh", "sh"),
+];
+
+// Home subdirectories holding startup hooks that aren't hidden; never written to, like
+// anything hidden in home. Compared case-insensitively, as on macOS and Windows
+const PROTECTED_DIRS: &[&str] = &[
+    "Library/LaunchAgents",
+    "AppData/Roaming/Microsoft/Windows/Start Menu/Programs/Startup",
+];
+
+/// A fenced or indented code block from a stored message. `id` is stable, so it can be
+/// passed to save_code_block later without extracting again.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct CodeBlock {
+    pub id: String,
+    #[ts(as = "f64")]
+    pub index: usize,
+    pub language: Option<String>,
+    pub extension: Option<String>,
+    pub code: String,
+}
+
+#[tauri::command]
+pub fn extract_code_blocks(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+    message_id: String,
+    role: String,
+) -> Result<Vec<CodeBlock>, String> {
+    load_blocks(&store, &conversation_id, &message_id, &role)
+        .map_err(|e| format!("Failed to extract code blocks: {}", e))
+}
+
+/// Writes a block to `path`, adding the language's extension if the path has none.
+/// Only paths inside the home directory are allowed, outside of its hidden files and
+/// directories (.bashrc, .ssh...), PROTECTED_DIRS and any .git directory, and never
+/// through a symbolic link at the path. Existing files are left alone unless `overwrite`
+/// is set. Returns the path written.
+#[tauri::command]
+pub fn save_code_block(
+    app_handle: AppHandle,
+    store: State<'_, ConversationStore>,
+    block_id: String,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<String, String> {
+    let (conversation_id, message_id, role, index) =
+        parse_block_id(&block_id).ok_or_else(|| format!("Invalid block id {}", block_id))?;
+    let block = load_blocks(&store, conversation_id, message_id, role)
+        .map_err(|e| format!("Failed to load code block: {}", e))?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("No code block {}", block_id))?;
+
+    let mut path = PathBuf::from(path);
+    if let (None, Some(extension)) = (path.extension(), &block.extension) {
+        path.set_extension(extension);
+    }
+    check_path(&path, overwrite.unwrap_or(false)).map_err(|e| e.to_string())?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    let mut code = match redact::for_export(&app_handle).map_err(|e| e.to_string())? {
+        Some(redactor) => redactor.apply(&block.code),
+        None => block.code,
+    };
+    if !code.ends_with('\n') {
+        code.push('\n');
+    }
+    open_for_save(&path, overwrite.unwrap_or(false))
+        .and_then(|mut file| file.write_all(code.as_bytes()))
+        .map_err(|e| format!("Failed to save code block: {}", e))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Opens `path` for writing, without following a link there. It must not exist unless
+/// `overwrite` is set.
+pub fn open_for_save(path: &Path, overwrite: bool) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        // Fails on anything already at the path, dangling links included
+        options.create_new(true);
+    }
+    // Nor through a link swapped in after check_path
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, libc::O_NOFOLLOW);
+    options.open(path)
+}
+
+fn load_blocks(
+    store: &ConversationStore,
+    conversation_id: &str,
+    message_id: &str,
+    role: &str,
+) -> Result<Vec<CodeBlock>> {
+    let conversation = store.load(conversation_id)?;
+    let message = conversation
+        .find_message(message_id, role)
+        .with_context(|| format!("Message {} not found", message_id))?;
+
+    Ok(parse(&message.content)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (language, code))| CodeBlock {
+            id: format!("{}/{}/{}/{}", conversation_id, message_id, role, index),
+            index,
+            extension: language.as_deref().and_then(extension_for),
+            language,
+            code,
+        })
+        .collect())
+}
+
+fn parse(markdown: &str) -> Vec<(Option<String>, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(Option<String>, String)> = None;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                // Info strings can carry more than the language: ```rust,ignore or ```py title="x"
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .next()
+                        .filter(|language| !language.is_empty())
+                        .map(|language| language.to_ascii_lowercase()),
+                    CodeBlockKind::Indented => None,
+                };
+                current = Some((language, String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn extension_for(language: &str) -> Option<String> {
+    EXTENSIONS
+        .iter()
+        .find(|(name, _)| *name == language)
+        .map(|(_, extension)| extension.to_string())
+}
+
+// "<conversation>/<message>/<role>/<index>"; none of them can contain a slash
+fn parse_block_id(block_id: &str) -> Option<(&str, &str, &str, usize)> {
+    let mut parts = block_id.split('/');
+    let conversation_id = parts.next()?;
+    let message_id = parts.next()?;
+    let role = parts.next()?;
+    let index = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((conversation_id, message_id, role, index))
+}
+
+/// The policy save_code_block applies to `path`, also used for other files the webview
+/// asks to have written.
+pub fn check_path(path: &Path, overwrite: bool) -> Result<()> {
+    let home = tauri::api::path::home_dir()
+        .and_then(|home| home.canonicalize().ok())
+        .context("No home directory available")?;
+    check_path_in(&home, path, overwrite)
+}
+
+// check_path against `home`, which must already be canonical
+fn check_path_in(home: &Path, path: &Path, overwrite: bool) -> Result<()> {
+    if !path.is_absolute() {
+        return Err(anyhow!("The path must be absolute"));
+    }
+    // Not followed: writing through a link, even a dangling one, lands at its target
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if metadata.file_type().is_symlink() {
+            return Err(anyhow!("{} is a symbolic link", path.display()));
+        }
+        if metadata.is_dir() {
+            return Err(anyhow!("{} is a directory", path.display()));
+        }
+        if !overwrite {
+            return Err(anyhow!("{} already exists", path.display()));
+        }
+    }
+
+    // Resolve through the deepest existing ancestor so symlinks can't point outside home.
+    // A dangling link among the ancestors fails to resolve.
+    let existing = path
+        .ancestors()
+        .find(|ancestor| std::fs::symlink_metadata(ancestor).is_ok())
+        .context("No existing parent directory")?;
+    let resolved = existing
+        .canonicalize()
+        .context("Failed to resolve path")?
+        .join(path.strip_prefix(existing).unwrap_or(Path::new("")));
+    if resolved.components().any(|c| c.as_os_str() == "..") {
+        return Err(anyhow!("The path must not contain '..'"));
+    }
+
+    let Ok(relative) = resolved.strip_prefix(home) else {
+        return Err(anyhow!("Files must be saved inside your home directory"));
+    };
+    let components: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+        .collect();
+    // Shell startup files, credentials and tool configuration, some run on login
+    let hidden = components.first().is_some_and(|c| c.starts_with('.'));
+    // Hooks and config in a repository run commands on the next git operation
+    let in_git_dir = components.iter().any(|c| c == ".git");
+    let in_protected_dir = PROTECTED_DIRS.iter().any(|dir| {
+        let dir: Vec<String> = dir.split('/').map(str::to_lowercase).collect();
+        components.starts_with(&dir)
+    });
+    if hidden || in_git_dir || in_protected_dir {
+        return Err(anyhow!("Refusing to write to {}", relative.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A scratch directory standing in for home, canonical as check_path expects
+    struct Home(PathBuf);
+
+    impl Home {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "asst-code-blocks-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(dir.join("home")).unwrap();
+            Home(dir.canonicalize().unwrap())
+        }
+
+        fn path(&self) -> PathBuf {
+            self.0.join("home")
+        }
+
+        // Next to home, so outside it
+        fn outside(&self) -> PathBuf {
+            self.0.join("outside")
+        }
+
+        fn check(&self, relative: &str, overwrite: bool) -> Result<()> {
+            check_path_in(&self.path(), &self.path().join(relative), overwrite)
+        }
+    }
+
+    impl Drop for Home {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn paths_inside_home_are_allowed() {
+        let home = Home::new("inside");
+
+        assert!(home.check("notes.md", false).is_ok());
+        assert!(home.check("projects/new/main.rs", false).is_ok());
+        assert!(home
+            .check("projects/.github/workflows/ci.yml", false)
+            .is_ok());
+    }
+
+    #[test]
+    fn relative_paths_are_refused() {
+        let home = Home::new("relative");
+
+        assert!(check_path_in(&home.path(), Path::new("notes.md"), false).is_err());
+        assert!(check_path_in(&home.path(), Path::new("../notes.md"), false).is_err());
+    }
+
+    #[test]
+    fn parent_components_are_refused() {
+        let home = Home::new("parent");
+
+        assert!(home.check("missing/../notes.md", false).is_err());
+        assert!(home.check("missing/../../outside/notes.md", false).is_err());
+    }
+
+    #[test]
+    fn hidden_top_level_entries_are_refused() {
+        let home = Home::new("hidden");
+
+        assert!(home.check(".bashrc", true).is_err());
+        assert!(home.check(".ssh/authorized_keys", false).is_err());
+        assert!(home.check(".config/autostart/run.desktop", false).is_err());
+    }
+
+    #[test]
+    fn git_directories_are_refused() {
+        let home = Home::new("git");
+
+        assert!(home.check("project/.git/hooks/pre-commit", false).is_err());
+        assert!(home.check("project/.GIT/config", false).is_err());
+    }
+
+    #[test]
+    fn protected_dirs_match_case_insensitively() {
+        let home = Home::new("protected");
+
+        assert!(home.check("Library/LaunchAgents/run.plist", false).is_err());
+        assert!(home.check("library/launchagents/run.plist", false).is_err());
+        assert!(home
+            .check(
+                "appdata/roaming/microsoft/windows/start menu/programs/startup/run.bat",
+                false
+            )
+            .is_err());
+        assert!(home.check("Library/Documents/run.plist", false).is_ok());
+    }
+
+    #[test]
+    fn paths_outside_home_are_refused() {
+        let home = Home::new("outside");
+
+        assert!(check_path_in(&home.path(), &home.outside().join("notes.md"), false).is_err());
+    }
+
+    #[test]
+    fn existing_files_need_overwrite() {
+        let home = Home::new("existing");
+        std::fs::write(home.path().join("notes.md"), "").unwrap();
+        std::fs::create_dir(home.path().join("project")).unwrap();
+
+        assert!(home.check("notes.md", false).is_err());
+        assert!(home.check("notes.md", true).is_ok());
+        assert!(home.check("project", true).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_parents_are_resolved() {
+        let home = Home::new("linked-parent");
+        std::fs::create_dir_all(home.outside()).unwrap();
+        std::fs::create_dir(home.path().join("project")).unwrap();
+        std::os::unix::fs::symlink(home.outside(), home.path().join("out")).unwrap();
+        std::os::unix::fs::symlink(home.path().join("project"), home.path().join("in")).unwrap();
+        std::os::unix::fs::symlink(home.outside().join("missing"), home.path().join("gone"))
+            .unwrap();
+
+        assert!(home.check("out/notes.md", false).is_err());
+        assert!(home.check("in/notes.md", false).is_ok());
+        assert!(home.check("gone/notes.md", false).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_at_the_path_are_refused() {
+        let home = Home::new("linked-target");
+        std::fs::create_dir_all(home.outside()).unwrap();
+        std::fs::write(home.path().join("real.md"), "").unwrap();
+        std::os::unix::fs::symlink(
+            home.outside().join("missing.md"),
+            home.path().join("dangling.md"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(home.path().join("real.md"), home.path().join("alias.md"))
+            .unwrap();
+
+        assert!(home.check("dangling.md", false).is_err());
+        assert!(home.check("dangling.md", true).is_err());
+        assert!(home.check("alias.md", true).is_err());
+        assert!(open_for_save(&home.path().join("dangling.md"), false).is_err());
+        assert!(!home.outside().join("missing.md").exists());
+    }
+}
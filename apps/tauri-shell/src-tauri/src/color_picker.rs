@@ -0,0 +1,137 @@
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenColor {
+    // "#rrggbb", sRGB
+    pub hex: String,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl ScreenColor {
+    fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        ScreenColor {
+            hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+            r,
+            g,
+            b,
+        }
+    }
+}
+
+/// Lets the user pick a pixel anywhere on screen with the native eyedropper.
+/// Returns None if the pick was cancelled (Escape).
+#[tauri::command]
+pub async fn pick_screen_color(app_handle: AppHandle) -> Result<Option<ScreenColor>, String> {
+    let (tx, rx) = oneshot::channel();
+    sample(&app_handle, tx).map_err(|e| format!("Failed to start color picker: {}", e))?;
+
+    rx.await
+        .map_err(|_| "Color picker closed unexpectedly".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn sample(app_handle: &AppHandle, tx: oneshot::Sender<Option<ScreenColor>>) -> Result<(), String> {
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::Mutex;
+
+    // NSColorSampler has to be driven from the main thread
+    app_handle
+        .run_on_main_thread(move || unsafe {
+            // The handler is an Fn block but only ever called once
+            let tx = Mutex::new(Some(tx));
+            let handler = ConcreteBlock::new(move |color: id| {
+                let picked = if color == nil {
+                    None
+                } else {
+                    let srgb: id = msg_send![class!(NSColorSpace), sRGBColorSpace];
+                    let color: id = msg_send![color, colorUsingColorSpace: srgb];
+                    let component = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    let r: f64 = msg_send![color, redComponent];
+                    let g: f64 = msg_send![color, greenComponent];
+                    let b: f64 = msg_send![color, blueComponent];
+                    Some(ScreenColor::from_rgb(
+                        component(r),
+                        component(g),
+                        component(b),
+                    ))
+                };
+                if let Some(tx) = tx.lock().unwrap().take() {
+                    let _ = tx.send(picked);
+                }
+            })
+            .copy();
+
+            let sampler: id = msg_send![class!(NSColorSampler), new];
+            let _: () = msg_send![sampler, showSamplerWithSelectionHandler: &*handler];
+            let _: () = msg_send![sampler, release];
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(windows)]
+fn sample(_app_handle: &AppHandle, tx: oneshot::Sender<Option<ScreenColor>>) -> Result<(), String> {
+    use std::time::{Duration, Instant};
+    use windows::Win32::Foundation::{HWND, POINT};
+    use windows::Win32::Graphics::Gdi::{GetDC, GetPixel, ReleaseDC};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_ESCAPE, VK_LBUTTON};
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    // Windows has no system eyedropper: wait for the next click and read that pixel
+    const POLL_INTERVAL: Duration = Duration::from_millis(15);
+    const PICK_TIMEOUT: Duration = Duration::from_secs(60);
+
+    std::thread::spawn(move || {
+        let pressed = |key: u16| unsafe { GetAsyncKeyState(key as i32) as u16 & 0x8000 != 0 };
+        let started = Instant::now();
+
+        // The click that opened the picker may still be held down
+        while pressed(VK_LBUTTON.0) && started.elapsed() < PICK_TIMEOUT {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        let picked = loop {
+            if pressed(VK_ESCAPE.0) || started.elapsed() >= PICK_TIMEOUT {
+                break None;
+            }
+            if pressed(VK_LBUTTON.0) {
+                let mut point = POINT::default();
+                unsafe {
+                    if !GetCursorPos(&mut point).as_bool() {
+                        break None;
+                    }
+                    let screen = GetDC(HWND::default());
+                    // COLORREF is 0x00bbggrr; CLR_INVALID for pixels outside any display
+                    let color = GetPixel(screen, point.x, point.y);
+                    ReleaseDC(HWND::default(), screen);
+                    if color == 0xFFFF_FFFF {
+                        break None;
+                    }
+                    break Some(ScreenColor::from_rgb(
+                        color as u8,
+                        (color >> 8) as u8,
+                        (color >> 16) as u8,
+                    ));
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        let _ = tx.send(picked);
+    });
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn sample(
+    _app_handle: &AppHandle,
+    _tx: oneshot::Sender<Option<ScreenColor>>,
+) -> Result<(), String> {
+    Err("Screen color picking is not supported on this platform".to_string())
+}
@@ -0,0 +1,126 @@
+use crate::network_config;
+use crate::outbox;
+use crate::store;
+use crate::AppState;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Any HTTP answer from the API host means the provider is reachable
+const PROVIDER_URL: &str = "https://api.anthropic.com";
+// Neutral endpoint that tells "no internet" apart from "provider down"
+const INTERNET_URL: &str = "https://www.gstatic.com/generate_204";
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    network_config::client_builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkStatus {
+    Online,
+    Offline,
+    // The internet works but the model provider doesn't answer (or returns 5xx)
+    ProviderUnreachable,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct NetworkStatusEvent {
+    status: NetworkStatus,
+    #[ts(as = "f64")]
+    checked_at: i64,
+}
+
+/// Last probe result; None until the first probe finishes.
+#[derive(Default)]
+pub struct Connectivity {
+    status: Mutex<Option<NetworkStatus>>,
+}
+
+#[tauri::command]
+pub async fn get_network_status(app_handle: AppHandle) -> NetworkStatus {
+    let status = *app_handle.state::<Connectivity>().status.lock().unwrap();
+    match status {
+        Some(status) => status,
+        None => check(&app_handle).await,
+    }
+}
+
+/// Probes reachability every PROBE_INTERVAL, emitting `network_status` on changes.
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            check(&app_handle).await;
+            tokio::time::sleep(PROBE_INTERVAL).await;
+        }
+    });
+}
+
+/// Re-probes immediately, e.g. after the agent reported a network error.
+pub fn probe_now(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        check(&app_handle).await;
+    });
+}
+
+pub fn is_offline(app_handle: &AppHandle) -> bool {
+    *app_handle.state::<Connectivity>().status.lock().unwrap() == Some(NetworkStatus::Offline)
+}
+
+async fn check(app_handle: &AppHandle) -> NetworkStatus {
+    let status = probe().await;
+    let previous = app_handle
+        .state::<Connectivity>()
+        .status
+        .lock()
+        .unwrap()
+        .replace(status);
+
+    if previous != Some(status) {
+        eprintln!("[NETWORK] {:?}", status);
+
+        let event = NetworkStatusEvent {
+            status,
+            checked_at: store::now_millis(),
+        };
+        if let Err(e) = app_handle.emit_all("network_status", event) {
+            eprintln!("Failed to emit network status: {}", e);
+        }
+
+        // Back online: send whatever piled up in the outbox
+        if previous == Some(NetworkStatus::Offline) {
+            let state = app_handle.state::<AppState>();
+            let mut agent = state.agent().lock_owned().await;
+            if let Some(process) = agent.as_mut() {
+                outbox::flush(app_handle, process).await;
+            }
+        }
+    }
+
+    status
+}
+
+async fn probe() -> NetworkStatus {
+    if let Ok(response) = CLIENT.head(PROVIDER_URL).send().await {
+        if !response.status().is_server_error() {
+            return NetworkStatus::Online;
+        }
+    }
+
+    match CLIENT.head(INTERNET_URL).send().await {
+        Ok(_) => NetworkStatus::ProviderUnreachable,
+        Err(_) => NetworkStatus::Offline,
+    }
+}
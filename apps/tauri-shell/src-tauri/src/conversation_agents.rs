@@ -0,0 +1,184 @@
+use crate::agent_ipc::{self, AgentProcess, AgentRequest};
+use crate::{AgentSlot, AppState};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+// A bound process with nothing in flight is stopped after this long without output
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Conversations bound to an agent process of their own, so a long tool run in one
+/// chat doesn't hold up the others. A bound process is started on demand, stopped
+/// when idle, and unbound once no window shows its conversation.
+#[derive(Default)]
+pub struct ConversationAgents {
+    // Conversation id -> agent id
+    bindings: Mutex<HashMap<String, String>>,
+}
+
+/// Gives `conversation_id` its own agent process and returns that agent's id. Requests
+/// for the conversation are routed there from now on.
+#[tauri::command]
+pub async fn bind_conversation_agent(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<String, String> {
+    let agent_id = agent_id_for(&conversation_id);
+    app_handle
+        .state::<ConversationAgents>()
+        .bindings
+        .lock()
+        .unwrap()
+        .insert(conversation_id.clone(), agent_id.clone());
+
+    let slot = app_handle.state::<AppState>().slot(&agent_id);
+    if slot.lock().await.is_none() {
+        start(&app_handle, &agent_id, &conversation_id, &slot)
+            .await
+            .map_err(|e| format!("Failed to start conversation agent: {}", e))?;
+    }
+    Ok(agent_id)
+}
+
+/// Routes `conversation_id` back to the default agent and stops its own process.
+#[tauri::command]
+pub async fn unbind_conversation_agent(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<(), String> {
+    release(&app_handle, &conversation_id).await;
+    Ok(())
+}
+
+/// Bound conversations and the agent id serving each.
+#[tauri::command]
+pub fn list_conversation_agents(agents: State<'_, ConversationAgents>) -> HashMap<String, String> {
+    agents.bindings.lock().unwrap().clone()
+}
+
+/// The agent serving `conversation_id`, if it is bound to one of its own.
+pub fn route(app_handle: &AppHandle, conversation_id: Option<&str>) -> Option<String> {
+    let bindings = app_handle.state::<ConversationAgents>();
+    let bindings = bindings.bindings.lock().unwrap();
+    bindings.get(conversation_id?).cloned()
+}
+
+/// The conversation that `agent_id` was bound for, if it is a conversation agent.
+pub fn conversation_of(app_handle: &AppHandle, agent_id: &str) -> Option<String> {
+    let bindings = app_handle.state::<ConversationAgents>();
+    let bindings = bindings.bindings.lock().unwrap();
+    bindings
+        .iter()
+        .find(|(_, bound)| bound.as_str() == agent_id)
+        .map(|(conversation_id, _)| conversation_id.clone())
+}
+
+/// Starts a bound agent into its slot with its conversation loaded. The slot isn't held
+/// while it starts; one started meanwhile by someone else is kept instead.
+pub async fn start(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    conversation_id: &str,
+    slot: &AgentSlot,
+) -> Result<()> {
+    let mut process = AgentProcess::spawn(app_handle.clone(), agent_id).await?;
+    let request = AgentRequest::LoadConversation {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: conversation_id.to_string(),
+    };
+    if let Err(e) = process.send_and_wait(&request).await {
+        process.shutdown().await;
+        return Err(e);
+    }
+    crate::install_agent(app_handle, agent_id, slot, process).await;
+    Ok(())
+}
+
+/// Unbinds a conversation no window shows any more.
+pub fn on_conversation_closed(app_handle: &AppHandle, conversation_id: &str) {
+    if route(app_handle, Some(conversation_id)).is_none() {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let conversation_id = conversation_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        eprintln!("[AGENTS] {} closed, stopping its agent", conversation_id);
+        release(&app_handle, &conversation_id).await;
+    });
+}
+
+/// Stops bound processes that have been idle for IDLE_TIMEOUT. Their conversations
+/// stay bound; the next message starts them again.
+pub fn start_reaper(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REAP_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let agent_ids: Vec<String> = {
+                let bindings = app_handle.state::<ConversationAgents>();
+                let bindings = bindings.bindings.lock().unwrap();
+                bindings.values().cloned().collect()
+            };
+            for agent_id in agent_ids {
+                let Some(slot) = app_handle.state::<AppState>().find(&agent_id) else {
+                    continue;
+                };
+                // Busy spawning or sending; not idle
+                let Ok(mut agent) = slot.try_lock_owned() else {
+                    continue;
+                };
+                let Some(process) = agent.as_mut() else {
+                    continue;
+                };
+
+                let status = process.status().await;
+                let last_active = status.last_message_at.max(status.spawned_at).unwrap_or(0);
+                let idle_ms = crate::store::now_millis() - last_active;
+                if status.in_flight > 0 || idle_ms < IDLE_TIMEOUT.as_millis() as i64 {
+                    continue;
+                }
+
+                eprintln!("[AGENTS] Stopping idle agent {}", agent_id);
+                if let Some(process) = agent.take() {
+                    let lost_requests = process.shutdown().await;
+                    agent_ipc::emit_lifecycle(
+                        &app_handle,
+                        &agent_id,
+                        "agent_stopped",
+                        lost_requests,
+                    );
+                }
+            }
+        }
+    });
+}
+
+fn agent_id_for(conversation_id: &str) -> String {
+    format!("conversation:{}", conversation_id)
+}
+
+/// Drops the binding of `conversation_id` and shuts its process down.
+pub async fn release(app_handle: &AppHandle, conversation_id: &str) {
+    let agent_id = app_handle
+        .state::<ConversationAgents>()
+        .bindings
+        .lock()
+        .unwrap()
+        .remove(conversation_id);
+    let Some(agent_id) = agent_id else {
+        return;
+    };
+    let Some(slot) = app_handle.state::<AppState>().find(&agent_id) else {
+        return;
+    };
+
+    let process = slot.lock().await.take();
+    if let Some(process) = process {
+        let lost_requests = process.shutdown().await;
+        agent_ipc::emit_lifecycle(app_handle, &agent_id, "agent_stopped", lost_requests);
+    }
+}
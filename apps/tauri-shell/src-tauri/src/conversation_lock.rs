@@ -0,0 +1,39 @@
+use crate::store::ConversationStore;
+use tauri::State;
+
+/// Prefix of the error returned for changes to a locked conversation, so the webview
+/// can tell it apart from other failures.
+pub const LOCKED_ERROR: &str = "conversation_locked";
+
+/// Makes a conversation read-only, for finished threads kept for reference. New
+/// messages, drafts and merges are refused with LOCKED_ERROR until it is unlocked.
+#[tauri::command]
+pub fn lock_conversation(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<(), String> {
+    store
+        .set_locked(&conversation_id, true)
+        .map_err(|e| format!("Failed to lock conversation: {}", e))
+}
+
+#[tauri::command]
+pub fn unlock_conversation(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<(), String> {
+    store
+        .set_locked(&conversation_id, false)
+        .map_err(|e| format!("Failed to unlock conversation: {}", e))
+}
+
+/// Fails with LOCKED_ERROR if `conversation_id` is locked.
+pub fn ensure_unlocked(store: &ConversationStore, conversation_id: &str) -> Result<(), String> {
+    if store.is_locked(conversation_id) {
+        return Err(format!(
+            "{}: conversation {} is read-only",
+            LOCKED_ERROR, conversation_id
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::agent_ipc::MessageState;
+use crate::data_dir;
+use crate::redact;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use ts_rs::TS;
+
+// Reports kept on disk; the oldest are deleted as new ones are written
+const MAX_REPORTS: usize = 20;
+
+/// What was known about an agent process when it died, saved as
+/// crash-reports/<report_id>.json for the user to view or send.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CrashReport {
+    pub report_id: String,
+    pub agent_id: String,
+    // For get_agent_session_logs
+    pub session_id: String,
+    pub shell_version: String,
+    pub os: String,
+    #[ts(as = "f64")]
+    pub started_at: i64,
+    #[ts(as = "f64")]
+    pub crashed_at: i64,
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub stderr_tail: Vec<String>,
+    pub stdout_tail: Vec<String>,
+    pub in_flight: Vec<InFlightRequest>,
+}
+
+/// A request the agent was working on when it died.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct InFlightRequest {
+    pub id: String,
+    pub kind: String,
+    pub conversation_id: Option<String>,
+    pub message: Option<String>,
+    pub state: MessageState,
+    // Reply characters received before the crash
+    pub response_chars: usize,
+}
+
+/// Crash reports, newest first.
+#[tauri::command]
+pub fn get_crash_reports(app_handle: AppHandle) -> Result<Vec<CrashReport>, String> {
+    let Some(dir) = reports_dir(&app_handle) else {
+        return Ok(Vec::new());
+    };
+    let mut reports: Vec<CrashReport> = report_ids(&dir)
+        .iter()
+        .filter_map(|id| read_report(&dir, id).ok())
+        .collect();
+    reports.reverse();
+    Ok(reports)
+}
+
+/// Saves `report` with the redaction settings applied, so it can be sent as is.
+pub fn write(app_handle: &AppHandle, mut report: CrashReport) -> Result<PathBuf> {
+    if let Some(redactor) = redact::for_export(app_handle)? {
+        for line in report.stderr_tail.iter_mut().chain(&mut report.stdout_tail) {
+            *line = redactor.apply(line);
+        }
+        for request in &mut report.in_flight {
+            request.message = request.message.as_deref().map(|m| redactor.apply(m));
+        }
+    }
+
+    let dir = reports_dir(app_handle).context("No data directory available")?;
+    std::fs::create_dir_all(&dir).context("Failed to create crash report directory")?;
+    prune(&dir);
+
+    let path = dir.join(format!("{}.json", report.report_id));
+    let json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(&path, json).context("Failed to write crash report")?;
+    Ok(path)
+}
+
+fn reports_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("crash-reports"))
+}
+
+fn read_report(dir: &Path, report_id: &str) -> Result<CrashReport> {
+    let json = std::fs::read_to_string(dir.join(format!("{}.json", report_id)))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+// Ids of the reports on disk, oldest first
+fn report_ids(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.strip_suffix(".json").map(str::to_string)
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+// Makes room for one more report
+fn prune(dir: &Path) {
+    let ids = report_ids(dir);
+    let excess = (ids.len() + 1).saturating_sub(MAX_REPORTS);
+    for id in &ids[..excess] {
+        let path = dir.join(format!("{}.json", id));
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("Failed to remove old crash report {:?}: {}", path, e);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use crate::settings::SettingsStore;
+use crate::shutdown;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// Takes precedence over the data_dir setting
+const DATA_DIR_ENV: &str = "ASST_DATA_DIR";
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum DataDirSource {
+    Environment,
+    Settings,
+    // Tauri's per-platform app data dir ($XDG_DATA_HOME on Linux)
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct DataDirInfo {
+    pub path: Option<String>,
+    pub source: DataDirSource,
+    pub default_path: Option<String>,
+}
+
+/// Root for conversations, drafts, the outbox, agent bundles and diagnostics.
+/// Settings are read from the config dir so they can point elsewhere. On macOS and
+/// Windows that is the default data dir itself, so moves leave settings files behind.
+pub fn resolve(app_handle: &AppHandle) -> Option<PathBuf> {
+    resolve_with_source(app_handle).0
+}
+
+fn resolve_with_source(app_handle: &AppHandle) -> (Option<PathBuf>, DataDirSource) {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return (Some(PathBuf::from(dir)), DataDirSource::Environment);
+    }
+    if let Some(dir) = app_handle.state::<SettingsStore>().get().data_dir {
+        return (Some(dir), DataDirSource::Settings);
+    }
+    (default_dir(app_handle), DataDirSource::Default)
+}
+
+fn default_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    app_handle.path_resolver().app_data_dir()
+}
+
+#[tauri::command]
+pub fn get_data_dir(app_handle: AppHandle) -> DataDirInfo {
+    let (path, source) = resolve_with_source(&app_handle);
+    let display = |path: Option<PathBuf>| path.map(|path| path.to_string_lossy().into_owned());
+
+    DataDirInfo {
+        path: display(path),
+        source,
+        default_path: display(default_dir(&app_handle)),
+    }
+}
+
+/// Moves all data to `path` (or back to the default location when None), saves the
+/// new location and restarts, since the stores keep their paths for the app's lifetime.
+/// The agents are stopped and the session recorded as closed cleanly before anything
+/// moves; if the move fails, what was moved goes back and the app restarts as it was.
+#[tauri::command]
+pub async fn move_data_dir(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    path: Option<String>,
+) -> Result<(), String> {
+    if std::env::var_os(DATA_DIR_ENV).is_some() {
+        return Err(format!(
+            "The data directory is set by {}; unset it to move data",
+            DATA_DIR_ENV
+        ));
+    }
+
+    let source = resolve(&app_handle).ok_or_else(|| "No data directory available".to_string())?;
+    let target = match &path {
+        Some(path) => PathBuf::from(path),
+        None => default_dir(&app_handle)
+            .ok_or_else(|| "No default data directory available".to_string())?,
+    };
+    if !target.is_absolute() {
+        return Err("The data directory must be an absolute path".to_string());
+    }
+
+    check_target(&source, &target).map_err(|e| format!("Can't move data: {}", e))?;
+    // Nothing may write to the old location mid-move
+    if !shutdown::prepare_restart(&app_handle).await {
+        return Err("The app is quitting".to_string());
+    }
+
+    let move_source = source.clone();
+    let move_target = target.clone();
+    let moved =
+        tauri::async_runtime::spawn_blocking(move || move_contents(&move_source, &move_target))
+            .await
+            .map_err(|e| anyhow!("Move task failed: {}", e))
+            .and_then(|moved| moved);
+
+    match moved {
+        Ok(()) => {
+            // Storing None keeps following the platform default if it ever changes
+            let setting = path.map(|_| target);
+            match settings.update(|s| s.data_dir = setting) {
+                Ok(()) => eprintln!("[DATA] Moved data directory, restarting"),
+                Err(e) => eprintln!("Failed to save data directory: {}", e),
+            }
+        }
+        Err(e) => eprintln!("Failed to move data directory, restarting: {:#}", e),
+    }
+    app_handle.restart();
+    Ok(())
+}
+
+// Settings files, which stay in the config dir even when that is the data dir
+fn is_settings_file(name: &std::ffi::OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name == "settings.json" || (name.starts_with("settings-") && name.ends_with(".json"))
+}
+
+fn check_target(source: &Path, target: &Path) -> Result<()> {
+    if source != target && target.starts_with(source) {
+        return Err(anyhow!("Can't move data into a subdirectory of itself"));
+    }
+    let target_is_empty = match std::fs::read_dir(target) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .all(|entry| is_settings_file(&entry.file_name())),
+        Err(_) => true,
+    };
+    if source != target && !target_is_empty {
+        return Err(anyhow!("{} is not empty", target.display()));
+    }
+    Ok(())
+}
+
+fn move_contents(source: &Path, target: &Path) -> Result<()> {
+    if source == target {
+        return Ok(());
+    }
+    std::fs::create_dir_all(target).context("Failed to create data directory")?;
+    if !source.exists() {
+        return Ok(());
+    }
+
+    let mut moved = Vec::new();
+    for entry in std::fs::read_dir(source).context("Failed to read data directory")? {
+        let entry = entry?;
+        if is_settings_file(&entry.file_name()) {
+            continue;
+        }
+        let destination = target.join(entry.file_name());
+        if let Err(e) = move_entry(&entry.path(), &destination) {
+            // Back where the saved setting still points
+            for (from, to) in moved.iter().rev() {
+                if let Err(e) = move_entry(to, from) {
+                    eprintln!("Failed to move {} back: {:#}", to.display(), e);
+                }
+            }
+            return Err(e);
+        }
+        moved.push((entry.path(), destination));
+    }
+
+    // Only succeeds if nothing else was left behind
+    let _ = std::fs::remove_dir(source);
+    Ok(())
+}
+
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    // Rename fails across filesystems; fall back to copy + delete
+    if std::fs::rename(from, to).is_err() {
+        if let Err(e) = copy_recursive(from, to) {
+            // Leaves the original in place
+            let _ = remove_recursive(to);
+            return Err(e).with_context(|| format!("Failed to copy {}", from.display()));
+        }
+        remove_recursive(from)?;
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn remove_recursive(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+    .with_context(|| format!("Failed to remove {}", path.display()))
+}
@@ -0,0 +1,86 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Window;
+use ts_rs::TS;
+
+// Identical messages closer together than this are treated as a double submit
+const DUPLICATE_WINDOW: Duration = Duration::from_millis(1500);
+
+/// Recently submitted user messages, keyed by a hash of their content, so a
+/// double-click or double-Enter doesn't start (and bill) two generations.
+#[derive(Default)]
+pub struct DuplicateGuard {
+    recent: Mutex<HashMap<u64, Submission>>,
+}
+
+struct Submission {
+    id: String,
+    at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct DuplicateSuppressed<'a> {
+    id: &'a str,
+    // The earlier request this one repeated
+    original_id: &'a str,
+    conversation_id: Option<&'a str>,
+}
+
+impl DuplicateGuard {
+    /// Records the submission and returns None, or returns the id of an identical
+    /// one from the same window within DUPLICATE_WINDOW.
+    fn check(&self, key: u64, id: &str) -> Option<String> {
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|_, submission| now.duration_since(submission.at) < DUPLICATE_WINDOW);
+
+        match recent.get(&key) {
+            // A resend of the same request (e.g. after an error) isn't a duplicate
+            Some(submission) if submission.id != id => Some(submission.id.clone()),
+            _ => {
+                recent.insert(
+                    key,
+                    Submission {
+                        id: id.to_string(),
+                        at: now,
+                    },
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Whether the message repeats one this window just sent. If so, the sender gets
+/// `duplicate_suppressed` and the message should be dropped.
+pub fn is_duplicate(
+    guard: &DuplicateGuard,
+    window: &Window,
+    id: &str,
+    message: &str,
+    images: Option<&str>,
+    conversation_id: Option<&str>,
+) -> bool {
+    let mut hasher = DefaultHasher::new();
+    (window.label(), message, images, conversation_id).hash(&mut hasher);
+
+    let Some(original_id) = guard.check(hasher.finish(), id) else {
+        return false;
+    };
+    eprintln!("[AGENT] Suppressed {}, a duplicate of {}", id, original_id);
+
+    let event = DuplicateSuppressed {
+        id,
+        original_id: &original_id,
+        conversation_id,
+    };
+    if let Err(e) = window.emit("duplicate_suppressed", event) {
+        eprintln!("Failed to emit duplicate_suppressed: {}", e);
+    }
+    true
+}
@@ -0,0 +1,227 @@
+use crate::data_dir;
+use crate::store;
+use anyhow::{anyhow, Context, Result};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, Window};
+use tokio::sync::oneshot;
+
+// Older window captures are pruned so the bundle stays small
+const MAX_SCREENSHOTS: usize = 5;
+
+/// Directory collected into bug reports (`<app data>/diagnostics`).
+pub fn diagnostics_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("diagnostics"))
+}
+
+/// Screenshots the calling window only — never the rest of the desktop — and saves it
+/// as a PNG in the diagnostics directory. Returns the saved file path.
+#[tauri::command]
+pub async fn capture_app_window(window: Window) -> Result<String, String> {
+    let dir = diagnostics_dir(&window.app_handle())
+        .ok_or_else(|| "No data directory available".to_string())?;
+
+    let (tx, rx) = oneshot::channel();
+    let window_clone = window.clone();
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(capture(&window_clone));
+        })
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+    let png = rx
+        .await
+        .map_err(|_| "Window capture was dropped".to_string())?
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+
+    tauri::async_runtime::spawn_blocking(move || -> Result<String> {
+        std::fs::create_dir_all(&dir).context("Failed to create diagnostics directory")?;
+        let path = dir.join(format!("window-{}.png", store::now_millis()));
+        std::fs::write(&path, png).context("Failed to write screenshot")?;
+        prune_screenshots(&dir);
+
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| format!("Capture task failed: {}", e))?
+    .map_err(|e| format!("Failed to save screenshot: {}", e))
+}
+
+fn prune_screenshots(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    // Timestamped names sort chronologically
+    let mut screenshots: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("window-") && name.ends_with(".png"))
+                .unwrap_or(false)
+        })
+        .collect();
+    screenshots.sort();
+
+    let excess = screenshots.len().saturating_sub(MAX_SCREENSHOTS);
+    for path in &screenshots[..excess] {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Failed to remove old screenshot {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGWindowListCreateImage(
+        bounds: cocoa::foundation::NSRect,
+        list_option: u32,
+        window_id: u32,
+        image_option: u32,
+    ) -> cocoa::base::id;
+    fn CGImageRelease(image: cocoa::base::id);
+}
+
+#[cfg(target_os = "macos")]
+fn capture(window: &Window) -> Result<Vec<u8>> {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    // kCGWindowListOptionIncludingWindow, kCGWindowImageBoundsIgnoreFraming
+    const INCLUDING_WINDOW: u32 = 1 << 3;
+    const IGNORE_FRAMING: u32 = 1 << 0;
+    // NSBitmapImageFileTypePNG
+    const PNG_FILE_TYPE: u64 = 4;
+
+    let ns_window = window.ns_window()? as id;
+
+    unsafe {
+        let window_number: i64 = msg_send![ns_window, windowNumber];
+        // CGRectNull: the bounds of the captured window itself
+        let null_rect = NSRect::new(
+            NSPoint::new(f64::INFINITY, f64::INFINITY),
+            NSSize::new(0.0, 0.0),
+        );
+        let image = CGWindowListCreateImage(
+            null_rect,
+            INCLUDING_WINDOW,
+            window_number as u32,
+            IGNORE_FRAMING,
+        );
+        if image == nil {
+            return Err(anyhow!("CGWindowListCreateImage returned no image"));
+        }
+
+        let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+        let rep: id = msg_send![rep, initWithCGImage: image];
+        CGImageRelease(image);
+
+        let properties: id = msg_send![class!(NSDictionary), dictionary];
+        let data: id =
+            msg_send![rep, representationUsingType: PNG_FILE_TYPE properties: properties];
+        let png = if data == nil {
+            None
+        } else {
+            let bytes: *const u8 = msg_send![data, bytes];
+            let length: usize = msg_send![data, length];
+            Some(std::slice::from_raw_parts(bytes, length).to_vec())
+        };
+        let _: () = msg_send![rep, release];
+
+        png.ok_or_else(|| anyhow!("Failed to encode screenshot"))
+    }
+}
+
+#[cfg(windows)]
+fn capture(window: &Window) -> Result<Vec<u8>> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS};
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    // PW_RENDERFULLCONTENT: needed to capture WebView2 content
+    const RENDER_FULL_CONTENT: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(2);
+
+    let hwnd = window.hwnd()?;
+
+    unsafe {
+        let mut rect = RECT::default();
+        if !GetWindowRect(hwnd, &mut rect).as_bool() {
+            return Err(anyhow!("Failed to read window bounds"));
+        }
+        let width = rect.right - rect.left;
+        let height = rect.bottom - rect.top;
+        if width <= 0 || height <= 0 {
+            return Err(anyhow!("Window has no visible area"));
+        }
+
+        let window_dc = GetDC(hwnd);
+        let memory_dc = CreateCompatibleDC(window_dc);
+        let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+
+        let printed = PrintWindow(hwnd, memory_dc, RENDER_FULL_CONTENT).as_bool();
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height: rows top-down
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: 0, // BI_RGB
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let lines = GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            pixels.as_mut_ptr() as *mut c_void,
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        SelectObject(memory_dc, previous);
+        DeleteObject(bitmap);
+        DeleteDC(memory_dc);
+        ReleaseDC(hwnd, window_dc);
+
+        if !printed || lines == 0 {
+            return Err(anyhow!("PrintWindow failed"));
+        }
+
+        // BGRX -> RGBA
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+            pixel[3] = 255;
+        }
+        encode_png(width as u32, height as u32, pixels)
+    }
+}
+
+#[cfg(windows)]
+fn encode_png(width: u32, height: u32, rgba: Vec<u8>) -> Result<Vec<u8>> {
+    let image = image::RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| anyhow!("Screenshot buffer has the wrong size"))?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .context("Failed to encode screenshot")?;
+    Ok(png.into_inner())
+}
+
+// Neither X11 nor Wayland offers a permission-free capture of a single window
+#[cfg(not(any(target_os = "macos", windows)))]
+fn capture(_window: &Window) -> Result<Vec<u8>> {
+    Err(anyhow!("Window capture is not supported on this platform"))
+}
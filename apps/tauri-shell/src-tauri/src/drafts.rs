@@ -0,0 +1,32 @@
+use crate::conversation_lock;
+use crate::store::{self, ConversationStore, Draft};
+use tauri::State;
+
+#[tauri::command]
+pub fn save_draft(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+    text: String,
+    attachments: Option<String>,
+) -> Result<(), String> {
+    conversation_lock::ensure_unlocked(&store, &conversation_id)?;
+    let draft = Draft {
+        text,
+        attachments: attachments.filter(|attachments| !attachments.is_empty()),
+        updated_at: store::now_millis(),
+    };
+
+    store
+        .save_draft(&conversation_id, &draft)
+        .map_err(|e| format!("Failed to save draft: {}", e))
+}
+
+#[tauri::command]
+pub fn get_draft(
+    store: State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<Option<Draft>, String> {
+    store
+        .load_draft(&conversation_id)
+        .map_err(|e| format!("Failed to load draft: {}", e))
+}
@@ -0,0 +1,258 @@
+use crate::agent_ipc::{self, AgentProtocolError, AgentResponse};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use ts_rs::TS;
+
+// Events waiting for the webview before the reader holds off reading agent output
+const CAPACITY: usize = 256;
+// How often queued events go out; tokens of a reply arriving in between go as one
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// An event on its way from an agent's stdout reader to the webview.
+pub enum QueuedEvent {
+    Response {
+        // Window that issued the request, None for everywhere
+        owner: Option<String>,
+        response: AgentResponse,
+    },
+    // Diagnostics, the first to go when the queue is full
+    ProtocolError(AgentProtocolError),
+}
+
+/// What a full queue has cost, since the agent started.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct EventQueueStats {
+    pub capacity: usize,
+    pub queued: usize,
+    #[ts(as = "f64")]
+    pub emitted: u64,
+    // Tokens merged into one already queued for the same reply
+    #[ts(as = "f64")]
+    pub coalesced_tokens: u64,
+    #[ts(as = "f64")]
+    pub dropped_logs: u64,
+    // Times the reader waited for room
+    #[ts(as = "f64")]
+    pub stalls: u64,
+}
+
+/// Bounded queue between an agent's stdout reader and the events it emits, so a fast
+/// agent can't flood the webview. Queued events go out once per frame, with the tokens
+/// each reply streamed meanwhile merged into one. When full, diagnostics are dropped,
+/// and only then does the reader wait. Every agent_response of the agent goes through
+/// here, so nothing the shell reports for a reply overtakes what it streamed.
+pub struct EventQueue {
+    app_handle: AppHandle,
+    agent_id: String,
+    events: Mutex<VecDeque<QueuedEvent>>,
+    // Signalled on push and on close
+    pushed: Notify,
+    // Signalled once the emitter has taken everything queued
+    drained: Notify,
+    closed: AtomicBool,
+    // Set under the events lock once the emitter has emitted its last event
+    finished: AtomicBool,
+    emitter: Mutex<Option<JoinHandle<()>>>,
+    emitted: AtomicU64,
+    coalesced_tokens: AtomicU64,
+    dropped_logs: AtomicU64,
+    stalls: AtomicU64,
+}
+
+impl EventQueue {
+    /// Creates the queue of `agent_id` and starts emitting from it.
+    pub fn start(app_handle: AppHandle, agent_id: String) -> Arc<Self> {
+        let queue = Arc::new(EventQueue {
+            app_handle,
+            agent_id,
+            events: Mutex::new(VecDeque::with_capacity(CAPACITY)),
+            pushed: Notify::new(),
+            drained: Notify::new(),
+            closed: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            emitter: Mutex::new(None),
+            emitted: AtomicU64::new(0),
+            coalesced_tokens: AtomicU64::new(0),
+            dropped_logs: AtomicU64::new(0),
+            stalls: AtomicU64::new(0),
+        });
+        let emitter = tokio::spawn(queue.clone().run());
+        *queue.emitter.lock().unwrap() = Some(emitter);
+        queue
+    }
+
+    /// Queues `event`, waiting for room if nothing can be merged or dropped.
+    pub async fn push(&self, event: QueuedEvent) {
+        let mut event = Some(event);
+        loop {
+            {
+                let mut events = self.events.lock().unwrap();
+                event = event.and_then(|event| self.coalesce(&mut events, event));
+                if events.len() < CAPACITY {
+                    events.extend(event.take());
+                } else if let Some(rejected) = self.make_room(&mut events, event.take()) {
+                    event = Some(rejected);
+                }
+            }
+            if event.is_none() {
+                self.pushed.notify_one();
+                return;
+            }
+            self.stalls.fetch_add(1, Ordering::Relaxed);
+            self.drained.notified().await;
+        }
+    }
+
+    /// Queues a response the shell produced itself, e.g. a failure, behind what the
+    /// reply already streamed. Never waits; once the emitter has finished, the response
+    /// goes out right away.
+    pub fn push_now(&self, owner: Option<String>, response: AgentResponse) {
+        let event = QueuedEvent::Response { owner, response };
+        {
+            let mut events = self.events.lock().unwrap();
+            if !self.finished.load(Ordering::SeqCst) {
+                if let Some(event) = self.coalesce(&mut events, event) {
+                    events.push_back(event);
+                }
+                drop(events);
+                self.pushed.notify_one();
+                return;
+            }
+        }
+        self.emit(event);
+    }
+
+    // Merges a token into its reply's latest queued event if that is a token too, so
+    // nothing overtakes what followed it. Hands back anything it didn't merge.
+    fn coalesce(
+        &self,
+        events: &mut VecDeque<QueuedEvent>,
+        event: QueuedEvent,
+    ) -> Option<QueuedEvent> {
+        if let QueuedEvent::Response {
+            response:
+                AgentResponse::Token {
+                    id,
+                    token,
+                    timestamp,
+                },
+            ..
+        } = &event
+        {
+            let latest = events.iter_mut().rev().find_map(|queued| match queued {
+                QueuedEvent::Response { response, .. } if response.id() == Some(id.as_str()) => {
+                    Some(response)
+                }
+                _ => None,
+            });
+            if let Some(AgentResponse::Token {
+                token: queued,
+                timestamp: queued_at,
+                ..
+            }) = latest
+            {
+                queued.push_str(token);
+                *queued_at = *timestamp;
+                self.coalesced_tokens.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        }
+        Some(event)
+    }
+
+    // Applies the overflow policy to a full queue. Hands `event` back if it has to wait.
+    fn make_room(
+        &self,
+        events: &mut VecDeque<QueuedEvent>,
+        event: Option<QueuedEvent>,
+    ) -> Option<QueuedEvent> {
+        let event = event?;
+        let oldest_log = events
+            .iter()
+            .position(|queued| matches!(queued, QueuedEvent::ProtocolError(_)));
+        match oldest_log {
+            Some(index) => {
+                events.remove(index);
+                events.push_back(event);
+            }
+            // A new diagnostic goes instead of waiting
+            None if matches!(event, QueuedEvent::ProtocolError(_)) => {}
+            None => return Some(event),
+        }
+        self.dropped_logs.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Stops accepting events and waits until the queued ones have been emitted.
+    pub async fn finish(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.pushed.notify_one();
+        let emitter = self.emitter.lock().unwrap().take();
+        if let Some(emitter) = emitter {
+            let _ = emitter.await;
+        }
+    }
+
+    pub fn stats(&self) -> EventQueueStats {
+        EventQueueStats {
+            capacity: CAPACITY,
+            queued: self.events.lock().unwrap().len(),
+            emitted: self.emitted.load(Ordering::Relaxed),
+            coalesced_tokens: self.coalesced_tokens.load(Ordering::Relaxed),
+            dropped_logs: self.dropped_logs.load(Ordering::Relaxed),
+            stalls: self.stalls.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn run(self: Arc<Self>) {
+        loop {
+            let batch = {
+                let mut events = self.events.lock().unwrap();
+                if events.is_empty() && self.closed.load(Ordering::SeqCst) {
+                    self.finished.store(true, Ordering::SeqCst);
+                    return;
+                }
+                std::mem::take(&mut *events)
+            };
+            if batch.is_empty() {
+                self.pushed.notified().await;
+                continue;
+            }
+            self.drained.notify_one();
+
+            for event in batch {
+                self.emit(event);
+            }
+            // What arrives meanwhile waits for the next frame, merged where it can be
+            if !self.closed.load(Ordering::SeqCst) {
+                tokio::time::sleep(FRAME_INTERVAL).await;
+            }
+        }
+    }
+
+    fn emit(&self, event: QueuedEvent) {
+        match event {
+            QueuedEvent::Response { owner, response } => {
+                agent_ipc::emit_response(
+                    &self.app_handle,
+                    &self.agent_id,
+                    owner.as_deref(),
+                    &response,
+                );
+            }
+            QueuedEvent::ProtocolError(error) => {
+                if let Err(e) = self.app_handle.emit_all("agent_protocol_error", &error) {
+                    eprintln!("Failed to emit agent_protocol_error: {}", e);
+                }
+            }
+        }
+        self.emitted.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::i18n;
+use std::path::Path;
+use tauri::api::dialog;
+use tokio::sync::oneshot;
+
+// URL schemes the frontend may hand to the OS; everything else is treated as a path
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+// Extensions that launch code when opened, regardless of the executable bit
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app", "bat", "cmd", "com", "command", "cpl", "exe", "hta", "jar", "js", "lnk", "msc", "msi",
+    "pkg", "ps1", "reg", "scr", "sh", "url", "vbe", "vbs", "workflow", "wsf",
+];
+
+/// Opens a URL or local file with the user's default application after validating it.
+/// Executables require an explicit confirmation from the user before they are launched.
+#[tauri::command]
+pub async fn open_external(window: tauri::Window, path_or_url: String) -> Result<(), String> {
+    let target = path_or_url.trim().to_string();
+
+    if let Some(scheme) = url_scheme(&target) {
+        if !ALLOWED_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!("Refusing to open URL with scheme '{}'", scheme));
+        }
+        return open::that(&target).map_err(|e| format!("Failed to open URL: {}", e));
+    }
+
+    let path = Path::new(&target);
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", target));
+    }
+
+    if is_executable(path) {
+        let (tx, rx) = oneshot::channel();
+        dialog::ask(
+            Some(&window),
+            i18n::t("dialog-open-executable-title"),
+            i18n::t_args(
+                "dialog-open-executable-body",
+                &[("path", target.clone().into())],
+            ),
+            move |confirmed| {
+                let _ = tx.send(confirmed);
+            },
+        );
+
+        if !rx.await.unwrap_or(false) {
+            return Err("Opening executable was cancelled".to_string());
+        }
+    }
+
+    open::that(path).map_err(|e| format!("Failed to open path: {}", e))
+}
+
+// The lowercased scheme if `target` is a URL; None for local paths
+fn url_scheme(target: &str) -> Option<String> {
+    let (scheme, _) = target.split_once(':')?;
+    // Single letters are Windows drive prefixes (C:\...), not schemes; UNC and verbatim
+    // paths (\\server\share, \\?\C:\...) have separators before any colon
+    (scheme.len() > 1 && !scheme.contains(['/', '\\'])).then(|| scheme.to_ascii_lowercase())
+}
+
+fn is_executable(path: &Path) -> bool {
+    let has_executable_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| EXECUTABLE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false);
+
+    if has_executable_extension {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Ok(metadata) = path.metadata() {
+            return metadata.is_file() && metadata.permissions().mode() & 0o111 != 0;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(target: &str) -> Option<bool> {
+        url_scheme(target).map(|scheme| ALLOWED_SCHEMES.contains(&scheme.as_str()))
+    }
+
+    #[test]
+    fn only_allowed_schemes_open_as_urls() {
+        assert_eq!(allowed("https://example.com"), Some(true));
+        assert_eq!(allowed("HTTP://example.com"), Some(true));
+        assert_eq!(allowed("mailto:someone@example.com"), Some(true));
+        assert_eq!(allowed("file:///etc/passwd"), Some(false));
+        assert_eq!(allowed("javascript:alert(1)"), Some(false));
+        assert_eq!(allowed("smb://server/share"), Some(false));
+        assert_eq!(allowed("ms-settings:privacy"), Some(false));
+    }
+
+    #[test]
+    fn windows_paths_are_not_urls() {
+        assert_eq!(url_scheme(r"C:\Users\me\notes.txt"), None);
+        assert_eq!(url_scheme("c:/Users/me/notes.txt"), None);
+        assert_eq!(url_scheme(r"\\server\share\notes.txt"), None);
+        assert_eq!(url_scheme(r"\\?\C:\Users\me\notes.txt"), None);
+        assert_eq!(url_scheme(r"\\.\C:\notes.txt"), None);
+    }
+
+    #[test]
+    fn unix_paths_are_not_urls() {
+        assert_eq!(url_scheme("/home/me/notes.txt"), None);
+        assert_eq!(url_scheme("/home/me/a:b.txt"), None);
+        assert_eq!(url_scheme("./a:b.txt"), None);
+    }
+
+    #[test]
+    fn executable_extensions_match_case_insensitively() {
+        for name in [
+            "setup.exe",
+            "Setup.EXE",
+            "run.Bat",
+            "link.lnk",
+            "site.url",
+            "x.wsf",
+        ] {
+            assert!(is_executable(Path::new(name)), "{}", name);
+        }
+        for name in [
+            "notes.txt",
+            "photo.png",
+            "archive.zip",
+            "exe",
+            "no_extension",
+        ] {
+            assert!(!is_executable(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_bit_marks_files_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("asst-external-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let set_mode = |path: &Path, mode: u32| {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+
+        let script = dir.join("script");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        set_mode(&script, 0o644);
+        assert!(!is_executable(&script));
+        set_mode(&script, 0o744);
+        assert!(is_executable(&script));
+        set_mode(&script, 0o601);
+        assert!(is_executable(&script));
+
+        let folder = dir.join("folder");
+        std::fs::create_dir_all(&folder).unwrap();
+        set_mode(&folder, 0o755);
+        assert!(!is_executable(&folder));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
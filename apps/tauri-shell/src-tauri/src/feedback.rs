@@ -0,0 +1,154 @@
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cue {
+    Completed,
+    Error,
+    ApprovalNeeded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedbackSettings {
+    pub sounds_enabled: bool,
+    // Force Touch trackpad haptics, macOS only
+    pub haptics_enabled: bool,
+    // Per-cue sound overrides: an NSSound name on macOS, a freedesktop sound id on Linux
+    pub completed_sound: Option<String>,
+    pub error_sound: Option<String>,
+    pub approval_needed_sound: Option<String>,
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        FeedbackSettings {
+            sounds_enabled: true,
+            haptics_enabled: true,
+            completed_sound: None,
+            error_sound: None,
+            approval_needed_sound: None,
+        }
+    }
+}
+
+impl FeedbackSettings {
+    fn sound_for(&self, cue: Cue) -> Option<&str> {
+        match cue {
+            Cue::Completed => self.completed_sound.as_deref(),
+            Cue::Error => self.error_sound.as_deref(),
+            Cue::ApprovalNeeded => self.approval_needed_sound.as_deref(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_feedback_settings(settings: State<'_, SettingsStore>) -> FeedbackSettings {
+    settings.get().feedback
+}
+
+#[tauri::command]
+pub fn set_feedback_settings(
+    settings: State<'_, SettingsStore>,
+    feedback: FeedbackSettings,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.feedback = feedback)
+        .map_err(|e| format!("Failed to save feedback settings: {}", e))
+}
+
+/// Lets the frontend trigger cues the shell can't observe itself (e.g. approval prompts).
+#[tauri::command]
+pub fn play_feedback(app_handle: AppHandle, cue: Cue) {
+    play(&app_handle, cue);
+}
+
+/// Plays the configured sound and haptic for `cue`. Runs natively, so it works
+/// while the window is hidden.
+pub fn play(app_handle: &AppHandle, cue: Cue) {
+    let feedback = app_handle.state::<SettingsStore>().get().feedback;
+
+    if feedback.sounds_enabled {
+        let sound = feedback.sound_for(cue).map(str::to_string);
+        std::thread::spawn(move || play_sound(cue, sound.as_deref()));
+    }
+
+    if feedback.haptics_enabled {
+        let _ = app_handle.run_on_main_thread(perform_haptic);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn play_sound(cue: Cue, sound: Option<&str>) {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::NSString;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let name = sound.unwrap_or(match cue {
+        Cue::Completed => "Glass",
+        Cue::Error => "Basso",
+        Cue::ApprovalNeeded => "Ping",
+    });
+
+    unsafe {
+        let name = NSString::alloc(nil).init_str(name);
+        let sound: id = msg_send![class!(NSSound), soundNamed: name];
+        if sound != nil {
+            let _: bool = msg_send![sound, play];
+        }
+        let _: () = msg_send![name, release];
+    }
+}
+
+#[cfg(windows)]
+fn play_sound(cue: Cue, _sound: Option<&str>) {
+    use windows::Win32::System::Diagnostics::Debug::MessageBeep;
+    use windows::Win32::UI::WindowsAndMessaging::{MB_ICONASTERISK, MB_ICONHAND, MB_ICONQUESTION};
+
+    let kind = match cue {
+        Cue::Completed => MB_ICONASTERISK,
+        Cue::Error => MB_ICONHAND,
+        Cue::ApprovalNeeded => MB_ICONQUESTION,
+    };
+
+    unsafe {
+        MessageBeep(kind.0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn play_sound(cue: Cue, sound: Option<&str>) {
+    let id = sound.unwrap_or(match cue {
+        Cue::Completed => "complete",
+        Cue::Error => "dialog-error",
+        Cue::ApprovalNeeded => "dialog-question",
+    });
+
+    if let Err(e) = std::process::Command::new("canberra-gtk-play")
+        .args(["-i", id])
+        .status()
+    {
+        eprintln!("Failed to play sound {}: {}", id, e);
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn play_sound(_cue: Cue, _sound: Option<&str>) {}
+
+#[cfg(target_os = "macos")]
+fn perform_haptic() {
+    use cocoa::base::id;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let performer: id = msg_send![class!(NSHapticFeedbackManager), defaultPerformer];
+        // NSHapticFeedbackPatternGeneric, NSHapticFeedbackPerformanceTimeNow
+        let _: () = msg_send![performer, performFeedbackPattern: 0i64 performanceTime: 1u64];
+    }
+}
+
+// Haptics are only available on Force Touch trackpads
+#[cfg(not(target_os = "macos"))]
+fn perform_haptic() {}
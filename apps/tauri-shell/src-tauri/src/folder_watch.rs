@@ -0,0 +1,473 @@
+use crate::data_dir;
+use crate::settings::SettingsStore;
+use crate::store;
+use anyhow::{anyhow, Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// Files indexed per folder; the rest are still reported as changes
+const MAX_FILES: usize = 20_000;
+// Changes kept across all folders, oldest dropped first
+const MAX_CHANGES: usize = 500;
+// How often a changed index is written out for the agent
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+// Build output and dependencies churn constantly and say little about the project, so
+// they are neither indexed nor watched
+const IGNORED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    ".next",
+    "__pycache__",
+    ".venv",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct FileChange {
+    #[ts(type = "string")]
+    pub folder: PathBuf,
+    // Relative to `folder`
+    pub path: String,
+    pub kind: ChangeKind,
+    #[ts(as = "f64")]
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct IndexedFile {
+    size: u64,
+    modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct WatchedFolder {
+    #[ts(type = "string")]
+    pub path: PathBuf,
+    #[ts(as = "f64")]
+    pub files: usize,
+    // More than MAX_FILES files; the index holds only the first ones found
+    pub truncated: bool,
+}
+
+// Written to folder-index.json, which the agent's project tools read
+#[derive(Serialize)]
+struct IndexFile<'a> {
+    updated_at: i64,
+    folders: Vec<IndexFileFolder<'a>>,
+    changes: &'a VecDeque<FileChange>,
+}
+
+#[derive(Serialize)]
+struct IndexFileFolder<'a> {
+    path: &'a Path,
+    files: &'a BTreeMap<String, IndexedFile>,
+}
+
+#[derive(Default)]
+struct FolderIndex {
+    files: BTreeMap<String, IndexedFile>,
+    truncated: bool,
+    // Each watched on its own, which keeps IGNORED_DIRS out of the watcher
+    dirs: HashSet<PathBuf>,
+}
+
+#[derive(Default)]
+struct WatchState {
+    folders: HashMap<PathBuf, FolderIndex>,
+    changes: VecDeque<FileChange>,
+    dirty: bool,
+}
+
+/// Directories the user asked the assistant to keep an eye on, with a file index and
+/// recent changes the agent can query for ambient project context.
+#[derive(Default)]
+pub struct FolderWatcher {
+    // Separate from `state`: notify's backends wait on their event thread to (un)watch,
+    // and that thread may be blocked in on_event waiting for `state`
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    state: Mutex<WatchState>,
+}
+
+#[tauri::command]
+pub async fn watch_folder(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    path: String,
+) -> Result<WatchedFolder, String> {
+    let folder = allowed_folder(Path::new(&path))?;
+
+    let handle = app_handle.clone();
+    let watched_folder = folder.clone();
+    let watched = tauri::async_runtime::spawn_blocking(move || watch(&handle, &watched_folder))
+        .await
+        .map_err(|e| format!("Watch task failed: {}", e))?
+        .map_err(|e| format!("Failed to watch {}: {}", path, e))?;
+
+    settings
+        .update(|s| {
+            if !s.watched_folders.contains(&folder) {
+                s.watched_folders.push(folder);
+            }
+        })
+        .map_err(|e| format!("Failed to save watched folders: {}", e))?;
+    Ok(watched)
+}
+
+#[tauri::command]
+pub fn unwatch_folder(
+    watcher: State<'_, FolderWatcher>,
+    settings: State<'_, SettingsStore>,
+    path: String,
+) -> Result<(), String> {
+    // Saved paths are canonical; the frontend may pass the one it was given
+    let folder = Path::new(&path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&path));
+    let dirs = {
+        let mut state = watcher.state.lock().unwrap();
+        let Some(index) = state.folders.remove(&folder) else {
+            return Err(format!("{} is not being watched", path));
+        };
+        state.changes.retain(|change| change.folder != folder);
+        state.dirty = true;
+        index.dirs
+    };
+    if let Some(notify_watcher) = watcher.watcher.lock().unwrap().as_mut() {
+        for dir in &dirs {
+            if let Err(e) = notify_watcher.unwatch(dir) {
+                eprintln!("Failed to unwatch {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    settings
+        .update(|s| s.watched_folders.retain(|watched| *watched != folder))
+        .map_err(|e| format!("Failed to save watched folders: {}", e))
+}
+
+#[tauri::command]
+pub fn list_watched_folders(watcher: State<'_, FolderWatcher>) -> Vec<WatchedFolder> {
+    let state = watcher.state.lock().unwrap();
+    state
+        .folders
+        .iter()
+        .map(|(path, index)| WatchedFolder {
+            path: path.clone(),
+            files: index.files.len(),
+            truncated: index.truncated,
+        })
+        .collect()
+}
+
+/// Where the index is written; passed to the agent so its tools can read it.
+pub fn index_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("folder-index.json"))
+}
+
+/// Resumes watching saved folders and starts writing the index out as it changes.
+pub fn start(app_handle: AppHandle) {
+    let saved = app_handle.state::<SettingsStore>().get().watched_folders;
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        for folder in saved {
+            if let Err(e) = watch(&handle, &folder) {
+                eprintln!("Failed to resume watching {:?}: {}", folder, e);
+            }
+        }
+    });
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if let Err(e) = flush(&app_handle) {
+                eprintln!("Failed to write folder index: {}", e);
+            }
+        }
+    });
+}
+
+// Absolute, existing, inside the home directory and not the home directory itself
+fn allowed_folder(path: &Path) -> Result<PathBuf, String> {
+    let folder = path
+        .canonicalize()
+        .map_err(|e| format!("Can't watch {}: {}", path.display(), e))?;
+    if !folder.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+
+    let home = tauri::api::path::home_dir()
+        .and_then(|home| home.canonicalize().ok())
+        .ok_or_else(|| "No home directory available".to_string())?;
+    if folder == home || !folder.starts_with(&home) {
+        return Err("Only folders inside your home directory can be watched".to_string());
+    }
+    Ok(folder)
+}
+
+fn watch(app_handle: &AppHandle, folder: &Path) -> Result<WatchedFolder> {
+    let index = scan(folder)?;
+    let watched = WatchedFolder {
+        path: folder.to_path_buf(),
+        files: index.files.len(),
+        truncated: index.truncated,
+    };
+
+    let watcher = app_handle.state::<FolderWatcher>();
+    let mut notify_watcher = watcher.watcher.lock().unwrap();
+    if notify_watcher.is_none() {
+        let handle = app_handle.clone();
+        let created =
+            notify::recommended_watcher(
+                move |result: notify::Result<notify::Event>| match result {
+                    Ok(event) => on_event(&handle, event),
+                    Err(e) => eprintln!("Folder watch error: {}", e),
+                },
+            )
+            .context("Failed to create file watcher")?;
+        *notify_watcher = Some(created);
+    }
+
+    let notify_watcher = notify_watcher.as_mut().unwrap();
+    notify_watcher
+        .watch(folder, RecursiveMode::NonRecursive)
+        .context("Failed to watch folder")?;
+    for dir in index.dirs.iter().filter(|dir| dir.as_path() != folder) {
+        if let Err(e) = notify_watcher.watch(dir, RecursiveMode::NonRecursive) {
+            eprintln!("Failed to watch {}: {}", dir.display(), e);
+        }
+    }
+    let mut state = watcher.state.lock().unwrap();
+    state.folders.insert(folder.to_path_buf(), index);
+    state.dirty = true;
+    Ok(watched)
+}
+
+fn on_event(app_handle: &AppHandle, event: notify::Event) {
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Modify(_) => ChangeKind::Modified,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        _ => return,
+    };
+
+    let watcher = app_handle.state::<FolderWatcher>();
+    let mut state = watcher.state.lock().unwrap();
+    let state = &mut *state;
+    let mut new_dirs = Vec::new();
+    for path in event.paths {
+        let Some((folder, index)) = state
+            .folders
+            .iter_mut()
+            .find(|(folder, _)| path.starts_with(folder))
+        else {
+            continue;
+        };
+        let Some(relative) = relative_path(folder, &path) else {
+            continue;
+        };
+
+        // Renames arrive as Modify; whether the path still exists says which side it is
+        let kind = match (kind, path.metadata()) {
+            (ChangeKind::Removed, _) | (_, Err(_)) => {
+                index.files.remove(&relative);
+                if index.dirs.contains(&path) {
+                    index.dirs.retain(|dir| !dir.starts_with(&path));
+                }
+                ChangeKind::Removed
+            }
+            (_, Ok(metadata)) if metadata.is_dir() => {
+                if index.dirs.insert(path.clone()) {
+                    new_dirs.push(path);
+                }
+                continue;
+            }
+            (kind, Ok(metadata)) if metadata.is_file() => {
+                let existed = index.files.contains_key(&relative);
+                if existed || index.files.len() < MAX_FILES {
+                    index
+                        .files
+                        .insert(relative.clone(), indexed_file(&metadata));
+                }
+                match kind {
+                    ChangeKind::Modified if !existed => ChangeKind::Created,
+                    kind => kind,
+                }
+            }
+            _ => continue,
+        };
+
+        // Editors save in bursts; collapse repeats of the latest change
+        let repeat = state
+            .changes
+            .back()
+            .map(|last| last.folder == *folder && last.path == relative)
+            .unwrap_or(false);
+        if repeat {
+            state.changes.pop_back();
+        }
+        state.changes.push_back(FileChange {
+            folder: folder.clone(),
+            path: relative,
+            kind,
+            at: store::now_millis(),
+        });
+        if state.changes.len() > MAX_CHANGES {
+            state.changes.pop_front();
+        }
+        state.dirty = true;
+    }
+
+    // Watching waits on the thread this runs on
+    if !new_dirs.is_empty() {
+        let handle = app_handle.clone();
+        std::thread::spawn(move || watch_new_dirs(&handle, new_dirs));
+    }
+}
+
+// Watches directories created in or moved into a watched folder, and those inside them
+fn watch_new_dirs(app_handle: &AppHandle, new_dirs: Vec<PathBuf>) {
+    let dirs: Vec<PathBuf> = new_dirs
+        .iter()
+        .filter_map(|dir| scan(dir).ok())
+        .flat_map(|index| index.dirs)
+        .collect();
+
+    let watcher = app_handle.state::<FolderWatcher>();
+    if let Some(notify_watcher) = watcher.watcher.lock().unwrap().as_mut() {
+        for dir in &dirs {
+            if let Err(e) = notify_watcher.watch(dir, RecursiveMode::NonRecursive) {
+                eprintln!("Failed to watch {}: {}", dir.display(), e);
+            }
+        }
+    }
+
+    let mut state = watcher.state.lock().unwrap();
+    for dir in dirs {
+        if let Some(index) = state
+            .folders
+            .iter_mut()
+            .find(|(folder, _)| dir.starts_with(folder))
+            .map(|(_, index)| index)
+        {
+            index.dirs.insert(dir);
+        }
+    }
+}
+
+fn scan(folder: &Path) -> Result<FolderIndex> {
+    if !folder.is_dir() {
+        return Err(anyhow!("{} is not a directory", folder.display()));
+    }
+
+    let mut index = FolderIndex::default();
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        index.dirs.insert(dir);
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if metadata.is_dir() {
+                if !is_ignored(Path::new(&entry.file_name())) {
+                    pending.push(path);
+                }
+            } else if metadata.is_file() {
+                // Past the limit the walk goes on, so deeper directories are still watched
+                if index.files.len() >= MAX_FILES {
+                    index.truncated = true;
+                    continue;
+                }
+                if let Some(relative) = relative_path(folder, &path) {
+                    index.files.insert(relative, indexed_file(&metadata));
+                }
+            }
+        }
+    }
+    Ok(index)
+}
+
+fn flush(app_handle: &AppHandle) -> Result<()> {
+    let watcher = app_handle.state::<FolderWatcher>();
+    let mut state = watcher.state.lock().unwrap();
+    if !state.dirty {
+        return Ok(());
+    }
+
+    let path = index_path(app_handle).context("No data directory available")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+    }
+    let index = IndexFile {
+        updated_at: store::now_millis(),
+        folders: state
+            .folders
+            .iter()
+            .map(|(path, index)| IndexFileFolder {
+                path,
+                files: &index.files,
+            })
+            .collect(),
+        changes: &state.changes,
+    };
+    let json = serde_json::to_string(&index).context("Failed to serialize folder index")?;
+
+    // Written whole then renamed, so the agent never reads half a file
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json).context("Failed to write folder index")?;
+    std::fs::rename(&partial, &path).context("Failed to replace folder index")?;
+    state.dirty = false;
+    Ok(())
+}
+
+fn relative_path(folder: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(folder).ok()?;
+    if relative.as_os_str().is_empty() || is_ignored(relative) {
+        return None;
+    }
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .map(|name| IGNORED_DIRS.contains(&name))
+            .unwrap_or(false)
+    })
+}
+
+fn indexed_file(metadata: &std::fs::Metadata) -> IndexedFile {
+    IndexedFile {
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0),
+    }
+}
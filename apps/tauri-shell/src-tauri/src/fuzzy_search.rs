@@ -0,0 +1,333 @@
+use crate::data_dir;
+use crate::settings::SettingsStore;
+use crate::store::ConversationStore;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager, State};
+
+// Bump when the vectors change; older indexes are dropped and rebuilt
+const INDEX_VERSION: i32 = 1;
+const DIMENSIONS: usize = 512;
+// Character trigrams let "install", "installer" and "installation" land close together
+const TRIGRAM_WEIGHT: f32 = 0.5;
+const MAX_RESULTS: usize = 50;
+const SNIPPET_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzyMatch {
+    pub conversation_id: String,
+    pub message_id: String,
+    pub role: String,
+    pub snippet: String,
+    // Cosine similarity, 0..=1 for anything worth showing
+    pub score: f32,
+}
+
+/// Vectors of the words and character trigrams of every stored message, so a search
+/// finds messages that share words or word parts with the query, even when spelled
+/// differently. Lexical, not semantic: synonyms don't match. Kept in
+/// search-index.sqlite in the app data directory and brought up to date from file
+/// modification times on each search.
+#[derive(Default)]
+pub struct FuzzyIndex {
+    db: Mutex<Option<Connection>>,
+}
+
+// A message's row in the index, worked out before the index is locked
+struct Entry {
+    message_id: String,
+    role: String,
+    snippet: String,
+    vector: Vec<u8>,
+}
+
+// A conversation to reindex: its id, modification time and entries
+type Changed = (String, i64, Vec<Entry>);
+
+#[tauri::command]
+pub async fn fuzzy_search(
+    app_handle: AppHandle,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<FuzzyMatch>, String> {
+    if !app_handle.state::<SettingsStore>().get().fuzzy_search {
+        return Err("Fuzzy search is turned off".to_string());
+    }
+    let k = k.unwrap_or(10).clamp(1, MAX_RESULTS);
+
+    tauri::async_runtime::spawn_blocking(move || search(&app_handle, query.trim(), k))
+        .await
+        .map_err(|e| format!("Search task failed: {}", e))?
+        .map_err(|e| format!("Failed to search history: {}", e))
+}
+
+#[tauri::command]
+pub fn get_fuzzy_search_enabled(settings: State<'_, SettingsStore>) -> bool {
+    settings.get().fuzzy_search
+}
+
+/// Turning the index off also deletes it; it is rebuilt on the first search once back on.
+#[tauri::command]
+pub fn set_fuzzy_search_enabled(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.fuzzy_search = enabled)
+        .map_err(|e| format!("Failed to save fuzzy search setting: {}", e))?;
+
+    if !enabled {
+        app_handle.state::<FuzzyIndex>().db.lock().unwrap().take();
+        if let Some(path) = index_path(&app_handle) {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("Failed to delete search index: {}", e));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn search(app_handle: &AppHandle, query: &str, k: usize) -> Result<Vec<FuzzyMatch>> {
+    let index = app_handle.state::<FuzzyIndex>();
+    let indexed = {
+        let mut db = index.db.lock().unwrap();
+        if db.is_none() {
+            *db = Some(open(app_handle)?);
+        }
+        indexed(db.as_ref().unwrap())?
+    };
+
+    // Loading and vectorizing changed conversations is the slow part, done unlocked
+    let (changed, removed) = changes(app_handle, &indexed)?;
+
+    let mut db = index.db.lock().unwrap();
+    // Turned off meanwhile
+    let Some(db) = db.as_mut() else {
+        return Ok(Vec::new());
+    };
+    update(db, changed, removed)?;
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = vectorize(query);
+    let mut statement =
+        db.prepare("SELECT conversation_id, message_id, role, snippet, vector FROM embeddings")?;
+    let rows = statement.query_map([], |row| {
+        let vector: Vec<u8> = row.get(4)?;
+        Ok(FuzzyMatch {
+            conversation_id: row.get(0)?,
+            message_id: row.get(1)?,
+            role: row.get(2)?,
+            snippet: row.get(3)?,
+            score: dot(&query, &vector),
+        })
+    })?;
+
+    let mut matches = Vec::new();
+    for row in rows {
+        let candidate = row?;
+        if candidate.score > 0.0 {
+            matches.push(candidate);
+        }
+    }
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(k);
+    Ok(matches)
+}
+
+fn index_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("search-index.sqlite"))
+}
+
+fn open(app_handle: &AppHandle) -> Result<Connection> {
+    let path = index_path(app_handle).context("No data directory available")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        // Left by builds that called this semantic search
+        let _ = std::fs::remove_file(dir.join("embeddings.sqlite"));
+    }
+    let db = Connection::open(&path).with_context(|| format!("Failed to open {:?}", path))?;
+
+    let version: i32 = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version != INDEX_VERSION {
+        db.execute_batch(&format!(
+            "DROP TABLE IF EXISTS embeddings;
+             DROP TABLE IF EXISTS indexed;
+             CREATE TABLE embeddings (
+                 conversation_id TEXT NOT NULL,
+                 message_id TEXT NOT NULL,
+                 role TEXT NOT NULL,
+                 snippet TEXT NOT NULL,
+                 vector BLOB NOT NULL,
+                 PRIMARY KEY (conversation_id, message_id, role)
+             );
+             CREATE TABLE indexed (
+                 conversation_id TEXT PRIMARY KEY,
+                 modified INTEGER NOT NULL
+             );
+             PRAGMA user_version = {};",
+            INDEX_VERSION
+        ))
+        .context("Failed to create search index")?;
+    }
+    Ok(db)
+}
+
+// Modification times of the conversations as last indexed
+fn indexed(db: &Connection) -> Result<HashMap<String, i64>> {
+    let indexed = db
+        .prepare("SELECT conversation_id, modified FROM indexed")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(indexed)
+}
+
+// Entries of the conversations whose files changed since they were last indexed, with
+// their modification times, and the ids of conversations that are gone
+fn changes(
+    app_handle: &AppHandle,
+    indexed: &HashMap<String, i64>,
+) -> Result<(Vec<Changed>, Vec<String>)> {
+    let store = app_handle.state::<ConversationStore>();
+    let files: HashMap<String, i64> = store
+        .list_modified()?
+        .into_iter()
+        .map(|(id, modified)| {
+            let millis = modified
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+            (id, millis)
+        })
+        .collect();
+    let removed = indexed
+        .keys()
+        .filter(|id| !files.contains_key(*id))
+        .cloned()
+        .collect();
+
+    let mut changed = Vec::new();
+    for (id, modified) in files {
+        if indexed.get(&id) == Some(&modified) {
+            continue;
+        }
+        let conversation = match store.load(&id) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                eprintln!("Skipping conversation {} in search index: {}", id, e);
+                continue;
+            }
+        };
+
+        let mut entries = Vec::new();
+        for message in &conversation.messages {
+            if message.content.trim().is_empty() {
+                continue;
+            }
+            let snippet: String = message
+                .content
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(SNIPPET_CHARS)
+                .collect();
+            let vector = vectorize(&message.content)
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            entries.push(Entry {
+                message_id: message.id.clone(),
+                role: message.role.clone(),
+                snippet,
+                vector,
+            });
+        }
+        changed.push((id, modified, entries));
+    }
+    Ok((changed, removed))
+}
+
+fn update(db: &mut Connection, changed: Vec<Changed>, removed: Vec<String>) -> Result<()> {
+    let tx = db.transaction()?;
+    for id in &removed {
+        tx.execute("DELETE FROM embeddings WHERE conversation_id = ?", [id])?;
+        tx.execute("DELETE FROM indexed WHERE conversation_id = ?", [id])?;
+    }
+    for (id, modified, entries) in changed {
+        tx.execute("DELETE FROM embeddings WHERE conversation_id = ?", [&id])?;
+        for entry in entries {
+            tx.execute(
+                "INSERT OR REPLACE INTO embeddings
+                 (conversation_id, message_id, role, snippet, vector) VALUES (?, ?, ?, ?, ?)",
+                params![
+                    id,
+                    entry.message_id,
+                    entry.role,
+                    entry.snippet,
+                    entry.vector
+                ],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO indexed (conversation_id, modified) VALUES (?, ?)",
+            params![id, modified],
+        )?;
+    }
+    tx.commit().context("Failed to update search index")
+}
+
+/// Unit-length feature-hashed vector of the text's words and their character
+/// trigrams, compared by cosine similarity. Small and dependency-free; hashes must
+/// stay stable across builds.
+fn vectorize(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; DIMENSIONS];
+    let mut add = |feature: &str, weight: f32| {
+        let hash = fnv1a(feature.as_bytes());
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % DIMENSIONS as u64) as usize] += sign * weight;
+    };
+
+    let lowercase = text.to_lowercase();
+    for word in lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+    {
+        add(word, 1.0);
+
+        let padded: Vec<char> = format!("#{}#", word).chars().collect();
+        for trigram in padded.windows(3) {
+            add(&trigram.iter().collect::<String>(), TRIGRAM_WEIGHT);
+        }
+    }
+
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+    vector
+}
+
+fn dot(query: &[f32], stored: &[u8]) -> f32 {
+    stored
+        .chunks_exact(4)
+        .zip(query)
+        .map(|(bytes, value)| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) * value)
+        .sum()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,186 @@
+use crate::annotate::ImageAttachment;
+use crate::attachments;
+use crate::data_dir;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// An image the agent produced, kept in generated-images/ under the data dir. The
+/// webview gets this instead of the image data the agent sent.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct GeneratedImage {
+    pub image_id: String,
+    // The user message whose reply the image belongs to
+    pub request_id: String,
+    pub mime_type: String,
+    pub file_name: String,
+    pub size_bytes: usize,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    // PNG data: URL, None if the image couldn't be decoded
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct ImageGenerated<'a> {
+    agent_id: &'a str,
+    #[serde(flatten)]
+    image: &'a GeneratedImage,
+}
+
+/// What the webview needs to let a generated image be dragged out of the window.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct GeneratedImageDrag {
+    pub path: String,
+    pub file_name: String,
+    pub mime_type: String,
+    // "mime:name:url", the value of a DownloadURL drag data item
+    pub download_url: String,
+}
+
+/// Copies generated image `image_id` to `path`, or into it if `path` is a directory.
+/// Returns the path written.
+#[tauri::command]
+pub async fn save_generated_image(
+    app_handle: AppHandle,
+    image_id: String,
+    path: String,
+) -> Result<String, String> {
+    let source = find(&app_handle, &image_id).map_err(|e| e.to_string())?;
+    let mut target = PathBuf::from(path);
+    if target.is_dir() {
+        if let Some(name) = source.file_name() {
+            target.push(name);
+        }
+    }
+
+    tokio::fs::copy(&source, &target)
+        .await
+        .map_err(|e| format!("Failed to save generated image: {}", e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub fn generated_image_drag(
+    app_handle: AppHandle,
+    image_id: String,
+) -> Result<GeneratedImageDrag, String> {
+    let path = find(&app_handle, &image_id).map_err(|e| e.to_string())?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mime_type = mime_for(&extension).to_string();
+    let download_url = format!("{}:{}:{}", mime_type, file_name, file_url(&path));
+
+    Ok(GeneratedImageDrag {
+        path: path.to_string_lossy().into_owned(),
+        file_name,
+        mime_type,
+        download_url,
+    })
+}
+
+/// Writes an image_output payload to disk. Also returns it as an attachment, so it
+/// is stored with the reply like any other image.
+pub fn store(
+    app_handle: &AppHandle,
+    request_id: &str,
+    bytes: &[u8],
+    mime: &str,
+) -> Result<(GeneratedImage, ImageAttachment)> {
+    let extension =
+        extension_for(mime).ok_or_else(|| anyhow!("Unsupported image type {}", mime))?;
+    let dir = images_dir(app_handle).ok_or_else(|| anyhow!("No data directory available"))?;
+    std::fs::create_dir_all(&dir).context("Failed to create generated image directory")?;
+
+    let image_id = uuid::Uuid::new_v4().to_string();
+    let file_name = format!("{}.{}", image_id, extension);
+    std::fs::write(dir.join(&file_name), bytes).context("Failed to write generated image")?;
+
+    let decoded = image::load_from_memory(bytes).ok();
+    let image = GeneratedImage {
+        image_id,
+        request_id: request_id.to_string(),
+        mime_type: mime.to_string(),
+        file_name: file_name.clone(),
+        size_bytes: bytes.len(),
+        width: decoded.as_ref().map(|image| image.width()),
+        height: decoded.as_ref().map(|image| image.height()),
+        thumbnail: decoded.as_ref().and_then(attachments::thumbnail),
+    };
+    let attachment = ImageAttachment {
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+        mime_type: mime.to_string(),
+        name: Some(file_name),
+    };
+    Ok((image, attachment))
+}
+
+/// Tells the window that sent the request (every window when None) about a new image.
+pub fn emit(app_handle: &AppHandle, agent_id: &str, owner: Option<&str>, image: &GeneratedImage) {
+    let event = ImageGenerated { agent_id, image };
+    let result = match owner {
+        Some(label) => app_handle.emit_to(label, "image_generated", event),
+        None => app_handle.emit_all("image_generated", event),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit image_generated: {}", e);
+    }
+}
+
+fn images_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("generated-images"))
+}
+
+fn find(app_handle: &AppHandle, image_id: &str) -> Result<PathBuf> {
+    // Ids come from the frontend; only ones we generated may name a file
+    if image_id.is_empty() || !image_id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(anyhow!("Invalid image id {}", image_id));
+    }
+    let dir = images_dir(app_handle).ok_or_else(|| anyhow!("No data directory available"))?;
+    ["png", "jpg", "gif", "webp"]
+        .iter()
+        .map(|extension| dir.join(format!("{}.{}", image_id, extension)))
+        .find(|path| path.exists())
+        .ok_or_else(|| anyhow!("No generated image {}", image_id))
+}
+
+fn extension_for(mime: &str) -> Option<&'static str> {
+    match mime {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        _ => None,
+    }
+}
+
+fn mime_for(extension: &str) -> &'static str {
+    match extension {
+        "jpg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/png",
+    }
+}
+
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        // Windows drive paths
+        format!("file:///{}", path)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::agent_backend::AgentStdin;
+use crate::agent_ipc::{self, write_request, AgentRequest};
+use crate::settings::SettingsStore;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{watch, Mutex};
+use ts_rs::TS;
+
+/// Ping cadence and what happens when the agent stops answering. Read on every tick,
+/// so changes apply without respawning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatSettings {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    // Restart the agent when a pong is missed instead of only reporting it
+    pub auto_restart: bool,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        HeartbeatSettings {
+            enabled: true,
+            interval_secs: 15,
+            timeout_secs: 10,
+            auto_restart: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentUnresponsive<'a> {
+    agent_id: &'a str,
+    // Time since the last pong
+    #[ts(as = "f64")]
+    silent_for_ms: u64,
+    restarting: bool,
+}
+
+/// Pings the agent every interval and waits for the pong carrying the same id.
+/// `pongs` holds the id of the last pong received. `missed` is set while the latest
+/// ping of an agent that has answered before went unanswered, for the watchdog.
+/// Skipped while `suspended`, and stops once the process is dropped.
+pub fn spawn(
+    app_handle: AppHandle,
+    agent_id: String,
+    serial: u64,
+    stdin: Weak<Mutex<AgentStdin>>,
+    mut pongs: watch::Receiver<String>,
+    missed: watch::Sender<bool>,
+    suspended: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        let mut last_pong = tokio::time::Instant::now();
+        // Agents that predate ping never answer; only judge one that has ponged before
+        let mut answered = false;
+
+        loop {
+            let settings = app_handle.state::<SettingsStore>().get().heartbeat;
+            tokio::time::sleep(Duration::from_secs(settings.interval_secs.max(1))).await;
+            if !settings.enabled {
+                // Nothing to go by until pings resume
+                missed.send_replace(false);
+                continue;
+            }
+            if suspended.load(Ordering::Relaxed) {
+                continue;
+            }
+            let Some(stdin) = stdin.upgrade() else {
+                break;
+            };
+
+            let ping = AgentRequest::Ping {
+                id: uuid::Uuid::new_v4().to_string(),
+            };
+            // A broken pipe is the supervisor's business
+            if write_request(&stdin, &ping).await.is_err() {
+                break;
+            }
+            drop(stdin);
+
+            let timeout = Duration::from_secs(settings.timeout_secs.max(1));
+            let pong = tokio::time::timeout(timeout, pongs.wait_for(|id| id == ping.id())).await;
+            match pong {
+                Ok(Ok(_)) => {
+                    last_pong = tokio::time::Instant::now();
+                    answered = true;
+                    missed.send_replace(false);
+                }
+                Ok(Err(_)) => break,
+                // Not while paused, which may have happened after the ping went out
+                Err(_) if answered && !suspended.load(Ordering::Relaxed) => {
+                    missed.send_replace(true);
+                    let silent_for = last_pong.elapsed();
+                    if unresponsive(&app_handle, &agent_id, serial, &settings, silent_for).await {
+                        break;
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+// Reports a missed pong and restarts the agent if configured. Returns true once this
+// process has been replaced.
+async fn unresponsive(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    serial: u64,
+    settings: &HeartbeatSettings,
+    silent_for: Duration,
+) -> bool {
+    let Some(slot) = app_handle.state::<AppState>().find(agent_id) else {
+        return false;
+    };
+    // A warm standby isn't reported until it is promoted
+    let current = slot
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|process| process.serial() == serial);
+    if !current {
+        return false;
+    }
+
+    eprintln!("[HEARTBEAT] No pong for {}s", silent_for.as_secs());
+    let event = AgentUnresponsive {
+        agent_id,
+        silent_for_ms: silent_for.as_millis() as u64,
+        restarting: settings.auto_restart,
+    };
+    if let Err(e) = app_handle.emit_all("agent_unresponsive", event) {
+        eprintln!("Failed to emit agent_unresponsive: {}", e);
+    }
+    if !settings.auto_restart {
+        return false;
+    }
+
+    match agent_ipc::restart(&slot, Some(serial)).await {
+        Ok(Some(lost_requests)) => {
+            agent_ipc::emit_lifecycle(app_handle, agent_id, "agent_stopped", lost_requests);
+            agent_ipc::emit_lifecycle(app_handle, agent_id, "agent_started", Vec::new());
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            eprintln!("Failed to restart unresponsive agent: {}", e);
+            false
+        }
+    }
+}
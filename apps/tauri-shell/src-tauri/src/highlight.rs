@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+// Loaded on first use; parsing the bundled definitions takes a noticeable moment
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEMES: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+// Light theme for paper and other white backgrounds
+pub const PRINT_THEME: &str = "InspiredGitHub";
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightFormat {
+    // A <pre> with inline styles, usable without any stylesheet
+    #[default]
+    Html,
+    // 24-bit color escape sequences for terminals
+    Ansi,
+}
+
+/// Highlights `code` as `lang` (a fence name like "rust" or an extension like "rs");
+/// unknown languages come back as plain text in the same format.
+#[tauri::command]
+pub fn highlight_code(
+    code: String,
+    lang: Option<String>,
+    theme: Option<String>,
+    format: Option<HighlightFormat>,
+) -> Result<String, String> {
+    let theme = theme.as_deref().unwrap_or(DEFAULT_THEME);
+    match format.unwrap_or_default() {
+        HighlightFormat::Html => html(&code, lang.as_deref(), theme),
+        HighlightFormat::Ansi => ansi(&code, lang.as_deref(), theme),
+    }
+    .map_err(|e| format!("Failed to highlight code: {}", e))
+}
+
+#[tauri::command]
+pub fn list_highlight_themes() -> Vec<String> {
+    THEMES.themes.keys().cloned().collect()
+}
+
+pub fn html(code: &str, lang: Option<&str>, theme: &str) -> Result<String> {
+    Ok(highlighted_html_for_string(
+        code,
+        &SYNTAXES,
+        syntax(lang),
+        find_theme(theme)?,
+    )?)
+}
+
+pub fn ansi(code: &str, lang: Option<&str>, theme: &str) -> Result<String> {
+    let mut highlighter = HighlightLines::new(syntax(lang), find_theme(theme)?);
+    let mut output = String::new();
+    for line in LinesWithEndings::from(code) {
+        let ranges = highlighter.highlight_line(line, &SYNTAXES)?;
+        output.push_str(&as_24_bit_terminal_escaped(&ranges, false));
+    }
+    // Don't leave the terminal colored
+    output.push_str("\x1b[0m");
+    Ok(output)
+}
+
+fn syntax(lang: Option<&str>) -> &'static SyntaxReference {
+    lang.map(str::trim)
+        .filter(|lang| !lang.is_empty())
+        .and_then(|lang| {
+            SYNTAXES
+                .find_syntax_by_token(lang)
+                .or_else(|| SYNTAXES.find_syntax_by_extension(lang))
+        })
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+fn find_theme(name: &str) -> Result<&'static Theme> {
+    THEMES
+        .themes
+        .get(name)
+        .ok_or_else(|| anyhow!("Unknown theme {:?}", name))
+}
@@ -0,0 +1,117 @@
+use crate::agent_ipc::{self, DEFAULT_AGENT_ID};
+use crate::standby;
+use crate::AppState;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use ts_rs::TS;
+
+// Editors write a file in several steps; reload once things have been quiet this long
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentReloaded {
+    agent_id: String,
+    // Files that changed, relative to the runtime's src directory
+    changed: Vec<String>,
+    lost_requests: Vec<String>,
+}
+
+/// Debug builds only: restarts running agents when a file under the dev checkout's
+/// agent-runtime/src changes, emitting agent_reloaded for each.
+pub fn start(app_handle: AppHandle) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    if let Err(e) = watch(app_handle) {
+        eprintln!("[RELOAD] Hot reload disabled: {}", e);
+    }
+}
+
+fn watch(app_handle: AppHandle) -> Result<()> {
+    let src = agent_ipc::dev_runtime_dir()?
+        .join("src")
+        .canonicalize()
+        .context("No agent-runtime checkout to watch")?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) => {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    let _ = tx.send(event.paths);
+                }
+            }
+            Err(e) => eprintln!("[RELOAD] Watch error: {}", e),
+        })
+        .context("Failed to create file watcher")?;
+    watcher
+        .watch(&src, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", src.display()))?;
+    eprintln!("[RELOAD] Watching {:?}", src);
+
+    tauri::async_runtime::spawn(async move {
+        // Owned by the task so watching lasts as long as the app
+        let _watcher = watcher;
+        while let Some(paths) = rx.recv().await {
+            let mut changed = paths;
+            while let Ok(Some(paths)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                changed.extend(paths);
+            }
+
+            let changed = relative(&src, changed);
+            eprintln!("[RELOAD] Agent runtime changed: {:?}", changed);
+            reload(&app_handle, &changed).await;
+        }
+    });
+    Ok(())
+}
+
+// Restarts every running agent onto the changed source
+async fn reload(app_handle: &AppHandle, changed: &[String]) {
+    // The standby runs the old source; it is respawned once the default agent restarted
+    standby::discard(app_handle).await;
+
+    for (agent_id, slot) in app_handle.state::<AppState>().slots() {
+        match agent_ipc::restart(&slot, None).await {
+            Ok(None) => {}
+            Ok(Some(lost_requests)) => {
+                let reloaded = AgentReloaded {
+                    agent_id: agent_id.clone(),
+                    changed: changed.to_vec(),
+                    lost_requests,
+                };
+                if let Err(e) = app_handle.emit_all("agent_reloaded", reloaded) {
+                    eprintln!("Failed to emit agent_reloaded: {}", e);
+                }
+                if agent_id == DEFAULT_AGENT_ID {
+                    standby::replenish(app_handle);
+                }
+            }
+            Err(e) => eprintln!("[RELOAD] Failed to restart agent {}: {}", agent_id, e),
+        }
+    }
+}
+
+fn relative(src: &Path, paths: Vec<PathBuf>) -> Vec<String> {
+    let mut changed: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.strip_prefix(src)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
@@ -0,0 +1,572 @@
+use crate::agent_backend::{AgentBackend, AgentConnection, AgentHandle};
+use crate::annotate::ImageAttachment;
+use crate::network_config;
+use crate::protocol::{AgentRequest, AgentResponse, Capabilities, Usage, PROTOCOL_VERSION};
+use crate::store::{self, ConversationStore, DEFAULT_CONVERSATION_ID};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::{AbortHandle, JoinSet};
+
+// Bytes buffered in each direction between the shell and the in-process agent
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+// Same instructions as the Node agent gives for these one-shot kinds
+const TRANSFORM_PROMPT: &str = "You transform text as instructed. Reply with only the \
+    transformed text, without any preamble, quotes or commentary.";
+const SUMMARIZE_PROMPT: &str = "You summarize conversations between a user and an assistant \
+    so they can be continued without the full transcript. Fold in the earlier summary if one \
+    is given. Keep decisions, facts, open questions, names, file paths and code identifiers; \
+    drop pleasantries. Reply with only the summary.";
+
+/// An OpenAI-compatible chat completions API (OpenAI, or a local server such as
+/// llama.cpp or Ollama) used as the agent. Text and images only: no tools.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpEndpoint {
+    // Up to the version, e.g. https://api.openai.com/v1; /chat/completions is appended
+    pub base_url: String,
+    pub model: String,
+    // Variable in the profile's env or the agent secrets holding the bearer token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+}
+
+/// Runs the agent protocol inside the shell and answers requests from the endpoint.
+pub struct HttpBackend {
+    endpoint: HttpEndpoint,
+}
+
+impl HttpBackend {
+    pub fn new(endpoint: HttpEndpoint) -> Self {
+        HttpBackend { endpoint }
+    }
+}
+
+impl AgentBackend for HttpBackend {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn start(
+        &self,
+        app_handle: &AppHandle,
+        env: &HashMap<String, String>,
+    ) -> Result<AgentConnection> {
+        let api_key = match &self.endpoint.api_key_env {
+            Some(name) => Some(env.get(name).cloned().with_context(|| {
+                format!("{} is not set in the profile or the agent secrets", name)
+            })?),
+            None => None,
+        };
+        eprintln!(
+            "[DEBUG] Starting HTTP agent for {} at {}",
+            self.endpoint.model, self.endpoint.base_url
+        );
+
+        let (stdin, requests) = tokio::io::duplex(PIPE_CAPACITY);
+        let (responses, stdout) = tokio::io::duplex(PIPE_CAPACITY);
+        let agent = Arc::new(HttpAgent {
+            app_handle: app_handle.clone(),
+            endpoint: self.endpoint.clone(),
+            api_key,
+            conversation: Mutex::new(Conversation::load(app_handle, DEFAULT_CONVERSATION_ID)),
+        });
+        let task = tokio::spawn(agent.run(requests, responses));
+
+        Ok(AgentConnection {
+            stdin: Box::new(stdin),
+            stdout: Box::new(stdout),
+            reconnects: None,
+            stderr: Box::new(tokio::io::empty()),
+            handle: AgentHandle::task(task),
+            limits: Default::default(),
+        })
+    }
+}
+
+struct HttpAgent {
+    app_handle: AppHandle,
+    endpoint: HttpEndpoint,
+    api_key: Option<String>,
+    conversation: Mutex<Conversation>,
+}
+
+// The current conversation as chat messages, without the system prompt
+struct Conversation {
+    id: String,
+    history: Vec<Value>,
+}
+
+// Payload of load_conversation_compressed, see checkpoints
+#[derive(Debug, Deserialize)]
+struct CompressedHistory {
+    summary: String,
+    messages: Vec<HistoryMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryMessage {
+    role: String,
+    content: String,
+}
+
+// A failed API call, classified like the Node agent's errors
+struct ApiError {
+    message: String,
+    code: Option<&'static str>,
+    retry_after_ms: Option<u64>,
+}
+
+impl Conversation {
+    // The shell's transcript of `id`, so history survives restarts like the Node agent's
+    fn load(app_handle: &AppHandle, id: &str) -> Self {
+        let history = app_handle
+            .state::<ConversationStore>()
+            .load(id)
+            .map(|conversation| {
+                conversation
+                    .messages
+                    .iter()
+                    .filter(|m| m.role == "user" || m.role == "assistant")
+                    .map(|m| json!({ "role": m.role, "content": m.content }))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Conversation {
+            id: id.to_string(),
+            history,
+        }
+    }
+}
+
+impl HttpAgent {
+    // Reads requests until stdin closes or a shutdown arrives. Replies go through one
+    // channel so lines from concurrent requests never interleave. Request tasks live in
+    // a JoinSet, so they are cancelled with this task when the agent is killed.
+    async fn run(self: Arc<Self>, requests: DuplexStream, mut out: DuplexStream) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tasks = JoinSet::new();
+        let mut in_flight: HashMap<String, AbortHandle> = HashMap::new();
+        let mut lines = BufReader::new(requests).lines();
+
+        let ready = AgentResponse::Ready {
+            protocol_version: Some(PROTOCOL_VERSION),
+            agent_version: Some(format!("http/{}", env!("CARGO_PKG_VERSION"))),
+            timestamp: store::now_millis(),
+        };
+        if write(&mut out, &ready).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                line = lines.next_line() => {
+                    let Ok(Some(line)) = line else {
+                        break;
+                    };
+                    let request = match serde_json::from_str::<AgentRequest>(&line) {
+                        Ok(request) => request,
+                        Err(e) => {
+                            // Answered when the id can still be read, e.g. an unknown kind
+                            let id = serde_json::from_str::<Value>(&line)
+                                .ok()
+                                .and_then(|line| line["id"].as_str().map(str::to_string));
+                            if let Some(id) = id {
+                                let error = format!("Unsupported request: {}", e);
+                                let _ = tx.send(invalid(&id, &error));
+                            }
+                            continue;
+                        }
+                    };
+                    if let AgentRequest::Shutdown { id } = &request {
+                        let _ = write(&mut out, &done(id, None)).await;
+                        break;
+                    }
+                    in_flight.retain(|_, task| !task.is_finished());
+                    self.dispatch(request, &tx, &mut tasks, &mut in_flight);
+                }
+                Some(response) = rx.recv() => {
+                    if write(&mut out, &response).await.is_err() {
+                        break;
+                    }
+                }
+                Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+            }
+        }
+    }
+
+    fn dispatch(
+        self: &Arc<Self>,
+        request: AgentRequest,
+        tx: &UnboundedSender<AgentResponse>,
+        tasks: &mut JoinSet<()>,
+        in_flight: &mut HashMap<String, AbortHandle>,
+    ) {
+        let reply = |response: AgentResponse| {
+            let _ = tx.send(response);
+        };
+
+        let kind = request.kind();
+        match request {
+            AgentRequest::Ping { id } => reply(AgentResponse::Pong {
+                id,
+                timestamp: store::now_millis(),
+            }),
+            // In-process, so plain JSON lines cost nothing worth saving
+            AgentRequest::Hello { id, .. } => reply(AgentResponse::Hello {
+                id,
+                capabilities: Capabilities::default(),
+                timestamp: store::now_millis(),
+            }),
+            AgentRequest::Interrupt { id } => {
+                // Only the targeted generation stops; it ends with an 'interrupted' error
+                if let Some(task) = in_flight.remove(&id) {
+                    task.abort();
+                    reply(AgentResponse::Error {
+                        id,
+                        error: "Interrupted".to_string(),
+                        code: Some("interrupted".to_string()),
+                        retry_after_ms: None,
+                        timestamp: store::now_millis(),
+                    });
+                }
+            }
+            AgentRequest::ClearHistory { id } => {
+                self.conversation.lock().unwrap().history.clear();
+                reply(done(&id, None));
+            }
+            AgentRequest::LoadConversation {
+                id,
+                conversation_id,
+            } => {
+                let mut conversation = self.conversation.lock().unwrap();
+                *conversation = Conversation::load(&self.app_handle, &conversation_id);
+                reply(loaded(&id, &conversation));
+            }
+            AgentRequest::MergeConversations {
+                id,
+                conversation_id,
+                source_id,
+            } => {
+                // A merge continues in the target, which the shell has already written
+                let mut conversation = self.conversation.lock().unwrap();
+                if conversation.id == source_id || conversation.id == conversation_id {
+                    *conversation = Conversation::load(&self.app_handle, &conversation_id);
+                }
+                reply(done(&id, None));
+            }
+            AgentRequest::LoadConversationCompressed {
+                id,
+                conversation_id,
+                message,
+            } => match serde_json::from_str::<CompressedHistory>(&message) {
+                Ok(compressed) => {
+                    let conversation = Conversation {
+                        id: conversation_id,
+                        history: compressed_history(compressed),
+                    };
+                    reply(loaded(&id, &conversation));
+                    *self.conversation.lock().unwrap() = conversation;
+                }
+                Err(_) => reply(invalid(&id, "Invalid compressed conversation")),
+            },
+            AgentRequest::UserMessage {
+                id,
+                message,
+                images,
+                conversation_id,
+            } => {
+                reply(AgentResponse::Ack {
+                    id: id.clone(),
+                    timestamp: store::now_millis(),
+                });
+
+                let user = json!({
+                    "role": "user",
+                    "content": user_content(&message, images.as_deref()),
+                });
+                let (conversation_id, messages) = {
+                    let mut conversation = self.conversation.lock().unwrap();
+                    if let Some(requested) = &conversation_id {
+                        if *requested != conversation.id {
+                            *conversation = Conversation::load(&self.app_handle, requested);
+                        }
+                    }
+                    conversation.history.push(user);
+                    (
+                        conversation.id.clone(),
+                        self.with_system(&conversation.history),
+                    )
+                };
+
+                let agent = self.clone();
+                let tx = tx.clone();
+                let request_id = id.clone();
+                let task = tasks.spawn(async move {
+                    let response = match agent.chat(messages, Some((&request_id, &tx))).await {
+                        Ok((text, usage)) => {
+                            let mut conversation = agent.conversation.lock().unwrap();
+                            if conversation.id == conversation_id {
+                                conversation
+                                    .history
+                                    .push(json!({ "role": "assistant", "content": text }));
+                            }
+                            done(&request_id, usage)
+                        }
+                        Err(e) => e.response(&request_id),
+                    };
+                    let _ = tx.send(response);
+                });
+                in_flight.insert(id, task);
+            }
+            AgentRequest::Transform { id, message } | AgentRequest::Summarize { id, message } => {
+                let system = if kind == "transform" {
+                    TRANSFORM_PROMPT
+                } else {
+                    SUMMARIZE_PROMPT
+                };
+                let messages = vec![
+                    json!({ "role": "system", "content": system }),
+                    json!({ "role": "user", "content": message }),
+                ];
+
+                let agent = self.clone();
+                let tx = tx.clone();
+                let request_id = id.clone();
+                let task = tasks.spawn(async move {
+                    let response = match agent.chat(messages, None).await {
+                        Ok((text, usage)) => {
+                            let _ = tx.send(AgentResponse::Token {
+                                id: request_id.clone(),
+                                token: text,
+                                timestamp: store::now_millis(),
+                            });
+                            done(&request_id, usage)
+                        }
+                        Err(e) => e.response(&request_id),
+                    };
+                    let _ = tx.send(response);
+                });
+                in_flight.insert(id, task);
+            }
+            // Handled by run()
+            AgentRequest::Shutdown { .. } => {}
+        }
+    }
+
+    fn with_system(&self, history: &[Value]) -> Vec<Value> {
+        let system = self
+            .endpoint
+            .system_prompt
+            .iter()
+            .map(|prompt| json!({ "role": "system", "content": prompt }));
+        system.chain(history.iter().cloned()).collect()
+    }
+
+    // One chat completion. With `tokens`, the reply is streamed to the shell as it
+    // arrives; either way the full text is returned.
+    async fn chat(
+        &self,
+        messages: Vec<Value>,
+        tokens: Option<(&str, &UnboundedSender<AgentResponse>)>,
+    ) -> Result<(String, Option<Usage>), ApiError> {
+        let mut body = json!({ "model": self.endpoint.model, "messages": messages });
+        if tokens.is_some() {
+            body["stream"] = json!(true);
+            body["stream_options"] = json!({ "include_usage": true });
+        }
+        let url = format!(
+            "{}/chat/completions",
+            self.endpoint.base_url.trim_end_matches('/')
+        );
+        let mut request = network_config::client().post(url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let mut response = request.send().await.map_err(ApiError::network)?;
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after_ms = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(|secs| secs * 1000);
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::status(status.as_u16(), &body, retry_after_ms));
+        }
+
+        let Some((id, tx)) = tokens else {
+            let reply: Value = response.json().await.map_err(ApiError::network)?;
+            let text = reply["choices"][0]["message"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            return Ok((text, self.usage(&reply["usage"])));
+        };
+
+        // Server-sent events, one JSON chunk per data: line
+        let mut text = String::new();
+        let mut usage = None;
+        let mut buffer: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(ApiError::network)? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let Ok(event) = serde_json::from_str::<Value>(data.trim()) else {
+                    // Includes the closing [DONE]
+                    continue;
+                };
+                if let Some(token) = event["choices"][0]["delta"]["content"].as_str() {
+                    if !token.is_empty() {
+                        text.push_str(token);
+                        let _ = tx.send(AgentResponse::Token {
+                            id: id.to_string(),
+                            token: token.to_string(),
+                            timestamp: store::now_millis(),
+                        });
+                    }
+                }
+                if let Some(reported) = self.usage(&event["usage"]) {
+                    usage = Some(reported);
+                }
+            }
+        }
+        Ok((text, usage))
+    }
+
+    fn usage(&self, usage: &Value) -> Option<Usage> {
+        Some(Usage {
+            model: self.endpoint.model.clone(),
+            input_tokens: usage["prompt_tokens"].as_u64()?,
+            output_tokens: usage["completion_tokens"].as_u64()?,
+        })
+    }
+}
+
+impl ApiError {
+    fn network(error: reqwest::Error) -> Self {
+        ApiError {
+            message: error.to_string(),
+            code: Some("network_error"),
+            retry_after_ms: None,
+        }
+    }
+
+    fn status(status: u16, body: &str, retry_after_ms: Option<u64>) -> Self {
+        let code = match status {
+            429 => Some("rate_limited"),
+            401 | 403 => Some("auth_error"),
+            500..=599 => Some("provider_unavailable"),
+            _ => None,
+        };
+        // OpenAI-style {"error": {"message": ...}}, else the raw body
+        let detail = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|body| body["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| body.trim().to_string());
+        ApiError {
+            message: format!("HTTP {}: {}", status, detail),
+            code,
+            retry_after_ms,
+        }
+    }
+
+    fn response(self, id: &str) -> AgentResponse {
+        AgentResponse::Error {
+            id: id.to_string(),
+            error: self.message,
+            code: self.code.map(str::to_string),
+            retry_after_ms: self.retry_after_ms,
+            timestamp: store::now_millis(),
+        }
+    }
+}
+
+// Plain text, or text and image parts when images are attached
+fn user_content(message: &str, images: Option<&str>) -> Value {
+    let images: Vec<ImageAttachment> = images
+        .and_then(|images| serde_json::from_str(images).ok())
+        .unwrap_or_default();
+    if images.is_empty() {
+        return json!(message);
+    }
+
+    let mut parts = vec![json!({ "type": "text", "text": message })];
+    parts.extend(images.iter().map(|image| {
+        json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{};base64,{}", image.mime_type, image.data) },
+        })
+    }));
+    Value::Array(parts)
+}
+
+// The summary as an opening exchange, then the recent messages
+fn compressed_history(compressed: CompressedHistory) -> Vec<Value> {
+    let mut history = vec![
+        json!({
+            "role": "user",
+            "content": format!("Summary of our conversation so far:\n\n{}", compressed.summary),
+        }),
+        json!({ "role": "assistant", "content": "Understood, I will continue from there." }),
+    ];
+    history.extend(
+        compressed
+            .messages
+            .into_iter()
+            .map(|m| json!({ "role": m.role, "content": m.content })),
+    );
+    history
+}
+
+fn done(id: &str, usage: Option<Usage>) -> AgentResponse {
+    AgentResponse::Done {
+        id: id.to_string(),
+        usage,
+        data: None,
+        timestamp: store::now_millis(),
+    }
+}
+
+// Done for a load, with what the Node agent reports about the loaded conversation
+fn loaded(id: &str, conversation: &Conversation) -> AgentResponse {
+    AgentResponse::Done {
+        id: id.to_string(),
+        usage: None,
+        data: Some(json!({
+            "conversation_id": conversation.id,
+            "message_count": conversation.history.len(),
+        })),
+        timestamp: store::now_millis(),
+    }
+}
+
+fn invalid(id: &str, error: &str) -> AgentResponse {
+    AgentResponse::Error {
+        id: id.to_string(),
+        error: error.to_string(),
+        code: None,
+        retry_after_ms: None,
+        timestamp: store::now_millis(),
+    }
+}
+
+async fn write(out: &mut DuplexStream, response: &AgentResponse) -> Result<()> {
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    out.write_all(line.as_bytes()).await?;
+    Ok(())
+}
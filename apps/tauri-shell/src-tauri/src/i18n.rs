@@ -0,0 +1,130 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use unic_langid::LanguageIdentifier;
+
+const FALLBACK_LOCALE: &str = "en-US";
+
+// Fluent sources bundled into the binary, keyed by locale
+const LOCALES: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+    ("ja", include_str!("../locales/ja.ftl")),
+];
+
+static LOCALIZER: Lazy<Localizer> = Lazy::new(Localizer::detect);
+
+/// Localized strings for native surfaces (tray, menus, dialogs, notifications).
+struct Localizer {
+    locale: String,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl Localizer {
+    fn detect() -> Self {
+        let requested = std::env::var("ASST_LOCALE")
+            .ok()
+            .or_else(sys_locale::get_locale)
+            .unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+        let locale = negotiate(&requested);
+
+        Localizer {
+            bundle: bundle_for(locale),
+            fallback: bundle_for(FALLBACK_LOCALE),
+            locale: locale.to_string(),
+        }
+    }
+
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in [&self.bundle, &self.fallback] {
+            if let Some(pattern) = bundle.get_message(key).and_then(|m| m.value()) {
+                let mut errors = Vec::new();
+                let value = bundle.format_pattern(pattern, args, &mut errors);
+                if !errors.is_empty() {
+                    eprintln!("Failed to format '{}': {:?}", key, errors);
+                }
+                return value.into_owned();
+            }
+        }
+
+        key.to_string()
+    }
+}
+
+/// Returns the negotiated UI locale (e.g. "de"), not the raw system locale.
+#[tauri::command]
+pub fn get_locale() -> String {
+    LOCALIZER.locale.clone()
+}
+
+/// Localizes a set of keys in one call so the frontend can reuse native strings.
+#[tauri::command]
+pub fn get_localized_strings(keys: Vec<String>) -> HashMap<String, String> {
+    keys.into_iter()
+        .map(|key| {
+            let value = t(&key);
+            (key, value)
+        })
+        .collect()
+}
+
+pub fn t(key: &str) -> String {
+    LOCALIZER.format(key, None)
+}
+
+pub fn t_args(key: &str, args: &[(&str, FluentValue)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+
+    LOCALIZER.format(key, Some(&fluent_args))
+}
+
+// Picks the best bundled locale: exact match, then language-only match
+fn negotiate(requested: &str) -> &'static str {
+    let requested: LanguageIdentifier = match requested.replace('_', "-").parse() {
+        Ok(id) => id,
+        Err(_) => return FALLBACK_LOCALE,
+    };
+
+    let available = || {
+        LOCALES
+            .iter()
+            .filter_map(|(name, _)| Some((*name, name.parse::<LanguageIdentifier>().ok()?)))
+    };
+
+    available()
+        .find(|(_, id)| *id == requested)
+        .or_else(|| available().find(|(_, id)| id.language == requested.language))
+        .map(|(name, _)| name)
+        .unwrap_or(FALLBACK_LOCALE)
+}
+
+fn bundle_for(locale: &str) -> FluentBundle<FluentResource> {
+    let source = LOCALES
+        .iter()
+        .find(|(name, _)| *name == locale)
+        .map(|(_, source)| *source)
+        .unwrap_or(LOCALES[0].1);
+    let langid: LanguageIdentifier = locale.parse().unwrap_or_default();
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks show up as stray characters in native menus
+    bundle.set_use_isolating(false);
+
+    match FluentResource::try_new(source.to_string()) {
+        Ok(resource) => {
+            if let Err(errors) = bundle.add_resource(resource) {
+                eprintln!("Duplicate messages in {} locale: {:?}", locale, errors);
+            }
+        }
+        Err((_, errors)) => eprintln!("Failed to parse {} locale: {:?}", locale, errors),
+    }
+
+    bundle
+}
@@ -0,0 +1,93 @@
+use crate::agent_ipc::AgentRequest;
+use crate::outbox;
+use crate::profiles;
+use crate::window_registry;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+/// Command-line flags (declared under `tauri.cli` in tauri.conf.json).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LaunchOptions {
+    pub hidden: bool,
+    pub conversation: Option<String>,
+    pub prompt: Option<String>,
+    pub profile: Option<String>,
+    // Request id the --prompt message was queued under, so the UI can follow its stream
+    pub prompt_id: Option<String>,
+}
+
+impl LaunchOptions {
+    pub fn parse(app: &tauri::App) -> Self {
+        let matches = match app.get_cli_matches() {
+            Ok(matches) => matches,
+            Err(e) => {
+                eprintln!("Failed to parse command line: {}", e);
+                return LaunchOptions::default();
+            }
+        };
+
+        let string = |name: &str| {
+            matches
+                .args
+                .get(name)
+                .and_then(|arg| arg.value.as_str())
+                .map(str::to_string)
+        };
+
+        LaunchOptions {
+            hidden: matches
+                .args
+                .get("hidden")
+                .and_then(|arg| arg.value.as_bool())
+                .unwrap_or(false),
+            conversation: string("conversation"),
+            prompt: string("prompt").filter(|prompt| !prompt.trim().is_empty()),
+            profile: string("profile").filter(|profile| {
+                let valid = profiles::valid_name(profile);
+                if !valid {
+                    eprintln!("Ignoring invalid profile name: {}", profile);
+                }
+                valid
+            }),
+            prompt_id: None,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_launch_options(options: State<'_, LaunchOptions>) -> LaunchOptions {
+    options.inner().clone()
+}
+
+/// Acts on the flags once the app is set up: titles the window after --conversation,
+/// queues --prompt for the agent and shows the main window unless --hidden was given.
+pub fn apply(app_handle: &AppHandle, options: &mut LaunchOptions) {
+    // The frontend opens it via get_launch_options; the title can reflect it right away
+    if let Some(conversation_id) = &options.conversation {
+        window_registry::set_conversation(app_handle, "main", conversation_id);
+    }
+
+    if let Some(prompt) = &options.prompt {
+        let request = AgentRequest::UserMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message: prompt.clone(),
+            images: None,
+            conversation_id: options.conversation.clone(),
+        };
+        let id = request.id().to_string();
+
+        // Sent as soon as the frontend spawns the agent
+        match outbox::enqueue(app_handle, request, Some("main".to_string())) {
+            Ok(()) => options.prompt_id = Some(id),
+            Err(e) => eprintln!("Failed to queue launch prompt: {}", e),
+        }
+    }
+
+    if options.hidden {
+        return;
+    }
+    if let Some(window) = app_handle.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
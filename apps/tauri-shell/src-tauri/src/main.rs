@@ -1,135 +1,910 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod accessibility;
+mod activity;
+mod agent_backend;
 mod agent_ipc;
+mod agent_logs;
+mod agent_profiles;
+mod agent_runtime;
+mod agent_updates;
+mod annotate;
+mod attachments;
+mod bookmarks;
+mod budget;
+mod cache;
+mod capabilities;
+mod checkpoints;
+mod clipboard;
+mod code_blocks;
+mod color_picker;
+mod connectivity;
+mod conversation_agents;
+mod conversation_lock;
+mod crash_reports;
+mod data_dir;
+mod dedupe;
+mod diagnostics;
+mod drafts;
+mod event_queue;
+mod external;
+mod feedback;
+mod folder_watch;
+mod fuzzy_search;
+mod generated_images;
+mod heartbeat;
+mod highlight;
+mod hot_reload;
+mod http_agent;
+mod i18n;
+mod launch;
+mod merge;
+mod message_image;
+mod migration;
+mod network_config;
+mod onboarding;
+mod outbox;
+mod pacing;
+mod pdf_export;
+mod print;
+mod profiles;
+mod protocol;
+mod quick_switch;
+mod redact;
+mod resource_limits;
+mod secrets;
+mod session;
+mod settings;
+mod settings_export;
+mod shortcut;
+mod shutdown;
+mod snapshot;
+mod snippets;
+mod spaces;
+mod spill;
+mod stall;
+mod standby;
+mod startup_report;
+mod store;
+mod suspend;
+mod taskbar;
+mod text_transform;
+mod time_format;
+mod transport;
+mod unread;
+mod updates;
+mod watchdog;
+mod window_presets;
+mod window_registry;
+mod window_title;
+mod zoom;
 
-use agent_ipc::{AgentProcess, AgentRequest};
+use agent_ipc::{AgentProcess, AgentRequest, AgentStatus, DEFAULT_AGENT_ID};
+use agent_profiles::AgentProfiles;
+use bookmarks::Bookmarks;
+use budget::UsageBudget;
+use checkpoints::Checkpoints;
+use connectivity::Connectivity;
+use conversation_agents::ConversationAgents;
+use dedupe::DuplicateGuard;
+use event_queue::EventQueueStats;
+use folder_watch::FolderWatcher;
+use fuzzy_search::FuzzyIndex;
+use launch::LaunchOptions;
+use onboarding::PermissionWatch;
+use outbox::Outbox;
+use protocol::{Capabilities, LoadedConversation};
+use quick_switch::QuickSwitchIndex;
+use session::Session;
+use settings::SettingsStore;
+use snippets::Snippets;
+use standby::Standby;
+use startup_report::StartupReports;
+use store::{ConversationStore, DEFAULT_CONVERSATION_ID};
+use taskbar::TaskbarProgress;
+use unread::UnreadTracker;
+use window_presets::WindowPresets;
+use window_registry::WindowRegistry;
+use window_title::WindowTitles;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{
-    CustomMenuItem, Manager, State, SystemTray, SystemTrayEvent, SystemTrayMenu,
-    SystemTrayMenuItem, GlobalShortcutManager, WindowEvent
+    CustomMenuItem, Manager, Menu, RunEvent, State, Submenu, SystemTray, SystemTrayEvent,
+    SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu, WindowEvent
 };
 use tokio::sync::Mutex;
 
-// State to hold the agent process
+// Process slot of one agent. Each has its own lock, so agents stream in parallel
+type AgentSlot = Arc<Mutex<Option<AgentProcess>>>;
+
+// State to hold the agent processes, keyed by agent id
+#[derive(Default)]
 struct AppState {
-    agent: Arc<Mutex<Option<AgentProcess>>>,
+    agents: std::sync::Mutex<HashMap<String, AgentSlot>>,
+}
+
+impl AppState {
+    // The default agent, used by everything that isn't addressed to a specific one
+    fn agent(&self) -> AgentSlot {
+        self.slot(DEFAULT_AGENT_ID)
+    }
+
+    // The slot of `agent_id`, created empty on first use
+    fn slot(&self, agent_id: &str) -> AgentSlot {
+        self.agents
+            .lock()
+            .unwrap()
+            .entry(agent_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn find(&self, agent_id: &str) -> Option<AgentSlot> {
+        self.agents.lock().unwrap().get(agent_id).cloned()
+    }
+
+    fn slots(&self) -> Vec<(String, AgentSlot)> {
+        self.agents
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(agent_id, slot)| (agent_id.clone(), slot.clone()))
+            .collect()
+    }
 }
 
 // Tauri commands
+
+/// Starts the agent `agent_id` (the default agent when None) and returns its id. Other
+/// ids run as separate processes, so their conversations don't wait on each other.
+/// With `profile_id`, the agent runs that agent profile from now on.
 #[tauri::command]
 async fn spawn_agent(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let mut agent = state.agent.lock().await;
+    agent_id: Option<String>,
+    profile_id: Option<String>,
+) -> Result<String, String> {
+    let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let slot = state.slot(&agent_id);
+    if slot.lock().await.is_some() {
+        return Err(format!("Agent {} already running", agent_id));
+    }
 
-    if agent.is_some() {
-        return Err("Agent already running".to_string());
+    if profile_id.is_some() {
+        agent_profiles::assign(&app_handle, &agent_id, profile_id)
+            .map_err(|e| format!("Failed to spawn agent: {}", e))?;
+        refresh_tray(&app_handle);
     }
+    let started = start_agent(&app_handle, &agent_id, &slot)
+        .await
+        .map_err(|e| format!("Failed to spawn agent: {}", e))?;
+    if !started {
+        return Err(format!("Agent {} already running", agent_id));
+    }
+    Ok(agent_id)
+}
 
-    match AgentProcess::spawn(app_handle).await {
-        Ok(process) => {
+// Spawns the agent into its slot, waiting for it to be ready without holding the slot
+async fn start_agent(
+    app_handle: &tauri::AppHandle,
+    agent_id: &str,
+    slot: &AgentSlot,
+) -> anyhow::Result<bool> {
+    let process = AgentProcess::spawn(app_handle.clone(), agent_id).await?;
+    Ok(install_agent(app_handle, agent_id, slot, process).await)
+}
+
+// Puts a ready process into the slot and, for the default agent, sends what is queued
+// in the outbox. Returns false, shutting `process` down, if another process got there
+// first while it started.
+async fn install_agent(
+    app_handle: &tauri::AppHandle,
+    agent_id: &str,
+    slot: &AgentSlot,
+    process: AgentProcess,
+) -> bool {
+    let mut agent = slot.lock().await;
+    let spare = match agent.as_ref() {
+        Some(_) => Some(process),
+        None => {
             *agent = Some(process);
-            Ok(())
+            agent_ipc::emit_lifecycle(app_handle, agent_id, "agent_started", Vec::new());
+            None
+        }
+    };
+    if let (DEFAULT_AGENT_ID, Some(current)) = (agent_id, agent.as_mut()) {
+        outbox::flush(app_handle, current).await;
+    }
+    drop(agent);
+
+    match spare {
+        Some(spare) => {
+            spare.shutdown().await;
+            false
         }
-        Err(e) => Err(format!("Failed to spawn agent: {}", e)),
+        None => {
+            if agent_id == DEFAULT_AGENT_ID {
+                standby::replenish(app_handle);
+            }
+            true
+        }
+    }
+}
+
+/// Shuts the agent down (and, for the default agent, the warm standby with it) until
+/// spawn_agent is called again.
+#[tauri::command]
+async fn stop_agent(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let slot = state.slot(&agent_id);
+    let mut agent = slot.lock().await;
+    let Some(process) = agent.take() else {
+        return Err(format!("Agent {} not running", agent_id));
+    };
+
+    let lost_requests = process.shutdown().await;
+    if agent_id == DEFAULT_AGENT_ID {
+        standby::discard(&app_handle).await;
+    }
+    agent_ipc::emit_lifecycle(&app_handle, &agent_id, "agent_stopped", lost_requests);
+    Ok(())
+}
+
+/// Replaces the agent with a fresh process, keeping the active conversation. The old
+/// process keeps running if the new one fails to start.
+#[tauri::command]
+async fn restart_agent(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let slot = state.slot(&agent_id);
+    let Some(agent_ipc::Replaced { mut agent, old, .. }) = agent_ipc::replace(&slot, None)
+        .await
+        .map_err(|e| format!("Failed to restart agent: {}", e))?
+    else {
+        return Err(format!("Agent {} not running", agent_id));
+    };
+    if let (DEFAULT_AGENT_ID, Some(process)) = (agent_id.as_str(), agent.as_mut()) {
+        outbox::flush(&app_handle, process).await;
+    }
+    drop(agent);
+
+    let lost_requests = old.shutdown().await;
+    agent_ipc::emit_lifecycle(&app_handle, &agent_id, "agent_stopped", lost_requests);
+    agent_ipc::emit_lifecycle(&app_handle, &agent_id, "agent_started", Vec::new());
+    Ok(())
+}
+
+/// Status of every agent that has been started, keyed by agent id.
+#[tauri::command]
+async fn list_agents(state: State<'_, AppState>) -> Result<HashMap<String, AgentStatus>, String> {
+    let mut agents = HashMap::new();
+    for (agent_id, slot) in state.slots() {
+        let status = match slot.lock().await.as_mut() {
+            Some(process) => process.status().await,
+            None => AgentStatus::stopped(),
+        };
+        agents.insert(agent_id, status);
     }
+    Ok(agents)
 }
 
 #[tauri::command]
 async fn send_message(
+    window: tauri::Window,
     state: State<'_, AppState>,
     id: String,
     message: String,
     images: Option<String>,
+    conversation_id: Option<String>,
+    agent_id: Option<String>,
 ) -> Result<(), String> {
-    let agent = state.agent.lock().await;
+    conversation_lock::ensure_unlocked(
+        &window.state::<ConversationStore>(),
+        conversation_id
+            .as_deref()
+            .unwrap_or(DEFAULT_CONVERSATION_ID),
+    )?;
+    budget::check(&window.app_handle()).map_err(|e| format!("Message not sent: {}", e))?;
+    if dedupe::is_duplicate(
+        &window.state::<DuplicateGuard>(),
+        &window,
+        &id,
+        &message,
+        images.as_deref(),
+        conversation_id.as_deref(),
+    ) {
+        return Ok(());
+    }
 
-    match agent.as_ref() {
-        Some(process) => {
-            let request = AgentRequest {
-                id,
-                kind: "user_message".to_string(),
-                message: Some(message),
-                images,
-            };
+    // Bound conversations go to their own process
+    let agent_id = agent_id
+        .or_else(|| conversation_agents::route(&window.app_handle(), conversation_id.as_deref()))
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let slot = state.slot(&agent_id);
+    let mut agent = slot.lock().await;
+    let request = AgentRequest::UserMessage {
+        id,
+        message,
+        images,
+        conversation_id,
+    };
+    let owner = Some(window.label().to_string());
 
-            process
-                .send_request(&request)
+    // Only the default agent has an outbox; others are spawned and fed explicitly
+    if agent_id != DEFAULT_AGENT_ID {
+        let app_handle = window.app_handle();
+        if let (None, Some(conversation_id)) = (
+            agent.as_ref(),
+            conversation_agents::conversation_of(&app_handle, &agent_id),
+        ) {
+            // Stopped while idle; it comes back with its conversation loaded
+            drop(agent);
+            conversation_agents::start(&app_handle, &agent_id, &conversation_id, &slot)
                 .await
-                .map_err(|e| format!("Failed to send message: {}", e))
+                .map_err(|e| format!("Failed to start conversation agent: {}", e))?;
+            agent = slot.lock().await;
+        }
+        let process = agent
+            .as_mut()
+            .ok_or_else(|| format!("Agent {} not running", agent_id))?;
+        if let Err(e) = process.send_request(&request, owner).await {
+            process.forget(request.id()).await;
+            return Err(format!("Failed to send message: {}", e));
+        }
+        return Ok(());
+    }
+
+    // Messages the agent can't take right now, or composed offline, wait in the outbox
+    let offline = connectivity::is_offline(&window.app_handle());
+    if let Some(process) = agent.as_mut().filter(|_| !offline) {
+        match process.send_request(&request, owner.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                eprintln!("Failed to send message {}: {}", request.id(), e);
+                process.forget(request.id()).await;
+            }
         }
-        None => Err("Agent not running".to_string()),
     }
+    let spawn = agent.is_none() && !offline;
+    drop(agent);
+
+    let request_id = request.id().to_string();
+    outbox::enqueue(&window.app_handle(), request, owner)
+        .map_err(|e| format!("Failed to queue message: {}", e))?;
+    if spawn {
+        // Started on first use; the message is sent from the outbox once it's ready
+        eprintln!("[AGENT] Not running, spawning for message {}", request_id);
+        if let Err(e) = start_agent(&window.app_handle(), &agent_id, &slot).await {
+            // Not left queued behind the error, so sending it again doesn't duplicate it
+            if let Err(e) = outbox::withdraw(&window.app_handle(), &request_id) {
+                eprintln!("Failed to withdraw message {}: {}", request_id, e);
+            }
+            return Err(format!("Failed to spawn agent: {}", e));
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
-    let agent = state.agent.lock().await;
+async fn clear_history(
+    state: State<'_, AppState>,
+    store: State<'_, ConversationStore>,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let mut agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
 
-    match agent.as_ref() {
+    match agent.as_mut() {
         Some(process) => {
-            let request = AgentRequest {
+            conversation_lock::ensure_unlocked(
+                &store,
+                process
+                    .active_conversation()
+                    .unwrap_or(DEFAULT_CONVERSATION_ID),
+            )?;
+            let request = AgentRequest::ClearHistory {
                 id: uuid::Uuid::new_v4().to_string(),
-                kind: "clear_history".to_string(),
-                message: None,
-                images: None,
             };
 
             process
-                .send_request(&request)
+                .send_and_wait(&request)
                 .await
+                .map(|_| ())
                 .map_err(|e| format!("Failed to clear history: {}", e))
         }
         None => Err("Agent not running".to_string()),
     }
 }
 
+/// Loads `conversation_id` into its agent and returns once the agent has it.
+#[tauri::command]
+async fn load_conversation(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    conversation_id: String,
+    agent_id: Option<String>,
+) -> Result<LoadedConversation, String> {
+    let agent_id =
+        agent_id.or_else(|| conversation_agents::route(&app_handle, Some(&conversation_id)));
+    let mut agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+    let Some(process) = agent.as_mut() else {
+        return Err("Agent not running".to_string());
+    };
+
+    let request = AgentRequest::LoadConversation {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id,
+    };
+    let data = process
+        .send_and_wait(&request)
+        .await
+        .map_err(|e| format!("Failed to load conversation: {}", e))?
+        .ok_or_else(|| "The agent didn't report the loaded conversation".to_string())?;
+    serde_json::from_value(data).map_err(|e| format!("Invalid reply to load_conversation: {}", e))
+}
+
+/// Cancels the generation `id`, every generation in `conversation_id`, or (with
+/// neither) everything in flight. Returns the ids that were interrupted.
+#[tauri::command]
+async fn send_interrupt(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: Option<String>,
+    conversation_id: Option<String>,
+    agent_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let agent_id =
+        agent_id.or_else(|| conversation_agents::route(&app_handle, conversation_id.as_deref()));
+    let mut agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+    let Some(process) = agent.as_mut() else {
+        return Err("Agent not running".to_string());
+    };
+
+    let targets = process
+        .in_flight_matching(id.as_deref(), conversation_id.as_deref())
+        .await;
+    if let (Some(id), true) = (&id, targets.is_empty()) {
+        return Err(format!("No in-flight request with id {}", id));
+    }
+
+    // The agent cancels per request, so each target gets its own interrupt
+    for target in &targets {
+        let request = AgentRequest::Interrupt { id: target.clone() };
+
+        process
+            .send_request(&request, None)
+            .await
+            .map_err(|e| format!("Failed to send interrupt: {}", e))?;
+    }
+
+    Ok(targets)
+}
+
+#[tauri::command]
+async fn retry_stalled_request(
+    state: State<'_, AppState>,
+    id: String,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+
+    match agent.as_ref() {
+        Some(process) => process
+            .retry_stalled(&id)
+            .await
+            .map_err(|e| format!("Failed to retry request: {}", e)),
+        None => Err("Agent not running".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn pause_stream(
+    state: State<'_, AppState>,
+    id: String,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+
+    match agent.as_ref() {
+        Some(process) => process
+            .pause_stream(&id)
+            .await
+            .map_err(|e| format!("Failed to pause stream: {}", e)),
+        None => Err("Agent not running".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn resume_stream(
+    state: State<'_, AppState>,
+    id: String,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+
+    match agent.as_ref() {
+        Some(process) => process
+            .resume_stream(&id)
+            .await
+            .map_err(|e| format!("Failed to resume stream: {}", e)),
+        None => Err("Agent not running".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn agent_status(
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<AgentStatus, String> {
+    let mut agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+
+    match agent.as_mut() {
+        Some(process) => Ok(process.status().await),
+        None => Ok(AgentStatus::stopped()),
+    }
+}
+
+/// What the agent agreed to in hello; nothing while it is stopped or starting.
+#[tauri::command]
+async fn get_agent_capabilities(
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<Capabilities, String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+    Ok(agent
+        .as_ref()
+        .map(|process| process.capabilities())
+        .unwrap_or_default())
+}
+
+/// How the agent's events have been held back on their way to the webview.
+#[tauri::command]
+async fn get_event_queue_stats(
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<EventQueueStats, String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+    Ok(agent
+        .as_ref()
+        .map(|process| process.event_queue_stats())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+async fn list_in_flight(
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let agent = agent_slot(&state, agent_id.as_deref()).lock_owned().await;
+
+    match agent.as_ref() {
+        Some(process) => Ok(process.in_flight_ids().await),
+        None => Ok(Vec::new()),
+    }
+}
+
+// Slot addressed by a command's optional agent_id
+fn agent_slot(state: &AppState, agent_id: Option<&str>) -> AgentSlot {
+    state.slot(agent_id.unwrap_or(DEFAULT_AGENT_ID))
+}
+
+/// Tray menu, with profiles, agent profiles and favorite snippets once there are any.
+fn tray_menu(
+    profiles: Option<SystemTraySubmenu>,
+    agent_profiles: Option<SystemTraySubmenu>,
+    snippets: Option<SystemTraySubmenu>,
+    agent_suspended: bool,
+) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("show", i18n::t("tray-show")))
+        .add_item(suspend::tray_item(agent_suspended));
+    if let Some(profiles) = profiles {
+        menu = menu.add_submenu(profiles);
+    }
+    if let Some(agent_profiles) = agent_profiles {
+        menu = menu.add_submenu(agent_profiles);
+    }
+    if let Some(snippets) = snippets {
+        menu = menu.add_submenu(snippets);
+    }
+
+    menu.add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit", i18n::t("tray-quit")))
+}
+
+/// Rebuilds the tray menu after profiles, agent profiles or favorite snippets change,
+/// or the default agent is paused or resumed.
+pub fn refresh_tray(app_handle: &tauri::AppHandle) {
+    let menu = tray_menu(
+        profiles::tray_submenu(app_handle),
+        agent_profiles::tray_submenu(app_handle),
+        snippets::tray_submenu(app_handle),
+        suspend::is_suspended(app_handle),
+    );
+    if let Err(e) = app_handle.tray_handle().set_menu(menu) {
+        eprintln!("Failed to update tray menu: {}", e);
+    }
+}
+
 fn main() {
-    // Build system tray menu
-    let tray_menu = SystemTrayMenu::new()
-        .add_item(CustomMenuItem::new("show", "Show Assistant"))
-        .add_native_item(SystemTrayMenuItem::Separator)
-        .add_item(CustomMenuItem::new("quit", "Quit"));
+    // Sets environment variables, so before anything starts a thread
+    network_config::export_system_proxy();
 
-    let tray = SystemTray::new().with_menu(tray_menu);
+    // Build system tray menu; profiles and favorite snippets are added in setup
+    let tray = SystemTray::new().with_menu(tray_menu(None, None, None, false));
 
-    tauri::Builder::default()
-        .manage(AppState {
-            agent: Arc::new(Mutex::new(None)),
-        })
+    // Native app menu: OS defaults (Edit menu for copy/paste, etc.) plus zoom controls
+    let context = tauri::generate_context!();
+    let zoom_menu = Menu::new()
+        .add_item(
+            CustomMenuItem::new("zoom_in", i18n::t("menu-zoom-in")).accelerator("CmdOrCtrl+Plus"),
+        )
+        .add_item(
+            CustomMenuItem::new("zoom_out", i18n::t("menu-zoom-out")).accelerator("CmdOrCtrl+-"),
+        )
+        .add_item(
+            CustomMenuItem::new("zoom_reset", i18n::t("menu-zoom-reset"))
+                .accelerator("CmdOrCtrl+0"),
+        );
+    let menu = Menu::os_default(&context.package_info().name)
+        .add_submenu(Submenu::new(i18n::t("menu-zoom"), zoom_menu));
+
+    manage_state(tauri::Builder::default())
         .invoke_handler(tauri::generate_handler![
             spawn_agent,
+            stop_agent,
+            restart_agent,
+            list_agents,
+            agent_status,
             send_message,
-            clear_history
+            clear_history,
+            send_interrupt,
+            list_in_flight,
+            retry_stalled_request,
+            pause_stream,
+            resume_stream,
+            clipboard::write_clipboard,
+            external::open_external,
+            zoom::set_zoom,
+            zoom::get_zoom,
+            message_image::render_message_image,
+            print::print_conversation,
+            unread::get_unread_counts,
+            unread::mark_conversation_read,
+            unread::mark_all_read,
+            unread::set_active_conversation,
+            feedback::get_feedback_settings,
+            feedback::set_feedback_settings,
+            feedback::play_feedback,
+            window_title::set_window_title_context,
+            i18n::get_locale,
+            i18n::get_localized_strings,
+            updates::get_update_channel,
+            updates::set_update_channel,
+            updates::check_for_updates,
+            updates::install_update,
+            agent_updates::get_agent_version,
+            agent_updates::check_agent_update,
+            agent_updates::install_agent_update,
+            onboarding::get_onboarding_state,
+            onboarding::detect_agent,
+            onboarding::set_api_key,
+            onboarding::get_permissions,
+            onboarding::request_permission,
+            onboarding::open_permission_settings,
+            onboarding::advance_onboarding,
+            onboarding::skip_onboarding_step,
+            onboarding::reset_onboarding,
+            shortcut::get_global_shortcut,
+            shortcut::set_global_shortcut,
+            migration::detect_migrations,
+            migration::run_migration,
+            migration::confirm_migration,
+            migration::revert_migration,
+            outbox::list_outbox,
+            outbox::discard_outbox_item,
+            connectivity::get_network_status,
+            drafts::save_draft,
+            drafts::get_draft,
+            launch::get_launch_options,
+            quick_switch::quick_switch_candidates,
+            color_picker::pick_screen_color,
+            diagnostics::capture_app_window,
+            spaces::get_space_behavior,
+            spaces::set_space_behavior,
+            spaces::list_monitors,
+            spaces::get_window_pin,
+            spaces::pin_window,
+            spaces::unpin_window,
+            annotate::annotate_image,
+            data_dir::get_data_dir,
+            data_dir::move_data_dir,
+            spill::read_response_range,
+            time_format::format_timestamp,
+            snapshot::attach_environment_snapshot,
+            capabilities::get_capabilities,
+            bookmarks::bookmark_message,
+            bookmarks::remove_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::open_bookmark,
+            snippets::create_snippet,
+            snippets::delete_snippet,
+            snippets::list_snippets,
+            snippets::expand_snippet,
+            fuzzy_search::fuzzy_search,
+            fuzzy_search::get_fuzzy_search_enabled,
+            fuzzy_search::set_fuzzy_search_enabled,
+            folder_watch::watch_folder,
+            folder_watch::unwatch_folder,
+            folder_watch::list_watched_folders,
+            code_blocks::extract_code_blocks,
+            code_blocks::save_code_block,
+            pdf_export::export_conversation_pdf,
+            window_registry::list_windows,
+            standby::get_warm_standby_enabled,
+            standby::set_warm_standby_enabled,
+            session::get_unclean_session,
+            session::restore_session,
+            session::discard_session,
+            budget::get_usage_budget,
+            budget::set_usage_budget,
+            budget::override_usage_budget,
+            redact::get_redaction_settings,
+            redact::set_redaction_settings,
+            redact::preview_redaction,
+            profiles::list_profiles,
+            profiles::switch_profile,
+            agent_runtime::get_agent_runtime,
+            agent_runtime::set_agent_runtime,
+            agent_runtime::get_agent_command,
+            agent_runtime::set_agent_command,
+            highlight::highlight_code,
+            highlight::list_highlight_themes,
+            text_transform::get_text_transforms,
+            text_transform::set_text_transforms,
+            text_transform::run_text_transform,
+            settings_export::export_settings,
+            settings_export::import_settings,
+            secrets::list_agent_secrets,
+            secrets::set_agent_secret,
+            secrets::delete_agent_secret,
+            attachments::list_conversation_attachments,
+            attachments::export_conversation_attachments,
+            network_config::get_network_config,
+            merge::merge_conversations,
+            agent_logs::list_agent_sessions,
+            agent_logs::get_agent_session_logs,
+            conversation_agents::bind_conversation_agent,
+            conversation_agents::unbind_conversation_agent,
+            conversation_agents::list_conversation_agents,
+            agent_profiles::list_agent_profiles,
+            agent_profiles::save_agent_profile,
+            agent_profiles::delete_agent_profile,
+            agent_profiles::get_agent_profile,
+            agent_profiles::switch_agent_profile,
+            generated_images::save_generated_image,
+            generated_images::generated_image_drag,
+            conversation_lock::lock_conversation,
+            conversation_lock::unlock_conversation,
+            crash_reports::get_crash_reports,
+            startup_report::get_startup_report,
+            checkpoints::get_conversation_checkpoint,
+            checkpoints::load_conversation_compressed,
+            suspend::pause_agent,
+            suspend::resume_agent,
+            window_presets::apply_window_preset,
+            window_presets::get_window_presets,
+            window_presets::set_window_presets,
+            load_conversation,
+            cache::clear_cache,
+            activity::get_activity,
+            get_agent_capabilities,
+            get_event_queue_stats
         ])
+        .menu(menu)
+        .on_menu_event(|event| zoom::handle_menu_item(event.window(), event.menu_item_id()))
         .system_tray(tray)
         .on_system_tray_event(handle_tray_event)
+        .on_page_load(|window, _| {
+            zoom::restore(&window);
+            print::on_page_load(&window);
+        })
         .setup(setup_handler)
-        .on_window_event(|event| {
-            if let WindowEvent::CloseRequested { api, .. } = event.event() {
+        .on_window_event(|event| match event.event() {
+            WindowEvent::CloseRequested { api, .. } if event.window().label() == "main" => {
                 // Hide instead of closing
                 event.window().hide().unwrap();
                 api.prevent_close();
             }
+            WindowEvent::Focused(true) => {
+                unread::on_window_focused(event.window());
+            }
+            WindowEvent::Destroyed => {
+                window_registry::on_window_destroyed(
+                    &event.window().app_handle(),
+                    event.window().label(),
+                );
+            }
+            _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app_handle, event| match event {
+            RunEvent::Updater(updater_event) => {
+                updates::on_updater_event(app_handle, updater_event);
+            }
+            RunEvent::ExitRequested { api, .. } => {
+                shutdown::on_exit_requested(app_handle, api);
+            }
+            RunEvent::Exit => session::mark_clean(app_handle),
+            _ => {}
+        });
+}
+
+// State that needs no setup; the agent_ipc tests build their app with it too
+fn manage_state(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
+    builder
+        .manage(AppState::default())
+        .manage(UnreadTracker::default())
+        .manage(TaskbarProgress::default())
+        .manage(WindowTitles::default())
+        .manage(WindowRegistry::default())
+        .manage(Connectivity::default())
+        .manage(QuickSwitchIndex::default())
+        .manage(FuzzyIndex::default())
+        .manage(FolderWatcher::default())
+        .manage(Standby::default())
+        .manage(DuplicateGuard::default())
+        .manage(ConversationAgents::default())
+        .manage(AgentProfiles::default())
+        .manage(StartupReports::default())
+        .manage(Checkpoints::default())
+        .manage(WindowPresets::default())
+        .manage(PermissionWatch::default())
+}
+
+// The stores read from disk, settings first since the data dir can be set there
+fn open_stores(app: &tauri::App, profile: Option<&str>) {
+    app.manage(SettingsStore::load(&app.handle(), profile));
+    app.manage(ConversationStore::open(&app.handle()));
+    app.manage(Outbox::open(&app.handle()));
+    app.manage(Bookmarks::open(&app.handle()));
+    app.manage(Snippets::open(&app.handle()));
+    app.manage(Session::open(&app.handle()));
+    app.manage(UsageBudget::open(&app.handle()));
 }
 
 fn setup_handler(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    let main_window = app.get_window("main").unwrap();
-
-    // Register global shortcut (Cmd+Shift+Space)
-    let window_clone = main_window.clone();
-    app.global_shortcut_manager().register("CmdOrCtrl+Shift+Space", move || {
-        if window_clone.is_visible().unwrap_or(false) {
-            window_clone.hide().unwrap();
-        } else {
-            window_clone.show().unwrap();
-            window_clone.set_focus().unwrap();
-        }
-    })?;
+    let mut launch_options = LaunchOptions::parse(app);
+    open_stores(app, launch_options.profile.as_deref());
+    cache::start(app.handle());
+    network_config::setup(&app.handle());
+    connectivity::start(app.handle());
+    folder_watch::start(app.handle());
+    session::start(app.handle());
+    conversation_agents::start_reaper(app.handle());
+    hot_reload::start(app.handle());
+
+    // Register the global shortcut (Cmd+Shift+Space unless changed during onboarding)
+    let app_handle = app.handle();
+    shortcut::register(&app_handle, &shortcut::current(&app_handle))?;
+    spaces::apply(&app_handle);
+    snippets::setup(&app_handle);
+    text_transform::setup(&app_handle);
+    window_presets::setup(&app_handle);
+    profiles::apply_tray_icon(&app_handle);
+
+    launch::apply(&app_handle, &mut launch_options);
+    app.manage(launch_options);
+    // After everything it checks has been set up
+    startup_report::start(app.handle());
 
     Ok(())
 }
@@ -141,19 +916,22 @@ fn handle_tray_event(app: &tauri::AppHandle, event: SystemTrayEvent) {
             if window.is_visible().unwrap_or(false) {
                 window.hide().unwrap();
             } else {
-                window.show().unwrap();
-                window.set_focus().unwrap();
+                show_main_window(&window);
             }
         }
         SystemTrayEvent::MenuItemClick { id, .. } => {
             match id.as_str() {
-                "show" => {
-                    let window = app.get_window("main").unwrap();
-                    window.show().unwrap();
-                    window.set_focus().unwrap();
+                "show" => show_main_window(&app.get_window("main").unwrap()),
+                "quit" => shutdown::quit(app),
+                suspend::TRAY_ITEM => suspend::on_tray_click(app),
+                id if id.starts_with(agent_profiles::TRAY_PREFIX) => {
+                    agent_profiles::on_tray_click(app, &id[agent_profiles::TRAY_PREFIX.len()..]);
+                }
+                id if id.starts_with(profiles::TRAY_PREFIX) => {
+                    profiles::on_tray_click(app, &id[profiles::TRAY_PREFIX.len()..]);
                 }
-                "quit" => {
-                    std::process::exit(0);
+                id if id.starts_with(snippets::TRAY_PREFIX) => {
+                    snippets::fire(app, &id[snippets::TRAY_PREFIX.len()..]);
                 }
                 _ => {}
             }
@@ -161,3 +939,10 @@ fn handle_tray_event(app: &tauri::AppHandle, event: SystemTrayEvent) {
         _ => {}
     }
 }
+
+// Onto the current space or the pinned monitor first, then in front
+fn show_main_window(window: &tauri::Window) {
+    spaces::prepare_show(window);
+    window.show().unwrap();
+    window.set_focus().unwrap();
+}
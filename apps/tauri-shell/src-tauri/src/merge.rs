@@ -0,0 +1,126 @@
+use crate::agent_ipc::{AgentRequest, DEFAULT_AGENT_ID};
+use crate::bookmarks::Bookmarks;
+use crate::conversation_agents;
+use crate::conversation_lock;
+use crate::outbox;
+use crate::store::ConversationStore;
+use crate::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct MergeSummary {
+    pub conversation_id: String,
+    // Messages that came over from the source, not counting dividers
+    pub merged_messages: usize,
+    pub total_messages: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct ConversationsMerged<'a> {
+    source_id: &'a str,
+    target_id: &'a str,
+}
+
+/// Moves the messages of `source_id` into `target_id` in chronological order and
+/// deletes the source, for a topic that was accidentally split across chats. The
+/// agent serving the target merges its own history too, immediately or once it is
+/// running; an agent bound to the source alone is stopped.
+#[tauri::command]
+pub async fn merge_conversations(
+    app_handle: AppHandle,
+    source_id: String,
+    target_id: String,
+) -> Result<MergeSummary, String> {
+    if source_id == target_id {
+        return Err("Can't merge a conversation into itself".to_string());
+    }
+    let store = app_handle.state::<ConversationStore>();
+    conversation_lock::ensure_unlocked(&store, &source_id)?;
+    conversation_lock::ensure_unlocked(&store, &target_id)?;
+
+    // Held throughout, so no message reaches the source while it is being merged
+    let state = app_handle.state::<AppState>();
+    let source_agent = conversation_agents::route(&app_handle, Some(&source_id))
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let target_agent = conversation_agents::route(&app_handle, Some(&target_id))
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let source_slot = state.slot(&source_agent);
+    let mut source = source_slot.lock().await;
+    if let Some(process) = source.as_ref() {
+        if !process
+            .in_flight_matching(None, Some(&source_id))
+            .await
+            .is_empty()
+        {
+            return Err("Wait for the replies in that conversation to finish".to_string());
+        }
+    }
+
+    let source_len = store
+        .load(&source_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?
+        .messages
+        .len();
+    let merged = store
+        .merge(&source_id, &target_id)
+        .map_err(|e| format!("Failed to merge conversations: {}", e))?;
+
+    if let Err(e) = store.clear_draft(&source_id) {
+        eprintln!("Failed to clear draft of merged conversation: {}", e);
+    }
+    let bookmarks = app_handle.state::<Bookmarks>();
+    if let Err(e) = bookmarks.move_conversation(&source_id, &target_id, &merged.title) {
+        eprintln!("Failed to move bookmarks of merged conversation: {}", e);
+    }
+
+    let request = AgentRequest::MergeConversations {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id: target_id.clone(),
+        source_id: source_id.clone(),
+    };
+    let target_slot = state.slot(&target_agent);
+    let mut target_guard;
+    let target = if target_agent == source_agent {
+        &mut source
+    } else {
+        target_guard = target_slot.lock().await;
+        &mut target_guard
+    };
+    let sent = match target.as_mut() {
+        Some(process) => match process.send_request(&request, None).await {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Failed to send merge to the agent: {}", e);
+                false
+            }
+        },
+        None => false,
+    };
+    drop(source);
+    // Only the default agent has an outbox; a bound one loads the merged target when started
+    if !sent && target_agent == DEFAULT_AGENT_ID {
+        outbox::enqueue(&app_handle, request, None)
+            .map_err(|e| format!("Failed to queue merge for the agent: {}", e))?;
+    }
+    if source_agent != target_agent && source_agent != DEFAULT_AGENT_ID {
+        conversation_agents::release(&app_handle, &source_id).await;
+    }
+
+    let event = ConversationsMerged {
+        source_id: &source_id,
+        target_id: &target_id,
+    };
+    if let Err(e) = app_handle.emit_all("conversations_merged", event) {
+        eprintln!("Failed to emit conversations_merged: {}", e);
+    }
+
+    Ok(MergeSummary {
+        conversation_id: target_id,
+        merged_messages: source_len,
+        total_messages: merged.messages.len(),
+    })
+}
@@ -0,0 +1,361 @@
+use crate::clipboard;
+use crate::code_blocks;
+use crate::data_dir;
+use crate::redact;
+use crate::store::{ConversationStore, StoredMessage};
+use anyhow::{Context, Result};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use resvg::{tiny_skia, usvg};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+const WIDTH: f32 = 720.0;
+const PADDING: f32 = 32.0;
+const FONT_SIZE: f32 = 16.0;
+const CODE_FONT_SIZE: f32 = 14.0;
+const LINE_HEIGHT: f32 = 24.0;
+const BLOCK_SPACING: f32 = 12.0;
+// Rendered at 2x so the PNG stays crisp on high-DPI displays
+const SCALE: f32 = 2.0;
+
+pub enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    ListItem(String),
+    Code(String),
+}
+
+/// Renders a stored message to a PNG, saving it to `path` (or the exports directory)
+/// and optionally copying it to the clipboard. Returns the saved file path.
+/// Without `role`, a message that has been answered renders its reply. `path` is
+/// held to save_code_block's rules, and replaces an existing file only with `overwrite`.
+#[tauri::command]
+pub async fn render_message_image(
+    app_handle: AppHandle,
+    conversation_id: String,
+    message_id: String,
+    role: Option<String>,
+    path: Option<String>,
+    overwrite: Option<bool>,
+    copy_to_clipboard: Option<bool>,
+) -> Result<String, String> {
+    let store = app_handle.state::<ConversationStore>();
+    let conversation = store
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let message = match &role {
+        Some(role) => conversation.find_message(&message_id, role),
+        None => conversation.latest_message(&message_id),
+    }
+    .cloned()
+    .ok_or_else(|| format!("Message {} not found", message_id))?;
+    let message = match redact::for_export(&app_handle).map_err(|e| e.to_string())? {
+        Some(redactor) => redactor.message(&message),
+        None => message,
+    };
+
+    // Renders in the exports directory are replaced, as they are ours
+    let overwrite = path.is_none() || overwrite.unwrap_or(false);
+    let path = match path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            code_blocks::check_path(&path, overwrite).map_err(|e| e.to_string())?;
+            path
+        }
+        None => default_path(&app_handle, &conversation_id, &message_id, &message.role)
+            .ok_or_else(|| "No data directory available".to_string())?,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || -> Result<String> {
+        let png = render_png(&message)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create export directory")?;
+        }
+        code_blocks::open_for_save(&path, overwrite)
+            .and_then(|mut file| file.write_all(&png))
+            .context("Failed to write image")?;
+
+        if copy_to_clipboard.unwrap_or(false) {
+            clipboard::write_image(&png)?;
+        }
+
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| format!("Render task failed: {}", e))?
+    .map_err(|e| format!("Failed to render message: {}", e))
+}
+
+fn default_path(
+    app_handle: &AppHandle,
+    conversation_id: &str,
+    message_id: &str,
+    role: &str,
+) -> Option<PathBuf> {
+    let file_name: String = format!("{}-{}-{}.png", conversation_id, message_id, role)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    data_dir::resolve(app_handle).map(|dir| dir.join("exports").join(file_name))
+}
+
+pub fn render_png(message: &StoredMessage) -> Result<Vec<u8>> {
+    let svg = build_svg(message);
+
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+
+    let tree = usvg::Tree::from_str(&svg, &options).context("Failed to build image")?;
+    let size = tree.size().to_int_size();
+
+    let mut pixmap = tiny_skia::Pixmap::new(
+        (size.width() as f32 * SCALE) as u32,
+        (size.height() as f32 * SCALE) as u32,
+    )
+    .context("Image is too large")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(SCALE, SCALE),
+        &mut pixmap.as_mut(),
+    );
+
+    pixmap.encode_png().context("Failed to encode PNG")
+}
+
+pub fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut heading: Option<u8> = None;
+    let mut in_code = false;
+    let mut item_depth = 0;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => heading = Some(level as u8),
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::Start(Tag::Item) => {
+                // A nested list starts a new item; flush the parent's text first
+                if item_depth > 0 && !text.trim().is_empty() {
+                    blocks.push(Block::ListItem(std::mem::take(&mut text)));
+                }
+                item_depth += 1;
+            }
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::End(TagEnd::Heading(_)) => {
+                let level = heading.take().unwrap_or(1);
+                blocks.push(Block::Heading(level, std::mem::take(&mut text)));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code = false;
+                blocks.push(Block::Code(std::mem::take(&mut text)));
+            }
+            Event::End(TagEnd::Item) => {
+                item_depth -= 1;
+                if !text.trim().is_empty() {
+                    blocks.push(Block::ListItem(std::mem::take(&mut text)));
+                }
+            }
+            Event::End(TagEnd::Paragraph) if item_depth == 0 && !in_code => {
+                blocks.push(Block::Paragraph(std::mem::take(&mut text)));
+            }
+            Event::End(TagEnd::Paragraph) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    if !text.trim().is_empty() {
+        blocks.push(Block::Paragraph(text));
+    }
+
+    blocks
+}
+
+fn build_svg(message: &StoredMessage) -> String {
+    let content_width = WIDTH - PADDING * 2.0;
+    let mut body = String::new();
+    let mut y = PADDING;
+
+    let label = if message.role == "user" {
+        "You"
+    } else {
+        "Assistant"
+    };
+    body.push_str(&format!(
+        r##"<text x="{}" y="{}" font-family="sans-serif" font-size="13" font-weight="bold" fill="#6b7280">{}</text>"##,
+        PADDING,
+        y + 13.0,
+        label
+    ));
+    y += 13.0 + BLOCK_SPACING * 1.5;
+
+    for block in parse_blocks(&message.content) {
+        match block {
+            Block::Heading(level, text) => {
+                let size = FONT_SIZE + (4 - level.min(3)) as f32 * 3.0;
+                for line in wrap(&text, max_chars(content_width, size, 0.58)) {
+                    y += size * 1.4;
+                    body.push_str(&text_element(
+                        PADDING,
+                        y - size * 0.35,
+                        size,
+                        "bold",
+                        "sans-serif",
+                        &line,
+                    ));
+                }
+            }
+            Block::Paragraph(text) => {
+                for line in wrap(&text, max_chars(content_width, FONT_SIZE, 0.52)) {
+                    y += LINE_HEIGHT;
+                    body.push_str(&text_element(
+                        PADDING,
+                        y - 6.0,
+                        FONT_SIZE,
+                        "normal",
+                        "sans-serif",
+                        &line,
+                    ));
+                }
+            }
+            Block::ListItem(text) => {
+                let indent = 20.0;
+                let lines = wrap(&text, max_chars(content_width - indent, FONT_SIZE, 0.52));
+                for (i, line) in lines.iter().enumerate() {
+                    y += LINE_HEIGHT;
+                    if i == 0 {
+                        body.push_str(&text_element(
+                            PADDING + 4.0,
+                            y - 6.0,
+                            FONT_SIZE,
+                            "normal",
+                            "sans-serif",
+                            "•",
+                        ));
+                    }
+                    body.push_str(&text_element(
+                        PADDING + indent,
+                        y - 6.0,
+                        FONT_SIZE,
+                        "normal",
+                        "sans-serif",
+                        line,
+                    ));
+                }
+            }
+            Block::Code(text) => {
+                let code_line_height = CODE_FONT_SIZE * 1.5;
+                let max = max_chars(content_width - 24.0, CODE_FONT_SIZE, 0.6);
+                let lines: Vec<String> = text
+                    .trim_end_matches('\n')
+                    .lines()
+                    .flat_map(|line| hard_wrap(line, max))
+                    .collect();
+                let height = lines.len() as f32 * code_line_height + 24.0;
+
+                body.push_str(&format!(
+                    r##"<rect x="{}" y="{}" width="{}" height="{}" rx="6" fill="#f3f4f6"/>"##,
+                    PADDING,
+                    y + 4.0,
+                    content_width,
+                    height
+                ));
+                y += 16.0;
+                for line in &lines {
+                    y += code_line_height;
+                    body.push_str(&text_element(
+                        PADDING + 12.0,
+                        y - 6.0,
+                        CODE_FONT_SIZE,
+                        "normal",
+                        "monospace",
+                        line,
+                    ));
+                }
+                y += 12.0;
+            }
+        }
+        y += BLOCK_SPACING;
+    }
+
+    let height = y + PADDING;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}"><rect width="{w}" height="{h}" fill="#ffffff"/>{body}</svg>"##,
+        w = WIDTH,
+        h = height,
+        body = body
+    )
+}
+
+fn text_element(x: f32, y: f32, size: f32, weight: &str, family: &str, text: &str) -> String {
+    format!(
+        r##"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" fill="#111827" xml:space="preserve">{}</text>"##,
+        x,
+        y,
+        family,
+        size,
+        weight,
+        escape(text)
+    )
+}
+
+// Approximate glyph capacity of a line; there is no text shaping before layout
+pub fn max_chars(width: f32, font_size: f32, char_width_factor: f32) -> usize {
+    ((width / (font_size * char_width_factor)) as usize).max(10)
+}
+
+pub fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+
+            // Words longer than a whole line are broken hard
+            while line.chars().count() > max_chars {
+                let rest: String = line.chars().skip(max_chars).collect();
+                lines.push(line.chars().take(max_chars).collect());
+                line = rest;
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+pub fn hard_wrap(line: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+
+    chars
+        .chunks(max_chars)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,248 @@
+use crate::data_dir;
+use crate::store::{self, Conversation, ConversationStore};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// Older data layouts the shell knows how to import. There are none at the moment:
+/// ~/.claude/history.db is the agent's live database, not legacy data, and must never
+/// be imported and removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LegacySource {}
+
+impl LegacySource {
+    const ALL: &'static [LegacySource] = &[];
+
+    fn path(self) -> Option<PathBuf> {
+        match self {}
+    }
+
+    // Conversations in the source, converted to the store format
+    fn read(self) -> Result<Vec<Conversation>> {
+        match self {}
+    }
+}
+
+// What was imported from each source; persisted as migrations.json in the data dir
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MigrationLog {
+    completed: HashMap<LegacySource, MigrationRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MigrationRecord {
+    completed_at: i64,
+    // Conversations created by the import, removed again on revert
+    conversation_ids: Vec<String>,
+    // Set once the user confirmed and the originals were deleted
+    #[serde(default)]
+    confirmed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct PendingMigration {
+    pub source: LegacySource,
+    pub path: String,
+    // Migrated but awaiting confirmation before the originals are removed
+    pub awaiting_confirmation: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct MigrationProgress {
+    source: LegacySource,
+    #[ts(as = "f64")]
+    done: usize,
+    #[ts(as = "f64")]
+    total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum MigrationStatus {
+    Completed {
+        source: LegacySource,
+        #[ts(as = "f64")]
+        conversations: usize,
+    },
+    Failed {
+        source: LegacySource,
+        error: String,
+    },
+}
+
+/// Lists legacy data that hasn't been migrated, or whose originals are still kept.
+#[tauri::command]
+pub fn detect_migrations(app_handle: AppHandle) -> Vec<PendingMigration> {
+    let log = read_log(&app_handle);
+
+    LegacySource::ALL
+        .iter()
+        .filter_map(|&source| {
+            let path = source.path().filter(|path| path.exists())?;
+            let awaiting_confirmation = match log.completed.get(&source) {
+                Some(record) if record.confirmed => return None,
+                Some(_) => true,
+                None => false,
+            };
+
+            Some(PendingMigration {
+                source,
+                path: path.to_string_lossy().into_owned(),
+                awaiting_confirmation,
+            })
+        })
+        .collect()
+}
+
+/// Imports `source` into the conversation store. Progress arrives as
+/// `migration_progress` events and the outcome as a `migration_status` event.
+/// The original files are left in place until `confirm_migration`.
+#[tauri::command]
+pub async fn run_migration(app_handle: AppHandle, source: LegacySource) -> Result<usize, String> {
+    if read_log(&app_handle).completed.contains_key(&source) {
+        return Err("Already migrated".to_string());
+    }
+
+    let app_handle_clone = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || migrate(&app_handle_clone, source))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()));
+
+    let status = match &result {
+        Ok(conversations) => MigrationStatus::Completed {
+            source,
+            conversations: *conversations,
+        },
+        Err(error) => MigrationStatus::Failed {
+            source,
+            error: error.clone(),
+        },
+    };
+    if let Err(e) = app_handle.emit_all("migration_status", status) {
+        eprintln!("Failed to emit migration status: {}", e);
+    }
+
+    result.map_err(|e| format!("Migration failed: {}", e))
+}
+
+/// Deletes the originals of a completed migration.
+#[tauri::command]
+pub fn confirm_migration(app_handle: AppHandle, source: LegacySource) -> Result<(), String> {
+    let mut log = read_log(&app_handle);
+    let record = log
+        .completed
+        .get_mut(&source)
+        .ok_or_else(|| "Nothing to confirm".to_string())?;
+
+    if let Some(path) = source.path().filter(|path| path.exists()) {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {:?}: {}", path, e))?;
+    }
+
+    record.confirmed = true;
+    write_log(&app_handle, &log).map_err(|e| e.to_string())
+}
+
+/// Removes the conversations a migration created so it can be run again.
+#[tauri::command]
+pub fn revert_migration(app_handle: AppHandle, source: LegacySource) -> Result<(), String> {
+    let mut log = read_log(&app_handle);
+    let record = match log.completed.get(&source) {
+        Some(record) if !record.confirmed => record,
+        Some(_) => return Err("Originals were already removed".to_string()),
+        None => return Err("Nothing to revert".to_string()),
+    };
+
+    let store = app_handle.state::<ConversationStore>();
+    for conversation_id in &record.conversation_ids {
+        if let Err(e) = store.delete(conversation_id) {
+            eprintln!(
+                "Failed to remove migrated conversation {}: {}",
+                conversation_id, e
+            );
+        }
+    }
+
+    log.completed.remove(&source);
+    write_log(&app_handle, &log).map_err(|e| e.to_string())
+}
+
+fn migrate(app_handle: &AppHandle, source: LegacySource) -> Result<usize> {
+    let conversations = source.read()?;
+    let store = app_handle.state::<ConversationStore>();
+    let total = conversations.len();
+    let mut created = Vec::new();
+
+    for (done, conversation) in conversations.into_iter().enumerate() {
+        // Conversations the shell already recorded keep their messages; only missing ones are added
+        match store.load(&conversation.id) {
+            Ok(mut existing) => {
+                let known: HashSet<String> =
+                    existing.messages.iter().map(|m| m.id.clone()).collect();
+                existing.messages.extend(
+                    conversation
+                        .messages
+                        .into_iter()
+                        .filter(|m| !known.contains(&m.id)),
+                );
+                existing.messages.sort_by_key(|m| m.timestamp);
+                store.save(&existing)?;
+            }
+            Err(_) => {
+                store.save(&conversation)?;
+                created.push(conversation.id);
+            }
+        }
+
+        let progress = MigrationProgress {
+            source,
+            done: done + 1,
+            total,
+        };
+        if let Err(e) = app_handle.emit_all("migration_progress", progress) {
+            eprintln!("Failed to emit migration progress: {}", e);
+        }
+    }
+
+    let mut log = read_log(app_handle);
+    log.completed.insert(
+        source,
+        MigrationRecord {
+            completed_at: store::now_millis(),
+            conversation_ids: created,
+            confirmed: false,
+        },
+    );
+    write_log(app_handle, &log)?;
+
+    Ok(total)
+}
+
+fn log_path(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("migrations.json"))
+}
+
+fn read_log(app_handle: &AppHandle) -> MigrationLog {
+    log_path(app_handle)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn write_log(app_handle: &AppHandle, log: &MigrationLog) -> Result<()> {
+    let path = log_path(app_handle).context("No data directory available")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(log)?;
+    std::fs::write(path, json).context("Failed to record migration")
+}
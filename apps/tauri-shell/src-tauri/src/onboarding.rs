@@ -0,0 +1,532 @@
+use crate::agent_ipc;
+use crate::agent_runtime;
+use crate::agent_updates;
+use crate::network_config;
+use crate::secrets;
+use crate::settings::SettingsStore;
+use crate::shortcut;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+const MODELS_URL: &str = "https://api.anthropic.com/v1/models";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+// How often permissions are re-read while the user may be changing them
+const PERMISSION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// How long after a request or opening System Settings permissions are watched
+const PERMISSION_WATCH: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    #[default]
+    AgentDetection,
+    ApiKey,
+    Permissions,
+    Shortcut,
+    Complete,
+}
+
+impl OnboardingStep {
+    fn next(self) -> Self {
+        match self {
+            OnboardingStep::AgentDetection => OnboardingStep::ApiKey,
+            OnboardingStep::ApiKey => OnboardingStep::Permissions,
+            OnboardingStep::Permissions => OnboardingStep::Shortcut,
+            OnboardingStep::Shortcut | OnboardingStep::Complete => OnboardingStep::Complete,
+        }
+    }
+}
+
+/// Setup wizard progress, persisted in settings so it survives restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnboardingState {
+    pub step: OnboardingStep,
+    // Steps the user chose to finish later
+    pub skipped: Vec<OnboardingStep>,
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Accessibility,
+    ScreenRecording,
+    Microphone,
+    Notifications,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    NotDetermined,
+    // The platform has no such permission gate
+    NotRequired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct Permissions {
+    pub accessibility: PermissionState,
+    pub screen_recording: PermissionState,
+    pub microphone: PermissionState,
+    pub notifications: PermissionState,
+}
+
+impl Permissions {
+    // Notifications are optional, so onboarding doesn't wait for them
+    fn all_granted(&self) -> bool {
+        [self.accessibility, self.screen_recording, self.microphone]
+            .iter()
+            .all(|state| {
+                matches!(
+                    state,
+                    PermissionState::Granted | PermissionState::NotRequired
+                )
+            })
+    }
+}
+
+/// Until when permissions are polled for permissions_changed; None when not polling.
+#[derive(Default)]
+pub struct PermissionWatch {
+    until: Mutex<Option<Instant>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentSource {
+    // A managed build installed by agent_updates
+    Managed,
+    // The standalone executable bundled with the app
+    Sidecar,
+    // The agent-runtime sources next to the shell, run through tsx
+    Development,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct AgentDetection {
+    pub found: bool,
+    pub source: Option<AgentSource>,
+    pub version: Option<String>,
+    pub node_version: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_onboarding_state(settings: State<'_, SettingsStore>) -> OnboardingState {
+    settings.get().onboarding
+}
+
+#[tauri::command]
+pub async fn detect_agent(app_handle: AppHandle) -> AgentDetection {
+    detect(&app_handle).await
+}
+
+/// Validates the key against the Anthropic API before storing it for the agent.
+#[tauri::command]
+pub async fn set_api_key(app_handle: AppHandle, api_key: String) -> Result<(), String> {
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        return Err("API key is empty".to_string());
+    }
+
+    let response = network_config::client()
+        .get(MODELS_URL)
+        .header("x-api-key", &api_key)
+        .header("anthropic-version", ANTHROPIC_VERSION)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to validate API key: {}", e))?;
+
+    match response.status() {
+        status if status.is_success() => {}
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            return Err("API key was rejected".to_string());
+        }
+        status => return Err(format!("Failed to validate API key: HTTP {}", status)),
+    }
+
+    secrets::set(&app_handle, secrets::ANTHROPIC_API_KEY, &api_key)
+        .map_err(|e| format!("Failed to save API key: {}", e))
+}
+
+#[tauri::command]
+pub fn get_permissions() -> Permissions {
+    permissions()
+}
+
+/// Shows the system prompt for `permission`, or opens its Privacy pane if the
+/// user already answered it. Returns the state as of the call; permissions_changed
+/// follows when the user changes it.
+#[tauri::command]
+pub fn request_permission(app_handle: AppHandle, permission: Permission) -> PermissionState {
+    request(permission);
+    watch_permissions(&app_handle);
+    permission_state(permission)
+}
+
+/// Opens the System Settings pane where `permission` is granted, for features that
+/// found it missing. permissions_changed follows when the user changes it.
+#[tauri::command]
+pub fn open_permission_settings(
+    app_handle: AppHandle,
+    permission: Permission,
+) -> Result<(), String> {
+    open_settings(permission).map_err(|e| format!("Failed to open permission settings: {}", e))?;
+    watch_permissions(&app_handle);
+    Ok(())
+}
+
+/// Checks the current step for real and moves to the next one if it passes.
+#[tauri::command]
+pub async fn advance_onboarding(app_handle: AppHandle) -> Result<OnboardingState, String> {
+    let settings = app_handle.state::<SettingsStore>();
+    let step = settings.get().onboarding.step;
+
+    match step {
+        OnboardingStep::AgentDetection => {
+            if !detect(&app_handle).await.found {
+                return Err("Agent runtime not found".to_string());
+            }
+        }
+        OnboardingStep::ApiKey => {
+            let has_env_key = std::env::var(secrets::ANTHROPIC_API_KEY)
+                .map(|key| !key.is_empty())
+                .unwrap_or(false);
+            if !secrets::has(&app_handle, secrets::ANTHROPIC_API_KEY) && !has_env_key {
+                return Err("No API key configured".to_string());
+            }
+        }
+        OnboardingStep::Permissions => {
+            if !permissions().all_granted() {
+                return Err("Some permissions have not been granted".to_string());
+            }
+        }
+        OnboardingStep::Shortcut => {
+            if !shortcut::is_registered(&app_handle) {
+                return Err("Global shortcut is not registered".to_string());
+            }
+        }
+        OnboardingStep::Complete => {}
+    }
+
+    set_step(&settings, step.next(), None)
+}
+
+/// Permissions can be granted later from system settings; other steps can't be skipped.
+#[tauri::command]
+pub fn skip_onboarding_step(settings: State<'_, SettingsStore>) -> Result<OnboardingState, String> {
+    let step = settings.get().onboarding.step;
+    if step != OnboardingStep::Permissions {
+        return Err("This step can't be skipped".to_string());
+    }
+
+    set_step(&settings, step.next(), Some(step))
+}
+
+#[tauri::command]
+pub fn reset_onboarding(settings: State<'_, SettingsStore>) -> Result<(), String> {
+    settings
+        .update(|s| s.onboarding = OnboardingState::default())
+        .map_err(|e| format!("Failed to reset onboarding: {}", e))
+}
+
+fn set_step(
+    settings: &SettingsStore,
+    step: OnboardingStep,
+    skipped: Option<OnboardingStep>,
+) -> Result<OnboardingState, String> {
+    settings
+        .update(|s| {
+            s.onboarding.step = step;
+            s.onboarding.skipped.extend(skipped);
+            if step == OnboardingStep::Complete && s.onboarding.completed_at.is_none() {
+                s.onboarding.completed_at = Some(store::now_millis());
+            }
+        })
+        .map_err(|e| format!("Failed to save onboarding state: {}", e))?;
+
+    Ok(settings.get().onboarding)
+}
+
+pub async fn detect(app_handle: &AppHandle) -> AgentDetection {
+    let node_version = tokio::process::Command::new("node")
+        .arg("--version")
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+    let (source, version) = match agent_updates::active_bundle(app_handle) {
+        Some(bundle) => (Some(AgentSource::Managed), Some(bundle.version)),
+        None if agent_runtime::sidecar_available() => (Some(AgentSource::Sidecar), None),
+        None => {
+            let has_sources = agent_ipc::dev_runtime_dir()
+                .map(|dir| dir.join("src/index.ts").exists())
+                .unwrap_or(false);
+            (has_sources.then_some(AgentSource::Development), None)
+        }
+    };
+
+    AgentDetection {
+        // The sidecar embeds its own runtime
+        found: matches!(source, Some(AgentSource::Sidecar))
+            || (source.is_some() && node_version.is_some()),
+        source,
+        version,
+        node_version,
+    }
+}
+
+pub fn permissions() -> Permissions {
+    Permissions {
+        accessibility: permission_state(Permission::Accessibility),
+        screen_recording: permission_state(Permission::ScreenRecording),
+        microphone: permission_state(Permission::Microphone),
+        notifications: permission_state(Permission::Notifications),
+    }
+}
+
+// Polls permissions for the next PERMISSION_WATCH, emitting permissions_changed on
+// every change; a poll already running is extended
+fn watch_permissions(app_handle: &AppHandle) {
+    {
+        let watch = app_handle.state::<PermissionWatch>();
+        let mut until = watch.until.lock().unwrap();
+        let running = until.is_some();
+        *until = Some(Instant::now() + PERMISSION_WATCH);
+        if running {
+            return;
+        }
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last = tauri::async_runtime::spawn_blocking(permissions).await.ok();
+        loop {
+            tokio::time::sleep(PERMISSION_POLL_INTERVAL).await;
+            let Ok(current) = tauri::async_runtime::spawn_blocking(permissions).await else {
+                continue;
+            };
+            if last.as_ref() != Some(&current) {
+                if let Err(e) = app_handle.emit_all("permissions_changed", &current) {
+                    eprintln!("Failed to emit permissions_changed: {}", e);
+                }
+                last = Some(current);
+            }
+
+            let watch = app_handle.state::<PermissionWatch>();
+            let mut until = watch.until.lock().unwrap();
+            if until.map(|until| Instant::now() >= until).unwrap_or(true) {
+                *until = None;
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    static kAXTrustedCheckOptionPrompt: cocoa::base::id;
+    fn AXIsProcessTrusted() -> bool;
+    fn AXIsProcessTrustedWithOptions(options: cocoa::base::id) -> bool;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    static AVMediaTypeAudio: cocoa::base::id;
+}
+
+// Only linked, for UNUserNotificationCenter
+#[cfg(target_os = "macos")]
+#[link(name = "UserNotifications", kind = "framework")]
+extern "C" {}
+
+#[cfg(target_os = "macos")]
+fn permission_state(permission: Permission) -> PermissionState {
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let granted = |granted: bool| {
+        if granted {
+            PermissionState::Granted
+        } else {
+            PermissionState::Denied
+        }
+    };
+
+    unsafe {
+        match permission {
+            Permission::Accessibility => granted(AXIsProcessTrusted()),
+            Permission::ScreenRecording => granted(CGPreflightScreenCaptureAccess()),
+            Permission::Microphone => {
+                let status: i64 = msg_send![
+                    class!(AVCaptureDevice),
+                    authorizationStatusForMediaType: AVMediaTypeAudio
+                ];
+                // AVAuthorizationStatus: NotDetermined, Restricted, Denied, Authorized
+                match status {
+                    0 => PermissionState::NotDetermined,
+                    3 => PermissionState::Granted,
+                    _ => PermissionState::Denied,
+                }
+            }
+            Permission::Notifications => notification_state(),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn notification_state() -> PermissionState {
+    use block::ConcreteBlock;
+    use cocoa::base::id;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    if !has_bundle_identifier() {
+        return PermissionState::NotDetermined;
+    }
+    let center: id = msg_send![class!(UNUserNotificationCenter), currentNotificationCenter];
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handler = ConcreteBlock::new(move |settings: id| {
+        let status: i64 = msg_send![settings, authorizationStatus];
+        let _ = tx.send(status);
+    })
+    .copy();
+    let _: () = msg_send![center, getNotificationSettingsWithCompletionHandler: &*handler];
+
+    // UNAuthorizationStatus: NotDetermined, Denied, Authorized, Provisional, Ephemeral
+    match rx.recv_timeout(Duration::from_secs(2)) {
+        Ok(0) | Err(_) => PermissionState::NotDetermined,
+        Ok(1) => PermissionState::Denied,
+        Ok(_) => PermissionState::Granted,
+    }
+}
+
+// UNUserNotificationCenter raises outside an app bundle, e.g. under `cargo run`
+#[cfg(target_os = "macos")]
+unsafe fn has_bundle_identifier() -> bool {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let bundle: id = msg_send![class!(NSBundle), mainBundle];
+    let identifier: id = msg_send![bundle, bundleIdentifier];
+    identifier != nil
+}
+
+#[cfg(target_os = "macos")]
+fn open_settings(permission: Permission) -> std::io::Result<()> {
+    let url = match permission {
+        Permission::Accessibility => {
+            "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+        }
+        Permission::ScreenRecording => {
+            "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+        }
+        Permission::Microphone => {
+            "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"
+        }
+        Permission::Notifications => "x-apple.systempreferences:com.apple.preference.notifications",
+    };
+    open::that(url)
+}
+
+#[cfg(target_os = "macos")]
+fn request(permission: Permission) {
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil, YES};
+    use cocoa::foundation::NSDictionary;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let state = permission_state(permission);
+    if state == PermissionState::Granted {
+        return;
+    }
+
+    // The system only prompts once; after that the user has to flip the switch themselves
+    let open_pane = || {
+        if let Err(e) = open_settings(permission) {
+            eprintln!("Failed to open privacy settings: {}", e);
+        }
+    };
+
+    unsafe {
+        match permission {
+            Permission::Accessibility => {
+                let yes: id = msg_send![class!(NSNumber), numberWithBool: YES];
+                let options = NSDictionary::dictionaryWithObject_forKey_(
+                    nil,
+                    yes,
+                    kAXTrustedCheckOptionPrompt,
+                );
+                AXIsProcessTrustedWithOptions(options);
+            }
+            Permission::ScreenRecording => {
+                if !CGRequestScreenCaptureAccess() {
+                    open_pane();
+                }
+            }
+            Permission::Microphone if state == PermissionState::NotDetermined => {
+                // Needs NSMicrophoneUsageDescription from Info.plist, or macOS kills the app
+                let handler = ConcreteBlock::new(|_granted: bool| {}).copy();
+                let _: () = msg_send![
+                    class!(AVCaptureDevice),
+                    requestAccessForMediaType: AVMediaTypeAudio
+                    completionHandler: &*handler
+                ];
+            }
+            Permission::Microphone => open_pane(),
+            Permission::Notifications if state == PermissionState::NotDetermined => {
+                if !has_bundle_identifier() {
+                    return;
+                }
+                let center: id =
+                    msg_send![class!(UNUserNotificationCenter), currentNotificationCenter];
+                let handler = ConcreteBlock::new(|_granted: bool, _error: id| {}).copy();
+                // UNAuthorizationOptions: Badge | Sound | Alert
+                let options: u64 = 0b111;
+                let _: () = msg_send![
+                    center,
+                    requestAuthorizationWithOptions: options
+                    completionHandler: &*handler
+                ];
+            }
+            Permission::Notifications => open_pane(),
+        }
+    }
+}
+
+// Windows and Linux don't gate these behind per-app permissions
+#[cfg(not(target_os = "macos"))]
+fn permission_state(_permission: Permission) -> PermissionState {
+    PermissionState::NotRequired
+}
+
+#[cfg(not(target_os = "macos"))]
+fn request(_permission: Permission) {}
+
+#[cfg(not(target_os = "macos"))]
+fn open_settings(_permission: Permission) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "this platform doesn't gate it behind a per-app permission",
+    ))
+}
@@ -0,0 +1,223 @@
+use crate::agent_ipc::{self, AgentProcess, AgentRequest, MessageState, DEFAULT_AGENT_ID};
+use crate::conversation_lock;
+use crate::data_dir;
+use crate::store::{self, ConversationStore, DEFAULT_CONVERSATION_ID};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+// How long to wait for a freshly spawned agent before flushing anyway
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutboxItem {
+    pub request: AgentRequest,
+    // Window that composed the message, so the reply streams back to it
+    pub owner: Option<String>,
+    #[ts(as = "f64")]
+    pub queued_at: i64,
+}
+
+/// Messages composed while the agent couldn't take them or connectivity reported the
+/// machine offline, persisted as outbox.json in the app data directory until they are
+/// sent or discarded.
+pub struct Outbox {
+    path: Option<PathBuf>,
+    items: Mutex<Vec<OutboxItem>>,
+}
+
+impl Outbox {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = data_dir::resolve(app_handle).map(|dir| dir.join("outbox.json"));
+
+        let items = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Outbox {
+            path,
+            items: Mutex::new(items),
+        }
+    }
+
+    pub fn list(&self) -> Vec<OutboxItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn push(&self, request: AgentRequest, owner: Option<String>) -> Result<()> {
+        let mut items = self.items.lock().unwrap();
+        items.push(OutboxItem {
+            request,
+            owner,
+            queued_at: store::now_millis(),
+        });
+        self.save(&items)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let mut items = self.items.lock().unwrap();
+        let len = items.len();
+        items.retain(|item| item.request.id() != id);
+
+        if items.len() == len {
+            return Ok(false);
+        }
+        self.save(&items).map(|_| true)
+    }
+
+    fn take_all(&self) -> Vec<OutboxItem> {
+        let mut items = self.items.lock().unwrap();
+        let taken = std::mem::take(&mut *items);
+        if let Err(e) = self.save(&items) {
+            eprintln!("Failed to save outbox: {}", e);
+        }
+        taken
+    }
+
+    // Puts unsent items back in front of anything queued meanwhile
+    fn restore(&self, unsent: Vec<OutboxItem>) {
+        let mut items = self.items.lock().unwrap();
+        items.splice(0..0, unsent);
+        if let Err(e) = self.save(&items) {
+            eprintln!("Failed to save outbox: {}", e);
+        }
+    }
+
+    fn save(&self, items: &[OutboxItem]) -> Result<()> {
+        let path = self.path.as_ref().context("No data directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+
+        let json = serde_json::to_string(items).context("Failed to serialize outbox")?;
+        std::fs::write(path, json).context("Failed to write outbox")
+    }
+}
+
+#[tauri::command]
+pub fn list_outbox(outbox: State<'_, Outbox>) -> Vec<OutboxItem> {
+    outbox.list()
+}
+
+#[tauri::command]
+pub fn discard_outbox_item(app_handle: AppHandle, id: String) -> Result<(), String> {
+    let removed =
+        withdraw(&app_handle, &id).map_err(|e| format!("Failed to discard message: {}", e))?;
+
+    if !removed {
+        return Err(format!("No queued message with id {}", id));
+    }
+    Ok(())
+}
+
+/// Takes message `id` back out of the outbox and notifies the frontend. Returns
+/// whether it was queued.
+pub fn withdraw(app_handle: &AppHandle, id: &str) -> Result<bool> {
+    let removed = app_handle.state::<Outbox>().remove(id)?;
+    if removed {
+        publish(app_handle);
+    }
+    Ok(removed)
+}
+
+/// Queues a message the agent couldn't take and notifies the frontend.
+pub fn enqueue(app_handle: &AppHandle, request: AgentRequest, owner: Option<String>) -> Result<()> {
+    eprintln!("[OUTBOX] Queued {}", request.id());
+
+    if let AgentRequest::UserMessage { id, .. } = &request {
+        agent_ipc::emit_message_state(
+            app_handle,
+            DEFAULT_AGENT_ID,
+            owner.as_deref(),
+            id,
+            MessageState::Queued,
+            None,
+        );
+    }
+    app_handle.state::<Outbox>().push(request, owner)?;
+    publish(app_handle);
+    Ok(())
+}
+
+/// Sends queued messages once the agent in `agent` reports ready.
+pub fn flush_when_ready(
+    app_handle: AppHandle,
+    agent: Arc<tokio::sync::Mutex<Option<AgentProcess>>>,
+) {
+    tokio::spawn(async move {
+        let ready = agent
+            .lock()
+            .await
+            .as_ref()
+            .map(|process| process.ready_signal());
+        let Some(mut ready) = ready else {
+            return;
+        };
+        let _ = tokio::time::timeout(READY_TIMEOUT, ready.wait_for(|ready| *ready)).await;
+
+        let mut agent = agent.lock().await;
+        if let Some(process) = agent.as_mut() {
+            flush(&app_handle, process).await;
+        }
+    });
+}
+
+/// Sends every queued message in order, stopping at the first failure. Messages to
+/// conversations locked while they waited are failed instead.
+pub async fn flush(app_handle: &AppHandle, process: &mut AgentProcess) {
+    let outbox = app_handle.state::<Outbox>();
+    let items = outbox.take_all();
+    if items.is_empty() {
+        return;
+    }
+
+    let store = app_handle.state::<ConversationStore>();
+    for (index, item) in items.iter().enumerate() {
+        if let AgentRequest::UserMessage {
+            id,
+            conversation_id,
+            ..
+        } = &item.request
+        {
+            let conversation_id = conversation_id
+                .as_deref()
+                .unwrap_or(DEFAULT_CONVERSATION_ID);
+            if let Err(e) = conversation_lock::ensure_unlocked(&store, conversation_id) {
+                agent_ipc::emit_message_state(
+                    app_handle,
+                    DEFAULT_AGENT_ID,
+                    item.owner.as_deref(),
+                    id,
+                    MessageState::Failed,
+                    Some(&e),
+                );
+                continue;
+            }
+        }
+        if let Err(e) = process
+            .send_request(&item.request, item.owner.clone())
+            .await
+        {
+            eprintln!("Failed to send queued message {}: {}", item.request.id(), e);
+            process.forget(item.request.id()).await;
+            outbox.restore(items[index..].to_vec());
+            break;
+        }
+    }
+
+    publish(app_handle);
+}
+
+fn publish(app_handle: &AppHandle) {
+    let items = app_handle.state::<Outbox>().list();
+    if let Err(e) = app_handle.emit_all("outbox_changed", items) {
+        eprintln!("Failed to emit outbox: {}", e);
+    }
+}
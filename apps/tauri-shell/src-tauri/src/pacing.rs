@@ -0,0 +1,70 @@
+use crate::agent_backend::AgentStdin;
+use crate::agent_ipc::{write_request, AgentRequest};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use ts_rs::TS;
+
+// Give up after this many rate-limit retries for the same request
+pub const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+// Used when the agent reports a rate limit without a retry-after hint
+const DEFAULT_RETRY_AFTER_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct RetryCountdown {
+    pub id: String,
+    pub attempt: u32,
+    #[ts(as = "f64")]
+    pub remaining_secs: u64,
+}
+
+/// How long schedule_retry waits before resending.
+pub fn retry_delay(retry_after_ms: Option<u64>) -> Duration {
+    Duration::from_millis(retry_after_ms.unwrap_or(DEFAULT_RETRY_AFTER_MS))
+}
+
+/// Re-dispatches a rate-limited request after the delay requested by the agent,
+/// emitting a `request_retry` countdown event every second until it is resent.
+pub fn schedule_retry(
+    app_handle: AppHandle,
+    stdin: Arc<Mutex<AgentStdin>>,
+    request: AgentRequest,
+    // Window that sent the request; only it sees the countdown
+    owner: Option<String>,
+    attempt: u32,
+    retry_after_ms: Option<u64>,
+) {
+    let delay = retry_delay(retry_after_ms);
+
+    tokio::spawn(async move {
+        let mut remaining_secs = (delay.as_millis() as u64).div_ceil(1000);
+
+        while remaining_secs > 0 {
+            let countdown = RetryCountdown {
+                id: request.id().to_string(),
+                attempt,
+                remaining_secs,
+            };
+            let result = match &owner {
+                Some(label) => app_handle.emit_to(label, "request_retry", &countdown),
+                None => app_handle.emit_all("request_retry", &countdown),
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to emit retry countdown: {}", e);
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            remaining_secs -= 1;
+        }
+
+        eprintln!("[PACING] Retrying {} (attempt {})", request.id(), attempt);
+
+        if let Err(e) = write_request(&stdin, &request).await {
+            eprintln!("Failed to resend rate-limited request {}: {}", request.id(), e);
+        }
+    });
+}
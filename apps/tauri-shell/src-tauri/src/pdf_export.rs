@@ -0,0 +1,403 @@
+use crate::annotate::ImageAttachment;
+use crate::message_image::{self, Block};
+use crate::redact;
+use crate::store::{Conversation, ConversationStore, StoredMessage};
+use crate::time_format::{self, TimestampStyle};
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref};
+use resvg::usvg;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 48.0;
+// Space reserved above and below the content for the running header and page number
+const HEADER_HEIGHT: f32 = 24.0;
+const FOOTER_HEIGHT: f32 = 20.0;
+const FONT_SIZE: f32 = 10.5;
+const CODE_FONT_SIZE: f32 = 9.0;
+const CODE_PADDING: f32 = 6.0;
+const BLOCK_SPACING: f32 = 8.0;
+const MAX_IMAGE_HEIGHT: f32 = 320.0;
+
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - MARGIN * 2.0;
+const CONTENT_BOTTOM: f32 = PAGE_HEIGHT - MARGIN - FOOTER_HEIGHT;
+
+/// One line of laid-out content. Rows are never split across pages.
+struct Row {
+    height: f32,
+    kind: RowKind,
+}
+
+enum RowKind {
+    Text {
+        indent: f32,
+        size: f32,
+        weight: &'static str,
+        color: &'static str,
+        text: String,
+    },
+    // One line of a code block; the first and last carry the block's padding
+    Code {
+        text: String,
+        top_padding: f32,
+    },
+    Image {
+        href: String,
+        width: f32,
+    },
+    Gap,
+}
+
+/// Renders the conversation to a paginated A4 PDF with selectable text, code blocks
+/// and attached images. Returns the path written.
+#[tauri::command]
+pub async fn export_conversation_pdf(
+    app_handle: AppHandle,
+    conversation_id: String,
+    path: String,
+) -> Result<String, String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let conversation = match redact::for_export(&app_handle).map_err(|e| e.to_string())? {
+        Some(redactor) => redactor.conversation(&conversation),
+        None => conversation,
+    };
+    let path = PathBuf::from(path);
+
+    tauri::async_runtime::spawn_blocking(move || -> Result<String> {
+        let pdf = render_pdf(&conversation)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create export directory")?;
+        }
+        std::fs::write(&path, pdf).context("Failed to write PDF")?;
+        Ok(path.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Failed to export conversation: {}", e))
+}
+
+fn render_pdf(conversation: &Conversation) -> Result<Vec<u8>> {
+    let rows: Vec<Row> = conversation
+        .messages
+        .iter()
+        .flat_map(message_rows)
+        .collect();
+    let pages = paginate(rows);
+
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let date = time_format::format(conversation.updated_at, TimestampStyle::DateTime);
+
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let page_tree_id = alloc.bump();
+    let mut pdf = Pdf::new();
+    let mut page_ids = Vec::new();
+
+    for (number, page) in pages.iter().enumerate() {
+        let svg = page_svg(page, &conversation.title, &date, number + 1, pages.len());
+        let tree = usvg::Tree::from_str(&svg, &options).context("Failed to lay out page")?;
+        let (chunk, svg_ref) = svg2pdf::to_chunk(&tree, svg2pdf::ConversionOptions::default())
+            .map_err(|e| anyhow!("Failed to convert page: {:?}", e))?;
+
+        // Each page's chunk numbers its objects from 1; move them into our range
+        let mut renumbered = HashMap::new();
+        let chunk = chunk.renumber(|old| *renumbered.entry(old).or_insert_with(|| alloc.bump()));
+        let svg_ref = renumbered[&svg_ref];
+
+        let page_id = alloc.bump();
+        let content_id = alloc.bump();
+        page_ids.push(page_id);
+
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        page.resources().x_objects().pair(Name(b"P"), svg_ref);
+        page.finish();
+
+        // The converted SVG is a unit-square XObject, scaled up to the page
+        let mut content = Content::new();
+        content.transform([PAGE_WIDTH, 0.0, 0.0, PAGE_HEIGHT, 0.0, 0.0]);
+        content.x_object(Name(b"P"));
+        pdf.stream(content_id, &content.finish());
+        pdf.extend(&chunk);
+    }
+
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    Ok(pdf.finish())
+}
+
+fn message_rows(message: &StoredMessage) -> Vec<Row> {
+    let text_row =
+        |indent: f32, size: f32, weight: &'static str, color: &'static str, text: String| Row {
+            height: size * 1.45,
+            kind: RowKind::Text {
+                indent,
+                size,
+                weight,
+                color,
+                text,
+            },
+        };
+    let gap = |height: f32| Row {
+        height,
+        kind: RowKind::Gap,
+    };
+
+    let label = if message.role == "user" {
+        "You"
+    } else {
+        "Assistant"
+    };
+    let time = time_format::format(message.timestamp, TimestampStyle::Time);
+    let mut rows = vec![text_row(
+        0.0,
+        8.5,
+        "bold",
+        "#6b7280",
+        format!("{} · {}", label, time),
+    )];
+
+    for block in message_image::parse_blocks(&message.content) {
+        match block {
+            Block::Heading(level, text) => {
+                let size = FONT_SIZE + (4 - level.min(3)) as f32 * 2.0;
+                let max = message_image::max_chars(CONTENT_WIDTH, size, 0.58);
+                for line in message_image::wrap(&text, max) {
+                    rows.push(text_row(0.0, size, "bold", "#111827", line));
+                }
+            }
+            Block::Paragraph(text) => {
+                let max = message_image::max_chars(CONTENT_WIDTH, FONT_SIZE, 0.52);
+                for line in message_image::wrap(&text, max) {
+                    rows.push(text_row(0.0, FONT_SIZE, "normal", "#111827", line));
+                }
+            }
+            Block::ListItem(text) => {
+                let indent = 14.0;
+                let max = message_image::max_chars(CONTENT_WIDTH - indent, FONT_SIZE, 0.52);
+                for (i, line) in message_image::wrap(&text, max).into_iter().enumerate() {
+                    let line = if i == 0 {
+                        format!("•\u{2002}{}", line)
+                    } else {
+                        line
+                    };
+                    rows.push(text_row(indent / 2.0, FONT_SIZE, "normal", "#111827", line));
+                }
+            }
+            Block::Code(text) => {
+                let max = message_image::max_chars(
+                    CONTENT_WIDTH - CODE_PADDING * 2.0,
+                    CODE_FONT_SIZE,
+                    0.6,
+                );
+                let lines: Vec<String> = text
+                    .trim_end_matches('\n')
+                    .lines()
+                    .flat_map(|line| message_image::hard_wrap(line, max))
+                    .collect();
+                let count = lines.len();
+                for (i, line) in lines.into_iter().enumerate() {
+                    let top_padding = if i == 0 { CODE_PADDING } else { 0.0 };
+                    let bottom_padding = if i + 1 == count { CODE_PADDING } else { 0.0 };
+                    rows.push(Row {
+                        height: CODE_FONT_SIZE * 1.45 + top_padding + bottom_padding,
+                        kind: RowKind::Code {
+                            text: line,
+                            top_padding,
+                        },
+                    });
+                }
+            }
+        }
+        rows.push(gap(BLOCK_SPACING / 2.0));
+    }
+
+    for image in attachments(message) {
+        rows.push(image);
+        rows.push(gap(BLOCK_SPACING / 2.0));
+    }
+    rows.push(gap(BLOCK_SPACING * 2.0));
+    rows
+}
+
+// Attached images, scaled to fit the content width and MAX_IMAGE_HEIGHT
+fn attachments(message: &StoredMessage) -> Vec<Row> {
+    let Some(images) = message.images.as_deref() else {
+        return Vec::new();
+    };
+    let images: Vec<ImageAttachment> = match serde_json::from_str(images) {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("Skipping attachments of {} in PDF: {}", message.id, e);
+            return Vec::new();
+        }
+    };
+
+    images
+        .into_iter()
+        .filter_map(|attachment| {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(&attachment.data)
+                .ok()?;
+            let (width, height) = image::io::Reader::new(std::io::Cursor::new(&bytes))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()?;
+
+            let scale = (CONTENT_WIDTH / width as f32)
+                .min(MAX_IMAGE_HEIGHT / height as f32)
+                .min(1.0);
+            Some(Row {
+                height: height as f32 * scale,
+                kind: RowKind::Image {
+                    href: format!("data:{};base64,{}", attachment.mime_type, attachment.data),
+                    width: width as f32 * scale,
+                },
+            })
+        })
+        .collect()
+}
+
+fn paginate(rows: Vec<Row>) -> Vec<Vec<Row>> {
+    let mut pages = vec![Vec::new()];
+    let mut y = MARGIN + HEADER_HEIGHT;
+
+    for row in rows {
+        if y + row.height > CONTENT_BOTTOM && !pages.last().unwrap().is_empty() {
+            pages.push(Vec::new());
+            y = MARGIN + HEADER_HEIGHT;
+        }
+        let page = pages.last_mut().unwrap();
+        // Spacing at the top of a page only pushes content down
+        if page.is_empty() && matches!(row.kind, RowKind::Gap) {
+            continue;
+        }
+        y += row.height;
+        page.push(row);
+    }
+    pages
+}
+
+fn page_svg(rows: &[Row], title: &str, date: &str, number: usize, total: usize) -> String {
+    let mut body = String::new();
+    let header = |x: f32, anchor: &str, text: &str| {
+        format!(
+            r##"<text x="{}" y="{}" font-family="sans-serif" font-size="8" fill="#6b7280" text-anchor="{}">{}</text>"##,
+            x,
+            MARGIN,
+            anchor,
+            escape(text)
+        )
+    };
+    body.push_str(&header(MARGIN, "start", title));
+    body.push_str(&header(PAGE_WIDTH - MARGIN, "end", date));
+    body.push_str(&format!(
+        r##"<line x1="{m}" y1="{y}" x2="{r}" y2="{y}" stroke="#d1d5db" stroke-width="0.5"/>"##,
+        m = MARGIN,
+        r = PAGE_WIDTH - MARGIN,
+        y = MARGIN + 6.0
+    ));
+    body.push_str(&format!(
+        r##"<text x="{}" y="{}" font-family="sans-serif" font-size="8" fill="#6b7280" text-anchor="middle">{} / {}</text>"##,
+        PAGE_WIDTH / 2.0,
+        PAGE_HEIGHT - MARGIN,
+        number,
+        total
+    ));
+
+    let mut y = MARGIN + HEADER_HEIGHT;
+    for row in rows {
+        match &row.kind {
+            RowKind::Text {
+                indent,
+                size,
+                weight,
+                color,
+                text,
+            } => body.push_str(&text_element(
+                MARGIN + indent,
+                y + size * 1.1,
+                *size,
+                weight,
+                "sans-serif",
+                color,
+                text,
+            )),
+            RowKind::Code { text, top_padding } => {
+                body.push_str(&format!(
+                    r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#f3f4f6"/>"##,
+                    MARGIN,
+                    y,
+                    CONTENT_WIDTH,
+                    // Overlap the next row so adjacent lines don't show a seam
+                    row.height + 0.5
+                ));
+                body.push_str(&text_element(
+                    MARGIN + CODE_PADDING,
+                    y + top_padding + CODE_FONT_SIZE * 1.1,
+                    CODE_FONT_SIZE,
+                    "normal",
+                    "monospace",
+                    "#111827",
+                    text,
+                ));
+            }
+            RowKind::Image { href, width } => body.push_str(&format!(
+                r##"<image x="{}" y="{}" width="{}" height="{}" href="{}"/>"##,
+                MARGIN, y, width, row.height, href
+            )),
+            RowKind::Gap => {}
+        }
+        y += row.height;
+    }
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">{body}</svg>"##,
+        w = PAGE_WIDTH,
+        h = PAGE_HEIGHT,
+        body = body
+    )
+}
+
+fn text_element(
+    x: f32,
+    y: f32,
+    size: f32,
+    weight: &str,
+    family: &str,
+    fill: &str,
+    text: &str,
+) -> String {
+    format!(
+        r##"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{}" fill="{}" xml:space="preserve">{}</text>"##,
+        x,
+        y,
+        family,
+        size,
+        weight,
+        fill,
+        escape(text)
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,159 @@
+use crate::highlight;
+use crate::i18n;
+use crate::redact;
+use crate::store::{Conversation, ConversationStore};
+use crate::time_format::{self, TimestampStyle};
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use tauri::{AppHandle, Manager, Window, WindowBuilder, WindowUrl};
+
+// Windows opened for printing use this label prefix so page-load can trigger the dialog
+pub const PRINT_WINDOW_PREFIX: &str = "print-";
+
+// Name prefix of the pages written to the temp directory for printing
+pub const PRINT_FILE_PREFIX: &str = "asst-print-";
+
+const PRINT_STYLES: &str = r#"
+@page { margin: 20mm 16mm; }
+body { font-family: -apple-system, "Segoe UI", Roboto, sans-serif; font-size: 11pt; line-height: 1.5; color: #111; margin: 0; padding-top: 40px; }
+header { position: fixed; top: 0; left: 0; right: 0; display: flex; justify-content: space-between; font-size: 9pt; color: #666; border-bottom: 1px solid #ccc; padding-bottom: 4px; }
+.message { margin: 0 0 16px; page-break-inside: avoid; }
+.role { font-weight: 600; font-size: 9pt; text-transform: uppercase; color: #555; margin-bottom: 4px; }
+.user .content { background: #f3f4f6; padding: 8px 12px; border-radius: 6px; }
+pre { background: #f6f8fa; padding: 8px 12px; border-radius: 4px; white-space: pre-wrap; word-wrap: break-word; font-size: 9pt; }
+pre, code { font-family: "SF Mono", Menlo, Consolas, monospace; }
+img { max-width: 100%; }
+@media screen { body { padding: 40px 32px; } header { position: static; margin-bottom: 24px; } }
+"#;
+
+/// Renders the conversation to print-optimized HTML and opens it in a window that
+/// immediately shows the OS print dialog.
+#[tauri::command]
+pub async fn print_conversation(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<(), String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let conversation = match redact::for_export(&app_handle).map_err(|e| e.to_string())? {
+        Some(redactor) => redactor.conversation(&conversation),
+        None => conversation,
+    };
+
+    let path = std::env::temp_dir().join(format!("{}{}.html", PRINT_FILE_PREFIX, conversation_id));
+    std::fs::write(&path, conversation_html(&conversation))
+        .map_err(|e| format!("Failed to write print file: {}", e))?;
+
+    let url =
+        tauri::Url::from_file_path(&path).map_err(|_| "Invalid print file path".to_string())?;
+    let label = format!("{}{}", PRINT_WINDOW_PREFIX, uuid::Uuid::new_v4().simple());
+
+    WindowBuilder::new(&app_handle, label, WindowUrl::External(url))
+        .title(i18n::t_args(
+            "window-print-title",
+            &[("title", conversation.title.clone().into())],
+        ))
+        .inner_size(720.0, 900.0)
+        .build()
+        .map_err(|e| format!("Failed to open print window: {}", e))?;
+
+    Ok(())
+}
+
+/// Shows the print dialog once a print window has loaded its document.
+pub fn on_page_load(window: &Window) {
+    if !window.label().starts_with(PRINT_WINDOW_PREFIX) {
+        return;
+    }
+
+    if let Err(e) = window.print() {
+        eprintln!("Failed to open print dialog: {}", e);
+    }
+}
+
+/// Standalone HTML document for a conversation, shared by print and export paths.
+pub fn conversation_html(conversation: &Conversation) -> String {
+    let mut body = String::new();
+
+    for message in &conversation.messages {
+        let role = if message.role == "user" {
+            "You"
+        } else {
+            "Assistant"
+        };
+
+        let content = markdown_html(&message.content);
+
+        body.push_str(&format!(
+            r#"<section class="message {}"><div class="role">{}</div><div class="content">{}</div></section>"#,
+            escape(&message.role),
+            role,
+            content
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>{styles}</style>
+</head>
+<body>
+<header><span>{title}</span><span>{date}</span></header>
+<main>{body}</main>
+</body>
+</html>"#,
+        title = escape(&conversation.title),
+        styles = PRINT_STYLES,
+        body = body,
+        date = escape(&time_format::format(
+            conversation.updated_at,
+            TimestampStyle::DateTime
+        ))
+    )
+}
+
+// Markdown to HTML with code blocks pre-highlighted, so no script is needed to color them
+fn markdown_html(markdown: &str) -> String {
+    let mut events = Vec::new();
+    // Language and text of the code block being collected
+    let mut code: Option<(Option<String>, String)> = None;
+
+    for event in Parser::new_ext(markdown, Options::ENABLE_TABLES) {
+        match (event, &mut code) {
+            (Event::Start(Tag::CodeBlock(kind)), None) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().map(str::to_string)
+                    }
+                    CodeBlockKind::Indented => None,
+                };
+                code = Some((lang, String::new()));
+            }
+            (Event::Text(text), Some((_, buffer))) => buffer.push_str(&text),
+            (Event::End(TagEnd::CodeBlock), Some(_)) => {
+                let (lang, text) = code.take().unwrap_or_default();
+                let html = highlight::html(&text, lang.as_deref(), highlight::PRINT_THEME)
+                    .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", escape(&text)));
+                events.push(Event::Html(html.into()));
+            }
+            // Raw HTML from the conversation is shown as text, never rendered
+            (Event::Html(raw) | Event::InlineHtml(raw), None) => events.push(Event::Text(raw)),
+            (event, _) => events.push(event),
+        }
+    }
+
+    let mut content = String::new();
+    html::push_html(&mut content, events.into_iter());
+    content
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
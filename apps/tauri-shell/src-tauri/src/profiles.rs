@@ -0,0 +1,207 @@
+use crate::i18n;
+use crate::settings::SettingsStore;
+use crate::shutdown;
+use anyhow::{Context, Result};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use tauri::{AppHandle, CustomMenuItem, Icon, Manager, State, SystemTrayMenu, SystemTraySubmenu};
+
+/// Tray menu ids for profiles; the default profile's id has an empty name.
+pub const TRAY_PREFIX: &str = "profile:";
+
+/// The tray icon as bundled, before any badge is drawn on it.
+pub const TRAY_ICON: &[u8] = include_bytes!("../icons/icon.png");
+
+// Badge colors for named profiles, picked by hashing the name
+const BADGE_COLORS: &[[u8; 3]] = &[
+    [0xe5, 0x48, 0x4d],
+    [0x30, 0xa4, 0x6c],
+    [0x00, 0x90, 0xff],
+    [0xf7, 0x6b, 0x15],
+    [0x8e, 0x4e, 0xc6],
+    [0x12, 0xa5, 0x94],
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    // None is the default profile (settings.json)
+    pub name: Option<String>,
+    pub active: bool,
+}
+
+/// Profile names select settings-<name>.json, so they're limited to file-safe characters.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[tauri::command]
+pub fn list_profiles(app_handle: AppHandle) -> Vec<Profile> {
+    profiles(&app_handle)
+}
+
+/// Relaunches the app under `name` (the default profile when None). Every store keeps
+/// its paths for the app's lifetime, so switching can't happen in place.
+#[tauri::command]
+pub fn switch_profile(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    name: Option<String>,
+) -> Result<(), String> {
+    if name.as_deref() == settings.profile() {
+        return Ok(());
+    }
+    if let Some(name) = &name {
+        if !valid_name(name) {
+            return Err(format!("Invalid profile name: {}", name));
+        }
+    }
+
+    relaunch(&app_handle, name.as_deref()).map_err(|e| format!("Failed to switch profile: {}", e))
+}
+
+/// Profiles as a tray submenu with the active one checked, or None while only the
+/// default profile exists.
+pub fn tray_submenu(app_handle: &AppHandle) -> Option<SystemTraySubmenu> {
+    let profiles = profiles(app_handle);
+    if profiles.len() < 2 {
+        return None;
+    }
+
+    let menu = profiles
+        .iter()
+        .fold(SystemTrayMenu::new(), |menu, profile| {
+            let title = match &profile.name {
+                Some(name) => name.clone(),
+                None => i18n::t("tray-profile-default"),
+            };
+            let id = format!("{}{}", TRAY_PREFIX, profile.name.as_deref().unwrap_or(""));
+            let item = CustomMenuItem::new(id, title);
+            menu.add_item(if profile.active {
+                item.selected()
+            } else {
+                item
+            })
+        });
+    Some(SystemTraySubmenu::new(i18n::t("tray-profiles"), menu))
+}
+
+/// Handles a click on a profile in the tray menu; `name` follows TRAY_PREFIX.
+pub fn on_tray_click(app_handle: &AppHandle, name: &str) {
+    let name = Some(name)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    let settings = app_handle.state::<SettingsStore>();
+    if let Err(e) = switch_profile(app_handle.clone(), settings, name) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Marks the tray icon with a colored badge while a named profile is active, so it's
+/// clear at a glance which data set is live.
+pub fn apply_tray_icon(app_handle: &AppHandle) {
+    let Some(name) = app_handle
+        .state::<SettingsStore>()
+        .profile()
+        .map(str::to_string)
+    else {
+        return;
+    };
+    let icon = match badged_icon(&name) {
+        Ok(icon) => icon,
+        Err(e) => {
+            eprintln!("Failed to draw profile tray icon: {}", e);
+            return;
+        }
+    };
+
+    let tray = app_handle.tray_handle();
+    // Template icons are drawn monochrome, which would hide the badge
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(false);
+    if let Err(e) = tray.set_icon(icon) {
+        eprintln!("Failed to set profile tray icon: {}", e);
+    }
+}
+
+// The default profile first, then named profiles alphabetically
+fn profiles(app_handle: &AppHandle) -> Vec<Profile> {
+    let active = app_handle
+        .state::<SettingsStore>()
+        .profile()
+        .map(str::to_string);
+
+    let mut names: Vec<String> = app_handle
+        .path_resolver()
+        .app_config_dir()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            let name = file_name.strip_prefix("settings-")?.strip_suffix(".json")?;
+            valid_name(name).then(|| name.to_string())
+        })
+        .collect();
+    // The active profile may not have saved anything yet
+    if let Some(active) = &active {
+        if !names.contains(active) {
+            names.push(active.clone());
+        }
+    }
+    names.sort();
+
+    std::iter::once(None)
+        .chain(names.into_iter().map(Some))
+        .map(|name| Profile {
+            active: name == active,
+            name,
+        })
+        .collect()
+}
+
+fn relaunch(app_handle: &AppHandle, name: Option<&str>) -> Result<()> {
+    let binary = std::env::current_exe().context("Failed to locate the app binary")?;
+    let mut command = std::process::Command::new(binary);
+    if let Some(name) = name {
+        command.args(["--profile", name]);
+    }
+
+    command.spawn().context("Failed to launch the app")?;
+    eprintln!(
+        "[PROFILE] Switching to {}",
+        name.unwrap_or("the default profile")
+    );
+    shutdown::quit(app_handle);
+    Ok(())
+}
+
+fn badged_icon(name: &str) -> Result<Icon> {
+    let mut icon: RgbaImage = image::load_from_memory(TRAY_ICON)
+        .context("Failed to decode tray icon")?
+        .into_rgba8();
+
+    let hash = name.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    let [r, g, b] = BADGE_COLORS[hash % BADGE_COLORS.len()];
+
+    // Filled circle in the bottom-right corner, a little over a third of the icon wide
+    let (width, height) = icon.dimensions();
+    let radius = width.min(height) as f32 * 0.19;
+    let (cx, cy) = (width as f32 - radius - 1.0, height as f32 - radius - 1.0);
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        if dx * dx + dy * dy <= radius * radius {
+            *pixel = Rgba([r, g, b, 0xff]);
+        }
+    }
+
+    Ok(Icon::Rgba {
+        rgba: icon.into_raw(),
+        width,
+        height,
+    })
+}
@@ -0,0 +1,493 @@
+// Wire types for the protocol spoken with agent-runtime: JSON lines, or length-prefixed
+// JSON or MessagePack frames once hello has agreed on them. Requests are always JSON
+// lines. Kept free of Tauri types so the integration tests can include this file
+// directly.
+
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+use ts_rs::TS;
+
+/// Protocol version this shell speaks. 2: Ready carries versions, user messages are
+/// acked. 3: capabilities are agreed with hello.
+pub const PROTOCOL_VERSION: u32 = 3;
+/// Oldest agent protocol still understood. Agents from before the handshake count as 1.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// First protocol version with the hello exchange. Older agents get no capabilities.
+pub const HELLO_PROTOCOL_VERSION: u32 = 3;
+/// Largest frame accepted from an agent, after decompression.
+pub const MAX_FRAME_BYTES: usize = 256 * 1024 * 1024;
+// Set in a frame's length header when the payload is gzipped
+const COMPRESSED_FRAME: u32 = 1 << 31;
+
+/// How agent output is delimited. Ready and the hello answer are always lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum Framing {
+    #[default]
+    Lines,
+    // A 4-byte big-endian length, then that many bytes of JSON, which may span lines
+    LengthPrefixed,
+}
+
+/// How each agent message is serialized. MessagePack needs length-prefixed framing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+    // Maps with field names, so the serde attributes of the JSON types still apply
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+/// Optional protocol features. The shell offers the ones it handles in hello and the
+/// agent answers with those it will use; everything is off until then.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct Capabilities {
+    // Offered: the most the shell reads. Answered: what the agent writes from then on.
+    pub framing: Framing,
+    pub encoding: Encoding,
+    // Frames may be gzipped, flagged by the top bit of their length
+    pub compression: bool,
+    // User messages may carry files by path
+    pub attachments: bool,
+    // Tool calls may wait for the user to approve them
+    pub tool_approval: bool,
+}
+
+impl Capabilities {
+    /// Whether an answer to hello takes up only what `offered` holds, in a combination
+    /// the shell can read: MessagePack and compression need length-prefixed frames.
+    pub fn within(&self, offered: &Capabilities) -> bool {
+        let framed = self.framing == Framing::LengthPrefixed;
+        (!framed || offered.framing == Framing::LengthPrefixed)
+            && (self.encoding == Encoding::Json
+                || (framed && offered.encoding == Encoding::MessagePack))
+            && (!self.compression || (framed && offered.compression))
+            && (!self.attachments || offered.attachments)
+            && (!self.tool_approval || offered.tool_approval)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentResponse {
+    Ready {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        protocol_version: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent_version: Option<String>,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    // Answer to hello: the capabilities in use from the next message on
+    Hello {
+        id: String,
+        capabilities: Capabilities,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    // Receipt of a user message, sent before any work on it
+    Ack {
+        id: String,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    Token {
+        id: String,
+        token: String,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    ToolUse {
+        id: String,
+        data: serde_json::Value,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    ToolResult {
+        id: String,
+        data: serde_json::Value,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    // An image produced for the user: base64 in JSON, raw bytes in MessagePack
+    ImageOutput {
+        id: String,
+        #[serde(with = "image_data")]
+        #[ts(type = "string")]
+        data: Vec<u8>,
+        mime: String,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    Done {
+        id: String,
+        // Set for user messages by agents that report token usage
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+        // Result of a control request, e.g. {conversation_id, message_count} for
+        // load_conversation
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<serde_json::Value>,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    // Answer to a heartbeat ping, carrying the ping's id
+    Pong {
+        id: String,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+    Error {
+        id: String,
+        error: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(as = "Option<f64>")]
+        retry_after_ms: Option<u64>,
+        #[ts(as = "f64")]
+        timestamp: i64,
+    },
+}
+
+/// Tokens billed for one turn, summed over every API call it made.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Usage {
+    pub model: String,
+    #[ts(as = "f64")]
+    pub input_tokens: u64,
+    #[ts(as = "f64")]
+    pub output_tokens: u64,
+}
+
+/// Data of the Done answering load_conversation and load_conversation_compressed.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LoadedConversation {
+    pub conversation_id: String,
+    // Messages the agent now holds as history
+    #[ts(as = "f64")]
+    pub message_count: usize,
+}
+
+/// A request to the agent. Every kind carries the id its responses answer to.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AgentRequest {
+    UserMessage {
+        id: String,
+        message: String,
+        // JSON string of image attachments
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        images: Option<String>,
+        // None is the default conversation
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        conversation_id: Option<String>,
+    },
+    // Stops the generation of request `id`
+    Interrupt {
+        id: String,
+    },
+    ClearHistory {
+        id: String,
+    },
+    LoadConversation {
+        id: String,
+        conversation_id: String,
+    },
+    // The conversation as a checkpoint summary plus the messages after it
+    LoadConversationCompressed {
+        id: String,
+        conversation_id: String,
+        // JSON of {summary, messages: [{role, content}]}
+        message: String,
+    },
+    // `source_id` was merged into `conversation_id` and no longer exists
+    MergeConversations {
+        id: String,
+        conversation_id: String,
+        #[serde(rename = "message")]
+        source_id: String,
+    },
+    // One-shot prompts answered outside any conversation
+    Transform {
+        id: String,
+        message: String,
+    },
+    Summarize {
+        id: String,
+        message: String,
+    },
+    Ping {
+        id: String,
+    },
+    Shutdown {
+        id: String,
+    },
+    // Sent once after Ready by shells on protocol 3 or later, before any other request
+    Hello {
+        id: String,
+        protocol_version: u32,
+        capabilities: Capabilities,
+    },
+}
+
+/// The protocol version an agent announced in Ready, if the shell can talk to it.
+pub fn supported_protocol(announced: Option<u32>) -> Result<u32, u32> {
+    let version = announced.unwrap_or(1);
+    if (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        Ok(version)
+    } else {
+        Err(version)
+    }
+}
+
+impl AgentRequest {
+    pub fn id(&self) -> &str {
+        match self {
+            AgentRequest::UserMessage { id, .. }
+            | AgentRequest::Interrupt { id }
+            | AgentRequest::ClearHistory { id }
+            | AgentRequest::LoadConversation { id, .. }
+            | AgentRequest::LoadConversationCompressed { id, .. }
+            | AgentRequest::MergeConversations { id, .. }
+            | AgentRequest::Transform { id, .. }
+            | AgentRequest::Summarize { id, .. }
+            | AgentRequest::Ping { id }
+            | AgentRequest::Shutdown { id }
+            | AgentRequest::Hello { id, .. } => id,
+        }
+    }
+
+    /// The wire name of the request, e.g. "user_message".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AgentRequest::UserMessage { .. } => "user_message",
+            AgentRequest::Interrupt { .. } => "interrupt",
+            AgentRequest::ClearHistory { .. } => "clear_history",
+            AgentRequest::LoadConversation { .. } => "load_conversation",
+            AgentRequest::LoadConversationCompressed { .. } => "load_conversation_compressed",
+            AgentRequest::MergeConversations { .. } => "merge_conversations",
+            AgentRequest::Transform { .. } => "transform",
+            AgentRequest::Summarize { .. } => "summarize",
+            AgentRequest::Ping { .. } => "ping",
+            AgentRequest::Shutdown { .. } => "shutdown",
+            AgentRequest::Hello { .. } => "hello",
+        }
+    }
+}
+
+// Image bytes as a base64 string in human-readable formats (JSON, and so the webview
+// and logs), as a binary value in MessagePack
+mod image_data {
+    use base64::Engine;
+    use serde::de::{Error, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(data))
+        } else {
+            serializer.serialize_bytes(data)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_any(ImageData)
+    }
+
+    struct ImageData;
+
+    // Either form is read in either format, e.g. base64 strings from older agents
+    impl<'de> Visitor<'de> for ImageData {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("base64 image data or bytes")
+        }
+
+        fn visit_str<E: Error>(self, data: &str) -> Result<Vec<u8>, E> {
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|_| E::custom("image data is not valid base64"))
+        }
+
+        fn visit_bytes<E: Error>(self, data: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(data.to_vec())
+        }
+
+        fn visit_byte_buf<E: Error>(self, data: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(data)
+        }
+    }
+}
+
+impl AgentResponse {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            AgentResponse::Ready { .. } => None,
+            AgentResponse::Hello { id, .. }
+            | AgentResponse::Ack { id, .. }
+            | AgentResponse::Token { id, .. }
+            | AgentResponse::ToolUse { id, .. }
+            | AgentResponse::ToolResult { id, .. }
+            | AgentResponse::ImageOutput { id, .. }
+            | AgentResponse::Done { id, .. }
+            | AgentResponse::Pong { id, .. }
+            | AgentResponse::Error { id, .. } => Some(id),
+        }
+    }
+}
+
+/// Reads the next message written as `capabilities` say, or None once the stream has
+/// ended. Compressed frames are returned inflated.
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    capabilities: &Capabilities,
+) -> std::io::Result<Option<Vec<u8>>> {
+    match capabilities.framing {
+        Framing::Lines => {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                return Ok(None);
+            }
+            if line.ends_with(b"\n") {
+                line.pop();
+                if line.ends_with(b"\r") {
+                    line.pop();
+                }
+            }
+            Ok(Some(line))
+        }
+        Framing::LengthPrefixed => {
+            let mut header = [0u8; 4];
+            match reader.read_exact(&mut header).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            let header = u32::from_be_bytes(header);
+            let compressed = capabilities.compression && header & COMPRESSED_FRAME != 0;
+            let len = (if compressed {
+                header & !COMPRESSED_FRAME
+            } else {
+                header
+            }) as usize;
+            if len > MAX_FRAME_BYTES {
+                return Err(frame_too_large(len));
+            }
+            let mut payload = vec![0u8; len];
+            reader.read_exact(&mut payload).await?;
+            if !compressed {
+                return Ok(Some(payload));
+            }
+
+            let mut inflated = Vec::new();
+            GzDecoder::new(payload.as_slice())
+                .take(MAX_FRAME_BYTES as u64 + 1)
+                .read_to_end(&mut inflated)?;
+            if inflated.len() > MAX_FRAME_BYTES {
+                return Err(frame_too_large(inflated.len()));
+            }
+            Ok(Some(inflated))
+        }
+    }
+}
+
+fn frame_too_large(len: usize) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Frame of {} bytes is over the limit", len),
+    )
+}
+
+/// What is wrong with a message that isn't a valid AgentResponse, as far as a lenient
+/// parse can tell.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct InvalidResponse {
+    // The message's type, when it has one
+    pub kind: Option<String>,
+    pub id: Option<String>,
+    pub error: String,
+    // Fields that are missing or hold the wrong type of value
+    pub fields: Vec<String>,
+}
+
+/// Works out why `message` failed to parse as an AgentResponse.
+pub fn diagnose(message: &[u8], encoding: Encoding) -> InvalidResponse {
+    let value = match decode::<serde_json::Value>(message, encoding) {
+        Ok(value) => value,
+        Err(e) => {
+            let format = match encoding {
+                Encoding::Json => "JSON",
+                Encoding::MessagePack => "MessagePack",
+            };
+            return InvalidResponse {
+                error: format!("Not valid {}: {}", format, e),
+                ..Default::default()
+            };
+        }
+    };
+    let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let mut invalid = InvalidResponse {
+        kind: text("type"),
+        id: text("id"),
+        ..Default::default()
+    };
+    let Err(e) = serde_json::from_value::<AgentResponse>(value.clone()) else {
+        // Only the strict decoder objected, e.g. to how MessagePack encoded a number
+        invalid.error = "Rejected only by the strict decoder".to_string();
+        return invalid;
+    };
+    invalid.error = e.to_string();
+
+    if let Some(field) = missing_field(&invalid.error) {
+        invalid.fields.push(field.to_string());
+        return invalid;
+    }
+    let Some(object) = value.as_object() else {
+        return invalid;
+    };
+    // Otherwise a value has the wrong type. Without that field the message parses, or
+    // misses just that field; wrong types are reported before missing fields.
+    for key in object.keys().filter(|key| *key != "type") {
+        let mut trial = object.clone();
+        trial.remove(key);
+        let wrong = match serde_json::from_value::<AgentResponse>(trial.into()) {
+            Ok(_) => true,
+            Err(e) => missing_field(&e.to_string()) == Some(key.as_str()),
+        };
+        if wrong {
+            invalid.fields.push(key.clone());
+        }
+    }
+    invalid
+}
+
+fn missing_field(error: &str) -> Option<&str> {
+    error
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+}
+
+/// Parses a message read with read_message.
+pub fn decode<T: DeserializeOwned>(message: &[u8], encoding: Encoding) -> Result<T, String> {
+    match encoding {
+        Encoding::Json => serde_json::from_slice(message).map_err(|e| e.to_string()),
+        Encoding::MessagePack => rmp_serde::from_slice(message).map_err(|e| e.to_string()),
+    }
+}
@@ -0,0 +1,216 @@
+use crate::i18n;
+use crate::store::ConversationStore;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+const MAX_RESULTS: usize = 50;
+// Recent messages per conversation that are searchable besides the title
+const RECENT_MESSAGES: usize = 3;
+const SNIPPET_CHARS: usize = 160;
+
+// Actions the frontend can run from the switcher, with their label keys
+const ACTIONS: &[(&str, &str)] = &[
+    ("new_conversation", "action-new-conversation"),
+    ("clear_history", "action-clear-history"),
+    ("open_settings", "action-open-settings"),
+    ("check_updates", "action-check-updates"),
+    ("print_conversation", "action-print-conversation"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Candidate {
+    Conversation {
+        conversation_id: String,
+        title: String,
+        // Char positions in `title` that matched, for highlighting
+        indices: Vec<usize>,
+        score: i64,
+    },
+    Message {
+        conversation_id: String,
+        message_id: String,
+        snippet: String,
+        indices: Vec<usize>,
+        score: i64,
+    },
+    Action {
+        id: String,
+        label: String,
+        indices: Vec<usize>,
+        score: i64,
+    },
+}
+
+impl Candidate {
+    fn score(&self) -> i64 {
+        match self {
+            Candidate::Conversation { score, .. }
+            | Candidate::Message { score, .. }
+            | Candidate::Action { score, .. } => *score,
+        }
+    }
+}
+
+struct IndexedConversation {
+    modified: SystemTime,
+    title: String,
+    updated_at: i64,
+    // (message id, snippet)
+    recent: Vec<(String, String)>,
+}
+
+/// Titles and recent snippets of every stored conversation, refreshed per query
+/// from file modification times so only changed conversations are re-read.
+#[derive(Default)]
+pub struct QuickSwitchIndex {
+    conversations: Mutex<HashMap<String, IndexedConversation>>,
+}
+
+#[tauri::command]
+pub async fn quick_switch_candidates(
+    app_handle: AppHandle,
+    query: String,
+) -> Result<Vec<Candidate>, String> {
+    tauri::async_runtime::spawn_blocking(move || search(&app_handle, query.trim()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn search(app_handle: &AppHandle, query: &str) -> Result<Vec<Candidate>, String> {
+    let index = app_handle.state::<QuickSwitchIndex>();
+    let mut conversations = index.conversations.lock().unwrap();
+    refresh(app_handle, &mut conversations)?;
+
+    // No query: most recent conversations, then every action
+    if query.is_empty() {
+        let mut recent: Vec<_> = conversations.iter().collect();
+        recent.sort_by_key(|(_, conversation)| std::cmp::Reverse(conversation.updated_at));
+
+        return Ok(recent
+            .into_iter()
+            .map(|(id, conversation)| Candidate::Conversation {
+                conversation_id: id.clone(),
+                title: conversation.title.clone(),
+                indices: Vec::new(),
+                score: 0,
+            })
+            .take(MAX_RESULTS - ACTIONS.len())
+            .chain(actions(&SkimMatcherV2::default(), query))
+            .collect());
+    }
+
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut candidates: Vec<Candidate> = actions(&matcher, query).collect();
+
+    for (id, conversation) in conversations.iter() {
+        if let Some((score, indices)) = matcher.fuzzy_indices(&conversation.title, query) {
+            candidates.push(Candidate::Conversation {
+                conversation_id: id.clone(),
+                title: conversation.title.clone(),
+                indices,
+                score,
+            });
+        }
+
+        for (message_id, snippet) in &conversation.recent {
+            if let Some((score, indices)) = matcher.fuzzy_indices(snippet, query) {
+                // Titles are what people usually mean; message hits rank below them
+                candidates.push(Candidate::Message {
+                    conversation_id: id.clone(),
+                    message_id: message_id.clone(),
+                    snippet: snippet.clone(),
+                    indices,
+                    score: score / 2,
+                });
+            }
+        }
+    }
+
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score()));
+    candidates.truncate(MAX_RESULTS);
+    Ok(candidates)
+}
+
+fn actions<'a>(matcher: &'a SkimMatcherV2, query: &'a str) -> impl Iterator<Item = Candidate> + 'a {
+    ACTIONS.iter().filter_map(move |(id, key)| {
+        let label = i18n::t(key);
+        let (score, indices) = if query.is_empty() {
+            (0, Vec::new())
+        } else {
+            matcher.fuzzy_indices(&label, query)?
+        };
+
+        Some(Candidate::Action {
+            id: id.to_string(),
+            label,
+            indices,
+            score,
+        })
+    })
+}
+
+fn refresh(
+    app_handle: &AppHandle,
+    conversations: &mut HashMap<String, IndexedConversation>,
+) -> Result<(), String> {
+    let store = app_handle.state::<ConversationStore>();
+    let files = store
+        .list_modified()
+        .map_err(|e| format!("Failed to list conversations: {}", e))?;
+
+    let ids: HashSet<&String> = files.iter().map(|(id, _)| id).collect();
+    conversations.retain(|id, _| ids.contains(id));
+
+    for (id, modified) in files {
+        let unchanged = conversations
+            .get(&id)
+            .map(|conversation| conversation.modified == modified)
+            .unwrap_or(false);
+        if unchanged {
+            continue;
+        }
+
+        let conversation = match store.load(&id) {
+            Ok(conversation) => conversation,
+            Err(e) => {
+                eprintln!("Skipping conversation {} in quick switcher: {}", id, e);
+                continue;
+            }
+        };
+        let recent = conversation
+            .messages
+            .iter()
+            .rev()
+            .take(RECENT_MESSAGES)
+            .map(|message| {
+                let snippet = message
+                    .content
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (
+                    message.id.clone(),
+                    snippet.chars().take(SNIPPET_CHARS).collect(),
+                )
+            })
+            .collect();
+
+        conversations.insert(
+            id,
+            IndexedConversation {
+                modified,
+                title: conversation.title,
+                updated_at: conversation.updated_at,
+                recent,
+            },
+        );
+    }
+
+    Ok(())
+}
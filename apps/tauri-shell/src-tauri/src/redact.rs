@@ -0,0 +1,205 @@
+use crate::settings::SettingsStore;
+use crate::store::{Conversation, ConversationStore, StoredMessage};
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+static EMAIL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap());
+
+// Provider keys with recognizable prefixes, and bearer tokens
+static SECRET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk-(?:ant-)?[A-Za-z0-9_-]{20,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}",
+        r"|xox[abpr]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})\b",
+        r"|(?i:bearer)\s+[A-Za-z0-9._~+/-]{20,}=*"
+    ))
+    .unwrap()
+});
+
+// Paths inside a home directory, which give away the user name
+static FILE_PATH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"~/[^\s"'`<>()\[\]]*|(?:/Users/|/home/|[A-Za-z]:\\Users\\)[^\s"'`<>()\[\]]+"#)
+        .unwrap()
+});
+
+/// What exports and shares strip before writing anything. Off by default; once on,
+/// every built-in category applies unless turned off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionSettings {
+    pub enabled: bool,
+    pub emails: bool,
+    pub secrets: bool,
+    pub file_paths: bool,
+    // Extra regular expressions; matches are replaced with [redacted]
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        RedactionSettings {
+            enabled: false,
+            emails: true,
+            secrets: true,
+            file_paths: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Redaction {
+    pub original: String,
+    pub replacement: &'static str,
+    // Byte offsets into the original message content
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedactionPreview {
+    pub message_id: String,
+    pub role: String,
+    pub redactions: Vec<Redaction>,
+    pub redacted: String,
+}
+
+pub struct Redactor {
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl Redactor {
+    pub fn new(settings: &RedactionSettings) -> Result<Self> {
+        let mut rules = Vec::new();
+        if settings.emails {
+            rules.push((EMAIL.clone(), "[email]"));
+        }
+        if settings.secrets {
+            rules.push((SECRET.clone(), "[secret]"));
+        }
+        if settings.file_paths {
+            rules.push((FILE_PATH.clone(), "[path]"));
+        }
+        for pattern in &settings.custom_patterns {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Invalid redaction pattern {:?}", pattern))?;
+            rules.push((regex, "[redacted]"));
+        }
+        Ok(Redactor { rules })
+    }
+
+    /// Every match in `text`, earliest first. Where rules overlap the earlier rule wins.
+    pub fn find(&self, text: &str) -> Vec<Redaction> {
+        let mut found: Vec<Redaction> = Vec::new();
+        for (regex, replacement) in &self.rules {
+            for m in regex.find_iter(text) {
+                let overlaps = found
+                    .iter()
+                    .any(|other| m.start() < other.end && other.start < m.end());
+                if !overlaps && !m.as_str().is_empty() {
+                    found.push(Redaction {
+                        original: m.as_str().to_string(),
+                        replacement,
+                        start: m.start(),
+                        end: m.end(),
+                    });
+                }
+            }
+        }
+        found.sort_by_key(|redaction| redaction.start);
+        found
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for redaction in self.find(text) {
+            redacted.push_str(&text[last..redaction.start]);
+            redacted.push_str(redaction.replacement);
+            last = redaction.end;
+        }
+        redacted.push_str(&text[last..]);
+        redacted
+    }
+
+    pub fn message(&self, message: &StoredMessage) -> StoredMessage {
+        StoredMessage {
+            content: self.apply(&message.content),
+            ..message.clone()
+        }
+    }
+
+    pub fn conversation(&self, conversation: &Conversation) -> Conversation {
+        Conversation {
+            title: self.apply(&conversation.title),
+            messages: conversation
+                .messages
+                .iter()
+                .map(|message| self.message(message))
+                .collect(),
+            ..conversation.clone()
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_redaction_settings(settings: State<'_, SettingsStore>) -> RedactionSettings {
+    settings.get().redaction
+}
+
+#[tauri::command]
+pub fn set_redaction_settings(
+    settings: State<'_, SettingsStore>,
+    redaction: RedactionSettings,
+) -> Result<(), String> {
+    // Reject patterns that don't compile now rather than failing the next export
+    Redactor::new(&redaction).map_err(|e| e.to_string())?;
+
+    settings
+        .update(|s| s.redaction = redaction)
+        .map_err(|e| format!("Failed to save redaction settings: {}", e))
+}
+
+/// What exporting the conversation would remove, per message, using the current
+/// settings even while redaction is off. Messages without matches are left out.
+#[tauri::command]
+pub fn preview_redaction(
+    app_handle: AppHandle,
+    conversation_id: String,
+) -> Result<Vec<RedactionPreview>, String> {
+    let conversation = app_handle
+        .state::<ConversationStore>()
+        .load(&conversation_id)
+        .map_err(|e| format!("Failed to load conversation: {}", e))?;
+    let redactor = Redactor::new(&app_handle.state::<SettingsStore>().get().redaction)
+        .map_err(|e| e.to_string())?;
+
+    Ok(conversation
+        .messages
+        .iter()
+        .filter_map(|message| {
+            let redactions = redactor.find(&message.content);
+            if redactions.is_empty() {
+                return None;
+            }
+            Some(RedactionPreview {
+                message_id: message.id.clone(),
+                role: message.role.clone(),
+                redacted: redactor.apply(&message.content),
+                redactions,
+            })
+        })
+        .collect())
+}
+
+/// The redactor export paths should run content through, or None while redaction is off.
+pub fn for_export(app_handle: &AppHandle) -> Result<Option<Redactor>> {
+    let settings = app_handle.state::<SettingsStore>().get().redaction;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    Redactor::new(&settings).map(Some)
+}
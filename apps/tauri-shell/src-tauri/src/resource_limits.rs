@@ -0,0 +1,237 @@
+use crate::settings::SettingsStore;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::process::{Child, Command};
+use ts_rs::TS;
+
+// Agent output when an allocation failed, from Node/V8 or the C runtime
+const OUT_OF_MEMORY_MARKERS: &[&str] = &[
+    "out of memory",
+    "Out of memory",
+    "std::bad_alloc",
+    "Cannot allocate memory",
+    "ENOMEM",
+];
+
+/// Limits applied to agent processes when they are spawned, and inherited by the
+/// processes they start (tool calls). Changes apply from the next spawn. In-process
+/// agents such as the HTTP backend aren't limited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    // Niceness from 0 (normal) to 19 (lowest priority); raising priority needs root
+    pub nice: Option<i32>,
+    // Ceiling on the memory each process may allocate, in megabytes. macOS accepts
+    // but doesn't enforce RLIMIT_DATA, so there it has no effect.
+    pub memory_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentLimitExceeded<'a> {
+    agent_id: &'a str,
+    // Which limit was hit: "memory"
+    limit: &'a str,
+    #[ts(as = "f64")]
+    memory_mb: u64,
+}
+
+/// The configured limits, with niceness clamped to what an unprivileged user can set.
+pub fn configured(app_handle: &AppHandle) -> ResourceLimits {
+    let mut limits = app_handle.state::<SettingsStore>().get().agent_limits;
+    limits.nice = limits.nice.map(|nice| nice.clamp(0, 19));
+    limits
+}
+
+/// Sets up `command` so the process starts under `limits`.
+#[cfg(unix)]
+pub fn apply(command: &mut Command, limits: &ResourceLimits) {
+    if limits.nice.is_none() && limits.memory_mb.is_none() {
+        return;
+    }
+    let limits = limits.clone();
+    // Runs in the forked child before exec, so only async-signal-safe calls
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(nice) = limits.nice {
+                // Refused when the shell already runs nicer than that, which leaves the
+                // agent at the shell's niceness; running unlimited beats not running
+                libc::setpriority(libc::PRIO_PROCESS as _, 0, nice);
+            }
+            if let Some(memory_mb) = limits.memory_mb {
+                // RLIMIT_AS would also count the address space V8 reserves up front.
+                // Not enforced on macOS, which has no per-process memory ceiling.
+                let bytes = memory_mb.saturating_mul(1024 * 1024) as libc::rlim_t;
+                let limit = libc::rlimit {
+                    rlim_cur: bytes,
+                    rlim_max: bytes,
+                };
+                if libc::setrlimit(libc::RLIMIT_DATA, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub fn apply(_command: &mut Command, _limits: &ResourceLimits) {}
+
+/// Puts the spawned `child` under `limits`. On Windows it joins a Job Object, named
+/// after its pid so job_processes() finds it, even without limits; the processes it
+/// starts join too.
+#[cfg(windows)]
+pub fn attach(child: &Child, limits: &ResourceLimits) -> Result<()> {
+    use anyhow::{anyhow, Context};
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_PRIORITY_CLASS, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+    use windows::Win32::System::Threading::{
+        OpenProcess, BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, PROCESS_SET_QUOTA,
+        PROCESS_TERMINATE,
+    };
+
+    let pid = child.id().context("Agent process has exited")?;
+
+    let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    if let Some(nice) = limits.nice.filter(|nice| *nice > 0) {
+        // Windows has priority classes instead of a niceness scale
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+        info.BasicLimitInformation.PriorityClass = if nice >= 10 {
+            IDLE_PRIORITY_CLASS.0
+        } else {
+            BELOW_NORMAL_PRIORITY_CLASS.0
+        };
+    }
+    if let Some(memory_mb) = limits.memory_mb {
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        info.ProcessMemoryLimit = memory_mb.saturating_mul(1024 * 1024) as usize;
+    }
+
+    unsafe {
+        let job = CreateJobObjectW(None, &HSTRING::from(job_name(pid)))
+            .context("Failed to create job object")?;
+        let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, pid);
+        let result = match process {
+            Ok(process) => {
+                let assigned = SetInformationJobObject(
+                    job,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const std::ffi::c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                )
+                .as_bool()
+                    && AssignProcessToJobObject(job, process).as_bool();
+                CloseHandle(process);
+                if assigned {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "Failed to limit agent process: {}",
+                        std::io::Error::last_os_error()
+                    ))
+                }
+            }
+            Err(e) => Err(anyhow!("Failed to open agent process: {}", e)),
+        };
+        // The job lives on as long as a process is in it
+        CloseHandle(job);
+        result
+    }
+}
+
+// Unix limits are set before exec
+#[cfg(not(windows))]
+pub fn attach(_child: &Child, _limits: &ResourceLimits) -> Result<()> {
+    Ok(())
+}
+
+/// The processes in the job of the agent process `pid`, or just `pid` if it has none.
+#[cfg(windows)]
+pub fn job_processes(pid: u32) -> Vec<u32> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::JobObjects::{
+        JobObjectBasicProcessIdList, OpenJobObjectW, QueryInformationJobObject, JOB_OBJECT_QUERY,
+    };
+
+    // JOBOBJECT_BASIC_PROCESS_ID_LIST with room for the ids
+    #[repr(C)]
+    struct ProcessIdList {
+        assigned: u32,
+        listed: u32,
+        ids: [usize; 256],
+    }
+
+    unsafe {
+        let Ok(job) = OpenJobObjectW(JOB_OBJECT_QUERY, false, &HSTRING::from(job_name(pid))) else {
+            return vec![pid];
+        };
+        let mut list = ProcessIdList {
+            assigned: 0,
+            listed: 0,
+            ids: [0; 256],
+        };
+        let listed = QueryInformationJobObject(
+            job,
+            JobObjectBasicProcessIdList,
+            &mut list as *mut _ as *mut std::ffi::c_void,
+            std::mem::size_of::<ProcessIdList>() as u32,
+            None,
+        )
+        .as_bool();
+        CloseHandle(job);
+        if !listed {
+            return vec![pid];
+        }
+        list.ids[..list.listed as usize]
+            .iter()
+            .map(|id| *id as u32)
+            .collect()
+    }
+}
+
+#[cfg(windows)]
+fn job_name(pid: u32) -> String {
+    format!("Local\\asst-agent-{}", pid)
+}
+
+/// Called when an agent exits unexpectedly. Emits agent_limit_exceeded when its
+/// output says it ran out of memory under the ceiling it was spawned with.
+pub fn check_exit(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    limits: &ResourceLimits,
+    stderr_tail: &[String],
+) {
+    let Some(memory_mb) = limits.memory_mb else {
+        return;
+    };
+    let out_of_memory = stderr_tail.iter().any(|line| {
+        OUT_OF_MEMORY_MARKERS
+            .iter()
+            .any(|marker| line.contains(marker))
+    });
+    if !out_of_memory {
+        return;
+    }
+
+    eprintln!(
+        "[LIMITS] Agent {} exceeded its {} MB memory ceiling",
+        agent_id, memory_mb
+    );
+    let event = AgentLimitExceeded {
+        agent_id,
+        limit: "memory",
+        memory_mb,
+    };
+    if let Err(e) = app_handle.emit_all("agent_limit_exceeded", event) {
+        eprintln!("Failed to emit agent_limit_exceeded: {}", e);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::agent_profiles::AgentProfile;
+use crate::settings::SettingsStore;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State};
+
+// Keychain service the values are filed under; the account is the variable name
+const KEYCHAIN_SERVICE: &str = "com.ericday.desktop-assistant";
+// Account looked up to tell whether the keychain works at all; never written
+const PROBE_ACCOUNT: &str = "ASST_KEYCHAIN_PROBE";
+
+pub const ANTHROPIC_API_KEY: &str = "ANTHROPIC_API_KEY";
+
+// Variables that decide what the agent's process loads or runs, or which certificates
+// it trusts, rather than configure the agent; never taken from the webview
+const RESERVED_NAMES: &[&str] = &[
+    "BASH_ENV",
+    "COMSPEC",
+    "ENV",
+    "HOME",
+    "NODE_EXTRA_CA_CERTS",
+    "NODE_OPTIONS",
+    "NODE_PATH",
+    "NODE_TLS_REJECT_UNAUTHORIZED",
+    "PATH",
+    "PATHEXT",
+    "PERL5LIB",
+    "PERL5OPT",
+    "PYTHONPATH",
+    "PYTHONSTARTUP",
+    "RUBYLIB",
+    "RUBYOPT",
+    "SHELL",
+    "SSL_CERT_DIR",
+    "SSL_CERT_FILE",
+    "SYSTEMROOT",
+];
+// Dynamic loader variables: LD_PRELOAD, LD_LIBRARY_PATH, DYLD_INSERT_LIBRARIES...
+const RESERVED_PREFIXES: &[&str] = &["DYLD_", "LD_"];
+
+/// Names of the secrets passed to the agent. Values never leave the keychain except
+/// into the agent's environment.
+#[tauri::command]
+pub fn list_agent_secrets(settings: State<'_, SettingsStore>) -> Vec<String> {
+    settings.get().agent_secrets
+}
+
+/// Stores `value` in the OS keychain and passes it to the agent as the environment
+/// variable `name` from the next spawn on.
+#[tauri::command]
+pub fn set_agent_secret(app_handle: AppHandle, name: String, value: String) -> Result<(), String> {
+    set(&app_handle, &name, &value).map_err(|e| format!("Failed to save secret: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_agent_secret(app_handle: AppHandle, name: String) -> Result<(), String> {
+    delete(&app_handle, &name).map_err(|e| format!("Failed to delete secret: {}", e))
+}
+
+pub fn set(app_handle: &AppHandle, name: &str, value: &str) -> Result<()> {
+    check_name(name)?;
+    if value.is_empty() {
+        return Err(anyhow!("The value is empty"));
+    }
+    entry(name)?
+        .set_password(value)
+        .context("Failed to write to the keychain")?;
+
+    app_handle.state::<SettingsStore>().update(|s| {
+        // The keychain copy supersedes the plain-text one from older versions
+        if name == ANTHROPIC_API_KEY {
+            s.anthropic_api_key = None;
+        }
+        if !s.agent_secrets.iter().any(|secret| secret == name) {
+            s.agent_secrets.push(name.to_string());
+        }
+    })
+}
+
+pub fn delete(app_handle: &AppHandle, name: &str) -> Result<()> {
+    match entry(name)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e).context("Failed to delete from the keychain"),
+    }
+    app_handle
+        .state::<SettingsStore>()
+        .update(|s| s.agent_secrets.retain(|secret| secret != name))
+}
+
+/// Whether the agent will receive `name`, from the keychain or the legacy setting.
+pub fn has(app_handle: &AppHandle, name: &str) -> bool {
+    agent_env(app_handle).iter().any(|(key, _)| key == name)
+}
+
+/// Environment variables holding the agent's secrets. Unreadable entries are skipped
+/// and reported by name only.
+pub fn agent_env(app_handle: &AppHandle) -> Vec<(String, String)> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    let mut env: Vec<(String, String)> = settings
+        .agent_secrets
+        .iter()
+        // Saved before reserved names were refused
+        .filter(|name| !reserved(name))
+        .filter_map(
+            |name| match entry(name).and_then(|entry| Ok(entry.get_password()?)) {
+                Ok(value) => Some((name.clone(), value)),
+                Err(e) => {
+                    eprintln!("Failed to read secret {} from the keychain: {}", name, e);
+                    None
+                }
+            },
+        )
+        .collect();
+
+    // Saved in settings before secrets moved to the keychain
+    if let Some(api_key) = settings.anthropic_api_key {
+        if !env.iter().any(|(name, _)| name == ANTHROPIC_API_KEY) {
+            env.push((ANTHROPIC_API_KEY.to_string(), api_key));
+        }
+    }
+    env
+}
+
+/// Moves the environment of `profile` and of its command into the keychain, leaving
+/// only the names in `secret_env`. An empty value keeps what the keychain holds.
+/// Variables of `previous` that the profile no longer lists are deleted.
+pub fn store_profile_env(
+    profile: &mut AgentProfile,
+    previous: Option<&AgentProfile>,
+) -> Result<()> {
+    // The profile's own variables win over its command's, as they do at spawn
+    let mut env = profile
+        .command
+        .as_mut()
+        .map(|command| std::mem::take(&mut command.env))
+        .unwrap_or_default();
+    env.extend(std::mem::take(&mut profile.env));
+
+    for (name, value) in env {
+        check_name(&name)?;
+        if !value.is_empty() {
+            entry(&profile_account(&profile.id, &name))?
+                .set_password(&value)
+                .context("Failed to write to the keychain")?;
+        }
+        if !profile.secret_env.contains(&name) {
+            profile.secret_env.push(name);
+        }
+    }
+
+    let removed = previous
+        .into_iter()
+        .flat_map(|previous| &previous.secret_env)
+        .filter(|name| !profile.secret_env.contains(name));
+    for name in removed {
+        delete_profile_entry(&profile.id, name);
+    }
+    Ok(())
+}
+
+/// The environment of `profile` with the values kept in the keychain read back.
+pub fn profile_env(profile: &AgentProfile) -> HashMap<String, String> {
+    // Plain-text values saved before profile variables moved to the keychain
+    let mut env = profile.env.clone();
+    for name in &profile.secret_env {
+        let account = profile_account(&profile.id, name);
+        match entry(&account).and_then(|entry| Ok(entry.get_password()?)) {
+            Ok(value) => {
+                env.insert(name.clone(), value);
+            }
+            Err(e) => eprintln!(
+                "Failed to read {} of profile {} from the keychain: {}",
+                name, profile.id, e
+            ),
+        }
+    }
+    // Saved before reserved names were refused
+    env.retain(|name, _| !reserved(name));
+    env
+}
+
+/// Removes the keychain entries of a deleted profile.
+pub fn delete_profile_env(profile: &AgentProfile) {
+    for name in &profile.secret_env {
+        delete_profile_entry(&profile.id, name);
+    }
+}
+
+fn delete_profile_entry(profile_id: &str, name: &str) {
+    let result =
+        entry(&profile_account(profile_id, name)).and_then(|entry| match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        });
+    if let Err(e) = result {
+        eprintln!(
+            "Failed to delete {} of profile {} from the keychain: {}",
+            name, profile_id, e
+        );
+    }
+}
+
+// Profile ids have no slashes, see agent_profiles::valid_id
+fn profile_account(profile_id: &str, name: &str) -> String {
+    format!("profile/{}/{}", profile_id, name)
+}
+
+/// Fails if the keychain can't be used at all, e.g. when it is locked or missing.
+pub fn probe() -> Result<()> {
+    match entry(PROBE_ACCOUNT)?.get_password() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).context("The keychain is not reachable"),
+    }
+}
+
+/// Names of configured secrets whose values can't be read from the keychain.
+pub fn unreadable(app_handle: &AppHandle) -> Vec<String> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    settings
+        .agent_secrets
+        .into_iter()
+        .filter(|name| {
+            entry(name)
+                .and_then(|entry| Ok(entry.get_password()?))
+                .is_err()
+        })
+        .collect()
+}
+
+fn entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, name).context("Failed to open the keychain")
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if !valid_name(name) {
+        return Err(anyhow!("{} is not a valid environment variable name", name));
+    }
+    if reserved(name) {
+        return Err(anyhow!("{} can't be set for the agent", name));
+    }
+    Ok(())
+}
+
+fn reserved(name: &str) -> bool {
+    RESERVED_NAMES.contains(&name)
+        || RESERVED_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase() || c == '_')
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_must_be_upper_case_identifiers() {
+        assert!(check_name("OPENAI_API_KEY").is_ok());
+        assert!(check_name("_TOKEN2").is_ok());
+        assert!(check_name("api_key").is_err());
+        assert!(check_name("2FA_CODE").is_err());
+        assert!(check_name("A=B").is_err());
+        assert!(check_name("").is_err());
+    }
+
+    #[test]
+    fn loader_runtime_and_path_variables_are_refused() {
+        for name in [
+            "NODE_OPTIONS",
+            "NODE_PATH",
+            "PATH",
+            "LD_PRELOAD",
+            "LD_LIBRARY_PATH",
+            "LD_AUDIT",
+            "DYLD_INSERT_LIBRARIES",
+            "DYLD_LIBRARY_PATH",
+            "PYTHONPATH",
+            "BASH_ENV",
+        ] {
+            assert!(check_name(name).is_err(), "{}", name);
+        }
+        // Only whole names and the loader prefixes are reserved
+        assert!(check_name("NODE_ENV").is_ok());
+        assert!(check_name("MY_PATH").is_ok());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::data_dir;
+use crate::outbox::{self, Outbox, OutboxItem};
+use crate::print;
+use crate::store::{self, ConversationStore, Draft};
+use crate::window_registry;
+use crate::AppState;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, State};
+use ts_rs::TS;
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Runtime state written to session.json every few seconds. `clean_exit` is only set
+/// on an orderly quit, so finding it false at launch means the app crashed or was
+/// force-quit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SessionSnapshot {
+    pub clean_exit: bool,
+    #[ts(as = "f64")]
+    pub saved_at: i64,
+    pub windows: Vec<WindowSession>,
+    // User messages still being answered when the snapshot was taken
+    pub interrupted: Vec<OutboxItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WindowSession {
+    pub label: String,
+    pub conversation_id: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct RestoredWindow {
+    pub label: String,
+    pub conversation_id: Option<String>,
+    pub draft: Option<Draft>,
+}
+
+pub struct Session {
+    path: Option<PathBuf>,
+    // Left behind by an unclean shutdown and not yet restored or discarded
+    previous: Mutex<Option<SessionSnapshot>>,
+    interrupted: Mutex<Vec<OutboxItem>>,
+    exiting: AtomicBool,
+}
+
+impl Session {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = data_dir::resolve(app_handle).map(|dir| dir.join("session.json"));
+
+        let previous = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<SessionSnapshot>(&json).ok())
+            .filter(|snapshot| !snapshot.clean_exit);
+        if previous.is_some() {
+            eprintln!("[SESSION] Previous session ended uncleanly");
+        }
+
+        Session {
+            path,
+            previous: Mutex::new(previous),
+            interrupted: Mutex::new(Vec::new()),
+            exiting: AtomicBool::new(false),
+        }
+    }
+
+    fn save(&self, snapshot: &SessionSnapshot) -> Result<()> {
+        let path = self.path.as_ref().context("No data directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+
+        let json = serde_json::to_string(snapshot).context("Failed to serialize session")?;
+        std::fs::write(path, json).context("Failed to write session")
+    }
+}
+
+/// The session an unclean shutdown left behind, if it hasn't been dealt with yet.
+#[tauri::command]
+pub fn get_unclean_session(session: State<'_, Session>) -> Option<SessionSnapshot> {
+    session.previous.lock().unwrap().clone()
+}
+
+/// Puts the previous session back: window geometry and conversations, plus the
+/// messages that were still being answered, which are queued again. Each restored
+/// window also receives session_restored with its draft.
+#[tauri::command]
+pub async fn restore_session(
+    app_handle: AppHandle,
+    session: State<'_, Session>,
+) -> Result<Vec<RestoredWindow>, String> {
+    let snapshot = session
+        .previous
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No session to restore".to_string())?;
+
+    let store = app_handle.state::<ConversationStore>();
+    let mut restored = Vec::new();
+    for saved in &snapshot.windows {
+        // Only windows the app recreates itself can be put back
+        let Some(window) = app_handle.get_window(&saved.label) else {
+            continue;
+        };
+        let _ = window.set_size(PhysicalSize::new(saved.width, saved.height));
+        let _ = window.set_position(PhysicalPosition::new(saved.x, saved.y));
+        if saved.maximized {
+            let _ = window.maximize();
+        }
+        if saved.visible {
+            let _ = window.show();
+        }
+
+        let draft = saved.conversation_id.as_ref().and_then(|conversation_id| {
+            window_registry::set_conversation(&app_handle, &saved.label, conversation_id);
+            store.load_draft(conversation_id).ok().flatten()
+        });
+        let window_restore = RestoredWindow {
+            label: saved.label.clone(),
+            conversation_id: saved.conversation_id.clone(),
+            draft,
+        };
+        if let Err(e) = window.emit("session_restored", &window_restore) {
+            eprintln!("Failed to emit session_restored: {}", e);
+        }
+        restored.push(window_restore);
+    }
+
+    let queued: Vec<String> = app_handle
+        .state::<Outbox>()
+        .list()
+        .into_iter()
+        .map(|item| item.request.id().to_string())
+        .collect();
+    for item in snapshot.interrupted {
+        if queued.iter().any(|id| id == item.request.id()) {
+            continue;
+        }
+        if let Err(e) = outbox::enqueue(&app_handle, item.request, item.owner) {
+            eprintln!("Failed to requeue interrupted message: {}", e);
+        }
+    }
+    outbox::flush_when_ready(app_handle.clone(), app_handle.state::<AppState>().agent());
+
+    Ok(restored)
+}
+
+#[tauri::command]
+pub fn discard_session(session: State<'_, Session>) {
+    session.previous.lock().unwrap().take();
+}
+
+/// Writes a snapshot now and then every SAVE_INTERVAL until the app quits.
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            capture_in_flight(&app_handle).await;
+            save_now(&app_handle, false);
+            tokio::time::sleep(SAVE_INTERVAL).await;
+        }
+    });
+}
+
+/// Records an orderly quit so the next launch doesn't offer a restore.
+pub fn mark_clean(app_handle: &AppHandle) {
+    app_handle
+        .state::<Session>()
+        .exiting
+        .store(true, Ordering::SeqCst);
+    save_now(app_handle, true);
+}
+
+// Skipped while the agent is busy, e.g. respawning; the last list stays valid
+async fn capture_in_flight(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let Ok(agent) = state.agent().try_lock_owned() else {
+        return;
+    };
+    let requests = match agent.as_ref() {
+        Some(process) => process.in_flight_requests().await,
+        None => Vec::new(),
+    };
+
+    *app_handle.state::<Session>().interrupted.lock().unwrap() = requests
+        .into_iter()
+        .map(|(request, owner)| OutboxItem {
+            request,
+            owner,
+            queued_at: store::now_millis(),
+        })
+        .collect();
+}
+
+fn save_now(app_handle: &AppHandle, clean_exit: bool) {
+    let session = app_handle.state::<Session>();
+    if !clean_exit && session.exiting.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let snapshot = SessionSnapshot {
+        clean_exit,
+        saved_at: store::now_millis(),
+        windows: windows(app_handle),
+        interrupted: session.interrupted.lock().unwrap().clone(),
+    };
+    if let Err(e) = session.save(&snapshot) {
+        eprintln!("Failed to save session: {}", e);
+    }
+}
+
+fn windows(app_handle: &AppHandle) -> Vec<WindowSession> {
+    app_handle
+        .windows()
+        .into_iter()
+        .filter(|(label, _)| !label.starts_with(print::PRINT_WINDOW_PREFIX))
+        .filter_map(|(label, window)| {
+            let position = window.outer_position().ok()?;
+            let size = window.inner_size().ok()?;
+            Some(WindowSession {
+                conversation_id: window_registry::conversation_of(app_handle, &label),
+                label,
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized: window.is_maximized().unwrap_or(false),
+                visible: window.is_visible().unwrap_or(false),
+            })
+        })
+        .collect()
+}
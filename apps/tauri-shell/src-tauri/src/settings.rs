@@ -0,0 +1,181 @@
+use crate::agent_ipc::{RecoverySettings, RequestTimeouts};
+use crate::agent_profiles::AgentProfile;
+use crate::agent_runtime::{AgentCommand, AgentRuntime};
+use crate::budget::BudgetSettings;
+use crate::cache::CacheSettings;
+use crate::checkpoints::CheckpointSettings;
+use crate::feedback::FeedbackSettings;
+use crate::heartbeat::HeartbeatSettings;
+use crate::onboarding::OnboardingState;
+use crate::redact::RedactionSettings;
+use crate::resource_limits::ResourceLimits;
+use crate::spaces::{SpaceBehavior, WindowPin};
+use crate::text_transform::TextTransformSettings;
+use crate::transport::TransportKind;
+use crate::updates::UpdateChannel;
+use crate::watchdog::WatchdogSettings;
+use crate::window_presets::WindowPresetSettings;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Webview zoom factor keyed by window label
+    pub zoom: HashMap<String, f64>,
+    pub feedback: FeedbackSettings,
+    pub update_channel: UpdateChannel,
+    pub onboarding: OnboardingState,
+    // Accelerator toggling the main window; None means shortcut::DEFAULT_SHORTCUT
+    pub global_shortcut: Option<String>,
+    // Passed to the agent as ANTHROPIC_API_KEY, taking precedence over .env. Only set by
+    // older versions; new keys go to the keychain (see agent_secrets)
+    pub anthropic_api_key: Option<String>,
+    // Environment variables passed to the agent whose values live in the OS keychain
+    pub agent_secrets: Vec<String>,
+    // Interrupt and resend stalled generations instead of only reporting them
+    pub auto_retry_stalled_streams: bool,
+    // Whether summoning the window moves it to the active Space / virtual desktop
+    pub space_behavior: SpaceBehavior,
+    // Monitor and desktop the main window is kept on; overrides space_behavior
+    pub window_pin: Option<WindowPin>,
+    // Overrides the platform data directory; ASST_DATA_DIR takes precedence
+    pub data_dir: Option<PathBuf>,
+    // Keep an on-device word and trigram index of the history for fuzzy_search
+    #[serde(alias = "semantic_search")]
+    pub fuzzy_search: bool,
+    // Folders watched for project context, resumed at startup
+    pub watched_folders: Vec<PathBuf>,
+    // Keep a second, idle agent process to take over when the primary dies
+    pub warm_standby: bool,
+    // Spending limits checked before each message is sent
+    pub budget: BudgetSettings,
+    // Masking applied by every export and share path
+    pub redaction: RedactionSettings,
+    // Ping/pong liveness check of the agent process
+    pub heartbeat: HeartbeatSettings,
+    // Restart of an agent that leaves a request unanswered and misses a ping
+    pub watchdog: WatchdogSettings,
+    // Respawn and resend when a request finds the agent's stdin closed
+    pub recovery: RecoverySettings,
+    // Per-kind limits after which an unanswered request is failed with "timeout"
+    pub request_timeouts: RequestTimeouts,
+    // How the built-in agent is launched: bundled sidecar or the dev checkout
+    pub agent_runtime: AgentRuntime,
+    // Custom agent executable, args, cwd and env; takes precedence over agent_runtime
+    pub agent_command: Option<AgentCommand>,
+    // Named agent setups (command, env, model) that agents can be spawned with
+    pub agent_profiles: Vec<AgentProfile>,
+    // Profile of the default agent; None runs agent_command or agent_runtime
+    pub agent_profile: Option<String>,
+    // Niceness and memory ceiling of spawned agent processes
+    pub agent_limits: ResourceLimits,
+    // Whether the built-in agent talks over stdio, a Unix socket or a named pipe; agent
+    // commands pick theirs in AgentCommand.transport
+    pub agent_transport: TransportKind,
+    // Shortcuts and animation of the window resize presets
+    pub window_presets: WindowPresetSettings,
+    // Selection transforms run from global shortcuts and pasted back in place
+    pub text_transforms: TextTransformSettings,
+    // Background summaries of long conversations for load_conversation_compressed
+    pub checkpoints: CheckpointSettings,
+    // Size budgets and sweep interval of spilled replies, screenshots and print pages
+    pub cache: CacheSettings,
+}
+
+/// Shell settings persisted as JSON in the app config directory.
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    // Named profile these settings belong to; None is the default profile
+    profile: Option<String>,
+    settings: Mutex<Settings>,
+    // Why settings.json couldn't be used at launch; defaults were loaded instead
+    load_error: Option<String>,
+    // Set until the unparsable file has been moved aside, before anything replaces it
+    unparsable: AtomicBool,
+}
+
+impl SettingsStore {
+    /// Loads settings.json, or settings-<profile>.json for a named profile.
+    pub fn load(app_handle: &AppHandle, profile: Option<&str>) -> Self {
+        let file_name = match profile {
+            Some(profile) => format!("settings-{}.json", profile),
+            None => "settings.json".to_string(),
+        };
+        let path = app_handle
+            .path_resolver()
+            .app_config_dir()
+            .map(|dir| dir.join(file_name));
+
+        let mut load_error = None;
+        let settings = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    eprintln!("Failed to parse settings, using defaults: {}", e);
+                    load_error = Some(e.to_string());
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        SettingsStore {
+            path,
+            profile: profile.map(str::to_string),
+            settings: Mutex::new(settings),
+            unparsable: AtomicBool::new(load_error.is_some()),
+            load_error,
+        }
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Applies `f` to the settings and writes the result to disk. A settings file that
+    /// couldn't be parsed at launch is first kept as settings.json.bak.
+    pub fn update<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.lock().unwrap();
+        f(&mut settings);
+
+        let path = self.path.as_ref().context("No config directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create config directory")?;
+        }
+
+        if self.unparsable.load(Ordering::SeqCst) {
+            let backup = path.with_extension("json.bak");
+            match std::fs::rename(path, &backup) {
+                Ok(()) => eprintln!("Moved unparsable settings to {}", backup.display()),
+                // Removed since launch; nothing left to keep
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context("Failed to back up unparsable settings"),
+            }
+            self.unparsable.store(false, Ordering::SeqCst);
+        }
+
+        let json = serde_json::to_string_pretty(&*settings).context("Failed to serialize settings")?;
+        // Written aside and renamed into place, so a crash can't leave half a settings file
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json).context("Failed to write settings")?;
+        std::fs::rename(&partial, path).context("Failed to write settings")
+    }
+}
@@ -0,0 +1,190 @@
+use crate::agent_profiles::{self, AgentProfile};
+use crate::settings::{Settings, SettingsStore};
+use crate::shortcut;
+use crate::snippets::{self, Snippet, Snippets};
+use crate::store;
+use crate::text_transform;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+const FORMAT_VERSION: u32 = 1;
+
+// Settings that are secret or only make sense on this machine; never exported, and
+// kept as they are on import
+const LOCAL_ONLY: &[&str] = &[
+    "anthropic_api_key",
+    "agent_command",
+    "agent_profile",
+    "agent_profiles",
+    "agent_secrets",
+    "data_dir",
+    "onboarding",
+    "watched_folders",
+];
+
+/// A portable copy of the user's setup: settings minus LOCAL_ONLY, plus snippets and
+/// agent profiles without their environment values.
+#[derive(Debug, Serialize, Deserialize)]
+struct SettingsFile {
+    format_version: u32,
+    exported_at: i64,
+    settings: serde_json::Map<String, Value>,
+    #[serde(default)]
+    snippets: Vec<Snippet>,
+    #[serde(default)]
+    agent_profiles: Vec<AgentProfile>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ImportSummary {
+    #[ts(as = "f64")]
+    pub snippets_added: usize,
+    #[ts(as = "f64")]
+    pub agent_profiles_added: usize,
+    // Profiles left out because importing them wasn't confirmed; they run commands
+    #[ts(as = "f64")]
+    pub agent_profiles_pending: usize,
+    // Parts that couldn't be applied, e.g. a shortcut already taken on this machine
+    pub warnings: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn export_settings(app_handle: AppHandle, path: String) -> Result<(), String> {
+    export(&app_handle, Path::new(&path)).map_err(|e| format!("Failed to export settings: {}", e))
+}
+
+/// Applies an exported settings file: shortcuts are re-registered right away and
+/// snippets missing here are added. Local-only settings are left untouched. Agent
+/// profiles are only added when `import_agent_profiles` confirms it, and never made
+/// active.
+#[tauri::command]
+pub async fn import_settings(
+    app_handle: AppHandle,
+    path: String,
+    import_agent_profiles: Option<bool>,
+) -> Result<ImportSummary, String> {
+    import(
+        &app_handle,
+        Path::new(&path),
+        import_agent_profiles.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to import settings: {}", e))
+}
+
+fn export(app_handle: &AppHandle, path: &Path) -> Result<()> {
+    let settings = app_handle.state::<SettingsStore>().get();
+    let Value::Object(mut settings) = serde_json::to_value(&settings)? else {
+        return Err(anyhow!("Settings didn't serialize to an object"));
+    };
+    for key in LOCAL_ONLY {
+        settings.remove(*key);
+    }
+
+    let file = SettingsFile {
+        format_version: FORMAT_VERSION,
+        exported_at: store::now_millis(),
+        settings,
+        snippets: app_handle.state::<Snippets>().list(),
+        agent_profiles: exported_profiles(app_handle),
+    };
+    let json = serde_json::to_string_pretty(&file).context("Failed to serialize settings")?;
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+// The profiles with the values of their environment left out, names only
+fn exported_profiles(app_handle: &AppHandle) -> Vec<AgentProfile> {
+    let mut profiles = app_handle.state::<SettingsStore>().get().agent_profiles;
+    for profile in &mut profiles {
+        let mut names: Vec<String> = profile.env.drain().map(|(name, _)| name).collect();
+        if let Some(command) = profile.command.as_mut() {
+            names.extend(command.env.drain().map(|(name, _)| name));
+        }
+        for name in names {
+            if !profile.secret_env.contains(&name) {
+                profile.secret_env.push(name);
+            }
+        }
+    }
+    profiles
+}
+
+fn import(app_handle: &AppHandle, path: &Path, with_profiles: bool) -> Result<ImportSummary> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: SettingsFile = serde_json::from_str(&json).context("Not a settings file")?;
+    if file.format_version > FORMAT_VERSION {
+        return Err(anyhow!(
+            "The file was exported by a newer version of the app"
+        ));
+    }
+
+    let store = app_handle.state::<SettingsStore>();
+    let current = store.get();
+    let Value::Object(local) = serde_json::to_value(&current)? else {
+        return Err(anyhow!("Settings didn't serialize to an object"));
+    };
+    let mut merged = file.settings;
+    for key in LOCAL_ONLY {
+        match local.get(*key) {
+            Some(value) => merged.insert(key.to_string(), value.clone()),
+            None => merged.remove(*key),
+        };
+    }
+    let imported: Settings =
+        serde_json::from_value(Value::Object(merged)).context("Invalid settings")?;
+
+    // Shortcuts go through their own setters, which register them before saving
+    let mut warnings = Vec::new();
+    let global_shortcut = imported
+        .global_shortcut
+        .clone()
+        .unwrap_or_else(|| shortcut::DEFAULT_SHORTCUT.to_string());
+    if let Err(e) =
+        shortcut::set_global_shortcut(app_handle.clone(), store.clone(), global_shortcut)
+    {
+        warnings.push(e);
+    }
+    if let Err(e) = text_transform::set_text_transforms(
+        app_handle.clone(),
+        store.clone(),
+        imported.text_transforms.clone(),
+    ) {
+        warnings.push(e);
+    }
+
+    store.update(|s| {
+        *s = Settings {
+            global_shortcut: s.global_shortcut.clone(),
+            text_transforms: s.text_transforms.clone(),
+            ..imported
+        }
+    })?;
+
+    let (snippets_added, snippet_warnings) = snippets::import(app_handle, file.snippets)?;
+    warnings.extend(snippet_warnings);
+
+    let (agent_profiles_added, agent_profiles_pending) = if with_profiles {
+        let (added, profile_warnings) = agent_profiles::import(app_handle, file.agent_profiles)?;
+        warnings.extend(profile_warnings);
+        (added, 0)
+    } else {
+        (0, file.agent_profiles.len())
+    };
+
+    eprintln!(
+        "[SETTINGS] Imported {} with {} warning(s)",
+        path.display(),
+        warnings.len()
+    );
+    Ok(ImportSummary {
+        snippets_added,
+        agent_profiles_added,
+        agent_profiles_pending,
+        warnings,
+    })
+}
@@ -0,0 +1,69 @@
+use crate::settings::SettingsStore;
+use crate::spaces;
+use tauri::{AppHandle, GlobalShortcutManager, Manager, State};
+
+pub const DEFAULT_SHORTCUT: &str = "CmdOrCtrl+Shift+Space";
+
+/// The accelerator that toggles the main window, as chosen by the user.
+pub fn current(app_handle: &AppHandle) -> String {
+    app_handle
+        .state::<SettingsStore>()
+        .get()
+        .global_shortcut
+        .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string())
+}
+
+/// Registers `accelerator` to show/hide the main window.
+pub fn register(app_handle: &AppHandle, accelerator: &str) -> tauri::Result<()> {
+    let Some(window) = app_handle.get_window("main") else {
+        return Ok(());
+    };
+
+    app_handle
+        .global_shortcut_manager()
+        .register(accelerator, move || {
+            if window.is_visible().unwrap_or(false) {
+                window.hide().unwrap();
+            } else {
+                spaces::prepare_show(&window);
+                window.show().unwrap();
+                window.set_focus().unwrap();
+            }
+        })
+}
+
+pub fn is_registered(app_handle: &AppHandle) -> bool {
+    app_handle
+        .global_shortcut_manager()
+        .is_registered(&current(app_handle))
+        .unwrap_or(false)
+}
+
+/// Swaps the global shortcut. The new accelerator is registered before the old one
+/// is released, so a shortcut taken by another app leaves the current one working.
+#[tauri::command]
+pub fn set_global_shortcut(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    accelerator: String,
+) -> Result<(), String> {
+    let previous = current(&app_handle);
+    if previous == accelerator {
+        return Ok(());
+    }
+
+    register(&app_handle, &accelerator)
+        .map_err(|e| format!("Shortcut {} is unavailable: {}", accelerator, e))?;
+    if let Err(e) = app_handle.global_shortcut_manager().unregister(&previous) {
+        eprintln!("Failed to unregister shortcut {}: {}", previous, e);
+    }
+
+    settings
+        .update(|s| s.global_shortcut = Some(accelerator))
+        .map_err(|e| format!("Failed to save shortcut: {}", e))
+}
+
+#[tauri::command]
+pub fn get_global_shortcut(app_handle: AppHandle) -> String {
+    current(&app_handle)
+}
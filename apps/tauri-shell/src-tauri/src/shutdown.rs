@@ -0,0 +1,72 @@
+use crate::session;
+use crate::standby;
+use crate::AppState;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, ExitRequestApi, Manager};
+
+// Set once quitting has begun; later exit requests are let through
+static QUITTING: AtomicBool = AtomicBool::new(false);
+
+/// Quits the app once the agent has been asked to exit, so it isn't killed mid-write
+/// or left running without its parent. AgentProcess::shutdown bounds the wait.
+pub fn quit(app_handle: &AppHandle) {
+    if QUITTING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    session::mark_clean(app_handle);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::join!(stop_agents(&app_handle), standby::discard(&app_handle));
+        app_handle.exit(0);
+    });
+}
+
+/// Stops the agents and records a clean exit ahead of a restart the app makes itself,
+/// e.g. after moving its data. Returns false if quitting has already begun.
+pub async fn prepare_restart(app_handle: &AppHandle) -> bool {
+    if QUITTING.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    session::mark_clean(app_handle);
+    tokio::join!(stop_agents(app_handle), standby::discard(app_handle));
+    true
+}
+
+/// Holds an exit the OS or Tauri initiated (e.g. Cmd+Q) until the agent is down.
+pub fn on_exit_requested(app_handle: &AppHandle, api: ExitRequestApi) {
+    if QUITTING.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    quit(app_handle);
+}
+
+async fn stop_agents(app_handle: &AppHandle) {
+    // In parallel, so quitting waits for one SHUTDOWN_GRACE at most
+    let shutdowns: Vec<_> = app_handle
+        .state::<AppState>()
+        .slots()
+        .into_iter()
+        .map(|(agent_id, slot)| {
+            tokio::spawn(async move {
+                let Some(process) = slot.lock().await.take() else {
+                    return;
+                };
+
+                eprintln!("[AGENT] Shutting down {} for quit", agent_id);
+                let lost_requests = process.shutdown().await;
+                if !lost_requests.is_empty() {
+                    eprintln!(
+                        "[AGENT] {} request(s) to {} were still in flight at quit",
+                        lost_requests.len(),
+                        agent_id
+                    );
+                }
+            })
+        })
+        .collect();
+    for shutdown in shutdowns {
+        let _ = shutdown.await;
+    }
+}
@@ -0,0 +1,555 @@
+use crate::annotate::ImageAttachment;
+use crate::clipboard;
+use crate::store;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::Window;
+
+// Longest screenshot edge; keeps the attachment well under the agent's 5MB image limit
+const MAX_SCREENSHOT_EDGE: u32 = 1920;
+const JPEG_QUALITY: u8 = 80;
+// Time for the previously active app to regain focus once our window is hidden
+const REFOCUS_DELAY: Duration = Duration::from_millis(250);
+// Clipboard and selection text beyond this is cut off
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// Which parts to include; all of them unless turned off.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct SnapshotOptions {
+    pub screenshot: bool,
+    pub window: bool,
+    pub clipboard: bool,
+    pub selection: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        SnapshotOptions {
+            screenshot: true,
+            window: true,
+            clipboard: true,
+            selection: true,
+        }
+    }
+}
+
+/// What the user was looking at, attached as context to their next message.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EnvironmentSnapshot {
+    pub captured_at: i64,
+    pub screenshot: Option<ImageAttachment>,
+    pub app_name: Option<String>,
+    pub window_title: Option<String>,
+    pub clipboard_text: Option<String>,
+    pub selected_text: Option<String>,
+    // The text parts as one block to prepend to the message
+    pub context: String,
+    // Parts that were requested but couldn't be captured, with the reason
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ForegroundWindow {
+    app_name: Option<String>,
+    title: Option<String>,
+}
+
+/// Hides the calling window so the app the user was working in is frontmost again,
+/// captures the requested parts, then brings the window back.
+#[tauri::command]
+pub async fn attach_environment_snapshot(
+    window: Window,
+    options: Option<SnapshotOptions>,
+) -> Result<EnvironmentSnapshot, String> {
+    let options = options.unwrap_or_default();
+
+    let was_visible = window.is_visible().unwrap_or(false);
+    if was_visible {
+        window
+            .hide()
+            .map_err(|e| format!("Failed to hide window: {}", e))?;
+        yield_focus(&window);
+        tokio::time::sleep(REFOCUS_DELAY).await;
+    }
+
+    let snapshot = tauri::async_runtime::spawn_blocking(move || capture(options))
+        .await
+        .map_err(|e| format!("Snapshot task failed: {}", e));
+
+    if was_visible {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+
+    snapshot
+}
+
+fn capture(options: SnapshotOptions) -> EnvironmentSnapshot {
+    let mut snapshot = EnvironmentSnapshot {
+        captured_at: store::now_millis(),
+        ..Default::default()
+    };
+    let mut errors = Vec::new();
+
+    if options.window {
+        let foreground = foreground_window();
+        snapshot.app_name = foreground.app_name;
+        snapshot.window_title = foreground.title;
+    }
+    if options.selection {
+        // Read before the screenshot, which can take long enough for focus to move
+        snapshot.selected_text = selected_text().map(truncate);
+    }
+    if options.clipboard {
+        match clipboard::read_text() {
+            Ok(text) if !text.trim().is_empty() => snapshot.clipboard_text = Some(truncate(text)),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("clipboard: {}", e)),
+        }
+    }
+    if options.screenshot {
+        match capture_screen().and_then(encode_screenshot) {
+            Ok(attachment) => snapshot.screenshot = Some(attachment),
+            Err(e) => errors.push(format!("screenshot: {}", e)),
+        }
+    }
+
+    snapshot.context = context_text(&snapshot);
+    snapshot.errors = errors;
+    snapshot
+}
+
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text,
+    }
+}
+
+fn context_text(snapshot: &EnvironmentSnapshot) -> String {
+    let mut lines = Vec::new();
+
+    match (&snapshot.app_name, &snapshot.window_title) {
+        (Some(app), Some(title)) => lines.push(format!("Active window: {} — {}", app, title)),
+        (Some(app), None) => lines.push(format!("Active app: {}", app)),
+        (None, Some(title)) => lines.push(format!("Active window: {}", title)),
+        (None, None) => {}
+    }
+    if let Some(text) = &snapshot.selected_text {
+        lines.push(format!("Selected text:\n```\n{}\n```", text));
+    }
+    if let Some(text) = &snapshot.clipboard_text {
+        lines.push(format!("Clipboard:\n```\n{}\n```", text));
+    }
+    if snapshot.screenshot.is_some() {
+        lines.push("A screenshot of the screen is attached.".to_string());
+    }
+
+    lines.join("\n\n")
+}
+
+fn encode_screenshot(image: image::RgbaImage) -> Result<ImageAttachment> {
+    let (width, height) = image.dimensions();
+    let image = image::DynamicImage::ImageRgba8(image);
+    let image = if width.max(height) > MAX_SCREENSHOT_EDGE {
+        image.resize(
+            MAX_SCREENSHOT_EDGE,
+            MAX_SCREENSHOT_EDGE,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        image
+    };
+
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .context("Failed to encode screenshot")?;
+
+    Ok(ImageAttachment {
+        data: base64::engine::general_purpose::STANDARD.encode(jpeg),
+        mime_type: "image/jpeg".to_string(),
+        name: Some("screen.jpg".to_string()),
+    })
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementCreateSystemWide() -> cocoa::base::id;
+    fn AXUIElementCreateApplication(pid: i32) -> cocoa::base::id;
+    fn AXUIElementCopyAttributeValue(
+        element: cocoa::base::id,
+        attribute: cocoa::base::id,
+        value: *mut cocoa::base::id,
+    ) -> i32;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGMainDisplayID() -> u32;
+    fn CGDisplayCreateImage(display: u32) -> cocoa::base::id;
+    fn CGImageRelease(image: cocoa::base::id);
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(object: cocoa::base::id);
+}
+
+// Hiding the window alone leaves the app active; hiding the app returns focus
+#[cfg(target_os = "macos")]
+fn yield_focus(window: &Window) {
+    let _ = window.run_on_main_thread(|| unsafe {
+        use cocoa::appkit::NSApp;
+        use cocoa::base::nil;
+        use objc::{msg_send, sel, sel_impl};
+
+        let _: () = msg_send![NSApp(), hide: nil];
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+fn yield_focus(_window: &Window) {}
+
+#[cfg(target_os = "macos")]
+unsafe fn ns_string_to_string(string: cocoa::base::id) -> Option<String> {
+    use objc::{msg_send, sel, sel_impl};
+
+    let utf8: *const std::os::raw::c_char = msg_send![string, UTF8String];
+    if utf8.is_null() {
+        return None;
+    }
+    Some(
+        std::ffi::CStr::from_ptr(utf8)
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+// Owned (+1) attribute value, or None if unset or not permitted
+#[cfg(target_os = "macos")]
+unsafe fn ax_attribute(element: cocoa::base::id, name: &str) -> Option<cocoa::base::id> {
+    use cocoa::base::nil;
+    use cocoa::foundation::NSString;
+    use objc::{msg_send, sel, sel_impl};
+
+    let attribute = NSString::alloc(nil).init_str(name);
+    let mut value = nil;
+    let status = AXUIElementCopyAttributeValue(element, attribute, &mut value);
+    let _: () = msg_send![attribute, release];
+
+    (status == 0 && value != nil).then_some(value)
+}
+
+// Window titles come from the accessibility API, which onboarding asks permission for
+#[cfg(target_os = "macos")]
+fn foreground_window() -> ForegroundWindow {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let app: id = msg_send![workspace, frontmostApplication];
+        if app == nil {
+            return ForegroundWindow::default();
+        }
+        let name: id = msg_send![app, localizedName];
+        let pid: i32 = msg_send![app, processIdentifier];
+
+        let element = AXUIElementCreateApplication(pid);
+        let title = ax_attribute(element, "AXFocusedWindow").and_then(|focused| {
+            let title = ax_attribute(focused, "AXTitle");
+            CFRelease(focused);
+            let text = title.and_then(|title| ns_string_to_string(title));
+            if let Some(title) = title {
+                CFRelease(title);
+            }
+            text
+        });
+        CFRelease(element);
+
+        ForegroundWindow {
+            app_name: if name == nil {
+                None
+            } else {
+                ns_string_to_string(name)
+            },
+            title: title.filter(|title| !title.is_empty()),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn selected_text() -> Option<String> {
+    unsafe {
+        let system = AXUIElementCreateSystemWide();
+        let focused = ax_attribute(system, "AXFocusedUIElement");
+        CFRelease(system);
+
+        let focused = focused?;
+        let selected = ax_attribute(focused, "AXSelectedText");
+        CFRelease(focused);
+
+        let selected = selected?;
+        let text = ns_string_to_string(selected);
+        CFRelease(selected);
+        text.filter(|text| !text.trim().is_empty())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn capture_screen() -> Result<image::RgbaImage> {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    // NSBitmapImageFileTypePNG
+    const PNG_FILE_TYPE: u64 = 4;
+
+    // Without Screen Recording permission this only contains the wallpaper and our windows
+    let png = unsafe {
+        let image = CGDisplayCreateImage(CGMainDisplayID());
+        if image == nil {
+            return Err(anyhow!("CGDisplayCreateImage returned no image"));
+        }
+
+        let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+        let rep: id = msg_send![rep, initWithCGImage: image];
+        CGImageRelease(image);
+
+        let properties: id = msg_send![class!(NSDictionary), dictionary];
+        let data: id =
+            msg_send![rep, representationUsingType: PNG_FILE_TYPE properties: properties];
+        let png = if data == nil {
+            None
+        } else {
+            let bytes: *const u8 = msg_send![data, bytes];
+            let length: usize = msg_send![data, length];
+            Some(std::slice::from_raw_parts(bytes, length).to_vec())
+        };
+        let _: () = msg_send![rep, release];
+        png.ok_or_else(|| anyhow!("Failed to encode screenshot"))?
+    };
+
+    Ok(image::load_from_memory(&png)
+        .context("Failed to decode screenshot")?
+        .to_rgba8())
+}
+
+#[cfg(windows)]
+fn foreground_window() -> ForegroundWindow {
+    use std::path::Path;
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+    };
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return ForegroundWindow::default();
+        }
+
+        let mut title = [0u16; 512];
+        let length = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+        let title = String::from_utf16_lossy(&title[..length]);
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        let app_name = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid)
+            .ok()
+            .and_then(|process| {
+                let mut path = [0u16; 1024];
+                let mut length = path.len() as u32;
+                let ok = QueryFullProcessImageNameW(
+                    process,
+                    PROCESS_NAME_WIN32,
+                    PWSTR(path.as_mut_ptr()),
+                    &mut length,
+                )
+                .as_bool();
+                CloseHandle(process);
+
+                // "C:\...\Code.exe" -> "Code"
+                ok.then(|| String::from_utf16_lossy(&path[..length as usize]))
+                    .and_then(|path| {
+                        Path::new(&path)
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned())
+                    })
+            });
+
+        ForegroundWindow {
+            app_name,
+            title: Some(title).filter(|title| !title.is_empty()),
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn selected_text() -> Option<String> {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationTextPattern, UIA_TextPatternId,
+    };
+
+    unsafe {
+        // Runs on a blocking-pool thread that may not have joined COM yet
+        let _ = CoInitializeEx(std::ptr::null(), COINIT_MULTITHREADED);
+
+        let automation: IUIAutomation =
+            CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let focused = automation.GetFocusedElement().ok()?;
+        let pattern: IUIAutomationTextPattern =
+            focused.GetCurrentPatternAs(UIA_TextPatternId).ok()?;
+        let ranges = pattern.GetSelection().ok()?;
+
+        let mut text = String::new();
+        for index in 0..ranges.Length().ok()? {
+            if let Ok(range) = ranges.GetElement(index) {
+                if let Ok(part) = range.GetText(-1) {
+                    text.push_str(&part.to_string());
+                }
+            }
+        }
+        Some(text).filter(|text| !text.trim().is_empty())
+    }
+}
+
+#[cfg(windows)]
+fn capture_screen() -> Result<image::RgbaImage> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+        GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, SRCCOPY,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+
+    unsafe {
+        // Primary monitor
+        let width = GetSystemMetrics(SM_CXSCREEN);
+        let height = GetSystemMetrics(SM_CYSCREEN);
+        if width <= 0 || height <= 0 {
+            return Err(anyhow!("No primary display"));
+        }
+
+        let screen_dc = GetDC(HWND::default());
+        let memory_dc = CreateCompatibleDC(screen_dc);
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+
+        let copied = BitBlt(memory_dc, 0, 0, width, height, screen_dc, 0, 0, SRCCOPY).as_bool();
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height: rows top-down
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: 0, // BI_RGB
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let lines = GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            pixels.as_mut_ptr() as *mut c_void,
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        SelectObject(memory_dc, previous);
+        DeleteObject(bitmap);
+        DeleteDC(memory_dc);
+        ReleaseDC(HWND::default(), screen_dc);
+
+        if !copied || lines == 0 {
+            return Err(anyhow!("BitBlt failed"));
+        }
+
+        // BGRX -> RGBA
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+            pixel[3] = 255;
+        }
+        image::RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .ok_or_else(|| anyhow!("Screenshot buffer has the wrong size"))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn foreground_window() -> ForegroundWindow {
+    // X11 only; Wayland doesn't expose other clients' windows
+    let title = command_output("xdotool", &["getactivewindow", "getwindowname"]);
+    let app_name = command_output("xdotool", &["getactivewindow", "getwindowpid"])
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid.trim())).ok())
+        .map(|name| name.trim().to_string());
+
+    ForegroundWindow { app_name, title }
+}
+
+// The primary selection is whatever text is currently highlighted
+#[cfg(target_os = "linux")]
+pub fn selected_text() -> Option<String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        command_output("wl-paste", &["--primary", "--no-newline"])
+    } else {
+        command_output("xclip", &["-o", "-selection", "primary"])
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn capture_screen() -> Result<image::RgbaImage> {
+    Err(anyhow!("Screen capture is not supported on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string(),
+    )
+    .filter(|text| !text.is_empty())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn foreground_window() -> ForegroundWindow {
+    ForegroundWindow::default()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+pub fn selected_text() -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn capture_screen() -> Result<image::RgbaImage> {
+    Err(anyhow!("Screen capture is not supported on this platform"))
+}
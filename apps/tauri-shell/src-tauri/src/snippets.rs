@@ -0,0 +1,326 @@
+use crate::clipboard;
+use crate::data_dir;
+use crate::i18n;
+use crate::snapshot;
+use crate::spaces;
+use crate::store;
+use crate::time_format::{self, TimestampStyle};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, CustomMenuItem, GlobalShortcutManager, Manager, State, SystemTrayMenu,
+    SystemTraySubmenu,
+};
+use ts_rs::TS;
+
+// Tray menu ids for favorites are this prefix plus the snippet id
+pub const TRAY_PREFIX: &str = "snippet:";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    // Text with {{clipboard}}, {{selection}}, {{date}} and {{time}} placeholders
+    pub body: String,
+    // Listed in the tray menu
+    #[serde(default)]
+    pub favorite: bool,
+    // Global accelerator that fires the snippet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+    #[ts(as = "f64")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct SnippetFired<'a> {
+    snippet_id: &'a str,
+    text: &'a str,
+}
+
+/// Prompt templates, persisted as snippets.json in the app data directory.
+pub struct Snippets {
+    path: Option<PathBuf>,
+    items: Mutex<Vec<Snippet>>,
+}
+
+impl Snippets {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let path = data_dir::resolve(app_handle).map(|dir| dir.join("snippets.json"));
+
+        let items = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Snippets {
+            path,
+            items: Mutex::new(items),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Snippet> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Snippet> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|snippet| snippet.id == id)
+            .cloned()
+    }
+
+    pub fn push(&self, snippet: Snippet) -> Result<()> {
+        let mut items = self.items.lock().unwrap();
+        items.push(snippet);
+        self.save(&items)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<Option<Snippet>> {
+        let mut items = self.items.lock().unwrap();
+        let Some(index) = items.iter().position(|snippet| snippet.id == id) else {
+            return Ok(None);
+        };
+
+        let removed = items.remove(index);
+        self.save(&items).map(|_| Some(removed))
+    }
+
+    fn save(&self, items: &[Snippet]) -> Result<()> {
+        let path = self.path.as_ref().context("No data directory available")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+
+        let json = serde_json::to_string(items).context("Failed to serialize snippets")?;
+        std::fs::write(path, json).context("Failed to write snippets")
+    }
+}
+
+#[tauri::command]
+pub fn create_snippet(
+    app_handle: AppHandle,
+    snippets: State<'_, Snippets>,
+    name: String,
+    body: String,
+    favorite: Option<bool>,
+    shortcut: Option<String>,
+) -> Result<Snippet, String> {
+    if name.trim().is_empty() {
+        return Err("Snippet name can't be empty".to_string());
+    }
+
+    let snippet = Snippet {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        body,
+        favorite: favorite.unwrap_or(false),
+        shortcut: shortcut.filter(|shortcut| !shortcut.trim().is_empty()),
+        created_at: store::now_millis(),
+    };
+
+    // Registered first so a taken accelerator doesn't leave a half-created snippet
+    if let Some(shortcut) = &snippet.shortcut {
+        register(&app_handle, shortcut, &snippet.id)
+            .map_err(|e| format!("Shortcut {} is unavailable: {}", shortcut, e))?;
+    }
+    if let Err(e) = snippets.push(snippet.clone()) {
+        if let Some(shortcut) = &snippet.shortcut {
+            let _ = app_handle.global_shortcut_manager().unregister(shortcut);
+        }
+        return Err(format!("Failed to save snippet: {}", e));
+    }
+
+    crate::refresh_tray(&app_handle);
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub fn delete_snippet(
+    app_handle: AppHandle,
+    snippets: State<'_, Snippets>,
+    id: String,
+) -> Result<(), String> {
+    let removed = snippets
+        .remove(&id)
+        .map_err(|e| format!("Failed to delete snippet: {}", e))?
+        .ok_or_else(|| format!("No snippet with id {}", id))?;
+
+    if let Some(shortcut) = &removed.shortcut {
+        if let Err(e) = app_handle.global_shortcut_manager().unregister(shortcut) {
+            eprintln!("Failed to unregister shortcut {}: {}", shortcut, e);
+        }
+    }
+    crate::refresh_tray(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_snippets(snippets: State<'_, Snippets>) -> Vec<Snippet> {
+    snippets.list()
+}
+
+/// Resolves the snippet's placeholders, ready to send as a message. {{selection}} reads
+/// the frontmost app, so it is only useful when fired from a hotkey or the tray.
+#[tauri::command]
+pub async fn expand_snippet(app_handle: AppHandle, id: String) -> Result<String, String> {
+    let snippet = app_handle
+        .state::<Snippets>()
+        .get(&id)
+        .ok_or_else(|| format!("No snippet with id {}", id))?;
+
+    tauri::async_runtime::spawn_blocking(move || expand(&snippet.body))
+        .await
+        .map_err(|e| format!("Expand task failed: {}", e))
+}
+
+/// Adds snippets from another machine that aren't here yet, keyed by id. Snippets
+/// whose shortcut is taken keep their text but lose the shortcut; those are reported.
+pub fn import(app_handle: &AppHandle, imported: Vec<Snippet>) -> Result<(usize, Vec<String>)> {
+    let snippets = app_handle.state::<Snippets>();
+    let mut added = 0;
+    let mut warnings = Vec::new();
+
+    for mut snippet in imported {
+        if snippets.get(&snippet.id).is_some() {
+            continue;
+        }
+        if let Some(shortcut) = snippet.shortcut.clone() {
+            if let Err(e) = register(app_handle, &shortcut, &snippet.id) {
+                warnings.push(format!(
+                    "Snippet {} lost its shortcut {}: {}",
+                    snippet.name, shortcut, e
+                ));
+                snippet.shortcut = None;
+            }
+        }
+        snippets.push(snippet)?;
+        added += 1;
+    }
+
+    crate::refresh_tray(app_handle);
+    Ok((added, warnings))
+}
+
+/// Registers the shortcuts of all stored snippets and lists favorites in the tray.
+pub fn setup(app_handle: &AppHandle) {
+    for snippet in app_handle.state::<Snippets>().list() {
+        if let Some(shortcut) = &snippet.shortcut {
+            if let Err(e) = register(app_handle, shortcut, &snippet.id) {
+                eprintln!("Failed to register snippet shortcut {}: {}", shortcut, e);
+            }
+        }
+    }
+    crate::refresh_tray(app_handle);
+}
+
+/// Expands the snippet while the user's app is still frontmost, then hands the text
+/// to the main window to send.
+pub fn fire(app_handle: &AppHandle, id: &str) {
+    let Some(snippet) = app_handle.state::<Snippets>().get(id) else {
+        eprintln!("Fired unknown snippet {}", id);
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let body = snippet.body.clone();
+        let text = match tauri::async_runtime::spawn_blocking(move || expand(&body)).await {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Failed to expand snippet {}: {}", snippet.id, e);
+                return;
+            }
+        };
+
+        let Some(window) = app_handle.get_window("main") else {
+            return;
+        };
+        spaces::prepare_show(&window);
+        let _ = window.show();
+        let _ = window.set_focus();
+
+        let event = SnippetFired {
+            snippet_id: &snippet.id,
+            text: &text,
+        };
+        if let Err(e) = window.emit("snippet_fired", event) {
+            eprintln!("Failed to emit snippet_fired: {}", e);
+        }
+    });
+}
+
+/// Favorites as a tray submenu, or None when there are none.
+pub fn tray_submenu(app_handle: &AppHandle) -> Option<SystemTraySubmenu> {
+    let favorites: Vec<Snippet> = app_handle
+        .state::<Snippets>()
+        .list()
+        .into_iter()
+        .filter(|snippet| snippet.favorite)
+        .collect();
+    if favorites.is_empty() {
+        return None;
+    }
+
+    let menu = favorites
+        .iter()
+        .fold(SystemTrayMenu::new(), |menu, snippet| {
+            menu.add_item(CustomMenuItem::new(
+                format!("{}{}", TRAY_PREFIX, snippet.id),
+                &snippet.name,
+            ))
+        });
+    Some(SystemTraySubmenu::new(i18n::t("tray-snippets"), menu))
+}
+
+fn register(app_handle: &AppHandle, shortcut: &str, id: &str) -> tauri::Result<()> {
+    let handle = app_handle.clone();
+    let id = id.to_string();
+
+    app_handle
+        .global_shortcut_manager()
+        .register(shortcut, move || fire(&handle, &id))
+}
+
+// Unknown placeholders are left as written
+fn expand(body: &str) -> String {
+    let mut expanded = String::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+
+        let name = rest[start + 2..end].trim();
+        match placeholder(name) {
+            Ok(Some(value)) => expanded.push_str(&value),
+            Ok(None) => expanded.push_str(&rest[start..end + 2]),
+            Err(e) => eprintln!("Failed to expand {{{{{}}}}}: {}", name, e),
+        }
+        rest = &rest[end + 2..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+fn placeholder(name: &str) -> Result<Option<String>> {
+    let now = store::now_millis();
+
+    Ok(Some(match name {
+        "clipboard" => clipboard::read_text()?,
+        "selection" => snapshot::selected_text().ok_or_else(|| anyhow!("Nothing is selected"))?,
+        "date" => time_format::format(now, TimestampStyle::Date),
+        "time" => time_format::format(now, TimestampStyle::Time),
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,323 @@
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, PhysicalPosition, State, Window};
+
+/// Where the main window appears when summoned while the user is on another
+/// macOS Space or Windows virtual desktop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceBehavior {
+    // Move to whichever Space / desktop is active
+    #[default]
+    ActiveSpace,
+    // Stay where it was last shown and switch the user there
+    LastSpace,
+}
+
+/// Keeps the main window on one monitor and desktop regardless of SpaceBehavior.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WindowPin {
+    // Monitor name as reported by the OS; None keeps whichever monitor it is on
+    pub monitor: Option<String>,
+    // Windows virtual desktop GUID as hex. macOS has no public Space ids, so there the
+    // window simply stays on the Space it was on when pinned
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorInfo {
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    // Holds the main window right now
+    pub current: bool,
+}
+
+#[tauri::command]
+pub fn get_space_behavior(settings: State<'_, SettingsStore>) -> SpaceBehavior {
+    settings.get().space_behavior
+}
+
+#[tauri::command]
+pub fn set_space_behavior(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    behavior: SpaceBehavior,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.space_behavior = behavior)
+        .map_err(|e| format!("Failed to save space behavior: {}", e))?;
+
+    apply(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_monitors(app_handle: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    let window = main_window(&app_handle)?;
+    let current = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+    let monitors = window
+        .available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+
+    Ok(monitors
+        .into_iter()
+        .filter_map(|monitor| {
+            let id = monitor.name()?.clone();
+            Some(MonitorInfo {
+                current: current.as_deref() == Some(id.as_str()),
+                width: monitor.size().width,
+                height: monitor.size().height,
+                scale_factor: monitor.scale_factor(),
+                id,
+            })
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_window_pin(settings: State<'_, SettingsStore>) -> Option<WindowPin> {
+    settings.get().window_pin
+}
+
+/// Pins the main window to `monitor_id` (from list_monitors) and `workspace`. Either
+/// defaults to where the window is now, so pinning with neither freezes it in place.
+#[tauri::command]
+pub fn pin_window(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    monitor_id: Option<String>,
+    workspace: Option<String>,
+) -> Result<WindowPin, String> {
+    let window = main_window(&app_handle)?;
+    let monitor = match monitor_id {
+        Some(id) => {
+            let known = window
+                .available_monitors()
+                .map_err(|e| format!("Failed to list monitors: {}", e))?
+                .iter()
+                .any(|monitor| monitor.name() == Some(&id));
+            if !known {
+                return Err(format!("No monitor named {}", id));
+            }
+            Some(id)
+        }
+        None => window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .and_then(|monitor| monitor.name().cloned()),
+    };
+    let pin = WindowPin {
+        monitor,
+        workspace: workspace.or_else(|| current_desktop(&window)),
+    };
+
+    settings
+        .update(|s| s.window_pin = Some(pin.clone()))
+        .map_err(|e| format!("Failed to save window pin: {}", e))?;
+    apply(&app_handle);
+    keep_pinned(&window, &pin);
+    Ok(pin)
+}
+
+/// Lets the window follow SpaceBehavior again.
+#[tauri::command]
+pub fn unpin_window(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.window_pin = None)
+        .map_err(|e| format!("Failed to save window pin: {}", e))?;
+    apply(&app_handle);
+    Ok(())
+}
+
+/// Applies the saved behavior to the main window. Called at startup and on change.
+pub fn apply(app_handle: &AppHandle) {
+    let Some(window) = app_handle.get_window("main") else {
+        return;
+    };
+    let settings = app_handle.state::<SettingsStore>().get();
+    // A pinned window must not be pulled onto the active Space
+    let behavior = match settings.window_pin {
+        Some(_) => SpaceBehavior::LastSpace,
+        None => settings.space_behavior,
+    };
+
+    let window_clone = window.clone();
+    let result =
+        window.run_on_main_thread(move || set_collection_behavior(&window_clone, behavior));
+    if let Err(e) = result {
+        eprintln!("Failed to apply space behavior: {}", e);
+    }
+}
+
+/// Call right before showing the main window. Windows has no collection behavior,
+/// so the window is moved onto the current virtual desktop by hand.
+pub fn prepare_show(window: &Window) {
+    let settings = window.state::<SettingsStore>().get();
+    if let Some(pin) = &settings.window_pin {
+        keep_pinned(window, pin);
+    } else if settings.space_behavior == SpaceBehavior::ActiveSpace {
+        move_to_current_desktop(window);
+    }
+}
+
+fn main_window(app_handle: &AppHandle) -> Result<Window, String> {
+    app_handle
+        .get_window("main")
+        .ok_or_else(|| "Main window not found".to_string())
+}
+
+// Moves the window back onto its pinned monitor and desktop if it left them
+fn keep_pinned(window: &Window, pin: &WindowPin) {
+    if let Some(monitor) = &pin.monitor {
+        move_to_monitor(window, monitor);
+    }
+    if let Some(workspace) = &pin.workspace {
+        move_to_desktop(window, workspace);
+    }
+}
+
+// Centers the window on `name` unless it is already there. A disconnected monitor
+// leaves the window where it is.
+fn move_to_monitor(window: &Window, name: &str) {
+    let on_monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .is_some_and(|monitor| monitor.name().map(String::as_str) == Some(name));
+    if on_monitor {
+        return;
+    }
+    let Some(monitor) = window
+        .available_monitors()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|monitor| monitor.name().map(String::as_str) == Some(name))
+    else {
+        return;
+    };
+
+    let size = window.outer_size().unwrap_or_default();
+    let x = monitor.position().x + (monitor.size().width as i32 - size.width as i32).max(0) / 2;
+    let y = monitor.position().y + (monitor.size().height as i32 - size.height as i32).max(0) / 2;
+    if let Err(e) = window.set_position(PhysicalPosition::new(x, y)) {
+        eprintln!("Failed to move window to monitor {}: {}", name, e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn set_collection_behavior(window: &Window, behavior: SpaceBehavior) {
+    use cocoa::base::id;
+    use objc::{msg_send, sel, sel_impl};
+
+    // NSWindowCollectionBehaviorMoveToActiveSpace
+    const MOVE_TO_ACTIVE_SPACE: u64 = 1 << 1;
+
+    let Ok(ns_window) = window.ns_window() else {
+        return;
+    };
+
+    unsafe {
+        let ns_window = ns_window as id;
+        let current: u64 = msg_send![ns_window, collectionBehavior];
+        let updated = match behavior {
+            SpaceBehavior::ActiveSpace => current | MOVE_TO_ACTIVE_SPACE,
+            SpaceBehavior::LastSpace => current & !MOVE_TO_ACTIVE_SPACE,
+        };
+        let _: () = msg_send![ns_window, setCollectionBehavior: updated];
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_collection_behavior(_window: &Window, _behavior: SpaceBehavior) {}
+
+#[cfg(windows)]
+fn move_to_current_desktop(window: &Window) {
+    use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    let (Ok(hwnd), Some(manager)) = (window.hwnd(), virtual_desktop_manager()) else {
+        return;
+    };
+
+    unsafe {
+        if manager
+            .IsWindowOnCurrentVirtualDesktop(hwnd)
+            .map(|on_current| on_current.as_bool())
+            .unwrap_or(true)
+        {
+            return;
+        }
+
+        // The app the user is in when pressing the shortcut sits on the current desktop
+        let foreground = GetForegroundWindow();
+        if foreground.0 == 0 {
+            return;
+        }
+        let result = manager
+            .GetWindowDesktopId(foreground)
+            .and_then(|desktop| manager.MoveWindowToDesktop(hwnd, &desktop));
+        if let Err(e) = result {
+            eprintln!("Failed to move window to the current desktop: {}", e);
+        }
+    }
+}
+
+// Linux window managers map a shown window onto the current workspace already
+#[cfg(not(windows))]
+fn move_to_current_desktop(_window: &Window) {}
+
+#[cfg(windows)]
+fn virtual_desktop_manager() -> Option<windows::Win32::UI::Shell::IVirtualDesktopManager> {
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::VirtualDesktopManager;
+
+    match unsafe { CoCreateInstance(&VirtualDesktopManager, None, CLSCTX_INPROC_SERVER) } {
+        Ok(manager) => Some(manager),
+        Err(e) => {
+            eprintln!("Failed to create IVirtualDesktopManager: {}", e);
+            None
+        }
+    }
+}
+
+// GUID of the virtual desktop the window is on, as hex
+#[cfg(windows)]
+fn current_desktop(window: &Window) -> Option<String> {
+    let hwnd = window.hwnd().ok()?;
+    let desktop = unsafe { virtual_desktop_manager()?.GetWindowDesktopId(hwnd).ok()? };
+    Some(format!("{:032x}", desktop.to_u128()))
+}
+
+#[cfg(not(windows))]
+fn current_desktop(_window: &Window) -> Option<String> {
+    None
+}
+
+#[cfg(windows)]
+fn move_to_desktop(window: &Window, workspace: &str) {
+    use windows::core::GUID;
+
+    let Ok(id) = u128::from_str_radix(workspace, 16) else {
+        eprintln!("Invalid virtual desktop id {}", workspace);
+        return;
+    };
+    let (Ok(hwnd), Some(manager)) = (window.hwnd(), virtual_desktop_manager()) else {
+        return;
+    };
+    // Fails when that desktop has been closed; the window then stays put
+    if let Err(e) = unsafe { manager.MoveWindowToDesktop(hwnd, &GUID::from_u128(id)) } {
+        eprintln!("Failed to move window to its pinned desktop: {}", e);
+    }
+}
+
+#[cfg(not(windows))]
+fn move_to_desktop(_window: &Window, _workspace: &str) {}
@@ -0,0 +1,268 @@
+use crate::data_dir;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+// Replies that grow past this are written to disk and no longer streamed token by token
+pub const SPILL_THRESHOLD: usize = 256 * 1024;
+
+// New text a single response_window event announces at most once spilled
+const WINDOW_BYTES: usize = 16 * 1024;
+
+// Spilled files are only a reading aid (the store keeps the full reply), so old ones go
+const RESPONSE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Bytes of a spilled reply already written to its file and announced to the webview.
+#[derive(Debug, Default)]
+pub struct Spill {
+    written: usize,
+    announced: usize,
+    // Kept open for the rest of the stream once created
+    file: Option<std::fs::File>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct ResponseSpilled<'a> {
+    id: &'a str,
+    path: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct ResponseWindow<'a> {
+    id: &'a str,
+    #[ts(as = "f64")]
+    offset: usize,
+    #[ts(as = "f64")]
+    length: usize,
+    #[ts(as = "f64")]
+    total: usize,
+    done: bool,
+}
+
+/// UTF-8 text of a spilled reply. `offset` and `end` are the byte positions actually
+/// returned, snapped to character boundaries; continue reading from `end`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct ResponseRange {
+    pub text: String,
+    #[ts(as = "f64")]
+    pub offset: u64,
+    #[ts(as = "f64")]
+    pub end: u64,
+    #[ts(as = "f64")]
+    pub total: u64,
+}
+
+#[tauri::command]
+pub async fn read_response_range(
+    app_handle: AppHandle,
+    id: String,
+    offset: u64,
+    len: u64,
+) -> Result<ResponseRange, String> {
+    let path = response_path(&app_handle, &id).map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || read_range(&path, offset, len))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))?
+        .map_err(|e| format!("Failed to read response {}: {}", id, e))
+}
+
+/// Called after each token with the reply so far. Returns true once the reply lives on
+/// disk, in which case the token should not be forwarded as an agent_response.
+pub fn on_token(
+    app_handle: &AppHandle,
+    owner: Option<&str>,
+    id: &str,
+    response: &str,
+    spill: &mut Option<Spill>,
+) -> bool {
+    if spill.is_none() && response.len() < SPILL_THRESHOLD {
+        return false;
+    }
+
+    let first = spill.is_none();
+    let state = spill.get_or_insert_with(Spill::default);
+    let unwritten = &response[state.written..];
+    match append(app_handle, id, state, unwritten) {
+        Ok(path) => {
+            state.written = response.len();
+            if let (true, Some(path)) = (first, path) {
+                let event = ResponseSpilled {
+                    id,
+                    path: path.to_string_lossy().into_owned(),
+                };
+                emit(app_handle, owner, "response_spilled", event);
+            }
+        }
+        // Keep streaming normally if the file can't even be created
+        Err(e) if first => {
+            eprintln!("Failed to spill response {}: {}", id, e);
+            *spill = None;
+            return false;
+        }
+        // Otherwise the unwritten tail is retried with the next token
+        Err(e) => eprintln!("Failed to spill response {}: {}", id, e),
+    }
+
+    if first || state.written - state.announced >= WINDOW_BYTES {
+        announce(app_handle, owner, id, state, false);
+    }
+    true
+}
+
+/// Writes whatever is left of a spilled reply and announces the final window.
+pub fn finish(
+    app_handle: &AppHandle,
+    owner: Option<&str>,
+    id: &str,
+    response: &str,
+    spill: &mut Spill,
+) {
+    let unwritten = &response[spill.written..];
+    match append(app_handle, id, spill, unwritten) {
+        Ok(_) => spill.written = response.len(),
+        Err(e) => eprintln!("Failed to spill response {}: {}", id, e),
+    }
+    announce(app_handle, owner, id, spill, true);
+}
+
+/// Removes spilled replies older than RESPONSE_TTL.
+pub fn prune(app_handle: &AppHandle) {
+    let Some(dir) = responses_dir(app_handle) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age >= RESPONSE_TTL)
+            .unwrap_or(false);
+        if expired {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                eprintln!(
+                    "Failed to remove spilled response {:?}: {}",
+                    entry.path(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+fn announce(app_handle: &AppHandle, owner: Option<&str>, id: &str, spill: &mut Spill, done: bool) {
+    let event = ResponseWindow {
+        id,
+        offset: spill.announced,
+        length: spill.written - spill.announced,
+        total: spill.written,
+        done,
+    };
+    emit(app_handle, owner, "response_window", event);
+    spill.announced = spill.written;
+}
+
+fn emit<S: Serialize + Clone>(
+    app_handle: &AppHandle,
+    owner: Option<&str>,
+    event: &str,
+    payload: S,
+) {
+    let result = match owner {
+        Some(label) => app_handle.emit_to(label, event, payload),
+        None => app_handle.emit_all(event, payload),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
+
+pub fn responses_dir(app_handle: &AppHandle) -> Option<PathBuf> {
+    data_dir::resolve(app_handle).map(|dir| dir.join("responses"))
+}
+
+fn response_path(app_handle: &AppHandle, id: &str) -> Result<PathBuf> {
+    // Request ids are uuids; anything else could escape the directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(anyhow!("Invalid response id {}", id));
+    }
+    let dir = responses_dir(app_handle).context("No data directory available")?;
+    Ok(dir.join(format!("{}.txt", id)))
+}
+
+// Writes `text` to the reply's file, creating it on first use. Returns the path when
+// the file was created.
+fn append(
+    app_handle: &AppHandle,
+    id: &str,
+    spill: &mut Spill,
+    text: &str,
+) -> Result<Option<PathBuf>> {
+    let mut created = None;
+    let file = match &mut spill.file {
+        Some(file) => file,
+        None => {
+            let path = response_path(app_handle, id)?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).context("Failed to create responses directory")?;
+            }
+            // Truncated: a retried reply starts over under the same id
+            let file = std::fs::File::create(&path).context("Failed to open response file")?;
+            created = Some(path);
+            spill.file.insert(file)
+        }
+    };
+    file.write_all(text.as_bytes())
+        .context("Failed to write response file")?;
+    Ok(created)
+}
+
+fn read_range(path: &std::path::Path, offset: u64, len: u64) -> Result<ResponseRange> {
+    let mut file = std::fs::File::open(path).context("Response not found")?;
+    let total = file.metadata()?.len();
+    let start = offset.min(total);
+    let end = start.saturating_add(len).min(total);
+
+    // Up to 3 extra bytes either side so a split character can be completed or dropped
+    let read_start = start.saturating_sub(3);
+    let read_end = end.saturating_add(3).min(total);
+    let mut bytes = vec![0u8; (read_end - read_start) as usize];
+    file.seek(SeekFrom::Start(read_start))?;
+    file.read_exact(&mut bytes)?;
+
+    let is_boundary = |position: u64| {
+        position == total
+            || bytes
+                .get((position - read_start) as usize)
+                .map(|byte| (*byte as i8) >= -0x40)
+                .unwrap_or(true)
+    };
+    let mut start = start;
+    while start < end && !is_boundary(start) {
+        start += 1;
+    }
+    let mut end = end;
+    while end > start && !is_boundary(end) {
+        end -= 1;
+    }
+
+    let slice = &bytes[(start - read_start) as usize..(end - read_start) as usize];
+    Ok(ResponseRange {
+        text: String::from_utf8_lossy(slice).into_owned(),
+        offset: start,
+        end,
+        total,
+    })
+}
@@ -0,0 +1,76 @@
+use crate::agent_backend::AgentStdin;
+use crate::agent_ipc::{write_request, AgentRequest};
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use ts_rs::TS;
+
+// A generation with no output for this long counts as stalled
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+// How often in-flight requests are checked (and stream_stalled re-emitted)
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// With auto-retry enabled, a message is retried at most this many times
+pub const MAX_STALL_RETRIES: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct StreamStalled<'a> {
+    id: &'a str,
+    #[ts(as = "f64")]
+    elapsed_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct StreamRetried<'a> {
+    id: &'a str,
+}
+
+/// Tells the issuing window that `id` has produced no output for `elapsed`.
+pub fn notify(app_handle: &AppHandle, owner: Option<&str>, id: &str, elapsed: Duration) {
+    let event = StreamStalled {
+        id,
+        elapsed_secs: elapsed.as_secs(),
+    };
+    emit(app_handle, owner, "stream_stalled", event);
+}
+
+/// Interrupts the stalled `request` and sends it again. The webview drops what it
+/// streamed so far on `stream_retried`; the retry streams under the same id.
+pub async fn interrupt_and_retry(
+    app_handle: &AppHandle,
+    stdin: &Mutex<AgentStdin>,
+    owner: Option<&str>,
+    request: &AgentRequest,
+) -> Result<()> {
+    eprintln!("[STALL] Retrying {}", request.id());
+
+    let interrupt = AgentRequest::Interrupt {
+        id: request.id().to_string(),
+    };
+    write_request(stdin, &interrupt).await?;
+    write_request(stdin, request).await?;
+
+    let event = StreamRetried { id: request.id() };
+    emit(app_handle, owner, "stream_retried", event);
+    Ok(())
+}
+
+fn emit<S: Serialize + Clone>(
+    app_handle: &AppHandle,
+    owner: Option<&str>,
+    event: &str,
+    payload: S,
+) {
+    let result = match owner {
+        Some(label) => app_handle.emit_to(label, event, payload),
+        None => app_handle.emit_all(event, payload),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
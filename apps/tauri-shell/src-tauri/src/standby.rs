@@ -0,0 +1,92 @@
+use crate::agent_ipc::{AgentProcess, DEFAULT_AGENT_ID};
+use crate::settings::SettingsStore;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+
+/// A second agent process, spawned and idle, promoted when the primary dies so
+/// recovery skips the cold start. Only kept while the warm_standby setting is on.
+#[derive(Default)]
+pub struct Standby {
+    process: Mutex<Option<AgentProcess>>,
+    spawning: AtomicBool,
+}
+
+#[tauri::command]
+pub fn get_warm_standby_enabled(settings: State<'_, SettingsStore>) -> bool {
+    settings.get().warm_standby
+}
+
+#[tauri::command]
+pub async fn set_warm_standby_enabled(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.warm_standby = enabled)
+        .map_err(|e| format!("Failed to save warm standby setting: {}", e))?;
+
+    if enabled {
+        replenish(&app_handle);
+    } else {
+        discard(&app_handle).await;
+    }
+    Ok(())
+}
+
+/// Spawns a standby in the background unless one exists or is already starting.
+pub fn replenish(app_handle: &AppHandle) {
+    if !app_handle.state::<SettingsStore>().get().warm_standby {
+        return;
+    }
+    let standby = app_handle.state::<Standby>();
+    if standby.spawning.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let standby = app_handle.state::<Standby>();
+        if standby.process.lock().await.is_none() {
+            match spawn_ready(&app_handle).await {
+                Some(process) => *standby.process.lock().await = Some(process),
+                None => eprintln!("[STANDBY] Failed to start a standby agent"),
+            }
+        }
+        standby.spawning.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Hands over the standby if it is still alive and starts warming the next one.
+pub async fn take(app_handle: &AppHandle) -> Option<AgentProcess> {
+    let taken = app_handle.state::<Standby>().process.lock().await.take();
+    replenish(app_handle);
+
+    let mut process = taken?;
+    if process.has_exited() {
+        eprintln!("[STANDBY] Standby agent had exited, spawning a fresh one");
+        return None;
+    }
+    eprintln!("[STANDBY] Promoting standby agent");
+    Some(process)
+}
+
+/// Shuts the standby down, e.g. because it runs an outdated agent build or the app
+/// is quitting.
+pub async fn discard(app_handle: &AppHandle) {
+    let taken = app_handle.state::<Standby>().process.lock().await.take();
+    if let Some(process) = taken {
+        process.shutdown().await;
+    }
+}
+
+async fn spawn_ready(app_handle: &AppHandle) -> Option<AgentProcess> {
+    match AgentProcess::spawn(app_handle.clone(), DEFAULT_AGENT_ID).await {
+        Ok(process) => Some(process),
+        Err(e) => {
+            eprintln!("[STANDBY] {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::migration::{self, PendingMigration};
+use crate::onboarding::{self, AgentDetection, PermissionState, Permissions};
+use crate::secrets;
+use crate::session::{self, Session};
+use crate::settings::SettingsStore;
+use crate::store;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+
+/// Everything checked at launch, emitted once as startup_report. `warnings` lists
+/// what needs the user's attention; the rest is there for details.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct StartupReport {
+    pub agent: AgentDetection,
+    // Set when settings.json couldn't be parsed and defaults are in use; the first save
+    // moves the file to settings.json.bak
+    pub settings_error: Option<String>,
+    // Set when the keychain can't be used, so no secret reaches the agent
+    pub keychain_error: Option<String>,
+    pub unreadable_secrets: Vec<String>,
+    pub permissions: Permissions,
+    pub pending_migrations: Vec<PendingMigration>,
+    // The previous run crashed or was force-quit and can be restored
+    pub unclean_shutdown: bool,
+    pub interrupted_messages: usize,
+    pub warnings: Vec<StartupWarning>,
+    #[ts(as = "f64")]
+    pub generated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct StartupWarning {
+    // Stable identifier the UI picks its action by, e.g. agent_missing
+    pub code: String,
+    pub message: String,
+}
+
+/// The report once the launch checks have finished, for windows that missed the event.
+#[derive(Default)]
+pub struct StartupReports {
+    report: Mutex<Option<StartupReport>>,
+}
+
+#[tauri::command]
+pub fn get_startup_report(reports: State<'_, StartupReports>) -> Option<StartupReport> {
+    reports.report.lock().unwrap().clone()
+}
+
+/// Runs the launch checks in the background and emits startup_report when done.
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let report = check(&app_handle).await;
+        for warning in &report.warnings {
+            eprintln!("[STARTUP] {}: {}", warning.code, warning.message);
+        }
+        *app_handle.state::<StartupReports>().report.lock().unwrap() = Some(report.clone());
+        if let Err(e) = app_handle.emit_all("startup_report", report) {
+            eprintln!("Failed to emit startup_report: {}", e);
+        }
+    });
+}
+
+async fn check(app_handle: &AppHandle) -> StartupReport {
+    let agent = onboarding::detect(app_handle).await;
+    let settings_error = app_handle
+        .state::<SettingsStore>()
+        .load_error()
+        .map(str::to_string);
+    let keychain_error = secrets::probe().err().map(|e| format!("{:#}", e));
+    // Every secret is unreadable without a keychain; that is reported once above
+    let unreadable_secrets = match keychain_error {
+        Some(_) => Vec::new(),
+        None => secrets::unreadable(app_handle),
+    };
+    let permissions = onboarding::permissions();
+    let pending_migrations = migration::detect_migrations(app_handle.clone());
+    let unclean = session::get_unclean_session(app_handle.state::<Session>());
+
+    let mut report = StartupReport {
+        agent,
+        settings_error,
+        keychain_error,
+        unreadable_secrets,
+        permissions,
+        pending_migrations,
+        unclean_shutdown: unclean.is_some(),
+        interrupted_messages: unclean.map_or(0, |snapshot| snapshot.interrupted.len()),
+        warnings: Vec::new(),
+        generated_at: store::now_millis(),
+    };
+    report.warnings = warnings(&report);
+    report
+}
+
+fn warnings(report: &StartupReport) -> Vec<StartupWarning> {
+    let mut warnings = Vec::new();
+    let mut warn = |code: &str, message: String| {
+        warnings.push(StartupWarning {
+            code: code.to_string(),
+            message,
+        })
+    };
+
+    if report.agent.source.is_none() {
+        warn("agent_missing", "No agent runtime was found".to_string());
+    } else if !report.agent.found {
+        warn(
+            "node_missing",
+            "Node.js is needed to run the agent but was not found".to_string(),
+        );
+    }
+    if let Some(error) = &report.settings_error {
+        warn(
+            "settings_unreadable",
+            format!(
+                "Settings could not be read and defaults are in use: {}",
+                error
+            ),
+        );
+    }
+    if let Some(error) = &report.keychain_error {
+        warn("keychain_unreachable", error.clone());
+    }
+    for name in &report.unreadable_secrets {
+        warn(
+            "secret_unreadable",
+            format!("{} could not be read from the keychain", name),
+        );
+    }
+    let permissions = [
+        ("Accessibility", report.permissions.accessibility),
+        ("Screen recording", report.permissions.screen_recording),
+        ("Microphone", report.permissions.microphone),
+    ];
+    for (name, state) in permissions {
+        if state == PermissionState::Denied {
+            warn(
+                "permission_denied",
+                format!("{} permission is denied", name),
+            );
+        }
+    }
+    for migration in &report.pending_migrations {
+        if !migration.awaiting_confirmation {
+            warn(
+                "migration_pending",
+                format!("History at {} has not been imported", migration.path),
+            );
+        }
+    }
+    if report.unclean_shutdown {
+        warn(
+            "unclean_shutdown",
+            format!(
+                "The last session ended unexpectedly with {} message(s) unanswered",
+                report.interrupted_messages
+            ),
+        );
+    }
+    warnings
+}
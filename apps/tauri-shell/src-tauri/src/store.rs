@@ -0,0 +1,367 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::AppHandle;
+use ts_rs::TS;
+
+// Conversation used when the frontend doesn't specify one
+pub const DEFAULT_CONVERSATION_ID: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: String,
+    pub role: String, // "user" | "assistant" | "divider" (marks merged-in messages)
+    pub content: String,
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<String>, // JSON string of image attachments, as sent to the agent
+    // Model that wrote a reply, when the agent reported usage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    // Tools called while writing a reply, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub messages: Vec<StoredMessage>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    // Read-only: the shell refuses new messages, drafts and merges
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+}
+
+impl Conversation {
+    /// Finds a message by id and role, as user messages and their replies share the
+    /// request id.
+    pub fn find_message(&self, message_id: &str, role: &str) -> Option<&StoredMessage> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.id == message_id && m.role == role)
+    }
+
+    /// The latest message with id `message_id`: the reply, once the request has one.
+    pub fn latest_message(&self, message_id: &str) -> Option<&StoredMessage> {
+        self.messages.iter().rev().find(|m| m.id == message_id)
+    }
+}
+
+/// Half-written prompt for a conversation, kept across restarts and window toggles.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Draft {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<String>, // JSON string of image attachments, as sent to the agent
+    #[ts(as = "f64")]
+    pub updated_at: i64,
+}
+
+/// Summary of the start of a long conversation, written by checkpoints so the agent
+/// can be given it in place of the messages it covers.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Checkpoint {
+    pub summary: String,
+    // Leading messages of the conversation the summary stands in for
+    pub covered_messages: usize,
+    #[ts(as = "f64")]
+    pub created_at: i64,
+}
+
+/// Shell-side transcript of every conversation that passed through the IPC layer,
+/// stored as one JSON file per conversation in the app data directory.
+pub struct ConversationStore {
+    dir: Option<PathBuf>,
+    drafts_dir: Option<PathBuf>,
+    checkpoints_dir: Option<PathBuf>,
+    // Serializes read-modify-write cycles on the conversation files
+    write_lock: Mutex<()>,
+}
+
+impl ConversationStore {
+    pub fn open(app_handle: &AppHandle) -> Self {
+        let data_dir = crate::data_dir::resolve(app_handle);
+
+        ConversationStore {
+            dir: data_dir.as_ref().map(|dir| dir.join("conversations")),
+            drafts_dir: data_dir.as_ref().map(|dir| dir.join("drafts")),
+            checkpoints_dir: data_dir.as_ref().map(|dir| dir.join("checkpoints")),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn load(&self, conversation_id: &str) -> Result<Conversation> {
+        let path = self.path_for(conversation_id)?;
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Conversation {} not found", conversation_id))?;
+
+        serde_json::from_str(&json).context("Failed to parse conversation")
+    }
+
+    pub fn append_message(&self, conversation_id: &str, message: StoredMessage) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+
+        // Anything but a missing file is left alone rather than replaced by a new thread
+        let mut conversation = match self.load(conversation_id) {
+            Ok(conversation) => conversation,
+            Err(e) if !is_not_found(&e) => return Err(e),
+            Err(_) => Conversation {
+                id: conversation_id.to_string(),
+                title: title_from(&message.content),
+                messages: Vec::new(),
+                created_at: message.timestamp,
+                updated_at: message.timestamp,
+                locked: false,
+            },
+        };
+
+        // Replayed requests (respawn, outbox) are recorded only once
+        let duplicate = conversation
+            .messages
+            .iter()
+            .any(|m| m.id == message.id && m.role == message.role);
+        if duplicate {
+            return Ok(());
+        }
+
+        conversation.updated_at = message.timestamp;
+        conversation.messages.push(message);
+
+        self.save(&conversation)
+    }
+
+    pub fn save(&self, conversation: &Conversation) -> Result<()> {
+        let path = self.path_for(&conversation.id)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create conversations directory")?;
+        }
+
+        let json =
+            serde_json::to_string(conversation).context("Failed to serialize conversation")?;
+        // Written aside and renamed into place, so a crash can't leave half a transcript
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json).context("Failed to write conversation")?;
+        std::fs::rename(&partial, &path).context("Failed to write conversation")
+    }
+
+    /// Marks the conversation read-only, or writable again. Its timestamps are kept.
+    pub fn set_locked(&self, conversation_id: &str, locked: bool) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let mut conversation = self.load(conversation_id)?;
+        conversation.locked = locked;
+        self.save(&conversation)
+    }
+
+    /// Whether the conversation is read-only; unknown conversations aren't.
+    pub fn is_locked(&self, conversation_id: &str) -> bool {
+        self.load(conversation_id)
+            .map(|conversation| conversation.locked)
+            .unwrap_or(false)
+    }
+
+    /// Moves every message of `source_id` into `target_id` and deletes the source.
+    /// Turns (a user message and what followed it) are interleaved by start time,
+    /// with a divider naming the origin wherever the thread switches between the two.
+    pub fn merge(&self, source_id: &str, target_id: &str) -> Result<Conversation> {
+        let _guard = self.write_lock.lock().unwrap();
+        let source = self.load(source_id)?;
+        let mut target = self.load(target_id)?;
+
+        let mut turns = split_turns(std::mem::take(&mut target.messages), false);
+        turns.extend(split_turns(source.messages, true));
+        // Stable, so a target turn stays ahead of a source turn with the same timestamp
+        turns.sort_by_key(|(_, turn)| turn[0].timestamp);
+
+        let mut from_source = false;
+        for (is_source, turn) in turns {
+            if is_source != from_source {
+                let title = if is_source {
+                    &source.title
+                } else {
+                    &target.title
+                };
+                target.messages.push(StoredMessage {
+                    id: format!("merge-{}", uuid::Uuid::new_v4()),
+                    role: "divider".to_string(),
+                    content: format!("Merged from \"{}\"", title),
+                    timestamp: turn[0].timestamp,
+                    images: None,
+                    model: None,
+                    tools: Vec::new(),
+                });
+                from_source = is_source;
+            }
+            target.messages.extend(turn);
+        }
+        target.created_at = target.created_at.min(source.created_at);
+        target.updated_at = now_millis();
+
+        self.save(&target)?;
+        std::fs::remove_file(self.path_for(source_id)?)
+            .context("Failed to delete merged conversation")?;
+        // Neither summary describes the interleaved thread; the next reply makes a new one
+        self.clear_checkpoint(source_id)?;
+        self.clear_checkpoint(target_id)?;
+        Ok(target)
+    }
+
+    pub fn delete(&self, conversation_id: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let path = self.path_for(conversation_id)?;
+
+        std::fs::remove_file(&path).context("Failed to delete conversation")?;
+        self.clear_checkpoint(conversation_id)
+    }
+
+    /// Ids of all stored conversations with their file modification times.
+    pub fn list_modified(&self) -> Result<Vec<(String, SystemTime)>> {
+        let dir = self.dir.as_ref().context("No data directory available")?;
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Failed to list conversations"),
+        };
+
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                let id = path.file_stem()?.to_str()?.to_string();
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                Some((id, modified))
+            })
+            .collect())
+    }
+
+    pub fn load_draft(&self, conversation_id: &str) -> Result<Option<Draft>> {
+        let path = file_in(self.drafts_dir.as_ref(), conversation_id)?;
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+
+        serde_json::from_str(&json)
+            .map(Some)
+            .context("Failed to parse draft")
+    }
+
+    /// Writes the draft, or removes it once the text and attachments are empty.
+    pub fn save_draft(&self, conversation_id: &str, draft: &Draft) -> Result<()> {
+        let path = file_in(self.drafts_dir.as_ref(), conversation_id)?;
+
+        if draft.text.trim().is_empty() && draft.attachments.is_none() {
+            return self.clear_draft(conversation_id);
+        }
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create drafts directory")?;
+        }
+        let json = serde_json::to_string(draft).context("Failed to serialize draft")?;
+        std::fs::write(&path, json).context("Failed to write draft")
+    }
+
+    pub fn clear_draft(&self, conversation_id: &str) -> Result<()> {
+        let path = file_in(self.drafts_dir.as_ref(), conversation_id)?;
+
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to delete draft")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn load_checkpoint(&self, conversation_id: &str) -> Result<Option<Checkpoint>> {
+        let path = file_in(self.checkpoints_dir.as_ref(), conversation_id)?;
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+
+        serde_json::from_str(&json)
+            .map(Some)
+            .context("Failed to parse checkpoint")
+    }
+
+    pub fn save_checkpoint(&self, conversation_id: &str, checkpoint: &Checkpoint) -> Result<()> {
+        let path = file_in(self.checkpoints_dir.as_ref(), conversation_id)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create checkpoints directory")?;
+        }
+
+        let json = serde_json::to_string(checkpoint).context("Failed to serialize checkpoint")?;
+        std::fs::write(&path, json).context("Failed to write checkpoint")
+    }
+
+    pub fn clear_checkpoint(&self, conversation_id: &str) -> Result<()> {
+        let path = file_in(self.checkpoints_dir.as_ref(), conversation_id)?;
+
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to delete checkpoint")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn path_for(&self, conversation_id: &str) -> Result<PathBuf> {
+        file_in(self.dir.as_ref(), conversation_id)
+    }
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+fn file_in(dir: Option<&PathBuf>, conversation_id: &str) -> Result<PathBuf> {
+    // Ids become file names, so only allow a conservative character set
+    let valid = !conversation_id.is_empty()
+        && conversation_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow!("Invalid conversation id: {}", conversation_id));
+    }
+
+    let dir = dir.context("No data directory available")?;
+    Ok(dir.join(format!("{}.json", conversation_id)))
+}
+
+// Groups messages into turns, each starting at a user message, tagged with `from_source`
+fn split_turns(messages: Vec<StoredMessage>, from_source: bool) -> Vec<(bool, Vec<StoredMessage>)> {
+    let mut turns: Vec<(bool, Vec<StoredMessage>)> = Vec::new();
+    for message in messages {
+        match turns.last_mut() {
+            Some((_, turn)) if message.role != "user" => turn.push(message),
+            _ => turns.push((from_source, vec![message])),
+        }
+    }
+    turns
+}
+
+fn title_from(content: &str) -> String {
+    let first_line = content.lines().next().unwrap_or("").trim();
+    if first_line.is_empty() {
+        return "New Conversation".to_string();
+    }
+
+    first_line.chars().take(60).collect()
+}
+
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
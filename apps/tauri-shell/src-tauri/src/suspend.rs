@@ -0,0 +1,258 @@
+use crate::agent_ipc::DEFAULT_AGENT_ID;
+use crate::i18n;
+use crate::profiles::{self, TRAY_ICON};
+use crate::AppState;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use tauri::{AppHandle, CustomMenuItem, Icon, Manager, State};
+use ts_rs::TS;
+
+/// Tray menu id of the item pausing or resuming the default agent.
+pub const TRAY_ITEM: &str = "toggle_agent_pause";
+
+// Alpha kept of the tray icon while the default agent is paused
+const PAUSED_OPACITY: f32 = 0.4;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentSuspended<'a> {
+    agent_id: &'a str,
+    suspended: bool,
+}
+
+/// Freezes the agent process where it is, e.g. to stop a runaway generation without
+/// losing it. Requests stay in flight and continue on resume_agent.
+#[tauri::command]
+pub async fn pause_agent(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    set_suspended(&app_handle, &state, &agent_id, true)
+        .await
+        .map_err(|e| format!("Failed to pause agent: {}", e))
+}
+
+#[tauri::command]
+pub async fn resume_agent(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    agent_id: Option<String>,
+) -> Result<(), String> {
+    let agent_id = agent_id.unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    set_suspended(&app_handle, &state, &agent_id, false)
+        .await
+        .map_err(|e| format!("Failed to resume agent: {}", e))
+}
+
+/// Pauses the default agent, or resumes it if it is paused.
+pub fn on_tray_click(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        let suspended = !is_suspended(&app_handle);
+        if let Err(e) = set_suspended(&app_handle, &state, DEFAULT_AGENT_ID, suspended).await {
+            eprintln!("[SUSPEND] {}", e);
+        }
+    });
+}
+
+/// Tray item for the default agent, titled by whether it is paused.
+pub fn tray_item(suspended: bool) -> CustomMenuItem {
+    let title = if suspended {
+        i18n::t("tray-resume-agent")
+    } else {
+        i18n::t("tray-pause-agent")
+    };
+    CustomMenuItem::new(TRAY_ITEM, title)
+}
+
+/// Whether the default agent is paused. A slot that is busy right now is reported
+/// as running; its holder refreshes the tray when it pauses or resumes.
+pub fn is_suspended(app_handle: &AppHandle) -> bool {
+    app_handle
+        .state::<AppState>()
+        .agent()
+        .try_lock()
+        .map(|agent| agent.as_ref().is_some_and(|process| process.is_suspended()))
+        .unwrap_or(false)
+}
+
+/// Reports that `agent_id` runs again after its paused process was shut down.
+pub fn released(app_handle: &AppHandle, agent_id: &str) {
+    notify(app_handle, agent_id, false);
+}
+
+async fn set_suspended(
+    app_handle: &AppHandle,
+    state: &AppState,
+    agent_id: &str,
+    suspended: bool,
+) -> Result<()> {
+    let slot = state
+        .find(agent_id)
+        .ok_or_else(|| anyhow!("Agent {} not running", agent_id))?;
+    {
+        let agent = slot.lock().await;
+        let process = agent
+            .as_ref()
+            .ok_or_else(|| anyhow!("Agent {} not running", agent_id))?;
+        if suspended {
+            process.suspend()?;
+        } else {
+            process.resume().await?;
+        }
+    }
+    eprintln!(
+        "[SUSPEND] Agent {} {}",
+        agent_id,
+        if suspended { "paused" } else { "resumed" }
+    );
+    notify(app_handle, agent_id, suspended);
+    Ok(())
+}
+
+fn notify(app_handle: &AppHandle, agent_id: &str, suspended: bool) {
+    let event = AgentSuspended {
+        agent_id,
+        suspended,
+    };
+    if let Err(e) = app_handle.emit_all("agent_suspended", event) {
+        eprintln!("Failed to emit agent_suspended: {}", e);
+    }
+    if agent_id == DEFAULT_AGENT_ID {
+        crate::refresh_tray(app_handle);
+        apply_tray_icon(app_handle, suspended);
+    }
+}
+
+// Dims the tray icon while the default agent is paused
+fn apply_tray_icon(app_handle: &AppHandle, suspended: bool) {
+    if !suspended {
+        let tray = app_handle.tray_handle();
+        #[cfg(target_os = "macos")]
+        let _ = tray.set_icon_as_template(true);
+        if let Err(e) = tray.set_icon(Icon::Raw(TRAY_ICON.to_vec())) {
+            eprintln!("Failed to restore tray icon: {}", e);
+        }
+        // Puts the profile badge back, if any
+        profiles::apply_tray_icon(app_handle);
+        return;
+    }
+
+    let icon = match paused_icon() {
+        Ok(icon) => icon,
+        Err(e) => {
+            eprintln!("Failed to draw paused tray icon: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = app_handle.tray_handle().set_icon(icon) {
+        eprintln!("Failed to set paused tray icon: {}", e);
+    }
+}
+
+fn paused_icon() -> Result<Icon> {
+    let mut icon = image::load_from_memory(TRAY_ICON)
+        .context("Failed to decode tray icon")?
+        .into_rgba8();
+    for pixel in icon.pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * PAUSED_OPACITY) as u8;
+    }
+
+    let (width, height) = icon.dimensions();
+    Ok(Icon::Rgba {
+        rgba: icon.into_raw(),
+        width,
+        height,
+    })
+}
+
+/// Stops process `pid` and the processes it started until cont() is called. Agents
+/// lead a process group of their own, see agent_backend::spawn.
+#[cfg(unix)]
+pub fn stop(pid: u32) -> Result<()> {
+    signal(pid, libc::SIGSTOP)
+}
+
+#[cfg(unix)]
+pub fn cont(pid: u32) -> Result<()> {
+    signal(pid, libc::SIGCONT)
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: libc::c_int) -> Result<()> {
+    if unsafe { libc::killpg(pid as libc::pid_t, signal) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to signal agent process");
+    }
+    Ok(())
+}
+
+// Windows has no stop signal; every thread of the processes in the agent's job is
+// suspended instead
+#[cfg(windows)]
+pub fn stop(pid: u32) -> Result<()> {
+    use windows::Win32::System::Threading::SuspendThread;
+    for pid in crate::resource_limits::job_processes(pid) {
+        for_each_thread(pid, |thread| unsafe {
+            SuspendThread(thread);
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn cont(pid: u32) -> Result<()> {
+    use windows::Win32::System::Threading::ResumeThread;
+    for pid in crate::resource_limits::job_processes(pid) {
+        for_each_thread(pid, |thread| unsafe {
+            ResumeThread(thread);
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn for_each_thread(pid: u32, f: impl Fn(windows::Win32::Foundation::HANDLE)) -> Result<()> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows::Win32::System::Threading::{OpenThread, THREAD_SUSPEND_RESUME};
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0)
+            .context("Failed to list agent threads")?;
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+        let mut more = Thread32First(snapshot, &mut entry).as_bool();
+        while more {
+            if entry.th32OwnerProcessID == pid {
+                if let Ok(thread) = OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID) {
+                    f(thread);
+                    CloseHandle(thread);
+                }
+            }
+            more = Thread32Next(snapshot, &mut entry).as_bool();
+        }
+        CloseHandle(snapshot);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn stop(_pid: u32) -> Result<()> {
+    Err(anyhow!(
+        "Pausing the agent is not supported on this platform"
+    ))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn cont(_pid: u32) -> Result<()> {
+    Err(anyhow!(
+        "Pausing the agent is not supported on this platform"
+    ))
+}
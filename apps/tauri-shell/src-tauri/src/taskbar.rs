@@ -0,0 +1,155 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+// Only redraw the native indicator when progress moves by at least this much
+const MIN_PROGRESS_DELTA: f64 = 0.02;
+
+// Characters of streamed output at which the estimated progress reaches ~63%
+const PROGRESS_SCALE_CHARS: f64 = 2000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    None,
+    // A request is in flight but nothing has streamed yet
+    Indeterminate,
+    Value(f64),
+}
+
+/// Last progress pushed to the Dock / taskbar, used to throttle native updates.
+pub struct TaskbarProgress(Mutex<Progress>);
+
+impl Default for TaskbarProgress {
+    fn default() -> Self {
+        TaskbarProgress(Mutex::new(Progress::None))
+    }
+}
+
+/// Updates the Dock tile / taskbar button from the current stream activity.
+/// Generations have no known length, so progress is an asymptotic estimate
+/// from the number of characters streamed so far.
+pub fn update(app_handle: &AppHandle, in_flight: usize, streamed_chars: usize) {
+    let progress = if in_flight == 0 {
+        Progress::None
+    } else if streamed_chars == 0 {
+        Progress::Indeterminate
+    } else {
+        Progress::Value(1.0 - (-(streamed_chars as f64) / PROGRESS_SCALE_CHARS).exp())
+    };
+
+    let state = app_handle.state::<TaskbarProgress>();
+    let mut last = state.0.lock().unwrap();
+
+    let changed = match (*last, progress) {
+        (Progress::Value(a), Progress::Value(b)) => (a - b).abs() >= MIN_PROGRESS_DELTA,
+        (a, b) => a != b,
+    };
+    if !changed {
+        return;
+    }
+    *last = progress;
+
+    let app_handle_clone = app_handle.clone();
+    let result = app_handle.run_on_main_thread(move || apply(&app_handle_clone, progress));
+    if let Err(e) = result {
+        eprintln!("Failed to update taskbar progress: {}", e);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn apply(_app_handle: &AppHandle, progress: Progress) {
+    use cocoa::appkit::NSApp;
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let app = NSApp();
+        let dock_tile: id = msg_send![app, dockTile];
+
+        if progress == Progress::None {
+            let _: () = msg_send![dock_tile, setContentView: nil];
+            let _: () = msg_send![dock_tile, display];
+            return;
+        }
+
+        // Dock tile = app icon with a progress bar along the bottom edge
+        let size: NSSize = msg_send![dock_tile, size];
+        let content: id = msg_send![class!(NSImageView), alloc];
+        let content: id =
+            msg_send![content, initWithFrame: NSRect::new(NSPoint::new(0.0, 0.0), size)];
+        let icon: id = msg_send![app, applicationIconImage];
+        let _: () = msg_send![content, setImage: icon];
+
+        let bar_frame = NSRect::new(
+            NSPoint::new(size.width * 0.1, size.height * 0.08),
+            NSSize::new(size.width * 0.8, 16.0),
+        );
+        let bar: id = msg_send![class!(NSProgressIndicator), alloc];
+        let bar: id = msg_send![bar, initWithFrame: bar_frame];
+        let _: () = msg_send![bar, setStyle: 0u64]; // NSProgressIndicatorStyleBar
+        let _: () = msg_send![bar, setMinValue: 0.0f64];
+        let _: () = msg_send![bar, setMaxValue: 1.0f64];
+
+        match progress {
+            Progress::Value(value) => {
+                let _: () = msg_send![bar, setIndeterminate: NO];
+                let _: () = msg_send![bar, setDoubleValue: value];
+            }
+            _ => {
+                let _: () = msg_send![bar, setIndeterminate: YES];
+            }
+        }
+
+        let _: () = msg_send![content, addSubview: bar];
+        let _: () = msg_send![bar, release];
+        let _: () = msg_send![dock_tile, setContentView: content];
+        let _: () = msg_send![content, release];
+        let _: () = msg_send![dock_tile, display];
+    }
+}
+
+#[cfg(windows)]
+fn apply(app_handle: &AppHandle, progress: Progress) {
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{
+        ITaskbarList3, TaskbarList, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL,
+    };
+
+    let Some(window) = app_handle.get_window("main") else {
+        return;
+    };
+    let Ok(hwnd) = window.hwnd() else {
+        return;
+    };
+
+    unsafe {
+        let taskbar: ITaskbarList3 =
+            match CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER) {
+                Ok(taskbar) => taskbar,
+                Err(e) => {
+                    eprintln!("Failed to create ITaskbarList3: {}", e);
+                    return;
+                }
+            };
+        // Required before any other ITaskbarList method
+        if let Err(e) = taskbar.HrInit() {
+            eprintln!("Failed to initialize ITaskbarList3: {}", e);
+            return;
+        }
+
+        let result = match progress {
+            Progress::None => taskbar.SetProgressState(hwnd, TBPF_NOPROGRESS),
+            Progress::Indeterminate => taskbar.SetProgressState(hwnd, TBPF_INDETERMINATE),
+            Progress::Value(value) => taskbar
+                .SetProgressState(hwnd, TBPF_NORMAL)
+                .and_then(|_| taskbar.SetProgressValue(hwnd, (value * 1000.0) as u64, 1000)),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to set taskbar progress: {}", e);
+        }
+    }
+}
+
+// No native progress surface on other platforms
+#[cfg(not(any(target_os = "macos", windows)))]
+fn apply(_app_handle: &AppHandle, _progress: Progress) {}
@@ -0,0 +1,310 @@
+use crate::agent_ipc::AgentRequest;
+use crate::budget;
+use crate::clipboard::{self, ClipboardFormat};
+use crate::settings::SettingsStore;
+use crate::snapshot;
+use crate::AppState;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, GlobalShortcutManager, Manager, State};
+use ts_rs::TS;
+
+// How long the source app gets to read the clipboard before the old contents return
+const RESTORE_DELAY: Duration = Duration::from_millis(400);
+// Transforms are answered by the model alone, so this only guards against a hung agent
+const TRANSFORM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// An instruction applied to the selected text of whatever app is frontmost, with
+/// the result pasted back in place of the selection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextTransform {
+    pub id: String,
+    pub name: String,
+    pub instruction: String,
+    // Global accelerator that runs the transform
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextTransformSettings {
+    pub transforms: Vec<TextTransform>,
+    // Put back what was on the clipboard once the result is pasted
+    pub restore_clipboard: bool,
+}
+
+impl Default for TextTransformSettings {
+    fn default() -> Self {
+        let transform =
+            |id: &str, name: &str, instruction: &str, shortcut: Option<&str>| TextTransform {
+                id: id.to_string(),
+                name: name.to_string(),
+                instruction: instruction.to_string(),
+                shortcut: shortcut.map(str::to_string),
+            };
+
+        TextTransformSettings {
+            transforms: vec![
+                transform(
+                    "fix_grammar",
+                    "Fix grammar",
+                    "Fix spelling, grammar and punctuation. Keep the wording, tone and \
+                     formatting otherwise unchanged.",
+                    Some("CmdOrCtrl+Alt+Shift+F"),
+                ),
+                transform(
+                    "translate",
+                    "Translate to English",
+                    "Translate the text into English, keeping its formatting.",
+                    None,
+                ),
+                transform(
+                    "summarize",
+                    "Summarize",
+                    "Summarize the text in a few sentences.",
+                    None,
+                ),
+            ],
+            restore_clipboard: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct TextTransformFailed<'a> {
+    transform_id: &'a str,
+    error: String,
+}
+
+#[tauri::command]
+pub fn get_text_transforms(settings: State<'_, SettingsStore>) -> TextTransformSettings {
+    settings.get().text_transforms
+}
+
+/// Replaces the transforms and re-registers their shortcuts. A shortcut that can't be
+/// registered rejects the whole change and keeps the previous shortcuts working.
+#[tauri::command]
+pub fn set_text_transforms(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    text_transforms: TextTransformSettings,
+) -> Result<(), String> {
+    let previous = settings.get().text_transforms;
+    unregister_all(&app_handle, &previous);
+
+    if let Err(e) = register_all(&app_handle, &text_transforms) {
+        unregister_all(&app_handle, &text_transforms);
+        if let Err(e) = register_all(&app_handle, &previous) {
+            eprintln!("Failed to restore text transform shortcuts: {}", e);
+        }
+        return Err(e.to_string());
+    }
+
+    settings
+        .update(|s| s.text_transforms = text_transforms)
+        .map_err(|e| format!("Failed to save text transforms: {}", e))
+}
+
+/// Runs a transform on the current selection, as its shortcut would.
+#[tauri::command]
+pub fn run_text_transform(app_handle: AppHandle, id: String) {
+    fire(&app_handle, &id);
+}
+
+/// Registers the shortcuts of the configured transforms.
+pub fn setup(app_handle: &AppHandle) {
+    let transforms = app_handle.state::<SettingsStore>().get().text_transforms;
+    if let Err(e) = register_all(app_handle, &transforms) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Grabs the selection while the source app is still frontmost, sends it to the agent
+/// with the transform's instruction and pastes the reply over the selection.
+pub fn fire(app_handle: &AppHandle, id: &str) {
+    let transforms = app_handle.state::<SettingsStore>().get().text_transforms;
+    let Some(transform) = transforms
+        .transforms
+        .into_iter()
+        .find(|transform| transform.id == id)
+    else {
+        eprintln!("Fired unknown text transform {}", id);
+        return;
+    };
+    let restore_clipboard = transforms.restore_clipboard;
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(&app_handle, &transform, restore_clipboard).await {
+            eprintln!("[TRANSFORM] {} failed: {}", transform.id, e);
+            let event = TextTransformFailed {
+                transform_id: &transform.id,
+                error: e.to_string(),
+            };
+            if let Err(e) = app_handle.emit_all("text_transform_failed", event) {
+                eprintln!("Failed to emit text_transform_failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn run(
+    app_handle: &AppHandle,
+    transform: &TextTransform,
+    restore_clipboard: bool,
+) -> Result<()> {
+    let selection = tauri::async_runtime::spawn_blocking(snapshot::selected_text)
+        .await?
+        .ok_or_else(|| anyhow!("Nothing is selected"))?;
+    budget::check(app_handle)?;
+
+    let request = AgentRequest::Transform {
+        id: uuid::Uuid::new_v4().to_string(),
+        message: prompt(transform, &selection),
+    };
+    let reply = {
+        let state = app_handle.state::<AppState>();
+        let mut agent = state.agent().lock_owned().await;
+        let process = agent
+            .as_mut()
+            .ok_or_else(|| anyhow!("Agent is not running"))?;
+        process.complete(&request).await?
+    };
+    let result = tokio::time::timeout(TRANSFORM_TIMEOUT, reply)
+        .await
+        .map_err(|_| anyhow!("The agent didn't answer in time"))?
+        .map_err(|_| anyhow!("The agent exited before answering"))??;
+
+    let result = result.trim();
+    if result.is_empty() {
+        return Err(anyhow!("The agent returned no text"));
+    }
+    eprintln!(
+        "[TRANSFORM] {} done, pasting {} chars",
+        transform.id,
+        result.len()
+    );
+
+    let previous = restore_clipboard
+        .then(|| clipboard::read_text().ok())
+        .flatten();
+    clipboard::write(result, ClipboardFormat::Text, None)?;
+    tauri::async_runtime::spawn_blocking(paste)
+        .await?
+        .context("Failed to paste the result")?;
+
+    if let Some(previous) = previous {
+        tokio::time::sleep(RESTORE_DELAY).await;
+        clipboard::write(&previous, ClipboardFormat::Text, None)?;
+    }
+    Ok(())
+}
+
+fn prompt(transform: &TextTransform, selection: &str) -> String {
+    format!(
+        "{}\n\nReply with only the resulting text.\n\n<text>\n{}\n</text>",
+        transform.instruction, selection
+    )
+}
+
+fn register_all(app_handle: &AppHandle, settings: &TextTransformSettings) -> Result<()> {
+    for transform in &settings.transforms {
+        let Some(shortcut) = &transform.shortcut else {
+            continue;
+        };
+        let handle = app_handle.clone();
+        let id = transform.id.clone();
+        app_handle
+            .global_shortcut_manager()
+            .register(shortcut, move || fire(&handle, &id))
+            .with_context(|| format!("Shortcut {} is unavailable", shortcut))?;
+    }
+    Ok(())
+}
+
+fn unregister_all(app_handle: &AppHandle, settings: &TextTransformSettings) {
+    let mut manager = app_handle.global_shortcut_manager();
+    for shortcut in settings
+        .transforms
+        .iter()
+        .filter_map(|t| t.shortcut.as_ref())
+    {
+        if manager.is_registered(shortcut).unwrap_or(false) {
+            let _ = manager.unregister(shortcut);
+        }
+    }
+}
+
+// Sends the platform paste keystroke to the frontmost app
+#[cfg(target_os = "macos")]
+fn paste() -> Result<()> {
+    run_command(
+        "osascript",
+        &[
+            "-e",
+            "tell application \"System Events\" to keystroke \"v\" using command down",
+        ],
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn paste() -> Result<()> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run_command("wtype", &["-M", "ctrl", "v", "-m", "ctrl"])
+    } else {
+        run_command("xdotool", &["key", "--clearmodifiers", "ctrl+v"])
+    }
+}
+
+#[cfg(windows)]
+fn paste() -> Result<()> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_CONTROL, VK_V,
+    };
+
+    let key = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let inputs = [
+        key(VK_CONTROL, KEYBD_EVENT_FLAGS(0)),
+        key(VK_V, KEYBD_EVENT_FLAGS(0)),
+        key(VK_V, KEYEVENTF_KEYUP),
+        key(VK_CONTROL, KEYEVENTF_KEYUP),
+    ];
+
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(anyhow!("SendInput was blocked"));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn paste() -> Result<()> {
+    Err(anyhow!("Pasting is not supported on this platform"))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn run_command(program: &str, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
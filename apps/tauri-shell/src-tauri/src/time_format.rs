@@ -0,0 +1,85 @@
+use crate::i18n;
+use crate::store;
+use chrono::{DateTime, Local, Locale, TimeZone};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+// Relative formatting switches to an absolute date past this age
+const RELATIVE_MAX_DAYS: i64 = 7;
+
+// Regional formats come from the full system locale (en-GB vs en-US), not the UI language
+static LOCALE: Lazy<Locale> = Lazy::new(detect_locale);
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampStyle {
+    Date,
+    Time,
+    DateTime,
+    // "2 min ago", falling back to Date for anything older than RELATIVE_MAX_DAYS
+    Relative,
+}
+
+/// Formats epoch millis in the system locale and the current local timezone.
+#[tauri::command]
+pub fn format_timestamp(ts: i64, style: TimestampStyle) -> String {
+    format(ts, style)
+}
+
+pub fn format(ts: i64, style: TimestampStyle) -> String {
+    // Local is re-resolved on every call, so timezone changes apply without a restart
+    let Some(time) = Local.timestamp_millis_opt(ts).single() else {
+        return ts.to_string();
+    };
+
+    match style {
+        TimestampStyle::Date => localized(&time, "%x"),
+        TimestampStyle::Time => localized(&time, "%X"),
+        TimestampStyle::DateTime => localized(&time, "%x %X"),
+        TimestampStyle::Relative => relative(ts).unwrap_or_else(|| localized(&time, "%x")),
+    }
+}
+
+fn localized(time: &DateTime<Local>, pattern: &str) -> String {
+    time.format_localized(pattern, *LOCALE).to_string()
+}
+
+fn relative(ts: i64) -> Option<String> {
+    let elapsed_secs = (store::now_millis() - ts) / 1000;
+    // Slightly-ahead clocks still read as "just now"; real future times get a date
+    if elapsed_secs < -60 {
+        return None;
+    }
+
+    let (key, count) = match elapsed_secs.max(0) {
+        secs if secs < 60 => return Some(i18n::t("time-just-now")),
+        secs if secs < 60 * 60 => ("time-minutes-ago", secs / 60),
+        secs if secs < 24 * 60 * 60 => ("time-hours-ago", secs / (60 * 60)),
+        secs if secs < RELATIVE_MAX_DAYS * 24 * 60 * 60 => ("time-days-ago", secs / (24 * 60 * 60)),
+        _ => return None,
+    };
+    Some(i18n::t_args(key, &[("count", count.into())]))
+}
+
+fn detect_locale() -> Locale {
+    let requested = std::env::var("ASST_LOCALE")
+        .ok()
+        .or_else(sys_locale::get_locale)
+        .unwrap_or_default();
+    // "de-DE.UTF-8" -> "de_DE"
+    let name = requested
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('-', "_");
+
+    let language_only = || {
+        // Bare languages ("de") map to their main region, where it shares the code
+        let region = name.to_ascii_uppercase();
+        Locale::try_from(format!("{}_{}", name, region).as_str()).ok()
+    };
+    Locale::try_from(name.as_str())
+        .ok()
+        .or_else(language_only)
+        .unwrap_or(Locale::POSIX)
+}
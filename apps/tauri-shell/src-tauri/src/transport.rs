@@ -0,0 +1,298 @@
+use crate::agent_backend::{AgentStdin, AgentStdout, Reconnects};
+use crate::settings::SettingsStore;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio as StdioPipe;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+
+/// Variable telling an agent spawned over a socket or named pipe where to connect.
+pub const SOCKET_ENV: &str = "ASST_SOCKET";
+
+// How long a spawned agent has to connect before it's treated as gone
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(30);
+// How long an agent that closed its connection has to connect again after restarting
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// Buffer between the socket and the shell's side of the connection
+const BRIDGE_BYTES: usize = 64 * 1024;
+
+/// How spawned agent processes exchange protocol messages with the shell. Only the
+/// built-in agent is reached over a socket or named pipe; agent commands, profiles and
+/// managed agent builds always use stdio, or the transport their command asks for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    #[default]
+    Stdio,
+    // The agent connects to a socket named in ASST_SOCKET; its stdout is left alone
+    UnixSocket,
+    // The same over a named pipe, on Windows
+    NamedPipe,
+}
+
+/// The channel between the shell and a spawned agent process.
+pub trait Transport: Send {
+    /// Short name for the logs, e.g. "stdio".
+    fn name(&self) -> &'static str;
+
+    /// Sets up `command` before it is spawned.
+    fn prepare(&mut self, command: &mut Command) -> Result<()>;
+
+    /// The streams requests are written to and responses read from, and the responses
+    /// of each later connection if the agent may reconnect after restarting.
+    fn connect(&mut self, child: &mut Child) -> Result<(AgentStdin, AgentStdout, Reconnects)>;
+}
+
+/// The transport the agent_transport setting selects for the built-in agent.
+pub fn configured(app_handle: &AppHandle) -> Box<dyn Transport> {
+    for_kind(app_handle.state::<SettingsStore>().get().agent_transport)
+}
+
+/// The transport of `kind`, or stdio where it isn't available.
+pub fn for_kind(kind: TransportKind) -> Box<dyn Transport> {
+    match kind {
+        TransportKind::Stdio => Box::new(Stdio),
+        #[cfg(unix)]
+        TransportKind::UnixSocket => Box::new(UnixSocket::default()),
+        #[cfg(not(unix))]
+        TransportKind::UnixSocket => {
+            eprintln!("[DEBUG] Unix sockets aren't available here, using stdio");
+            Box::new(Stdio)
+        }
+        #[cfg(windows)]
+        TransportKind::NamedPipe => Box::new(NamedPipe::default()),
+        #[cfg(not(windows))]
+        TransportKind::NamedPipe => {
+            eprintln!("[DEBUG] Named pipes aren't available here, using stdio");
+            Box::new(Stdio)
+        }
+    }
+}
+
+/// Requests on the child's stdin, responses on its stdout.
+pub struct Stdio;
+
+impl Transport for Stdio {
+    fn name(&self) -> &'static str {
+        "stdio"
+    }
+
+    fn prepare(&mut self, command: &mut Command) -> Result<()> {
+        command.stdin(StdioPipe::piped()).stdout(StdioPipe::piped());
+        Ok(())
+    }
+
+    fn connect(&mut self, child: &mut Child) -> Result<(AgentStdin, AgentStdout, Reconnects)> {
+        let stdin = child.stdin.take().context("Failed to get stdin")?;
+        let stdout = child.stdout.take().context("Failed to get stdout")?;
+        Ok((Box::new(stdin), Box::new(stdout), None))
+    }
+}
+
+// Waits for the next connection from the agent
+type Accepting<S> = Pin<Box<dyn Future<Output = std::io::Result<S>> + Send>>;
+type Accept<S> = Box<dyn FnMut() -> Accepting<S> + Send>;
+
+// Streams for AgentProcess that carry whatever the agent connects with through
+// `accept`, so connect() can return before the agent is up. Each connection's responses
+// get a stream of their own, so a restarted agent starts over with a fresh reader;
+// requests always go to the latest connection.
+fn bridge<S>(mut accept: Accept<S>) -> (AgentStdin, AgentStdout, Reconnects)
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    let (stdin, mut from_shell) = tokio::io::duplex(BRIDGE_BYTES);
+    let (first, stdout) = tokio::io::duplex(BRIDGE_BYTES);
+    let (reconnect_tx, reconnect_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let mut first = Some(first);
+        loop {
+            let timeout = if first.is_some() {
+                ACCEPT_TIMEOUT
+            } else {
+                RECONNECT_TIMEOUT
+            };
+            let connection = match tokio::time::timeout(timeout, accept()).await {
+                Ok(Ok(connection)) => connection,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to accept agent connection: {}", e);
+                    break;
+                }
+                Err(_) if first.is_some() => {
+                    eprintln!("Agent didn't connect within {}s", timeout.as_secs());
+                    break;
+                }
+                // Closed for good rather than restarted
+                Err(_) => break,
+            };
+            let mut to_shell = match first.take() {
+                Some(first) => first,
+                None => {
+                    let (to_shell, stdout) = tokio::io::duplex(BRIDGE_BYTES);
+                    let stdout: AgentStdout = Box::new(stdout);
+                    if reconnect_tx.send(stdout).await.is_err() {
+                        break;
+                    }
+                    eprintln!("[DEBUG] Agent reconnected");
+                    to_shell
+                }
+            };
+
+            let (mut from_agent, mut to_agent) = tokio::io::split(connection);
+            tokio::select! {
+                // The agent closed its end, or the shell stopped reading; the reader sees EOF
+                _ = tokio::io::copy(&mut from_agent, &mut to_shell) => {}
+                // The shell is done with the agent
+                _ = tokio::io::copy(&mut from_shell, &mut to_agent) => break,
+            }
+        }
+    });
+    (Box::new(stdin), Box::new(stdout), Some(reconnect_rx))
+}
+
+#[cfg(unix)]
+pub use unix::UnixSocket;
+
+#[cfg(unix)]
+mod unix {
+    use super::{bridge, Accepting, AgentStdin, AgentStdout, Reconnects, Transport, SOCKET_ENV};
+    use anyhow::{Context, Result};
+    use std::os::unix::fs::DirBuilderExt;
+    use std::path::PathBuf;
+    use std::process::Stdio;
+    use std::sync::Arc;
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::process::{Child, Command};
+
+    /// A socket in a private temp directory that the agent connects to, again after
+    /// restarting if it needs to. The directory is only open to the user, and removed
+    /// once the agent is gone.
+    #[derive(Default)]
+    pub struct UnixSocket {
+        dir: Option<SocketDir>,
+        listener: Option<UnixListener>,
+    }
+
+    // Removed when dropped: once the agent is gone or never connects
+    struct SocketDir(PathBuf);
+
+    impl Drop for SocketDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    impl Transport for UnixSocket {
+        fn name(&self) -> &'static str {
+            "unix_socket"
+        }
+
+        fn prepare(&mut self, command: &mut Command) -> Result<()> {
+            // Short, as socket paths are limited to about 100 bytes
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            let dir = std::env::temp_dir().join(format!("asst-{}", &id[..12]));
+            std::fs::DirBuilder::new()
+                .mode(0o700)
+                .create(&dir)
+                .with_context(|| format!("Failed to create socket directory {:?}", dir))?;
+            let path = dir.join("agent.sock");
+            self.dir = Some(SocketDir(dir));
+
+            let listener = UnixListener::bind(&path)
+                .with_context(|| format!("Failed to listen on {:?}", path))?;
+            self.listener = Some(listener);
+
+            command
+                .env(SOCKET_ENV, &path)
+                .stdin(Stdio::null())
+                .stdout(Stdio::inherit());
+            Ok(())
+        }
+
+        fn connect(&mut self, _child: &mut Child) -> Result<(AgentStdin, AgentStdout, Reconnects)> {
+            let listener = Arc::new(self.listener.take().context("Socket wasn't prepared")?);
+            let dir = Arc::new(self.dir.take());
+            Ok(bridge(Box::new(move || -> Accepting<UnixStream> {
+                let listener = listener.clone();
+                // Kept as long as the bridge may accept
+                let dir = dir.clone();
+                Box::pin(async move {
+                    let _dir = dir;
+                    listener.accept().await.map(|(socket, _)| socket)
+                })
+            })))
+        }
+    }
+}
+
+#[cfg(windows)]
+pub use windows::NamedPipe;
+
+#[cfg(windows)]
+mod windows {
+    use super::{bridge, Accepting, AgentStdin, AgentStdout, Reconnects, Transport, SOCKET_ENV};
+    use anyhow::{Context, Result};
+    use std::process::Stdio;
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use tokio::process::{Child, Command};
+
+    /// A named pipe of one instance that the agent connects to. It takes no further
+    /// clients while the agent is connected, and none from other machines; a new
+    /// instance waits for the agent once it disconnects to restart.
+    #[derive(Default)]
+    pub struct NamedPipe {
+        name: String,
+        server: Option<NamedPipeServer>,
+    }
+
+    fn server_options() -> ServerOptions {
+        let mut options = ServerOptions::new();
+        options.reject_remote_clients(true).max_instances(1);
+        options
+    }
+
+    impl Transport for NamedPipe {
+        fn name(&self) -> &'static str {
+            "named_pipe"
+        }
+
+        fn prepare(&mut self, command: &mut Command) -> Result<()> {
+            let name = format!(r"\\.\pipe\asst-{}", uuid::Uuid::new_v4().simple());
+            // Fails rather than joining a pipe someone else created under the name
+            let server = server_options()
+                .first_pipe_instance(true)
+                .create(&name)
+                .with_context(|| format!("Failed to create named pipe {}", name))?;
+            self.server = Some(server);
+
+            command
+                .env(SOCKET_ENV, &name)
+                .stdin(Stdio::null())
+                .stdout(Stdio::inherit());
+            self.name = name;
+            Ok(())
+        }
+
+        fn connect(&mut self, _child: &mut Child) -> Result<(AgentStdin, AgentStdout, Reconnects)> {
+            let mut server = Some(self.server.take().context("Named pipe wasn't prepared")?);
+            let name = std::mem::take(&mut self.name);
+            Ok(bridge(Box::new(move || -> Accepting<NamedPipeServer> {
+                // The previous instance is closed by now, so there's room for one more
+                let server = server
+                    .take()
+                    .map(Ok)
+                    .unwrap_or_else(|| server_options().create(&name));
+                Box::pin(async move {
+                    let server = server?;
+                    server.connect().await?;
+                    Ok(server)
+                })
+            })))
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::i18n;
+use crate::window_registry;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State, Window};
+
+/// Tracks replies that completed while the user wasn't looking at them: the window
+/// was hidden or showed a different conversation.
+#[derive(Default)]
+pub struct UnreadTracker {
+    counts: Mutex<HashMap<String, u32>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UnreadCounts<'a> {
+    counts: &'a HashMap<String, u32>,
+    total: u32,
+}
+
+#[tauri::command]
+pub fn get_unread_counts(tracker: State<'_, UnreadTracker>) -> HashMap<String, u32> {
+    tracker.counts.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn mark_conversation_read(app_handle: AppHandle, conversation_id: String) {
+    mark_read(&app_handle, &conversation_id);
+}
+
+#[tauri::command]
+pub fn mark_all_read(app_handle: AppHandle) {
+    let tracker = app_handle.state::<UnreadTracker>();
+    tracker.counts.lock().unwrap().clear();
+    publish(&app_handle);
+}
+
+#[tauri::command]
+pub fn set_active_conversation(window: Window, conversation_id: String) {
+    let app_handle = window.app_handle();
+    mark_read(&app_handle, &conversation_id);
+
+    window_registry::set_conversation(&app_handle, window.label(), &conversation_id);
+}
+
+/// Called when a reply finishes; counts it as unread unless the user is looking at it.
+pub fn record_reply(app_handle: &AppHandle, conversation_id: &str, owner: Option<&str>) {
+    let tracker = app_handle.state::<UnreadTracker>();
+
+    let label = owner.unwrap_or("main");
+    let window_visible = app_handle
+        .get_window(label)
+        .map(|window| window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false))
+        .unwrap_or(false);
+    let is_active =
+        window_registry::conversation_of(app_handle, label).as_deref() == Some(conversation_id);
+
+    if window_visible && is_active {
+        return;
+    }
+
+    *tracker
+        .counts
+        .lock()
+        .unwrap()
+        .entry(conversation_id.to_string())
+        .or_insert(0) += 1;
+    publish(app_handle);
+}
+
+/// Clears the count of the conversation a window shows once it regains focus.
+pub fn on_window_focused(window: &Window) {
+    let app_handle = window.app_handle();
+    if let Some(conversation_id) = window_registry::conversation_of(&app_handle, window.label()) {
+        mark_read(&app_handle, &conversation_id);
+    }
+}
+
+fn mark_read(app_handle: &AppHandle, conversation_id: &str) {
+    let tracker = app_handle.state::<UnreadTracker>();
+    if tracker
+        .counts
+        .lock()
+        .unwrap()
+        .remove(conversation_id)
+        .is_some()
+    {
+        publish(app_handle);
+    }
+}
+
+// Emits the new counts and mirrors the total onto the tray badge
+fn publish(app_handle: &AppHandle) {
+    let tracker = app_handle.state::<UnreadTracker>();
+    let counts = tracker.counts.lock().unwrap();
+    let total: u32 = counts.values().sum();
+
+    if let Err(e) = app_handle.emit_all(
+        "unread_counts",
+        UnreadCounts {
+            counts: &counts,
+            total,
+        },
+    ) {
+        eprintln!("Failed to emit unread counts: {}", e);
+    }
+
+    let tray = app_handle.tray_handle();
+    let tooltip = match total {
+        0 => i18n::t("tray-tooltip"),
+        n => i18n::t_args("tray-tooltip-unread", &[("count", n.into())]),
+    };
+    let _ = tray.set_tooltip(&tooltip);
+
+    #[cfg(target_os = "macos")]
+    {
+        let title = if total == 0 {
+            String::new()
+        } else {
+            total.to_string()
+        };
+        let _ = tray.set_title(&title);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::settings::SettingsStore;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Manager, State, UpdaterEvent};
+
+// Per-channel update manifests; stable matches the endpoint in tauri.conf.json
+const UPDATE_ENDPOINT: &str = "https://raw.githubusercontent.com/ericmday/asst/releases";
+
+// Bytes received for the current download, accumulated from per-chunk events
+static DOWNLOADED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn endpoint(self) -> String {
+        let name = match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        };
+        format!("{}/{}.json", UPDATE_ENDPOINT, name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub current_version: String,
+    pub latest_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgress {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum UpdateStatus {
+    Downloading,
+    Downloaded,
+    Installed,
+    UpToDate,
+    Failed { error: String },
+}
+
+#[tauri::command]
+pub fn get_update_channel(settings: State<'_, SettingsStore>) -> UpdateChannel {
+    settings.get().update_channel
+}
+
+#[tauri::command]
+pub fn set_update_channel(
+    settings: State<'_, SettingsStore>,
+    channel: UpdateChannel,
+) -> Result<(), String> {
+    settings
+        .update(|s| s.update_channel = channel)
+        .map_err(|e| format!("Failed to save update channel: {}", e))
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app_handle: AppHandle) -> Result<UpdateInfo, String> {
+    let update = builder(&app_handle)?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    Ok(UpdateInfo {
+        available: update.is_update_available(),
+        current_version: update.current_version().to_string(),
+        latest_version: update.latest_version().to_string(),
+        notes: update.body().cloned(),
+        date: update.date().map(|date| date.to_string()),
+    })
+}
+
+/// Downloads, verifies (against the pubkey in tauri.conf.json) and installs the
+/// latest release on the selected channel. Progress arrives as events.
+#[tauri::command]
+pub async fn install_update(app_handle: AppHandle) -> Result<(), String> {
+    let update = builder(&app_handle)?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    if !update.is_update_available() {
+        return Err("Already up to date".to_string());
+    }
+
+    DOWNLOADED.store(0, Ordering::Relaxed);
+    update
+        .download_and_install()
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))
+}
+
+/// Relays updater run events to the frontend as `update_download_progress` and
+/// `update_status` events.
+pub fn on_updater_event(app_handle: &AppHandle, event: UpdaterEvent) {
+    let result = match event {
+        UpdaterEvent::Pending => emit_status(app_handle, UpdateStatus::Downloading),
+        UpdaterEvent::DownloadProgress {
+            chunk_length,
+            content_length,
+        } => {
+            let downloaded =
+                DOWNLOADED.fetch_add(chunk_length as u64, Ordering::Relaxed) + chunk_length as u64;
+            app_handle.emit_all(
+                "update_download_progress",
+                DownloadProgress {
+                    downloaded,
+                    total: content_length,
+                },
+            )
+        }
+        UpdaterEvent::Downloaded => emit_status(app_handle, UpdateStatus::Downloaded),
+        UpdaterEvent::Updated => emit_status(app_handle, UpdateStatus::Installed),
+        UpdaterEvent::AlreadyUpToDate => emit_status(app_handle, UpdateStatus::UpToDate),
+        UpdaterEvent::Error(error) => emit_status(app_handle, UpdateStatus::Failed { error }),
+        UpdaterEvent::UpdateAvailable { .. } => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to emit updater event: {}", e);
+    }
+}
+
+// Fails until the updater is activated in tauri.conf.json, which needs the release
+// signing key's public half in place of the empty pubkey
+fn builder(app_handle: &AppHandle) -> Result<tauri::updater::UpdateBuilder<tauri::Wry>, String> {
+    let updater = &app_handle.config().tauri.updater;
+    if !updater.active || updater.pubkey.is_empty() {
+        return Err("Updates aren't enabled in this build".to_string());
+    }
+    let channel = app_handle.state::<SettingsStore>().get().update_channel;
+    Ok(tauri::updater::builder(app_handle.clone()).endpoints(&[channel.endpoint()]))
+}
+
+fn emit_status(app_handle: &AppHandle, status: UpdateStatus) -> tauri::Result<()> {
+    app_handle.emit_all("update_status", status)
+}
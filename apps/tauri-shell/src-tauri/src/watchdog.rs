@@ -0,0 +1,132 @@
+use crate::agent_ipc;
+use crate::settings::SettingsStore;
+use crate::{AgentSlot, AppState};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use ts_rs::TS;
+
+// How often in-flight requests are checked against the deadline
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// When a silent request makes the agent count as hung. Read on every check, so
+/// changes apply without respawning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    // A request with no Token/Done/Error for this long restarts the agent, if the
+    // heartbeat's latest ping went unanswered too
+    pub response_deadline_secs: u64,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        WatchdogSettings {
+            enabled: true,
+            response_deadline_secs: 90,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+struct AgentWatchdogRestarted<'a> {
+    agent_id: &'a str,
+    // Requests past the deadline that prompted the restart
+    overdue_requests: Vec<String>,
+    // Every request failed with the hung process
+    lost_requests: Vec<String>,
+}
+
+/// Watches the requests of process `serial`. When one has passed the response deadline
+/// and the heartbeat reports its latest ping `missed`, the process is killed and
+/// replaced, and its requests fail with `agent_unresponsive`. Stops once the process
+/// is dropped.
+pub fn spawn(app_handle: AppHandle, agent_id: String, serial: u64, missed: watch::Receiver<bool>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            // The heartbeat stops with the process
+            if missed.has_changed().is_err() {
+                break;
+            }
+            let settings = app_handle.state::<SettingsStore>().get().watchdog;
+            if !settings.enabled {
+                continue;
+            }
+
+            let deadline = Duration::from_secs(settings.response_deadline_secs.max(1));
+            let Some(slot) = app_handle.state::<AppState>().find(&agent_id) else {
+                break;
+            };
+            let overdue = {
+                let agent = slot.lock().await;
+                // A warm standby has nothing in flight until it is promoted, and a paused
+                // agent is silent on purpose
+                match agent.as_ref().filter(|process| process.serial() == serial) {
+                    Some(process) if !process.is_suspended() => {
+                        process.overdue_requests(deadline).await
+                    }
+                    _ => continue,
+                }
+            };
+            // A slow model still answers pings; only a hung process doesn't. Agents
+            // that never answered one aren't judged.
+            if overdue.is_empty() || !*missed.borrow() {
+                continue;
+            }
+
+            if restart(&app_handle, &slot, &agent_id, serial, overdue).await {
+                break;
+            }
+        }
+    });
+}
+
+// Replaces the hung process if it is still the active one. Returns true once it has
+// been replaced.
+async fn restart(
+    app_handle: &AppHandle,
+    slot: &AgentSlot,
+    agent_id: &str,
+    serial: u64,
+    overdue: Vec<String>,
+) -> bool {
+    let current = slot
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|process| process.serial() == serial && !process.is_suspended());
+    if !current {
+        return false;
+    }
+
+    eprintln!(
+        "[WATCHDOG] No response to {:?} and no pong, restarting agent",
+        overdue
+    );
+    match agent_ipc::replace(slot, Some(serial)).await {
+        Ok(None) => false,
+        Ok(Some(replaced)) => {
+            drop(replaced.agent);
+            let lost_requests = replaced.old.abandon().await;
+            let event = AgentWatchdogRestarted {
+                agent_id,
+                overdue_requests: overdue,
+                lost_requests: lost_requests.clone(),
+            };
+            if let Err(e) = app_handle.emit_all("agent_watchdog_restarted", event) {
+                eprintln!("Failed to emit agent_watchdog_restarted: {}", e);
+            }
+            agent_ipc::emit_lifecycle(app_handle, agent_id, "agent_stopped", lost_requests);
+            agent_ipc::emit_lifecycle(app_handle, agent_id, "agent_started", Vec::new());
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to restart hung agent: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use crate::settings::SettingsStore;
+use crate::spaces;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{
+    AppHandle, GlobalShortcutManager, Manager, Monitor, PhysicalPosition, PhysicalSize, State,
+    Window,
+};
+
+// Gap kept between the window and the monitor edges, in logical pixels
+const MARGIN: f64 = 8.0;
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Shapes the main window can be snapped to. Names are as in apply_window_preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowPreset {
+    // Full height, the right third of the monitor
+    SidebarRight,
+    // Full width along the bottom
+    BottomBar,
+    // Most of the monitor, centered
+    CenteredLarge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowPresetSettings {
+    // Global accelerator per preset
+    pub shortcuts: BTreeMap<WindowPreset, String>,
+    // Length of the move; 0 jumps straight to the preset
+    pub animation_ms: u64,
+}
+
+impl Default for WindowPresetSettings {
+    fn default() -> Self {
+        WindowPresetSettings {
+            shortcuts: BTreeMap::from([
+                (
+                    WindowPreset::SidebarRight,
+                    "CmdOrCtrl+Alt+Shift+Right".to_string(),
+                ),
+                (
+                    WindowPreset::BottomBar,
+                    "CmdOrCtrl+Alt+Shift+Down".to_string(),
+                ),
+                (
+                    WindowPreset::CenteredLarge,
+                    "CmdOrCtrl+Alt+Shift+Up".to_string(),
+                ),
+            ]),
+            animation_ms: 180,
+        }
+    }
+}
+
+/// Counts preset moves, so a newer one stops the animation of the previous.
+#[derive(Default)]
+pub struct WindowPresets {
+    generation: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Moves and resizes the main window to preset `name` on the monitor it is on.
+#[tauri::command]
+pub fn apply_window_preset(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let preset = serde_json::from_value(serde_json::Value::String(name.clone()))
+        .map_err(|_| format!("Unknown window preset: {}", name))?;
+    apply(&app_handle, preset).map_err(|e| format!("Failed to apply window preset: {}", e))
+}
+
+#[tauri::command]
+pub fn get_window_presets(settings: State<'_, SettingsStore>) -> WindowPresetSettings {
+    settings.get().window_presets
+}
+
+/// Replaces the preset settings and re-registers their shortcuts. A shortcut that
+/// can't be registered rejects the whole change and keeps the previous ones working.
+#[tauri::command]
+pub fn set_window_presets(
+    app_handle: AppHandle,
+    settings: State<'_, SettingsStore>,
+    window_presets: WindowPresetSettings,
+) -> Result<(), String> {
+    let previous = settings.get().window_presets;
+    unregister_all(&app_handle, &previous);
+
+    if let Err(e) = register_all(&app_handle, &window_presets) {
+        unregister_all(&app_handle, &window_presets);
+        if let Err(e) = register_all(&app_handle, &previous) {
+            eprintln!("Failed to restore window preset shortcuts: {}", e);
+        }
+        return Err(e.to_string());
+    }
+
+    settings
+        .update(|s| s.window_presets = window_presets)
+        .map_err(|e| format!("Failed to save window presets: {}", e))
+}
+
+/// Registers the preset shortcuts.
+pub fn setup(app_handle: &AppHandle) {
+    let presets = app_handle.state::<SettingsStore>().get().window_presets;
+    if let Err(e) = register_all(app_handle, &presets) {
+        eprintln!("{}", e);
+    }
+}
+
+/// Snaps the main window to `preset`, showing it first if it is hidden. A visible
+/// window is animated there.
+pub fn apply(app_handle: &AppHandle, preset: WindowPreset) -> Result<()> {
+    let window = app_handle
+        .get_window("main")
+        .context("Main window not found")?;
+    let visible = window.is_visible().unwrap_or(false);
+    if !visible {
+        spaces::prepare_show(&window);
+        window.show()?;
+    }
+    window.set_focus()?;
+    if window.is_maximized().unwrap_or(false) {
+        window.unmaximize()?;
+    }
+
+    let monitor = window
+        .current_monitor()?
+        .ok_or_else(|| anyhow!("The window is on no monitor"))?;
+    let target = geometry(&monitor, preset);
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    let from = Geometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    };
+
+    let presets = app_handle.state::<WindowPresets>();
+    let generation = presets.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let duration = app_handle
+        .state::<SettingsStore>()
+        .get()
+        .window_presets
+        .animation_ms;
+    if !visible || duration == 0 {
+        return set_geometry(&window, target);
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let frames = (duration / FRAME_INTERVAL.as_millis() as u64).max(1);
+        for frame in 1..=frames {
+            // A newer preset took over
+            if app_handle
+                .state::<WindowPresets>()
+                .generation
+                .load(Ordering::SeqCst)
+                != generation
+            {
+                return;
+            }
+            let t = ease_out(frame as f64 / frames as f64);
+            if let Err(e) = set_geometry(&window, interpolate(from, target, t)) {
+                eprintln!("Failed to animate window: {}", e);
+                return;
+            }
+            tokio::time::sleep(FRAME_INTERVAL).await;
+        }
+    });
+    Ok(())
+}
+
+// Where `preset` puts the window on `monitor`, in physical pixels
+fn geometry(monitor: &Monitor, preset: WindowPreset) -> Geometry {
+    let margin = (MARGIN * monitor.scale_factor()).round() as i32;
+    let area = work_area(monitor);
+    let width = area.width as i32 - 2 * margin;
+    let height = area.height as i32 - 2 * margin;
+
+    let (x, y, w, h) = match preset {
+        WindowPreset::SidebarRight => {
+            let w = width / 3;
+            (width - w, 0, w, height)
+        }
+        WindowPreset::BottomBar => {
+            let h = height * 3 / 10;
+            (0, height - h, width, h)
+        }
+        WindowPreset::CenteredLarge => {
+            let (w, h) = (width * 7 / 10, height * 8 / 10);
+            ((width - w) / 2, (height - h) / 2, w, h)
+        }
+    };
+    Geometry {
+        x: area.x + margin + x,
+        y: area.y + margin + y,
+        width: w.max(1) as u32,
+        height: h.max(1) as u32,
+    }
+}
+
+fn monitor_area(monitor: &Monitor) -> Geometry {
+    Geometry {
+        x: monitor.position().x,
+        y: monitor.position().y,
+        width: monitor.size().width,
+        height: monitor.size().height,
+    }
+}
+
+// The part of `monitor` the menu bar and Dock leave free, in physical pixels
+#[cfg(target_os = "macos")]
+fn work_area(monitor: &Monitor) -> Geometry {
+    use cocoa::appkit::NSScreen;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSRect};
+
+    let area = monitor_area(monitor);
+    let scale = monitor.scale_factor();
+    let physical = |points: f64| (points * scale).round() as i32;
+    unsafe {
+        let screens = NSScreen::screens(nil);
+        if screens.count() == 0 {
+            return area;
+        }
+        // Cocoa measures up from the bottom of the primary screen
+        let primary_height = NSScreen::frame(screens.objectAtIndex(0)).size.height;
+        for index in 0..screens.count() {
+            let screen: id = screens.objectAtIndex(index);
+            let frame: NSRect = NSScreen::frame(screen);
+            let top = primary_height - frame.origin.y - frame.size.height;
+            if (physical(frame.origin.x) - area.x).abs() > 1 || (physical(top) - area.y).abs() > 1 {
+                continue;
+            }
+            let visible: NSRect = NSScreen::visibleFrame(screen);
+            let left_inset = visible.origin.x - frame.origin.x;
+            let top_inset =
+                frame.origin.y + frame.size.height - (visible.origin.y + visible.size.height);
+            return Geometry {
+                x: area.x + physical(left_inset),
+                y: area.y + physical(top_inset),
+                width: physical(visible.size.width).max(1) as u32,
+                height: physical(visible.size.height).max(1) as u32,
+            };
+        }
+    }
+    area
+}
+
+// The part of `monitor` the taskbar leaves free
+#[cfg(windows)]
+fn work_area(monitor: &Monitor) -> Geometry {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::{
+        GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONULL,
+    };
+
+    let area = monitor_area(monitor);
+    let center = POINT {
+        x: area.x + area.width as i32 / 2,
+        y: area.y + area.height as i32 / 2,
+    };
+    unsafe {
+        let handle = MonitorFromPoint(center, MONITOR_DEFAULTTONULL);
+        if handle.0 == 0 {
+            return area;
+        }
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(handle, &mut info).as_bool() {
+            return area;
+        }
+        let work = info.rcWork;
+        Geometry {
+            x: work.left,
+            y: work.top,
+            width: (work.right - work.left).max(1) as u32,
+            height: (work.bottom - work.top).max(1) as u32,
+        }
+    }
+}
+
+// Linux desktops report their panels in too many ways; the whole monitor is used
+#[cfg(not(any(target_os = "macos", windows)))]
+fn work_area(monitor: &Monitor) -> Geometry {
+    monitor_area(monitor)
+}
+
+// `geometry` is the outer frame; set_size takes the inner size
+fn set_geometry(window: &Window, geometry: Geometry) -> Result<()> {
+    let outer = window.outer_size()?;
+    let inner = window.inner_size()?;
+    let frame_width = outer.width.saturating_sub(inner.width);
+    let frame_height = outer.height.saturating_sub(inner.height);
+
+    window.set_position(PhysicalPosition::new(geometry.x, geometry.y))?;
+    window.set_size(PhysicalSize::new(
+        geometry.width.saturating_sub(frame_width).max(1),
+        geometry.height.saturating_sub(frame_height).max(1),
+    ))?;
+    Ok(())
+}
+
+fn interpolate(from: Geometry, to: Geometry, t: f64) -> Geometry {
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    Geometry {
+        x: lerp(from.x as f64, to.x as f64).round() as i32,
+        y: lerp(from.y as f64, to.y as f64).round() as i32,
+        width: lerp(from.width as f64, to.width as f64).round() as u32,
+        height: lerp(from.height as f64, to.height as f64).round() as u32,
+    }
+}
+
+// Cubic ease-out: quick start, gentle landing
+fn ease_out(t: f64) -> f64 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+fn register_all(app_handle: &AppHandle, settings: &WindowPresetSettings) -> Result<()> {
+    for (preset, shortcut) in &settings.shortcuts {
+        let handle = app_handle.clone();
+        let preset = *preset;
+        app_handle
+            .global_shortcut_manager()
+            .register(shortcut, move || {
+                if let Err(e) = apply(&handle, preset) {
+                    eprintln!("Failed to apply window preset: {}", e);
+                }
+            })
+            .with_context(|| format!("Shortcut {} is unavailable", shortcut))?;
+    }
+    Ok(())
+}
+
+fn unregister_all(app_handle: &AppHandle, settings: &WindowPresetSettings) {
+    let mut manager = app_handle.global_shortcut_manager();
+    for shortcut in settings.shortcuts.values() {
+        if manager.is_registered(shortcut).unwrap_or(false) {
+            let _ = manager.unregister(shortcut);
+        }
+    }
+}
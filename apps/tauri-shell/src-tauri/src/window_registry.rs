@@ -0,0 +1,133 @@
+use crate::conversation_agents;
+use crate::window_title;
+use crate::AppState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Which conversation each open window shows. Requests are owned by the window that
+/// sent them (see agent_ipc); this is what decides where they go when it closes.
+#[derive(Default)]
+pub struct WindowRegistry {
+    conversations: Mutex<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RequestsReassigned<'a> {
+    from_window: &'a str,
+    request_ids: &'a [String],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowInfo {
+    pub label: String,
+    pub conversation_id: Option<String>,
+}
+
+#[tauri::command]
+pub fn list_windows(app_handle: AppHandle) -> Vec<WindowInfo> {
+    let registry = app_handle.state::<WindowRegistry>();
+    let conversations = registry.conversations.lock().unwrap();
+
+    app_handle
+        .windows()
+        .into_keys()
+        .map(|label| WindowInfo {
+            conversation_id: conversations.get(&label).cloned(),
+            label,
+        })
+        .collect()
+}
+
+/// Records the conversation shown in a window and retitles it.
+pub fn set_conversation(app_handle: &AppHandle, label: &str, conversation_id: &str) {
+    let closed = {
+        let registry = app_handle.state::<WindowRegistry>();
+        let mut conversations = registry.conversations.lock().unwrap();
+        let previous = conversations.insert(label.to_string(), conversation_id.to_string());
+        previous.filter(|previous| !shown(&conversations, previous))
+    };
+    window_title::conversation_changed(app_handle, label);
+    if let Some(closed) = closed {
+        conversation_agents::on_conversation_closed(app_handle, &closed);
+    }
+}
+
+pub fn conversation_of(app_handle: &AppHandle, label: &str) -> Option<String> {
+    app_handle
+        .state::<WindowRegistry>()
+        .conversations
+        .lock()
+        .unwrap()
+        .get(label)
+        .cloned()
+}
+
+/// Forgets a destroyed window. Its in-flight requests move to another open window
+/// showing the same conversation, or are interrupted when there is none.
+pub fn on_window_destroyed(app_handle: &AppHandle, label: &str) {
+    let (conversations, closed) = {
+        let registry = app_handle.state::<WindowRegistry>();
+        let mut conversations = registry.conversations.lock().unwrap();
+        let closed = conversations
+            .remove(label)
+            .filter(|shown_here| !shown(&conversations, shown_here));
+        (conversations.clone(), closed)
+    };
+    window_title::forget(app_handle, label);
+
+    // Successor per conversation; windows still being torn down don't qualify
+    let open_windows = app_handle.windows();
+    let successor = move |conversation_id: &str| {
+        conversations
+            .iter()
+            .find(|(other, shown)| {
+                shown.as_str() == conversation_id && open_windows.contains_key(other.as_str())
+            })
+            .map(|(other, _)| other.clone())
+    };
+
+    let app_handle = app_handle.clone();
+    let label = label.to_string();
+    tauri::async_runtime::spawn(async move {
+        let mut reassigned: HashMap<String, Vec<String>> = HashMap::new();
+        for (_, slot) in app_handle.state::<AppState>().slots() {
+            let agent = slot.lock().await;
+            let Some(process) = agent.as_ref() else {
+                continue;
+            };
+            for (owner, request_ids) in process.release_window(&label, &successor).await {
+                reassigned.entry(owner).or_default().extend(request_ids);
+            }
+        }
+
+        for (successor, request_ids) in reassigned {
+            eprintln!(
+                "[WINDOWS] Moved {} request(s) from {} to {}",
+                request_ids.len(),
+                label,
+                successor
+            );
+            let event = RequestsReassigned {
+                from_window: &label,
+                request_ids: &request_ids,
+            };
+            if let Err(e) = app_handle.emit_to(&successor, "requests_reassigned", event) {
+                eprintln!("Failed to emit requests_reassigned: {}", e);
+            }
+        }
+
+        // Only once its requests have been interrupted above
+        if let Some(closed) = closed {
+            conversation_agents::on_conversation_closed(&app_handle, &closed);
+        }
+    });
+}
+
+// Whether any window still shows `conversation_id`
+fn shown(conversations: &HashMap<String, String>, conversation_id: &str) -> bool {
+    conversations
+        .values()
+        .any(|shown| shown.as_str() == conversation_id)
+}
@@ -0,0 +1,104 @@
+use crate::store::ConversationStore;
+use crate::window_registry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Window};
+
+const APP_TITLE: &str = "Desktop Assistant";
+const STREAMING_INDICATOR: &str = "● ";
+
+/// Per-window title context: an explicit title and whether the window is currently
+/// streaming a reply. The conversation shown comes from the window registry.
+#[derive(Default)]
+pub struct WindowTitles {
+    // Explicit titles from the frontend, e.g. for a new chat not yet in the store
+    overrides: Mutex<HashMap<String, String>>,
+    streaming: Mutex<HashSet<String>>,
+}
+
+#[tauri::command]
+pub fn set_window_title_context(window: Window, title: Option<String>) {
+    let titles = window.state::<WindowTitles>();
+    {
+        let mut overrides = titles.overrides.lock().unwrap();
+        match title {
+            Some(title) => overrides.insert(window.label().to_string(), title),
+            None => overrides.remove(window.label()),
+        };
+    }
+    refresh(&window.app_handle(), window.label());
+}
+
+/// Retitles a window after it switched conversations.
+pub fn conversation_changed(app_handle: &AppHandle, label: &str) {
+    let titles = app_handle.state::<WindowTitles>();
+    titles.overrides.lock().unwrap().remove(label);
+    refresh(app_handle, label);
+}
+
+pub fn forget(app_handle: &AppHandle, label: &str) {
+    let titles = app_handle.state::<WindowTitles>();
+    titles.overrides.lock().unwrap().remove(label);
+    titles.streaming.lock().unwrap().remove(label);
+}
+
+/// Updates the streaming indicator for every window whose state changed.
+pub fn sync_streaming(app_handle: &AppHandle, streaming_labels: HashSet<String>) {
+    let titles = app_handle.state::<WindowTitles>();
+    let changed: Vec<String> = {
+        let mut streaming = titles.streaming.lock().unwrap();
+        if *streaming == streaming_labels {
+            return;
+        }
+        let changed = streaming
+            .symmetric_difference(&streaming_labels)
+            .cloned()
+            .collect();
+        *streaming = streaming_labels;
+        changed
+    };
+
+    for label in changed {
+        refresh(app_handle, &label);
+    }
+}
+
+fn refresh(app_handle: &AppHandle, label: &str) {
+    let Some(window) = app_handle.get_window(label) else {
+        return;
+    };
+    let titles = app_handle.state::<WindowTitles>();
+
+    let conversation_title = titles
+        .overrides
+        .lock()
+        .unwrap()
+        .get(label)
+        .cloned()
+        .or_else(|| {
+            let conversation_id = window_registry::conversation_of(app_handle, label)?;
+            app_handle
+                .state::<ConversationStore>()
+                .load(&conversation_id)
+                .ok()
+                .map(|conversation| conversation.title)
+        });
+    let streaming = titles.streaming.lock().unwrap().contains(label);
+
+    let mut title = String::new();
+    if streaming {
+        title.push_str(STREAMING_INDICATOR);
+    }
+    match conversation_title {
+        Some(conversation_title) => {
+            title.push_str(&conversation_title);
+            title.push_str(" — ");
+            title.push_str(APP_TITLE);
+        }
+        None => title.push_str(APP_TITLE),
+    }
+
+    if let Err(e) = window.set_title(&title) {
+        eprintln!("Failed to set window title: {}", e);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::settings::SettingsStore;
+use tauri::{Manager, State, Window};
+
+pub const DEFAULT_ZOOM: f64 = 1.0;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const ZOOM_STEP: f64 = 0.1;
+
+#[tauri::command]
+pub fn set_zoom(
+    window: Window,
+    settings: State<'_, SettingsStore>,
+    factor: f64,
+) -> Result<f64, String> {
+    apply_and_persist(&window, &settings, factor)
+}
+
+#[tauri::command]
+pub fn get_zoom(window: Window, settings: State<'_, SettingsStore>) -> f64 {
+    saved_zoom(&window, &settings)
+}
+
+/// Re-applies the persisted zoom, e.g. after the webview navigates or reloads.
+pub fn restore(window: &Window) {
+    let settings = window.state::<SettingsStore>();
+    let factor = saved_zoom(window, &settings);
+
+    if let Err(e) = apply(window, factor) {
+        eprintln!("Failed to restore zoom for {}: {}", window.label(), e);
+    }
+}
+
+/// Handles the Zoom In / Zoom Out / Actual Size menu items.
+pub fn handle_menu_item(window: &Window, id: &str) {
+    let settings = window.state::<SettingsStore>();
+    let current = saved_zoom(window, &settings);
+
+    let factor = match id {
+        "zoom_in" => current + ZOOM_STEP,
+        "zoom_out" => current - ZOOM_STEP,
+        "zoom_reset" => DEFAULT_ZOOM,
+        _ => return,
+    };
+
+    if let Err(e) = apply_and_persist(window, &settings, factor) {
+        eprintln!("{}", e);
+    }
+}
+
+fn saved_zoom(window: &Window, settings: &SettingsStore) -> f64 {
+    settings
+        .get()
+        .zoom
+        .get(window.label())
+        .copied()
+        .unwrap_or(DEFAULT_ZOOM)
+}
+
+fn apply_and_persist(
+    window: &Window,
+    settings: &SettingsStore,
+    factor: f64,
+) -> Result<f64, String> {
+    if !factor.is_finite() {
+        return Err("Invalid zoom factor".to_string());
+    }
+
+    // Round to the step so repeated menu presses don't accumulate float error
+    let factor = ((factor.clamp(MIN_ZOOM, MAX_ZOOM) / ZOOM_STEP).round() * ZOOM_STEP * 100.0)
+        .round()
+        / 100.0;
+
+    apply(window, factor).map_err(|e| format!("Failed to apply zoom: {}", e))?;
+
+    settings
+        .update(|s| {
+            s.zoom.insert(window.label().to_string(), factor);
+        })
+        .map_err(|e| format!("Failed to save zoom: {}", e))?;
+
+    Ok(factor)
+}
+
+fn apply(window: &Window, factor: f64) -> tauri::Result<()> {
+    window.eval(&format!(
+        "document.documentElement.style.zoom = '{}'",
+        factor
+    ))
+}
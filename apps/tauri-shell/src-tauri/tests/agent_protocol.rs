@@ -0,0 +1,502 @@
+//! End-to-end tests of the agent stdio protocol against `tests/support/fake_agent.rs`.
+//!
+//! Run with `cargo test --features integration-tests`; no Node install is needed.
+
+#![cfg(feature = "integration-tests")]
+
+#[path = "../src/protocol.rs"]
+mod protocol;
+
+use protocol::{AgentRequest, AgentResponse, Capabilities, Encoding, Framing, LoadedConversation};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct FakeAgent {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    capabilities: Capabilities,
+}
+
+// Everything a request produced: concatenated tokens plus the Done/Error that ended it
+struct Outcome {
+    text: String,
+    end: AgentResponse,
+}
+
+impl FakeAgent {
+    async fn spawn() -> Self {
+        Self::spawn_with_ready().await.0
+    }
+
+    // Also returns the Ready it announced itself with
+    async fn spawn_with_ready() -> (Self, AgentResponse) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_fake-agent"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("Failed to spawn fake agent");
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut agent = FakeAgent {
+            child,
+            stdin,
+            stdout,
+            capabilities: Capabilities::default(),
+        };
+
+        let ready = agent.next().await.expect("Agent exited before Ready");
+        assert!(matches!(ready, AgentResponse::Ready { .. }));
+        (agent, ready)
+    }
+
+    // Offers `offer` in hello, as the shell does, and reads the rest as agreed
+    async fn spawn_with_hello(offer: Capabilities) -> (Self, Capabilities) {
+        let mut agent = Self::spawn().await;
+        agent
+            .send(&AgentRequest::Hello {
+                id: "h".to_string(),
+                protocol_version: protocol::PROTOCOL_VERSION,
+                capabilities: offer,
+            })
+            .await;
+        let Some(AgentResponse::Hello {
+            id, capabilities, ..
+        }) = agent.next().await
+        else {
+            panic!("Expected the hello answer");
+        };
+        assert_eq!(id, "h");
+        agent.capabilities = capabilities.clone();
+        (agent, capabilities)
+    }
+
+    async fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.stdin.write_all(bytes).await?;
+        self.stdin.flush().await
+    }
+
+    async fn send(&mut self, request: &AgentRequest) {
+        let json = serde_json::to_string(request).unwrap();
+        self.write_raw(format!("{}\n", json).as_bytes())
+            .await
+            .expect("Failed to write request");
+    }
+
+    /// Next response, or None once the agent closed stdout.
+    async fn next(&mut self) -> Option<AgentResponse> {
+        let message = tokio::time::timeout(
+            TIMEOUT,
+            protocol::read_message(&mut self.stdout, &self.capabilities),
+        )
+        .await
+        .expect("Timed out waiting for the agent")
+        .expect("Failed to read agent stdout")?;
+
+        let response = protocol::decode(&message, self.capabilities.encoding);
+        Some(response.expect("Agent sent an unparseable message"))
+    }
+
+    /// Reads responses until every id in `ids` has ended, grouping them by id.
+    async fn settle(&mut self, ids: &[&str]) -> HashMap<String, Outcome> {
+        let mut outcomes: HashMap<String, Outcome> = HashMap::new();
+        let mut text: HashMap<String, String> = HashMap::new();
+
+        while outcomes.len() < ids.len() {
+            let response = self.next().await.expect("Agent exited early");
+            let id = response.id().expect("Unexpected Ready").to_string();
+            assert!(ids.contains(&id.as_str()), "Response for unknown id {}", id);
+
+            match response {
+                AgentResponse::Token { token, .. } => text.entry(id).or_default().push_str(&token),
+                AgentResponse::Done { .. } | AgentResponse::Error { .. } => {
+                    let outcome = Outcome {
+                        text: text.remove(&id).unwrap_or_default(),
+                        end: response,
+                    };
+                    outcomes.insert(id, outcome);
+                }
+                _ => {}
+            }
+        }
+
+        outcomes
+    }
+}
+
+fn user_message(id: &str, message: &str) -> AgentRequest {
+    AgentRequest::UserMessage {
+        id: id.to_string(),
+        message: message.to_string(),
+        images: None,
+        conversation_id: None,
+    }
+}
+
+fn interrupt(id: &str) -> AgentRequest {
+    AgentRequest::Interrupt { id: id.to_string() }
+}
+
+fn error_code(response: &AgentResponse) -> Option<&str> {
+    match response {
+        AgentResponse::Error { code, .. } => code.as_deref(),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn streams_tokens_then_done() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "hello there world")).await;
+
+    let outcomes = agent.settle(&["a"]).await;
+    assert_eq!(outcomes["a"].text, "hello there world");
+    assert!(matches!(outcomes["a"].end, AgentResponse::Done { .. }));
+}
+
+#[tokio::test]
+async fn concurrent_streams_are_correlated_by_id() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "one two three four")).await;
+    agent.send(&user_message("b", "five six seven eight")).await;
+
+    let outcomes = agent.settle(&["a", "b"]).await;
+    assert_eq!(outcomes["a"].text, "one two three four");
+    assert_eq!(outcomes["b"].text, "five six seven eight");
+}
+
+#[tokio::test]
+async fn requests_are_framed_by_newlines_not_writes() {
+    let mut agent = FakeAgent::spawn().await;
+    let first = serde_json::to_string(&user_message("a", "split")).unwrap();
+    let second = serde_json::to_string(&user_message("b", "joined")).unwrap();
+
+    // One request split over two writes, then the rest of it and a second request in one
+    let (head, tail) = first.split_at(first.len() / 2);
+    agent.write_raw(head.as_bytes()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    agent
+        .write_raw(format!("{}\n{}\n", tail, second).as_bytes())
+        .await
+        .unwrap();
+
+    let outcomes = agent.settle(&["a", "b"]).await;
+    assert_eq!(outcomes["a"].text, "split");
+    assert_eq!(outcomes["b"].text, "joined");
+}
+
+#[tokio::test]
+async fn interrupt_ends_a_stalled_stream() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "!stall")).await;
+    agent.send(&interrupt("a")).await;
+
+    let outcomes = agent.settle(&["a"]).await;
+    assert_eq!(error_code(&outcomes["a"].end), Some("interrupted"));
+}
+
+#[tokio::test]
+async fn interrupt_only_cancels_its_target() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "!stall")).await;
+    agent.send(&user_message("b", "still streaming")).await;
+    agent.send(&interrupt("a")).await;
+
+    let outcomes = agent.settle(&["a", "b"]).await;
+    assert_eq!(error_code(&outcomes["a"].end), Some("interrupted"));
+    assert_eq!(outcomes["b"].text, "still streaming");
+    assert!(matches!(outcomes["b"].end, AgentResponse::Done { .. }));
+}
+
+#[tokio::test]
+async fn rate_limit_carries_retry_after() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "!rate_limit")).await;
+
+    let outcomes = agent.settle(&["a"]).await;
+    assert_eq!(error_code(&outcomes["a"].end), Some("rate_limited"));
+    assert!(matches!(
+        outcomes["a"].end,
+        AgentResponse::Error {
+            retry_after_ms: Some(100),
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn ready_announces_a_supported_protocol() {
+    let (_agent, ready) = FakeAgent::spawn_with_ready().await;
+    let AgentResponse::Ready {
+        protocol_version,
+        agent_version,
+        ..
+    } = ready
+    else {
+        unreachable!()
+    };
+
+    assert_eq!(
+        protocol::supported_protocol(protocol_version),
+        Ok(protocol::PROTOCOL_VERSION)
+    );
+    assert_eq!(agent_version.as_deref(), Some("fake"));
+    // Agents from before the handshake are still understood, future ones aren't
+    assert_eq!(protocol::supported_protocol(None), Ok(1));
+    assert!(protocol::supported_protocol(Some(protocol::PROTOCOL_VERSION + 1)).is_err());
+}
+
+#[test]
+fn invalid_responses_name_the_wrong_fields() {
+    let diagnose = |json: &str| protocol::diagnose(json.as_bytes(), Encoding::Json);
+
+    let wrong_type = diagnose(r#"{"type":"token","id":"a","token":5,"timestamp":1}"#);
+    assert_eq!(wrong_type.kind.as_deref(), Some("token"));
+    assert_eq!(wrong_type.id.as_deref(), Some("a"));
+    assert_eq!(wrong_type.fields, ["token"]);
+
+    let missing = diagnose(r#"{"type":"done","timestamp":1}"#);
+    assert_eq!(missing.fields, ["id"]);
+
+    let unknown = diagnose(r#"{"type":"telepathy","id":"a"}"#);
+    assert!(unknown.error.contains("telepathy"));
+    assert!(unknown.fields.is_empty());
+
+    let garbled = diagnose("{\"type\":");
+    assert!(garbled.kind.is_none());
+    assert!(garbled.error.starts_with("Not valid JSON"));
+}
+
+#[tokio::test]
+async fn user_message_is_acked_before_tokens() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "hello world")).await;
+
+    match agent.next().await.expect("Agent exited") {
+        AgentResponse::Ack { id, .. } => assert_eq!(id, "a"),
+        other => panic!("Expected an ack first, got {:?}", other),
+    }
+    let outcomes = agent.settle(&["a"]).await;
+    assert_eq!(outcomes["a"].text, "hello world");
+}
+
+#[tokio::test]
+async fn image_output_precedes_done() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "!image")).await;
+
+    assert!(matches!(
+        agent.next().await,
+        Some(AgentResponse::Ack { .. })
+    ));
+    match agent.next().await.expect("Agent exited") {
+        AgentResponse::ImageOutput { id, data, mime, .. } => {
+            assert_eq!(id, "a");
+            assert_eq!(mime, "image/png");
+            assert!(!data.is_empty());
+        }
+        other => panic!("Expected an image, got {:?}", other),
+    }
+    assert!(matches!(
+        agent.next().await,
+        Some(AgentResponse::Done { .. })
+    ));
+}
+
+#[tokio::test]
+async fn hello_agrees_to_length_prefixed_frames() {
+    let (mut agent, agreed) = FakeAgent::spawn_with_hello(Capabilities {
+        framing: Framing::LengthPrefixed,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(agreed.framing, Framing::LengthPrefixed);
+    assert_eq!(agreed.encoding, Encoding::Json);
+
+    // The fake agent pretty-prints frames, so each message spans several lines
+    agent.send(&user_message("a", "!image")).await;
+    agent.send(&user_message("b", "hello world")).await;
+    let outcomes = agent.settle(&["a", "b"]).await;
+    assert!(matches!(outcomes["a"].end, AgentResponse::Done { .. }));
+    assert_eq!(outcomes["b"].text, "hello world");
+}
+
+#[tokio::test]
+async fn hello_agrees_to_compressed_msgpack_frames() {
+    let (mut agent, agreed) = FakeAgent::spawn_with_hello(Capabilities {
+        framing: Framing::LengthPrefixed,
+        encoding: Encoding::MessagePack,
+        compression: true,
+        attachments: true,
+        tool_approval: true,
+    })
+    .await;
+    assert!(agreed.compression);
+    assert_eq!(agreed.encoding, Encoding::MessagePack);
+    // Features the agent lacks stay off even when offered
+    assert!(!agreed.attachments);
+    assert!(!agreed.tool_approval);
+
+    agent.send(&user_message("a", "!image")).await;
+    assert!(matches!(
+        agent.next().await,
+        Some(AgentResponse::Ack { .. })
+    ));
+    match agent.next().await.expect("Agent exited") {
+        AgentResponse::ImageOutput { data, mime, .. } => {
+            assert_eq!(mime, "image/png");
+            assert!(!data.is_empty());
+        }
+        other => panic!("Expected an image, got {:?}", other),
+    }
+    assert!(matches!(
+        agent.next().await,
+        Some(AgentResponse::Done { .. })
+    ));
+}
+
+#[test]
+fn image_data_is_binary_in_msgpack_and_base64_in_json() {
+    let image = AgentResponse::ImageOutput {
+        id: "a".to_string(),
+        data: vec![0, 10, 255],
+        mime: "image/png".to_string(),
+        timestamp: 1,
+    };
+    let json = serde_json::to_value(&image).unwrap();
+    assert_eq!(json["data"], "AAr/");
+
+    let msgpack = rmp_serde::to_vec_named(&image).unwrap();
+    // bin 8 with a 3-byte length, not a str of base64
+    assert!(msgpack
+        .windows(5)
+        .any(|bytes| bytes == [0xc4, 3, 0, 10, 255]));
+    for (message, encoding) in [
+        (serde_json::to_vec(&json).unwrap(), Encoding::Json),
+        (msgpack, Encoding::MessagePack),
+    ] {
+        match protocol::decode::<AgentResponse>(&message, encoding).unwrap() {
+            AgentResponse::ImageOutput { data, .. } => assert_eq!(data, [0, 10, 255]),
+            other => panic!("Expected an image, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn answers_beyond_the_offer_are_refused() {
+    let offered = Capabilities {
+        framing: Framing::LengthPrefixed,
+        encoding: Encoding::MessagePack,
+        compression: true,
+        ..Default::default()
+    };
+    assert!(Capabilities::default().within(&offered));
+    assert!(offered.within(&offered));
+
+    let approval = Capabilities {
+        tool_approval: true,
+        ..Default::default()
+    };
+    assert!(!approval.within(&offered));
+    // MessagePack and compression can't be told apart in lines
+    let unframed = Capabilities {
+        encoding: Encoding::MessagePack,
+        ..Default::default()
+    };
+    assert!(!unframed.within(&offered));
+    let framed = Capabilities {
+        framing: Framing::LengthPrefixed,
+        ..Default::default()
+    };
+    assert!(!framed.within(&Capabilities::default()));
+}
+
+#[tokio::test]
+async fn ping_is_answered_with_pong() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "one two three")).await;
+    agent
+        .send(&AgentRequest::Ping {
+            id: "p".to_string(),
+        })
+        .await;
+
+    // Answered right away, even while a stream is in progress
+    loop {
+        match agent.next().await.expect("Agent exited") {
+            AgentResponse::Pong { id, .. } => {
+                assert_eq!(id, "p");
+                break;
+            }
+            AgentResponse::Done { .. } => panic!("Pong arrived after the stream ended"),
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn load_conversation_answers_with_data() {
+    let mut agent = FakeAgent::spawn().await;
+    agent
+        .send(&AgentRequest::LoadConversation {
+            id: "l".to_string(),
+            conversation_id: "c".to_string(),
+        })
+        .await;
+
+    let Some(AgentResponse::Done { id, data, .. }) = agent.next().await else {
+        panic!("Expected Done");
+    };
+    assert_eq!(id, "l");
+    let loaded: LoadedConversation = serde_json::from_value(data.unwrap()).unwrap();
+    assert_eq!(loaded.conversation_id, "c");
+}
+
+#[tokio::test]
+async fn crash_closes_stdout_and_breaks_stdin() {
+    let mut agent = FakeAgent::spawn().await;
+    agent.send(&user_message("a", "!crash")).await;
+
+    assert!(agent.next().await.is_none());
+    let status = tokio::time::timeout(TIMEOUT, agent.child.wait())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.code(), Some(1));
+
+    // The shell respawns on exactly this error kind
+    let error = agent.write_raw(b"{}\n").await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn requests_keep_their_wire_format() {
+    let merge = AgentRequest::MergeConversations {
+        id: "m".to_string(),
+        conversation_id: "target".to_string(),
+        source_id: "source".to_string(),
+    };
+    assert_eq!(
+        serde_json::to_value(&merge).unwrap(),
+        serde_json::json!({
+            "kind": "merge_conversations",
+            "id": "m",
+            "conversation_id": "target",
+            "message": "source",
+        })
+    );
+
+    // As written to the outbox by earlier versions
+    let queued: AgentRequest =
+        serde_json::from_str(r#"{"id":"a","kind":"user_message","message":"hi"}"#).unwrap();
+    assert_eq!(queued.kind(), "user_message");
+    assert_eq!(queued.id(), "a");
+}
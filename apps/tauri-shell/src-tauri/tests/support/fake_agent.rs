@@ -0,0 +1,199 @@
+//! Minimal stand-in for agent-runtime that speaks the stdio protocol without Node.
+//!
+//! Replies are scripted by the message text:
+//! - `!crash`       exits with status 1 without answering
+//! - `!stall`       streams nothing until interrupted
+//! - `!rate_limit`  answers with a `rate_limited` error (retry_after_ms 100)
+//! - `!image`       sends a 1x1 PNG as `image_output`, then `done`
+//! - anything else  echoes the message back word by word, then `done`
+//!
+//! Every message except `!crash` is acked first.
+//!
+//! Hello gets framing, encoding and compression as offered, never attachments or tool
+//! approval. Length-prefixed JSON frames are pretty-printed, and with compression every
+//! frame is gzipped.
+//!
+//! Run the shell against it with `ASST_AGENT_COMMAND=path/to/fake-agent`.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const TOKEN_DELAY: Duration = Duration::from_millis(5);
+// A single transparent pixel
+const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+// Top bit of a frame's length header
+const COMPRESSED_FRAME: u32 = 1 << 31;
+
+// Capabilities agreed in hello; unset means JSON lines
+static AGREED: OnceLock<Value> = OnceLock::new();
+
+fn main() {
+    let interrupted: Arc<Mutex<HashSet<String>>> = Arc::default();
+
+    send(json!({
+        "type": "ready",
+        "protocol_version": 3,
+        "agent_version": "fake",
+        "timestamp": now(),
+    }));
+
+    for line in std::io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("[fake-agent] Invalid request: {}", e);
+                continue;
+            }
+        };
+
+        let id = request["id"].as_str().unwrap_or_default().to_string();
+        match request["kind"].as_str() {
+            Some("user_message") => {
+                let message = request["message"].as_str().unwrap_or_default().to_string();
+                let interrupted = interrupted.clone();
+                thread::spawn(move || respond(&id, &message, &interrupted));
+            }
+            Some("interrupt") => {
+                interrupted.lock().unwrap().insert(id);
+            }
+            Some("ping") => {
+                send(json!({ "type": "pong", "id": id, "timestamp": now() }));
+            }
+            Some("hello") => hello(&id, &request["capabilities"]),
+            Some("load_conversation") => {
+                let conversation_id = request["conversation_id"].clone();
+                send(json!({
+                    "type": "done",
+                    "id": id,
+                    "data": { "conversation_id": conversation_id, "message_count": 0 },
+                    "timestamp": now(),
+                }));
+            }
+            Some("new_conversation" | "clear_history") => {
+                send(json!({ "type": "done", "id": id, "timestamp": now() }));
+            }
+            other => eprintln!("[fake-agent] Unknown request kind: {:?}", other),
+        }
+    }
+}
+
+fn respond(id: &str, message: &str, interrupted: &Mutex<HashSet<String>>) {
+    let is_interrupted = || interrupted.lock().unwrap().remove(id);
+
+    if message == "!crash" {
+        std::process::exit(1);
+    }
+    send(json!({ "type": "ack", "id": id, "timestamp": now() }));
+
+    match message {
+        "!rate_limit" => {
+            send(json!({
+                "type": "error",
+                "id": id,
+                "error": "Rate limited",
+                "code": "rate_limited",
+                "retry_after_ms": 100,
+                "timestamp": now(),
+            }));
+            return;
+        }
+        "!image" => {
+            send(json!({
+                "type": "image_output",
+                "id": id,
+                "data": PIXEL_PNG,
+                "mime": "image/png",
+                "timestamp": now(),
+            }));
+            send(json!({ "type": "done", "id": id, "timestamp": now() }));
+            return;
+        }
+        "!stall" => {
+            while !is_interrupted() {
+                thread::sleep(TOKEN_DELAY);
+            }
+            send_interrupted(id);
+            return;
+        }
+        _ => {}
+    }
+
+    for word in message.split_inclusive(' ') {
+        if is_interrupted() {
+            send_interrupted(id);
+            return;
+        }
+        send(json!({ "type": "token", "id": id, "token": word, "timestamp": now() }));
+        thread::sleep(TOKEN_DELAY);
+    }
+
+    send(json!({ "type": "done", "id": id, "timestamp": now() }));
+}
+
+fn send_interrupted(id: &str) {
+    send(json!({
+        "type": "error",
+        "id": id,
+        "error": "Interrupted",
+        "code": "interrupted",
+        "timestamp": now(),
+    }));
+}
+
+// One line or frame per response; the lock keeps concurrent streams from interleaving
+fn send(response: Value) {
+    let mut stdout = std::io::stdout().lock();
+    match AGREED.get() {
+        Some(agreed) if agreed["framing"] == "length_prefixed" => {
+            let mut payload = if agreed["encoding"] == "msgpack" {
+                rmp_serde::to_vec_named(&response).unwrap()
+            } else {
+                serde_json::to_vec_pretty(&response).unwrap()
+            };
+            let mut header = payload.len() as u32;
+            if agreed["compression"] == true {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(&payload).unwrap();
+                payload = encoder.finish().unwrap();
+                header = payload.len() as u32 | COMPRESSED_FRAME;
+            }
+            let _ = stdout.write_all(&header.to_be_bytes());
+            let _ = stdout.write_all(&payload);
+        }
+        _ => {
+            let _ = writeln!(stdout, "{}", response);
+        }
+    }
+    let _ = stdout.flush();
+}
+
+// Takes up what it can of the shell's offer, answering before switching to it
+fn hello(id: &str, offer: &Value) {
+    let framed = offer["framing"] == "length_prefixed";
+    let agreed = json!({
+        "framing": if framed { "length_prefixed" } else { "lines" },
+        // MessagePack only goes in frames
+        "encoding": if framed && offer["encoding"] == "msgpack" { "msgpack" } else { "json" },
+        "compression": framed && offer["compression"] == true,
+        "attachments": false,
+        "tool_approval": false,
+    });
+    send(json!({ "type": "hello", "id": id, "capabilities": agreed, "timestamp": now() }));
+    let _ = AGREED.set(agreed);
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
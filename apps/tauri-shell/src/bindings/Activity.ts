@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ActivityPeriod } from "./ActivityPeriod";
+import type { DayActivity } from "./DayActivity";
+
+/**
+ * Every day of the period, oldest first, including days without activity.
+ */
+export type Activity = { period: ActivityPeriod, days: Array<DayActivity>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Days ending today that get_activity covers.
+ */
+export type ActivityPeriod = "week" | "month" | "year";
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AgentSource } from "./AgentSource";
+
+export type AgentDetection = { found: boolean, source: AgentSource | null, version: string | null, node_version: string | null, };
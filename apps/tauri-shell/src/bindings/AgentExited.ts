@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AgentExited = { agent_id: string, session_id: string, exit_code: number | null, signal: number | null, stderr_tail: Array<string>, };
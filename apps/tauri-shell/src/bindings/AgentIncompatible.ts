@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Emitted as agent_incompatible when an agent's Ready announces a protocol version
+ * this shell can't speak.
+ */
+export type AgentIncompatible = { agent_id: string, protocol_version: number, agent_version: string | null, min_protocol_version: number, max_protocol_version: number, shell_version: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InvalidResponse } from "./InvalidResponse";
+
+/**
+ * Emitted as agent_protocol_error for each agent message that isn't a valid response.
+ * The message itself is dropped.
+ */
+export type AgentProtocolError = { agent_id: string, excerpt: string, } & InvalidResponse;
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AgentRecovered = { request_id: string, conversation_id: string | null, lost_requests: Array<string>, from_standby: boolean, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Capabilities } from "./Capabilities";
+
+/**
+ * A request to the agent. Every kind carries the id its responses answer to.
+ */
+export type AgentRequest = { "kind": "user_message", id: string, message: string, images: string | null, conversation_id: string | null, } | { "kind": "interrupt", id: string, } | { "kind": "clear_history", id: string, } | { "kind": "load_conversation", id: string, conversation_id: string, } | { "kind": "load_conversation_compressed", id: string, conversation_id: string, message: string, } | { "kind": "merge_conversations", id: string, conversation_id: string, message: string, } | { "kind": "transform", id: string, message: string, } | { "kind": "summarize", id: string, message: string, } | { "kind": "ping", id: string, } | { "kind": "shutdown", id: string, } | { "kind": "hello", id: string, protocol_version: number, capabilities: Capabilities, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Capabilities } from "./Capabilities";
+import type { Usage } from "./Usage";
+import type { JsonValue } from "./serde_json/JsonValue";
+
+export type AgentResponse = { "type": "ready", protocol_version: number | null, agent_version: string | null, timestamp: number, } | { "type": "hello", id: string, capabilities: Capabilities, timestamp: number, } | { "type": "ack", id: string, timestamp: number, } | { "type": "token", id: string, token: string, timestamp: number, } | { "type": "tool_use", id: string, data: JsonValue, timestamp: number, } | { "type": "tool_result", id: string, data: JsonValue, timestamp: number, } | { "type": "image_output", id: string, data: string, mime: string, timestamp: number, } | { "type": "done", id: string, usage: Usage | null, data: JsonValue | null, timestamp: number, } | { "type": "pong", id: string, timestamp: number, } | { "type": "error", id: string, error: string, code: string | null, retry_after_ms: number | null, timestamp: number, };
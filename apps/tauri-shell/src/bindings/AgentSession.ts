@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The lifetime of one agent process, saved as <id>.json next to its log.
+ */
+export type AgentSession = { session_id: string, agent_id: string, started_at: number, ended_at: number | null, exit_code: number | null, crashed: boolean, };
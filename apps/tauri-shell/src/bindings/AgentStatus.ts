@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AgentState } from "./AgentState";
+
+export type AgentStatus = { state: AgentState, pid: number | null, spawned_at: number | null, uptime_ms: number | null, last_message_at: number | null, in_flight: bigint, session_id: string | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BudgetPeriod } from "./BudgetPeriod";
+
+export type BudgetStatus = { period: BudgetPeriod, period_start: number, resets_at: number, spent_usd: number, input_tokens: number, output_tokens: number, soft_limit_usd: number | null, hard_limit_usd: number | null, blocked: boolean, overridden: boolean, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Encoding } from "./Encoding";
+import type { Framing } from "./Framing";
+
+/**
+ * Optional protocol features. The shell offers the ones it handles in hello and the
+ * agent answers with those it will use; everything is off until then.
+ */
+export type Capabilities = { framing: Framing, encoding: Encoding, compression: boolean, attachments: boolean, tool_approval: boolean, };
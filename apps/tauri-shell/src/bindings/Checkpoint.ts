@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Summary of the start of a long conversation, written by checkpoints so the agent
+ * can be given it in place of the messages it covers.
+ */
+export type Checkpoint = { summary: string, covered_messages: bigint, created_at: number, };
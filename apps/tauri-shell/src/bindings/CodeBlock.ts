@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A fenced or indented code block from a stored message. `id` is stable, so it can be
+ * passed to save_code_block later without extracting again.
+ */
+export type CodeBlock = { id: string, index: number, language: string | null, extension: string | null, code: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What load_conversation_compressed gave the agent.
+ */
+export type CompressedLoad = { conversation_id: string, agent_id: string, summarized_messages: bigint, recent_messages: bigint, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InFlightRequest } from "./InFlightRequest";
+
+/**
+ * What was known about an agent process when it died, saved as
+ * crash-reports/<report_id>.json for the user to view or send.
+ */
+export type CrashReport = { report_id: string, agent_id: string, session_id: string, shell_version: string, os: string, started_at: number, crashed_at: number, exit_code: number | null, signal: number | null, stderr_tail: Array<string>, stdout_tail: Array<string>, in_flight: Array<InFlightRequest>, };
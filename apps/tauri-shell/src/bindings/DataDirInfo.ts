@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DataDirSource } from "./DataDirSource";
+
+export type DataDirInfo = { path: string | null, source: DataDirSource, default_path: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What happened on one local calendar day.
+ */
+export type DayActivity = { date: string, user_messages: bigint, replies: bigint, conversations: bigint, models: { [key in string]?: bigint }, tools: { [key in string]?: bigint }, };
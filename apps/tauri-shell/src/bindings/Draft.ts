@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Half-written prompt for a conversation, kept across restarts and window toggles.
+ */
+export type Draft = { text: string, attachments: string | null, updated_at: number, };
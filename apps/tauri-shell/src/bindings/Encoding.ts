@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How each agent message is serialized. MessagePack needs length-prefixed framing.
+ */
+export type Encoding = "json" | "msgpack";
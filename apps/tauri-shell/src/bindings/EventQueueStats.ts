@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a full queue has cost, since the agent started.
+ */
+export type EventQueueStats = { capacity: bigint, queued: bigint, emitted: number, coalesced_tokens: number, dropped_logs: number, stalls: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How agent output is delimited. Ready and the hello answer are always lines.
+ */
+export type Framing = "lines" | "length_prefixed";
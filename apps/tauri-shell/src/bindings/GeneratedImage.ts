@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An image the agent produced, kept in generated-images/ under the data dir. The
+ * webview gets this instead of the image data the agent sent.
+ */
+export type GeneratedImage = { image_id: string, request_id: string, mime_type: string, file_name: string, size_bytes: bigint, width: number | null, height: number | null, thumbnail: string | null, };
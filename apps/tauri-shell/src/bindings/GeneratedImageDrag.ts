@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the webview needs to let a generated image be dragged out of the window.
+ */
+export type GeneratedImageDrag = { path: string, file_name: string, mime_type: string, download_url: string, };
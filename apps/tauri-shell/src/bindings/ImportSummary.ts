@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ImportSummary = { snippets_added: number, agent_profiles_added: number, agent_profiles_pending: number, warnings: Array<string>, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MessageState } from "./MessageState";
+
+/**
+ * A request the agent was working on when it died.
+ */
+export type InFlightRequest = { id: string, kind: string, conversation_id: string | null, message: string | null, state: MessageState, response_chars: bigint, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What is wrong with a message that isn't a valid AgentResponse, as far as a lenient
+ * parse can tell.
+ */
+export type InvalidResponse = { kind: string | null, id: string | null, error: string, fields: Array<string>, };
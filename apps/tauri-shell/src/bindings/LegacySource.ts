@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Older data layouts the shell knows how to import. There are none at the moment:
+ * ~/.claude/history.db is the agent's live database, not legacy data, and must never
+ * be imported and removed.
+ */
+export type LegacySource = never;
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Data of the Done answering load_conversation and load_conversation_compressed.
+ */
+export type LoadedConversation = { conversation_id: string, message_count: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Delivery state of a user message, emitted as message_state on each transition.
+ */
+export type MessageState = "queued" | "sent" | "acked" | "streaming" | "done" | "failed";
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MessageState } from "./MessageState";
+
+export type MessageStateChange = { agent_id: string, id: string, state: MessageState, error: string | null, timestamp: number, };
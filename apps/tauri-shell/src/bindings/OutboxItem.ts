@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AgentRequest } from "./AgentRequest";
+
+export type OutboxItem = { request: AgentRequest, owner: string | null, queued_at: number, };
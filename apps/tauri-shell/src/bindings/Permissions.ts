@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PermissionState } from "./PermissionState";
+
+export type Permissions = { accessibility: PermissionState, screen_recording: PermissionState, microphone: PermissionState, notifications: PermissionState, };
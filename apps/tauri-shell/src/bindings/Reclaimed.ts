@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Space freed by a sweep or clear_cache.
+ */
+export type Reclaimed = { bytes: number, files: bigint, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * UTF-8 text of a spilled reply. `offset` and `end` are the byte positions actually
+ * returned, snapped to character boundaries; continue reading from `end`.
+ */
+export type ResponseRange = { text: string, offset: number, end: number, total: number, };
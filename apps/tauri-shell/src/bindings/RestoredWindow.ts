@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Draft } from "./Draft";
+
+export type RestoredWindow = { label: string, conversation_id: string | null, draft: Draft | null, };
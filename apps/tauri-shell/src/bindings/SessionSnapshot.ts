@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OutboxItem } from "./OutboxItem";
+import type { WindowSession } from "./WindowSession";
+
+/**
+ * Runtime state written to session.json every few seconds. `clean_exit` is only set
+ * on an orderly quit, so finding it false at launch means the app crashed or was
+ * force-quit.
+ */
+export type SessionSnapshot = { clean_exit: boolean, saved_at: number, windows: Array<WindowSession>, interrupted: Array<OutboxItem>, };
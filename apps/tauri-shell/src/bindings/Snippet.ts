@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Snippet = { id: string, name: string, body: string, favorite: boolean, shortcut: string | null, created_at: number, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SpawnStage } from "./SpawnStage";
+
+/**
+ * Why an agent failed to start, emitted as agent_spawn_failed and returned from
+ * AgentProcess::spawn.
+ */
+export type SpawnFailure = { agent_id: string, stage: SpawnStage, message: string, probable_causes: Array<string>, stderr_tail: Array<string>, };
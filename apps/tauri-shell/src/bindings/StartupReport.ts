@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AgentDetection } from "./AgentDetection";
+import type { PendingMigration } from "./PendingMigration";
+import type { Permissions } from "./Permissions";
+import type { StartupWarning } from "./StartupWarning";
+
+/**
+ * Everything checked at launch, emitted once as startup_report. `warnings` lists
+ * what needs the user's attention; the rest is there for details.
+ */
+export type StartupReport = { agent: AgentDetection, settings_error: string | null, keychain_error: string | null, unreadable_secrets: Array<string>, permissions: Permissions, pending_migrations: Array<PendingMigration>, unclean_shutdown: boolean, interrupted_messages: bigint, warnings: Array<StartupWarning>, generated_at: number, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Tokens billed for one turn, summed over every API call it made.
+ */
+export type Usage = { model: string, input_tokens: number, output_tokens: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type WindowSession = { label: string, conversation_id: string | null, x: number, y: number, width: number, height: number, maximized: boolean, visible: boolean, };
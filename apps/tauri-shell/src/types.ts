@@ -1,60 +1,22 @@
-// IPC Protocol Types
-
-export type AgentResponse =
-  | ReadyResponse
-  | TokenResponse
-  | ToolUseResponse
-  | ToolResultResponse
-  | DoneResponse
-  | ErrorResponse;
-
-export interface ReadyResponse {
-  type: 'ready';
-  timestamp: number;
-}
-
-export interface TokenResponse {
-  type: 'token';
-  id: string;
-  token: string;
-  timestamp: number;
-}
-
-export interface ToolUseResponse {
-  type: 'tool_use';
-  id: string;
-  data: {
-    tool_use_id: string;
-    tool_name: string;
-    tool_input: any;
-  };
-  timestamp: number;
-}
-
-export interface ToolResultResponse {
-  type: 'tool_result';
-  id: string;
-  data: {
-    tool_use_id: string;
-    tool_name: string;
-    result?: any;
-    error?: string;
-  };
-  timestamp: number;
-}
-
-export interface DoneResponse {
-  type: 'done';
-  id: string;
-  timestamp: number;
-}
-
-export interface ErrorResponse {
-  type: 'error';
-  id: string;
-  error: string;
-  timestamp: number;
-}
+// IPC Protocol Types, generated from the Rust side into ./bindings (`pnpm bindings`)
+
+export type { AgentResponse } from './bindings/AgentResponse';
+// agent_response event payload: a response tagged with the agent that produced it
+export type { RoutedResponse } from './bindings/RoutedResponse';
+
+// The agent's tool_use and tool_result data, which the wire types leave as plain JSON
+export type ToolUseData = {
+  tool_use_id: string;
+  tool_name: string;
+  tool_input: any;
+};
+
+export type ToolResultData = {
+  tool_use_id: string;
+  tool_name: string;
+  result?: any;
+  error?: string;
+};
 
 // UI Message Types
 
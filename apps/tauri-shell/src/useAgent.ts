@@ -1,7 +1,14 @@
 import { useState, useEffect, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { listen } from '@tauri-apps/api/event';
-import type { AgentResponse, Message, ToolCall, ImageAttachment } from './types';
+import type {
+  RoutedResponse,
+  Message,
+  ToolCall,
+  ImageAttachment,
+  ToolUseData,
+  ToolResultData,
+} from './types';
 
 export function useAgent() {
   const [messages, setMessages] = useState<Message[]>([]);
@@ -25,7 +32,7 @@ export function useAgent() {
 
   // Listen to agent responses
   useEffect(() => {
-    const unlisten = listen<AgentResponse>('agent_response', (event) => {
+    const unlisten = listen<RoutedResponse>('agent_response', (event) => {
       const response = event.payload;
       console.log('Received agent response:', response);
 
@@ -66,12 +73,13 @@ export function useAgent() {
       }
 
       if (response.type === 'tool_use') {
+        const data = response.data as ToolUseData;
         setToolCalls((prev) => [
           ...prev,
           {
-            id: response.data.tool_use_id,
-            name: response.data.tool_name,
-            input: response.data.tool_input,
+            id: data.tool_use_id,
+            name: data.tool_name,
+            input: data.tool_input,
             timestamp: response.timestamp,
           },
         ]);
@@ -79,10 +87,11 @@ export function useAgent() {
       }
 
       if (response.type === 'tool_result') {
+        const data = response.data as ToolResultData;
         setToolCalls((prev) =>
           prev.map((call) =>
-            call.id === response.data.tool_use_id
-              ? { ...call, result: response.data.result || response.data.error }
+            call.id === data.tool_use_id
+              ? { ...call, result: data.result || data.error }
               : call
           )
         );